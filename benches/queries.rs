@@ -0,0 +1,180 @@
+//! End-to-end benchmarks of typical queries over a generated synthetic tree.
+//!
+//! Run with `cargo bench`, or `cargo bench -- <group>` to pick a single group
+//! (`traversal`, `ordering`, `hashing`, `output`).
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Bump when the layout of the tree changes, so that a stale one gets regenerated
+const TREE_VERSION: &str = "1";
+
+const SMALL_DIRS: usize = 40;
+const SMALL_FILES_PER_DIR: usize = 100;
+const DEEP_LEVELS: usize = 64;
+const LARGE_FILES: usize = 4;
+const LARGE_FILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Creates the synthetic tree once and reuses it between runs:
+///  - `small`: a few thousand small files of varying size spread over flat directories
+///  - `deep`: a single chain of nested directories with a couple of files on each level
+///  - `large`: a handful of big files to make content reading dominate
+fn synthetic_tree() -> PathBuf {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fselect-bench-tree");
+    let marker = root.join(".complete");
+
+    if fs::read_to_string(&marker).is_ok_and(|version| version == TREE_VERSION) {
+        return root;
+    }
+
+    let _ = fs::remove_dir_all(&root);
+
+    // A simple LCG keeps the sizes varied but identical between runs
+    let mut seed: u64 = 42;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+
+    let extensions = ["txt", "rs", "json", "log", "md"];
+    for dir in 0..SMALL_DIRS {
+        let dir_path = root.join("small").join(format!("dir{:03}", dir));
+        fs::create_dir_all(&dir_path).unwrap();
+
+        for file in 0..SMALL_FILES_PER_DIR {
+            let extension = extensions[file % extensions.len()];
+            let file_path = dir_path.join(format!("file{:04}.{}", file, extension));
+            let size = next() % 4096;
+            fs::write(file_path, "x".repeat(size)).unwrap();
+        }
+    }
+
+    let mut dir_path = root.join("deep");
+    for level in 0..DEEP_LEVELS {
+        dir_path = dir_path.join(format!("level{:02}", level));
+        fs::create_dir_all(&dir_path).unwrap();
+        fs::write(dir_path.join("a.txt"), format!("level {}\n", level)).unwrap();
+        fs::write(dir_path.join("b.rs"), "fn main() {}\n").unwrap();
+    }
+
+    let large_path = root.join("large");
+    fs::create_dir_all(&large_path).unwrap();
+    let chunk: Vec<u8> = (0..1024 * 1024).map(|_| next() as u8).collect();
+    for file in 0..LARGE_FILES {
+        let mut out = fs::File::create(large_path.join(format!("large{}.bin", file))).unwrap();
+        for _ in 0..LARGE_FILE_SIZE / chunk.len() {
+            out.write_all(&chunk).unwrap();
+        }
+    }
+
+    fs::write(marker, TREE_VERSION).unwrap();
+
+    root
+}
+
+/// Runs the fselect binary with the query, discarding the results
+fn run_query(query: &str) {
+    let status = Command::new(env!("CARGO_BIN_EXE_fselect"))
+        .args(["--nocolor", query])
+        .env_remove("FSELECT_LOG")
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+
+    assert!(status.success(), "query failed: {}", query);
+}
+
+fn bench_queries(c: &mut Criterion, group_name: &str, queries: &[(&str, String)]) {
+    let mut group = c.benchmark_group(group_name);
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+
+    for (name, query) in queries {
+        group.bench_with_input(BenchmarkId::from_parameter(name), query, |b, query| {
+            b.iter(|| run_query(query))
+        });
+    }
+
+    group.finish();
+}
+
+fn traversal(c: &mut Criterion) {
+    let root = synthetic_tree();
+    let small = root.join("small").display().to_string();
+    let deep = root.join("deep").display().to_string();
+
+    bench_queries(
+        c,
+        "traversal",
+        &[
+            ("many_small_files", format!("path from {}", small)),
+            ("deep_nesting", format!("path from {}", deep)),
+            ("deep_nesting_dfs", format!("path from {} dfs", deep)),
+            ("where_size", format!("path from {} where size gt 2k", small)),
+            ("where_name_regexp", format!("path from {} where name =~ '^file00[0-9]+\\.rs$'", small)),
+            ("count", format!("count(*) from {}", small)),
+        ],
+    );
+}
+
+fn ordering(c: &mut Criterion) {
+    let root = synthetic_tree();
+    let small = root.join("small").display().to_string();
+
+    bench_queries(
+        c,
+        "ordering",
+        &[
+            ("by_size", format!("path, size from {} order by size desc", small)),
+            ("by_name", format!("path from {} order by name", small)),
+            ("by_two_columns", format!("path from {} order by ext, size desc", small)),
+            ("with_limit", format!("path, size from {} order by size desc limit 10", small)),
+            ("group_by", format!("ext, count(*), sum(size) from {} group by ext", small)),
+        ],
+    );
+}
+
+fn hashing(c: &mut Criterion) {
+    let root = synthetic_tree();
+    let small = root.join("small").display().to_string();
+    let large = root.join("large").display().to_string();
+
+    bench_queries(
+        c,
+        "hashing",
+        &[
+            ("sha1_large", format!("sha1 from {}", large)),
+            ("sha256_large", format!("sha256 from {}", large)),
+            ("sha512_large", format!("sha512 from {}", large)),
+            ("sha3_large", format!("sha3 from {}", large)),
+            ("sha256_many_small", format!("sha256 from {}", small)),
+            ("line_count_large", format!("line_count from {}", large)),
+        ],
+    );
+}
+
+fn output(c: &mut Criterion) {
+    let root = synthetic_tree();
+    let small = root.join("small").display().to_string();
+
+    let formats = ["tabs", "lines", "list", "csv", "json", "html"];
+    let queries: Vec<(&str, String)> = formats
+        .iter()
+        .map(|format| {
+            (
+                *format,
+                format!("name, path, size, fsize, modified from {} into {}", small, format),
+            )
+        })
+        .collect();
+
+    bench_queries(c, "output", &queries);
+}
+
+criterion_group!(benches, traversal, ordering, hashing, output);
+criterion_main!(benches);
@@ -0,0 +1,128 @@
+//! Handles the opt-in results cache enabled with `--cache`.
+//!
+//! Results are stored by a key made of the parsed query, the configuration,
+//! and modification times of the search roots, so they're reused until any root directory changes
+//! or `cache_ttl` seconds pass. Nothing deeper than the roots is checked,
+//! so changes of the files within the directories go unnoticed until then.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use log::debug;
+use sha1::Digest;
+
+use crate::config::Config;
+use crate::query::Query;
+
+/// Computes the cache key for the query.
+/// Returns None if the query can't be cached, e.g. when it has side effects or a root is missing.
+pub fn get_cache_key(query: &Query, config: &Config, use_colors: bool) -> Option<String> {
+    // Partitioned results are never written to the output, so there's nothing to reuse
+    if query.action.is_some() || query.partition.is_some() {
+        return None;
+    }
+
+    // Changes of the current time or the file contents don't touch the root directories
+    if query.is_volatile() {
+        return None;
+    }
+
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(format!("{:?}", query));
+    hasher.update(toml::to_string(config).ok()?);
+    hasher.update([use_colors as u8]);
+
+    // Relative roots depend on where fselect was started
+    hasher.update(std::env::current_dir().ok()?.to_string_lossy().as_bytes());
+
+    for root in &query.roots {
+        let modified = fs::metadata(&root.path).ok()?.modified().ok()?;
+        let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        hasher.update(nanos.to_le_bytes());
+    }
+
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Results older than the TTL are not reused, as changes deeper in the roots could have happened since
+pub fn get_cached_results(key: &str, ttl: Duration) -> Option<Vec<u8>> {
+    let cache_file = get_cache_file(key)?;
+    let age = fs::metadata(&cache_file).ok()?.modified().ok()?.elapsed().ok()?;
+    let results = match age < ttl {
+        true => fs::read(&cache_file).ok(),
+        false => None,
+    };
+    debug!(target: "searcher", "Results cache {} for key {}", if results.is_some() { "hit" } else { "miss" }, key);
+
+    results
+}
+
+pub fn store_results(key: &str, results: &[u8]) {
+    let Some(cache_file) = get_cache_file(key) else {
+        return;
+    };
+
+    if let Some(cache_dir) = cache_file.parent() {
+        let _ = fs::create_dir_all(cache_dir);
+    }
+
+    // Write to a temporary file first, so that concurrent runs never read partial results
+    let tmp_file = cache_file.with_extension(format!("tmp{}", std::process::id()));
+    if fs::write(&tmp_file, results).is_err() || fs::rename(&tmp_file, &cache_file).is_err() {
+        let _ = fs::remove_file(&tmp_file);
+        debug!(target: "searcher", "Could not store results to cache at {}", cache_file.display());
+    }
+}
+
+pub fn get_cache_ttl(config: &Config, default_config: &Config) -> Duration {
+    Duration::from_secs(config.cache_ttl.or(default_config.cache_ttl).unwrap_or_default())
+}
+
+fn get_cache_file(key: &str) -> Option<PathBuf> {
+    Config::get_cache_dir().map(|dir| dir.join("results").join(key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::util::temp_dir::TempDir;
+
+    fn key(query: &str) -> Option<String> {
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        get_cache_key(&query, &Config::default(), false)
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let dir = TempDir::new("cache");
+        let dir = dir.to_string_lossy();
+
+        let keys = (
+            key(&format!("name, size from {} where size > 10", dir)),
+            key(&format!("NAME,  size FROM {}   where size > 10", dir)),
+            key(&format!("name, size from {} where size > 20", dir)),
+            key(&format!("name from {}/missing", dir)),
+            key(&format!("name from {} extract thumbnail to /tmp", dir)),
+            key(&format!("name from {} where modified gt '1 hour ago'", dir)),
+            key(&format!("name from {} where modified gt 2024-01-01", dir)),
+            key(&format!("name, sha256 from {}", dir)),
+            key(&format!("name from {} where is_open", dir)),
+            key(&format!("name, random() from {}", dir)),
+            key(&format!("name from {} where rand(10) > 5", dir)),
+        );
+
+        assert!(keys.0.is_some());
+        assert_eq!(keys.0, keys.1);
+        assert_ne!(keys.0, keys.2);
+        assert_eq!(keys.3, None);
+        assert_eq!(keys.4, None);
+        assert_eq!(keys.5, None);
+        assert!(keys.6.is_some());
+        assert_eq!(keys.7, None);
+        assert_eq!(keys.8, None);
+        assert_eq!(keys.9, None);
+        assert_eq!(keys.10, None);
+    }
+}
@@ -1,5 +1,6 @@
 //! Handles configuration loading and saving
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -32,15 +33,62 @@ pub struct Config {
     pub is_font: Option<Vec<String>>,
     pub is_image: Option<Vec<String>>,
     pub is_source: Option<Vec<String>>,
+    pub is_script: Option<Vec<String>>,
     pub is_video: Option<Vec<String>>,
+    pub classify_by: Option<String>,
     pub default_file_size_format: Option<String>,
+    pub max_content_size: Option<String>,
+    pub content_timeout: Option<u64>,
+    /// Annotations counted by `TODO_COUNT()`
+    pub todo_markers: Option<Vec<String>>,
+    pub noatime: Option<bool>,
+    /// Seconds the results stored with `--cache` are reused for
+    pub cache_ttl: Option<u64>,
+    /// Attempts to repeat reads of directories and metadata failing with transient errors
+    pub io_retries: Option<u32>,
+    /// Milliseconds to wait before the first retry, doubled for each next one
+    pub io_retry_delay: Option<u64>,
     pub check_for_updates: Option<bool>,
+    /// Root options given to every root without its own, e.g. `gitignore dfs maxdepth 10`
+    pub default_root_options: Option<String>,
+    /// Columns `*` expands to, e.g. `["name", "size", "modified"]`
+    pub star_columns: Option<Vec<String>>,
+    /// Write the column names before the first row of `tabs`, `lines`, and `list` output
+    pub output_headers: Option<bool>,
+    /// Named output settings selected with `into profile(NAME)`
+    pub output: Option<BTreeMap<String, OutputProfile>>,
+    /// Values substituted for `${NAME}` in queries
+    pub constants: Option<BTreeMap<String, String>>,
+    /// Styles of the column values in the terminal, the first matching rule of the column wins
+    pub color_rules: Option<Vec<ColorRule>>,
+    /// Values set with `\set` in the interactive mode, they take precedence over the constants
+    #[serde(skip)]
+    pub variables: BTreeMap<String, String>,
     #[serde(skip_serializing, default = "get_false")]
     pub debug: bool,
     #[serde(skip)]
     save: bool,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct OutputProfile {
+    pub format: Option<String>,
+    /// Write the column names first, with `csv` and `tabs` formats
+    pub headers: Option<bool>,
+    /// Column separator of `csv` and `tabs` formats
+    pub delimiter: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ColorRule {
+    /// Column the rule applies to, e.g. `size` or `modified`
+    pub column: String,
+    /// Condition in the syntax of `where`, e.g. `size > 1gb`, the rule applies to every file without it
+    pub when: Option<String>,
+    /// Style in the syntax of LS_COLORS, e.g. `1;31` for bold red
+    pub style: String,
+}
+
 fn get_false() -> bool {
     false
 }
@@ -105,6 +153,11 @@ impl Config {
             .map(|pd| pd.config_dir().parent().unwrap().to_path_buf())
     }
 
+    /// Directory for the results cache, see `--cache`
+    pub fn get_cache_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", ORGANIZATION, APPLICATION).map(|pd| pd.cache_dir().to_path_buf())
+    }
+
     pub fn save(&self) {
         if !self.save {
             return;
@@ -131,6 +184,54 @@ impl Config {
         }
     }
 
+    /// Handles the arguments of `\set NAME VALUE` given in the interactive mode,
+    /// the variable lasts until the session is over
+    pub fn set_variable(&mut self, args: &str) -> Result<(), String> {
+        let Some((name, value)) = args.trim().split_once(char::is_whitespace) else {
+            return Err(String::from("Usage: \\set NAME VALUE"));
+        };
+
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Invalid variable name: {}", name));
+        }
+
+        self.variables.insert(name.to_string(), value.trim().to_string());
+
+        Ok(())
+    }
+
+    /// Replaces `${NAME}` with the values from the `[constants]` section, before the query is parsed
+    pub fn expand_constants(&self, query: &str) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = query;
+
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+
+            let after = &rest[start + 2..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("Unterminated constant: {}", &rest[start..]))?;
+            let name = after[..end].trim();
+
+            let value = self
+                .variables
+                .get(name)
+                .or_else(|| self.constants.as_ref().and_then(|constants| constants.get(name)));
+
+            match value {
+                Some(value) => result.push_str(value),
+                None => return Err(format!("Unknown constant: {}", name)),
+            }
+
+            rest = &after[end + 1..];
+        }
+
+        result.push_str(rest);
+
+        Ok(result)
+    }
+
     pub fn default() -> Config {
         Config {
             no_color: Some(false),
@@ -167,12 +268,31 @@ impl Config {
                 ".pm", ".py", ".rb", ".rs", ".scala", ".sol", ".swift", ".tcl", ".ts", ".tsx",
                 ".vala", ".vb", ".zig"
             ],
+            is_script: vec_of_strings![
+                ".awk", ".bash", ".bat", ".cmd", ".fish", ".ksh", ".lua", ".php", ".pl", ".ps1",
+                ".py", ".rb", ".sh", ".tcl", ".zsh", "#!"
+            ],
             is_video: vec_of_strings![
                 ".3gp", ".avi", ".flv", ".m4p", ".m4v", ".mkv", ".mov", ".mp4", ".mpeg", ".mpg",
                 ".webm", ".wmv"
             ],
+            classify_by: Some(String::from("extension")),
             default_file_size_format: Some(String::new()),
+            max_content_size: Some(String::new()),
+            content_timeout: Some(0),
+            todo_markers: vec_of_strings!["TODO", "FIXME", "HACK", "XXX"],
+            noatime: Some(false),
+            cache_ttl: Some(3600),
+            io_retries: Some(0),
+            io_retry_delay: Some(100),
             check_for_updates: Some(false),
+            default_root_options: None,
+            star_columns: None,
+            output_headers: Some(false),
+            output: None,
+            constants: None,
+            color_rules: None,
+            variables: BTreeMap::new(),
             debug: false,
             save: true,
         }
@@ -189,4 +309,61 @@ mod tests {
 
         assert!(config.is_source.unwrap().contains(&String::from(".rs")));
     }
+
+    #[test]
+    fn color_rules() {
+        let config: Config = toml::from_str(
+            "[[color_rules]]\ncolumn = \"size\"\nwhen = \"size > 1gb\"\nstyle = \"1;31\"\n\n[[color_rules]]\ncolumn = \"modified\"\nstyle = \"2\"\n",
+        )
+        .unwrap();
+
+        let rules = config.color_rules.unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].when.as_deref(), Some("size > 1gb"));
+        assert_eq!(rules[0].style, "1;31");
+        assert_eq!(rules[1].when, None);
+    }
+
+    #[test]
+    fn output_profiles() {
+        let config: Config = toml::from_str(
+            "[output.report]\nformat = \"csv\"\nheaders = true\ndelimiter = \";\"\n\n[output.plain]\nformat = \"tabs\"\n",
+        )
+        .unwrap();
+
+        let profiles = config.output.unwrap();
+        let report = &profiles["report"];
+        assert_eq!(report.format.as_deref(), Some("csv"));
+        assert_eq!(report.headers, Some(true));
+        assert_eq!(report.delimiter.as_deref(), Some(";"));
+        assert_eq!(profiles["plain"].headers, None);
+    }
+
+    #[test]
+    fn constants() {
+        let config: Config =
+            toml::from_str("[constants]\nbig = \"1gb\"\nrecent = \"2024-01-01\"\n").unwrap();
+
+        assert_eq!(
+            config.expand_constants("path from /home where size > ${big} and modified > ${ recent }"),
+            Ok(String::from("path from /home where size > 1gb and modified > 2024-01-01"))
+        );
+        assert_eq!(config.expand_constants("name from /home"), Ok(String::from("name from /home")));
+        assert!(config.expand_constants("size > ${huge}").is_err());
+        assert!(config.expand_constants("size > ${big").is_err());
+    }
+
+    #[test]
+    fn variables() {
+        let mut config: Config = toml::from_str("[constants]\nbig = \"1gb\"\n").unwrap();
+
+        assert_eq!(config.set_variable("threshold  500mb "), Ok(()));
+        assert_eq!(config.set_variable("big 2gb"), Ok(()));
+        assert_eq!(
+            config.expand_constants("path from /home where size > ${threshold} and size < ${big}"),
+            Ok(String::from("path from /home where size > 500mb and size < 2gb"))
+        );
+        assert!(config.set_variable("threshold").is_err());
+        assert!(config.set_variable("${threshold} 1mb").is_err());
+    }
 }
@@ -5,9 +5,40 @@ use std::fmt::Formatter;
 
 use crate::field::Field;
 use crate::function::Function;
+use crate::function::VariantType;
 use crate::operators::ArithmeticOp;
 use crate::operators::LogicalOp;
 use crate::operators::Op;
+use crate::util::convert_like_to_pattern;
+use crate::util::is_relative_datetime;
+
+/// Relative cost of evaluating the conditions, from the cheapest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Cost {
+    /// Names and paths, as well as values not coming from the file
+    Name,
+    /// Metadata of the file
+    Metadata,
+    /// MIME type, detected from the first bytes of the file
+    Mime,
+    /// Dimensions of images, tags of audio and video, EXIF, and other headers of the file
+    Dimensions,
+    /// Hashes, contents, and walks of the directory tree
+    Content,
+}
+
+impl Display for Cost {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Cost::Name => "name",
+            Cost::Metadata => "metadata",
+            Cost::Mime => "mime",
+            Cost::Dimensions => "dimensions",
+            Cost::Content => "content",
+        };
+        fmt.write_str(name)
+    }
+}
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Eq, Hash, Serialize)]
 pub struct Expr {
@@ -129,6 +160,13 @@ impl Expr {
         }
     }
 
+    /// Window functions are supported only as the columns themselves, not nested into other expressions
+    pub fn is_window_function(&self) -> bool {
+        self.function
+            .as_ref()
+            .is_some_and(|function| function.is_window_function())
+    }
+
     pub fn has_aggregate_function(&self) -> bool {
         if let Some(ref left) = self.left {
             if left.has_aggregate_function() {
@@ -159,6 +197,105 @@ impl Expr {
         false
     }
 
+    /// Relative cost of evaluating the expression, which is the cost of its most expensive part
+    pub fn get_cost(&self) -> Cost {
+        let own_cost = [
+            self.field.map(|field| field.get_cost()),
+            self.function.as_ref().map(Function::get_cost),
+        ];
+
+        own_cost
+            .into_iter()
+            .flatten()
+            .chain(self.left.iter().chain(self.right.iter()).map(|expr| expr.get_cost()))
+            .chain(self.args.iter().flatten().map(Expr::get_cost))
+            .max()
+            .unwrap_or(Cost::Name)
+    }
+
+    /// Operands of the chain of the same logical operation, e.g. all three of `a and (b and c)`,
+    /// or the expression itself if it isn't a logical operation
+    pub fn get_operands(&self) -> Vec<&Expr> {
+        let mut operands = vec![];
+        self.collect_operands(&self.logical_op, &mut operands);
+        operands
+    }
+
+    fn collect_operands<'a>(&'a self, logical_op: &Option<LogicalOp>, operands: &mut Vec<&'a Expr>) {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) if logical_op.is_some() && self.logical_op == *logical_op => {
+                left.collect_operands(logical_op, operands);
+                right.collect_operands(logical_op, operands);
+            }
+            _ => operands.push(self),
+        }
+    }
+
+    /// Column and regex patterns of an OR chain of at least two `~=` and `like` conditions
+    /// on the same column with literal values, so they could be matched all at once
+    pub fn get_pattern_set(&self) -> Option<(&Expr, Vec<String>)> {
+        if self.logical_op != Some(LogicalOp::Or) {
+            return None;
+        }
+
+        let operands = self.get_operands();
+        if operands.len() < 2 {
+            return None;
+        }
+
+        let mut column: Option<&Expr> = None;
+        let mut patterns = vec![];
+
+        for operand in operands {
+            let (Some(left), Some(right), Some(op)) = (&operand.left, &operand.right, &operand.op) else {
+                return None;
+            };
+            let val = right.val.as_ref()?;
+            if operand.logical_op.is_some() || **right != Expr::value(val.clone()) {
+                return None;
+            }
+
+            match column {
+                Some(column) if column != left.as_ref() => return None,
+                _ => column = Some(left),
+            }
+
+            match op {
+                Op::Rx => patterns.push(val.clone()),
+                Op::Like => patterns.push(convert_like_to_pattern(val)),
+                _ => return None,
+            }
+        }
+
+        column.map(|column| (column, patterns))
+    }
+
+    /// Reorders the operands of AND and OR chains from the cheapest to the most expensive,
+    /// so the costly conditions are only evaluated if the cheap ones don't decide the result.
+    /// Operands of the same cost keep their order.
+    pub fn reorder_by_cost(self) -> Expr {
+        let Some(ref logical_op) = self.logical_op else {
+            return self;
+        };
+
+        let operands = self.get_operands();
+        let mut reordered: Vec<Expr> = operands
+            .iter()
+            .map(|operand| (*operand).clone().reorder_by_cost())
+            .collect();
+        reordered.sort_by_key(Expr::get_cost);
+
+        if reordered.iter().eq(operands) {
+            return self;
+        }
+
+        let logical_op = logical_op.clone();
+        reordered
+            .into_iter()
+            .reduce(|left, right| Expr::logical_op(left, logical_op.clone(), right))
+            .unwrap_or(self)
+    }
+
     pub fn get_required_fields(&self) -> HashSet<Field> {
         let mut result = HashSet::new();
 
@@ -183,6 +320,45 @@ impl Expr {
         result
     }
 
+    /// Type of the values of the expression as far as it's known before evaluating it,
+    /// e.g. functions of numeric fields could still return strings
+    pub fn get_value_type(&self) -> VariantType {
+        if let Some(ref function) = self.function {
+            return if function.is_boolean_function() {
+                VariantType::Bool
+            } else if function.is_numeric_function() {
+                VariantType::Int
+            } else {
+                VariantType::String
+            };
+        }
+
+        if let Some(ref field) = self.field {
+            return if field.is_boolean_field() {
+                VariantType::Bool
+            } else if field.is_numeric_field() {
+                VariantType::Int
+            } else if field.is_datetime_field() {
+                VariantType::DateTime
+            } else {
+                VariantType::String
+            };
+        }
+
+        if self.op.is_some() || self.logical_op.is_some() {
+            return VariantType::Bool;
+        }
+
+        match self.left {
+            Some(ref left) if self.arithmetic_op.is_some() => match left.get_value_type() {
+                VariantType::Int | VariantType::Float => VariantType::Float,
+                value_type => value_type,
+            },
+            Some(ref left) => left.get_value_type(),
+            None => VariantType::String,
+        }
+    }
+
     pub fn contains_numeric(&self) -> bool {
         Self::contains_numeric_field(self)
     }
@@ -232,6 +408,27 @@ impl Expr {
         }
     }
 
+    /// Whether the value depends on the moment of the search, e.g. `modified = today`, `is_open`
+    /// or `random()`, and not just on the file itself
+    pub fn is_time_relative(&self) -> bool {
+        if self.field.as_ref().is_some_and(Field::is_volatile)
+            || self.function.as_ref().is_some_and(Function::is_volatile)
+        {
+            return true;
+        }
+
+        if let (Some(left), Some(right)) = (&self.left, &self.right) {
+            if let Some(ref val) = right.val {
+                if left.contains_datetime() && is_relative_datetime(val) {
+                    return true;
+                }
+            }
+        }
+
+        self.left.iter().chain(self.right.iter()).any(|expr| expr.is_time_relative())
+            || self.args.iter().flatten().any(Expr::is_time_relative)
+    }
+
     pub fn contains_colorized(&self) -> bool {
         Self::contains_colorized_field(self)
     }
@@ -257,6 +454,8 @@ impl Expr {
     }
 }
 
+/// The text of the expression identifies its value within the row, e.g. as a grouping key,
+/// so function arguments and operators are all spelled out
 impl Display for Expr {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
         use std::fmt::Write;
@@ -271,9 +470,12 @@ impl Display for Expr {
             if let Some(ref left) = self.left {
                 fmt.write_str(&left.to_string())?;
             }
+            for arg in self.args.iter().flatten() {
+                write!(fmt, ", {}", arg)?;
+            }
             fmt.write_char(')')?;
         } else if let Some(ref left) = self.left {
-            fmt.write_str(&left.to_string())?;
+            left.fmt_operand(fmt)?;
         }
 
         if let Some(ref field) = self.field {
@@ -284,10 +486,31 @@ impl Display for Expr {
             fmt.write_str(val)?;
         }
 
+        if let Some(ref op) = self.arithmetic_op {
+            write!(fmt, " {} ", op)?;
+        } else if let Some(ref op) = self.op {
+            write!(fmt, " {} ", op)?;
+        } else if let Some(ref op) = self.logical_op {
+            write!(fmt, " {} ", op)?;
+        }
+
         if let Some(ref right) = self.right {
-            fmt.write_str(&right.to_string())?;
+            right.fmt_operand(fmt)?;
         }
 
         Ok(())
     }
 }
+
+impl Expr {
+    /// Nested operations are parenthesized to keep them apart from the enclosing one
+    fn fmt_operand(&self, fmt: &mut Formatter) -> fmt::Result {
+        let is_operation = self.function.is_none()
+            && (self.arithmetic_op.is_some() || self.op.is_some() || self.logical_op.is_some());
+
+        match is_operation {
+            true => write!(fmt, "({})", self),
+            false => write!(fmt, "{}", self),
+        }
+    }
+}
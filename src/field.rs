@@ -7,6 +7,8 @@ use std::str::FromStr;
 
 use serde::ser::{Serialize, Serializer};
 
+use crate::expr::Cost;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
 pub enum Field {
     Name,
@@ -17,6 +19,8 @@ pub enum Field {
     AbsDir,
     Size,
     FormattedSize,
+    DirSize,
+    SizeShare,
     Uid,
     Gid,
     #[cfg(all(unix, feature = "users"))]
@@ -24,8 +28,11 @@ pub enum Field {
     #[cfg(all(unix, feature = "users"))]
     Group,
     Created,
+    HasBirthTime,
     Accessed,
     Modified,
+    NewestChildModified,
+    OldestChildModified,
     IsDir,
     IsFile,
     IsSymlink,
@@ -34,6 +41,7 @@ pub enum Field {
     IsBlockDevice,
     IsSocket,
     Device,
+    MountId,
     Inode,
     Blocks,
     Hardlinks,
@@ -53,12 +61,41 @@ pub enum Field {
     Suid,
     Sgid,
     IsHidden,
+    IsGitRoot,
+    IsHgRoot,
+    IsGenerated,
+    IsVendored,
+    HgTracked,
+    HgModified,
+    SvnVersioned,
+    ProjectType,
+    IsValidUtf8Name,
+    HasWindowsReservedName,
+    HasControlChars,
     HasXattrs,
     Capabilities,
+    IsImmutable,
+    IsAppendOnly,
+    Dax,
+    Verity,
+    ImaHash,
+    IsOpen,
+    #[cfg(all(target_os = "linux", feature = "processes"))]
+    OpenByPids,
+    #[cfg(all(target_os = "linux", feature = "processes"))]
+    OpenByProcessNames,
     IsShebang,
     IsEmpty,
+    IsRecursivelyEmpty,
+    ChildCount,
+    SubdirCount,
+    FileCount,
     Width,
     Height,
+    #[cfg(feature = "image-colors")]
+    DominantColor,
+    #[cfg(feature = "image-colors")]
+    Brightness,
     Duration,
     Bitrate,
     Freq,
@@ -67,6 +104,16 @@ pub enum Field {
     Album,
     Year,
     Genre,
+    VideoTitle,
+    Encoder,
+    VideoCreated,
+    AudioTracks,
+    SubtitleTracks,
+    SubtitleLangs,
+    TorrentInfoHash,
+    TorrentPieceCount,
+    TorrentTotalSize,
+    TorrentFilesCount,
     ExifDateTime,
     ExifGpsAltitude,
     ExifGpsLatitude,
@@ -77,22 +124,200 @@ pub enum Field {
     ExifVersion,
     Mime,
     LineCount,
+    CodeLines,
+    CommentLines,
+    BlankLines,
+    License,
     IsBinary,
     IsText,
     IsArchive,
+    IsValidArchive,
+    InArchive,
+    ArchivePath,
     IsAudio,
     IsBook,
     IsDoc,
     IsFont,
     IsImage,
+    IsCorruptedImage,
+    IsEncrypted,
     IsSource,
+    IsScript,
     IsVideo,
     Sha1,
     Sha256,
     Sha512,
     Sha3,
+    TrashOriginalPath,
+    TrashDeleted,
+}
+
+/// Name, aliases, group, and description of a field, as listed by `--fields`
+pub struct FieldInfo {
+    pub field: Field,
+    /// Canonical name first, followed by the aliases
+    pub names: &'static [&'static str],
+    pub group: &'static str,
+    pub description: &'static str,
+}
+
+const fn describe(
+    field: Field,
+    names: &'static [&'static str],
+    group: &'static str,
+    description: &'static str,
+) -> FieldInfo {
+    FieldInfo {
+        field,
+        names,
+        group,
+        description,
+    }
 }
 
+#[rustfmt::skip]
+pub const FIELDS: &[FieldInfo] = &[
+    describe(Field::Name, &["name"], "file", "Returns the name (with extension) of the file"),
+    describe(Field::Path, &["path"], "file", "Returns the path of the file"),
+    describe(Field::AbsPath, &["abspath"], "file", "Returns the absolute path of the file"),
+    describe(Field::Extension, &["extension", "ext"], "file", "Returns the extension of the file"),
+    describe(Field::Directory, &["directory", "dir", "dirname"], "file", "Returns the directory of the file"),
+    describe(Field::AbsDir, &["absdir"], "file", "Returns the absolute directory of the file"),
+    describe(Field::Size, &["size"], "file", "Returns the size of the file in bytes"),
+    describe(Field::FormattedSize, &["fsize", "hsize"], "file", "Returns the size of the file accompanied with the unit"),
+    describe(Field::DirSize, &["dir_size"], "file", "Returns the total size of all files within the directory tree in bytes"),
+    describe(Field::SizeShare, &["size_share"], "file", "Returns the fraction of the parent directory's total size taken by the file or directory"),
+    describe(Field::Uid, &["uid"], "file", "Returns the UID of the owner"),
+    describe(Field::Gid, &["gid"], "file", "Returns the GID of the owner's group"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Field::User, &["user"], "permissions", "Returns the name of the owner for this file"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Field::Group, &["group"], "permissions", "Returns the name of the owner's group for this file"),
+    describe(Field::Created, &["created"], "datetime", "Returns the file creation date (YYYY-MM-DD HH:MM:SS)"),
+    describe(Field::HasBirthTime, &["has_birth_time"], "datetime", "Returns a boolean signifying whether the file system records the creation time of the file"),
+    describe(Field::Accessed, &["accessed"], "datetime", "Returns the time the file was last accessed (YYYY-MM-DD HH:MM:SS)"),
+    describe(Field::Modified, &["modified"], "datetime", "Returns the time the file was last modified (YYYY-MM-DD HH:MM:SS)"),
+    describe(Field::NewestChildModified, &["newest_child_modified"], "datetime", "Returns the modification time of the most recently modified entry within the directory tree"),
+    describe(Field::OldestChildModified, &["oldest_child_modified"], "datetime", "Returns the modification time of the least recently modified entry within the directory tree"),
+    describe(Field::IsDir, &["is_dir"], "attributes", "Returns a boolean signifying whether the file path is a directory"),
+    describe(Field::IsFile, &["is_file"], "attributes", "Returns a boolean signifying whether the file path is a file"),
+    describe(Field::IsSymlink, &["is_symlink"], "attributes", "Returns a boolean signifying whether the file path is a symlink"),
+    describe(Field::IsPipe, &["is_pipe", "is_fifo"], "attributes", "Returns a boolean signifying whether the file path is a FIFO or pipe file"),
+    describe(Field::IsCharacterDevice, &["is_char", "is_character"], "attributes", "Returns a boolean signifying whether the file path is a character device or character special file"),
+    describe(Field::IsBlockDevice, &["is_block"], "attributes", "Returns a boolean signifying whether the file path is a block or block special file"),
+    describe(Field::IsSocket, &["is_socket"], "attributes", "Returns a boolean signifying whether the file path is a socket file"),
+    describe(Field::Device, &["device"], "inode", "Returns the code of device the file is stored on"),
+    describe(Field::MountId, &["mount_id"], "inode", "Returns the ID of the mount the file is found on, as in /proc/self/mountinfo"),
+    describe(Field::Inode, &["inode"], "inode", "Returns the number of inode"),
+    describe(Field::Blocks, &["blocks"], "inode", "Returns the number of blocks (256 bytes) the file occupies"),
+    describe(Field::Hardlinks, &["hardlinks"], "inode", "Returns the number of hardlinks of the file"),
+    describe(Field::Mode, &["mode"], "permissions", "Returns the permissions of the owner, group, and everybody (similar to the first field in `ls -la`)"),
+    describe(Field::UserRead, &["user_read"], "permissions", "Returns a boolean signifying whether the file can be read by the owner"),
+    describe(Field::UserWrite, &["user_write"], "permissions", "Returns a boolean signifying whether the file can be written by the owner"),
+    describe(Field::UserExec, &["user_exec"], "permissions", "Returns a boolean signifying whether the file can be executed by the owner"),
+    describe(Field::UserAll, &["user_all", "user_rwx"], "permissions", "Returns a boolean signifying whether the file can be fully accessed by the owner"),
+    describe(Field::GroupRead, &["group_read"], "permissions", "Returns a boolean signifying whether the file can be read by the owner's group"),
+    describe(Field::GroupWrite, &["group_write"], "permissions", "Returns a boolean signifying whether the file can be written by the owner's group"),
+    describe(Field::GroupExec, &["group_exec"], "permissions", "Returns a boolean signifying whether the file can be executed by the owner's group"),
+    describe(Field::GroupAll, &["group_all", "group_rwx"], "permissions", "Returns a boolean signifying whether the file can be fully accessed by the group"),
+    describe(Field::OtherRead, &["other_read"], "permissions", "Returns a boolean signifying whether the file can be read by others"),
+    describe(Field::OtherWrite, &["other_write"], "permissions", "Returns a boolean signifying whether the file can be written by others"),
+    describe(Field::OtherExec, &["other_exec"], "permissions", "Returns a boolean signifying whether the file can be executed by others"),
+    describe(Field::OtherAll, &["other_all", "other_rwx"], "permissions", "Returns a boolean signifying whether the file can be fully accessed by the others"),
+    describe(Field::Suid, &["suid"], "permissions", "Returns a boolean signifying whether the file permissions have a SUID bit set"),
+    describe(Field::Sgid, &["sgid"], "permissions", "Returns a boolean signifying whether the file permissions have a SGID bit set"),
+    describe(Field::IsHidden, &["is_hidden"], "attributes", "Returns a boolean signifying whether the file is a hidden file (e.g., files that start with a dot on *nix)"),
+    describe(Field::IsValidUtf8Name, &["is_valid_utf8_name"], "attributes", "Returns a boolean signifying whether the file name is valid UTF-8"),
+    describe(Field::HasWindowsReservedName, &["has_windows_reserved_name"], "attributes", "Returns a boolean signifying whether the file name is reserved on Windows or ends with a dot or a space"),
+    describe(Field::HasControlChars, &["has_control_chars"], "attributes", "Returns a boolean signifying whether the file name contains control characters"),
+    describe(Field::IsGitRoot, &["is_git_root"], "attributes", "Returns a boolean signifying whether the directory is the root of a Git repository"),
+    describe(Field::IsHgRoot, &["is_hg_root"], "attributes", "Returns a boolean signifying whether the directory is the root of a Mercurial repository"),
+    describe(Field::IsGenerated, &["is_generated"], "attributes", "Returns a boolean signifying whether the file is generated according to .gitattributes or linguist heuristics"),
+    describe(Field::IsVendored, &["is_vendored"], "attributes", "Returns a boolean signifying whether the file is vendored according to .gitattributes or linguist heuristics"),
+    describe(Field::HgTracked, &["hg_tracked"], "attributes", "Returns a boolean signifying whether the file is tracked by Mercurial"),
+    describe(Field::HgModified, &["hg_modified"], "attributes", "Returns a boolean signifying whether the file tracked by Mercurial has uncommitted changes"),
+    describe(Field::SvnVersioned, &["svn_versioned"], "attributes", "Returns a boolean signifying whether the file is versioned in a Subversion working copy"),
+    describe(Field::ProjectType, &["project_type", "project"], "attributes", "Returns the type of the project in the directory (cargo, npm, maven, or python) detected by its marker files"),
+    describe(Field::HasXattrs, &["has_xattrs"], "attributes", "Returns a boolean signifying whether the file has extended attributes"),
+    describe(Field::Capabilities, &["capabilities", "caps"], "attributes", "Returns a string describing Linux capabilities assigned to a file"),
+    describe(Field::IsImmutable, &["is_immutable"], "attributes", "Returns a boolean signifying whether the file has the immutable flag set (chattr +i)"),
+    describe(Field::IsAppendOnly, &["is_append_only"], "attributes", "Returns a boolean signifying whether the file has the append-only flag set (chattr +a)"),
+    describe(Field::Dax, &["dax"], "attributes", "Returns a boolean signifying whether the file is accessed directly, bypassing the page cache (DAX)"),
+    describe(Field::Verity, &["verity", "has_verity"], "attributes", "Returns a boolean signifying whether the file is protected with fs-verity"),
+    describe(Field::ImaHash, &["ima_hash"], "attributes", "Returns the IMA measurement of the file as ALGO:DIGEST, or ALGO:signed for IMA signatures"),
+    describe(Field::IsOpen, &["is_open"], "attributes", "Returns a boolean signifying whether the file is currently open by any process"),
+    #[cfg(all(target_os = "linux", feature = "processes"))]
+    describe(Field::OpenByPids, &["open_by_pids"], "attributes", "Returns the comma-separated IDs of the processes holding the file open"),
+    #[cfg(all(target_os = "linux", feature = "processes"))]
+    describe(Field::OpenByProcessNames, &["open_by_process_names"], "attributes", "Returns the comma-separated names of the processes holding the file open"),
+    describe(Field::IsShebang, &["is_shebang"], "type", "Returns a boolean signifying whether the file starts with a shebang (#!)"),
+    describe(Field::IsEmpty, &["is_empty"], "type", "Returns a boolean signifying whether the file is empty or the directory is empty"),
+    describe(Field::IsRecursivelyEmpty, &["is_recursively_empty"], "type", "Returns a boolean signifying whether the directory contains nothing but empty directories"),
+    describe(Field::ChildCount, &["child_count", "children"], "type", "Returns the number of entries in the directory, without recursion"),
+    describe(Field::SubdirCount, &["subdir_count", "subdirs"], "type", "Returns the number of subdirectories in the directory, without recursion"),
+    describe(Field::FileCount, &["file_count", "files"], "type", "Returns the number of regular files in the directory, without recursion"),
+    describe(Field::Width, &["width"], "image", "Returns the number of pixels along the width of the photo or MP4 file"),
+    describe(Field::Height, &["height"], "image", "Returns the number of pixels along the height of the photo or MP4 file"),
+    #[cfg(feature = "image-colors")]
+    describe(Field::DominantColor, &["dominant_color"], "image", "Returns the most common color of the image as #rrggbb"),
+    #[cfg(feature = "image-colors")]
+    describe(Field::Brightness, &["brightness"], "image", "Returns the average brightness of the image from 0 (black) to 100 (white)"),
+    describe(Field::Duration, &["duration"], "audio", "Returns the duration of audio file in seconds"),
+    describe(Field::Bitrate, &["mp3_bitrate", "bitrate"], "audio", "Returns the bitrate of the audio file in kbps"),
+    describe(Field::Freq, &["mp3_freq", "freq"], "audio", "Returns the sampling rate of audio or video file"),
+    describe(Field::Title, &["mp3_title", "title"], "audio", "Returns the title of the audio file taken from the file's metadata"),
+    describe(Field::Artist, &["mp3_artist", "artist"], "audio", "Returns the artist of the audio file taken from the file's metadata"),
+    describe(Field::Album, &["mp3_album", "album"], "audio", "Returns the album name of the audio file taken from the file's metadata"),
+    describe(Field::Year, &["mp3_year"], "audio", "Returns the year of the audio file taken from the file's metadata"),
+    describe(Field::Genre, &["mp3_genre", "genre"], "audio", "Returns the genre of the audio file taken from the file's metadata"),
+    describe(Field::VideoTitle, &["video_title"], "video", "Returns the title of the MKV or MP4 video file taken from the file's metadata"),
+    describe(Field::Encoder, &["encoder", "video_encoder"], "video", "Returns the name of the application that encoded the MKV or MP4 video file"),
+    describe(Field::VideoCreated, &["video_created", "video_creation_date"], "video", "Returns the creation date embedded into the MKV or MP4 video file"),
+    describe(Field::AudioTracks, &["audio_tracks"], "video", "Returns the number of audio tracks in the MKV or MP4 video file"),
+    describe(Field::SubtitleTracks, &["subtitle_tracks", "subtitles"], "video", "Returns the number of subtitle tracks in the MKV or MP4 video file"),
+    describe(Field::SubtitleLangs, &["subtitle_langs", "subtitle_languages"], "video", "Returns the comma-separated languages of subtitle tracks in the MKV or MP4 video file"),
+    describe(Field::TorrentInfoHash, &["torrent_info_hash", "info_hash"], "torrent", "Returns the SHA-1 info hash of the .torrent file"),
+    describe(Field::TorrentPieceCount, &["torrent_piece_count", "torrent_pieces"], "torrent", "Returns the number of pieces described by the .torrent file"),
+    describe(Field::TorrentTotalSize, &["torrent_total_size", "torrent_size"], "torrent", "Returns the total size in bytes of the payload described by the .torrent file"),
+    describe(Field::TorrentFilesCount, &["torrent_files_count", "torrent_files"], "torrent", "Returns the number of payload files described by the .torrent file"),
+    describe(Field::ExifDateTime, &["exif_datetime"], "exif", "Returns date and time of taken photo"),
+    describe(Field::ExifGpsAltitude, &["exif_altitude", "exif_alt"], "exif", "Returns GPS altitude of taken photo"),
+    describe(Field::ExifGpsLatitude, &["exif_latitude", "exif_lat"], "exif", "Returns GPS latitude of taken photo"),
+    describe(Field::ExifGpsLongitude, &["exif_longitude", "exif_lon", "exif_lng"], "exif", "Returns GPS longitude of taken photo"),
+    describe(Field::ExifMake, &["exif_make"], "exif", "Returns name of the camera manufacturer"),
+    describe(Field::ExifModel, &["exif_model"], "exif", "Returns camera model"),
+    describe(Field::ExifSoftware, &["exif_software"], "exif", "Returns software name with which the photo was taken"),
+    describe(Field::ExifVersion, &["exif_version"], "exif", "Returns the version of EXIF metadata"),
+    describe(Field::Mime, &["mime"], "content", "Returns MIME type of the file"),
+    describe(Field::LineCount, &["line_count"], "content", "Returns a number of lines in a text file (.gz and .zst files are decompressed)"),
+    describe(Field::CodeLines, &["code_lines"], "content", "Returns a number of lines of code in a source file, without comments and blank lines"),
+    describe(Field::CommentLines, &["comment_lines"], "content", "Returns a number of comment lines in a source file"),
+    describe(Field::BlankLines, &["blank_lines"], "content", "Returns a number of blank lines in a source file"),
+    describe(Field::License, &["license"], "content", "Returns the SPDX identifier of the license found in the SPDX tag of the file, or in LICENSE and README files"),
+    describe(Field::IsBinary, &["is_binary"], "content", "Returns a boolean signifying whether the file has binary contents"),
+    describe(Field::IsText, &["is_text"], "content", "Returns a boolean signifying whether the file has text contents"),
+    describe(Field::IsArchive, &["is_archive"], "type", "Returns a boolean signifying whether the file is an archival file"),
+    describe(Field::IsValidArchive, &["is_valid_archive"], "content", "Returns a boolean signifying whether the archive can be read through and its checksums match"),
+    describe(Field::InArchive, &["in_archive"], "file", "Returns a boolean signifying whether the file is found within an archive"),
+    describe(Field::ArchivePath, &["archive_path"], "file", "Returns the path of the archive containing the file"),
+    describe(Field::IsAudio, &["is_audio"], "type", "Returns a boolean signifying whether the file is an audio file"),
+    describe(Field::IsBook, &["is_book"], "type", "Returns a boolean signifying whether the file is a book"),
+    describe(Field::IsDoc, &["is_doc"], "type", "Returns a boolean signifying whether the file is a document"),
+    describe(Field::IsFont, &["is_font"], "type", "Returns a boolean signifying whether the file is a font file"),
+    describe(Field::IsImage, &["is_image"], "type", "Returns a boolean signifying whether the file is an image"),
+    describe(Field::IsCorruptedImage, &["is_corrupted_image"], "content", "Returns a boolean signifying whether the JPEG or PNG file is truncated or malformed"),
+    describe(Field::IsEncrypted, &["is_encrypted"], "content", "Returns a boolean signifying whether the file is encrypted: PGP, age, LUKS, zip, or Office"),
+    describe(Field::IsSource, &["is_source"], "type", "Returns a boolean signifying whether the file is source code"),
+    describe(Field::IsScript, &["is_script"], "type", "Returns a boolean signifying whether the file is a script, by extension or shebang"),
+    describe(Field::IsVideo, &["is_video"], "type", "Returns a boolean signifying whether the file is a video file"),
+    describe(Field::Sha1, &["sha1"], "hash", "Returns SHA-1 digest of a file"),
+    describe(Field::Sha256, &["sha2_256", "sha256"], "hash", "Returns SHA2-256 digest of a file"),
+    describe(Field::Sha512, &["sha2_512", "sha512"], "hash", "Returns SHA2-512 digest of a file"),
+    describe(Field::Sha3, &["sha3_512", "sha3"], "hash", "Returns SHA-3 digest of a file"),
+    describe(Field::TrashOriginalPath, &["trash_original_path", "original_path"], "trash", "Returns the path the trashed file was deleted from"),
+    describe(Field::TrashDeleted, &["trash_deleted", "deleted"], "trash", "Returns the time the file was moved to the trash"),
+];
+
 impl FromStr for Field {
     type Err = String;
 
@@ -108,6 +333,8 @@ impl FromStr for Field {
             "absdir" => Ok(Field::AbsDir),
             "size" => Ok(Field::Size),
             "fsize" | "hsize" => Ok(Field::FormattedSize),
+            "dir_size" => Ok(Field::DirSize),
+            "size_share" => Ok(Field::SizeShare),
             "uid" => Ok(Field::Uid),
             "gid" => Ok(Field::Gid),
             #[cfg(all(unix, feature = "users"))]
@@ -115,8 +342,11 @@ impl FromStr for Field {
             #[cfg(all(unix, feature = "users"))]
             "group" => Ok(Field::Group),
             "created" => Ok(Field::Created),
+            "has_birth_time" => Ok(Field::HasBirthTime),
             "accessed" => Ok(Field::Accessed),
             "modified" => Ok(Field::Modified),
+            "newest_child_modified" => Ok(Field::NewestChildModified),
+            "oldest_child_modified" => Ok(Field::OldestChildModified),
             "is_dir" => Ok(Field::IsDir),
             "is_file" => Ok(Field::IsFile),
             "is_symlink" => Ok(Field::IsSymlink),
@@ -125,6 +355,7 @@ impl FromStr for Field {
             "is_block" => Ok(Field::IsBlockDevice),
             "is_socket" => Ok(Field::IsSocket),
             "device" => Ok(Field::Device),
+            "mount_id" => Ok(Field::MountId),
             "inode" => Ok(Field::Inode),
             "blocks" => Ok(Field::Blocks),
             "hardlinks" => Ok(Field::Hardlinks),
@@ -144,14 +375,47 @@ impl FromStr for Field {
             "suid" => Ok(Field::Suid),
             "sgid" => Ok(Field::Sgid),
             "is_hidden" => Ok(Field::IsHidden),
+            "is_git_root" => Ok(Field::IsGitRoot),
+            "is_hg_root" => Ok(Field::IsHgRoot),
+            "is_generated" => Ok(Field::IsGenerated),
+            "is_vendored" => Ok(Field::IsVendored),
+            "hg_tracked" => Ok(Field::HgTracked),
+            "hg_modified" => Ok(Field::HgModified),
+            "svn_versioned" => Ok(Field::SvnVersioned),
+            "project_type" | "project" => Ok(Field::ProjectType),
+            "is_valid_utf8_name" => Ok(Field::IsValidUtf8Name),
+            "has_windows_reserved_name" => Ok(Field::HasWindowsReservedName),
+            "has_control_chars" => Ok(Field::HasControlChars),
             "has_xattrs" => Ok(Field::HasXattrs),
             "capabilities" | "caps" => Ok(Field::Capabilities),
+            "is_immutable" => Ok(Field::IsImmutable),
+            "is_append_only" => Ok(Field::IsAppendOnly),
+            "dax" => Ok(Field::Dax),
+            "verity" | "has_verity" => Ok(Field::Verity),
+            "ima_hash" => Ok(Field::ImaHash),
+            "is_open" => Ok(Field::IsOpen),
+            #[cfg(all(target_os = "linux", feature = "processes"))]
+            "open_by_pids" => Ok(Field::OpenByPids),
+            #[cfg(all(target_os = "linux", feature = "processes"))]
+            "open_by_process_names" => Ok(Field::OpenByProcessNames),
             "is_shebang" => Ok(Field::IsShebang),
             "is_empty" => Ok(Field::IsEmpty),
+            "is_recursively_empty" => Ok(Field::IsRecursivelyEmpty),
+            "child_count" | "children" => Ok(Field::ChildCount),
+            "subdir_count" | "subdirs" => Ok(Field::SubdirCount),
+            "file_count" | "files" => Ok(Field::FileCount),
             "width" => Ok(Field::Width),
             "height" => Ok(Field::Height),
+            #[cfg(feature = "image-colors")]
+            "dominant_color" => Ok(Field::DominantColor),
+            #[cfg(feature = "image-colors")]
+            "brightness" => Ok(Field::Brightness),
             "mime" => Ok(Field::Mime),
             "line_count" => Ok(Field::LineCount),
+            "code_lines" => Ok(Field::CodeLines),
+            "comment_lines" => Ok(Field::CommentLines),
+            "blank_lines" => Ok(Field::BlankLines),
+            "license" => Ok(Field::License),
             "duration" => Ok(Field::Duration),
             "mp3_bitrate" | "bitrate" => Ok(Field::Bitrate),
             "mp3_freq" | "freq" => Ok(Field::Freq),
@@ -160,6 +424,16 @@ impl FromStr for Field {
             "mp3_album" | "album" => Ok(Field::Album),
             "mp3_year" => Ok(Field::Year),
             "mp3_genre" | "genre" => Ok(Field::Genre),
+            "video_title" => Ok(Field::VideoTitle),
+            "encoder" | "video_encoder" => Ok(Field::Encoder),
+            "video_created" | "video_creation_date" => Ok(Field::VideoCreated),
+            "audio_tracks" => Ok(Field::AudioTracks),
+            "subtitle_tracks" | "subtitles" => Ok(Field::SubtitleTracks),
+            "subtitle_langs" | "subtitle_languages" => Ok(Field::SubtitleLangs),
+            "torrent_info_hash" | "info_hash" => Ok(Field::TorrentInfoHash),
+            "torrent_piece_count" | "torrent_pieces" => Ok(Field::TorrentPieceCount),
+            "torrent_total_size" | "torrent_size" => Ok(Field::TorrentTotalSize),
+            "torrent_files_count" | "torrent_files" => Ok(Field::TorrentFilesCount),
             "exif_altitude" | "exif_alt" => Ok(Field::ExifGpsAltitude),
             "exif_datetime" => Ok(Field::ExifDateTime),
             "exif_latitude" | "exif_lat" => Ok(Field::ExifGpsLatitude),
@@ -171,17 +445,25 @@ impl FromStr for Field {
             "is_binary" => Ok(Field::IsBinary),
             "is_text" => Ok(Field::IsText),
             "is_archive" => Ok(Field::IsArchive),
+            "is_valid_archive" => Ok(Field::IsValidArchive),
+            "in_archive" => Ok(Field::InArchive),
+            "archive_path" => Ok(Field::ArchivePath),
             "is_audio" => Ok(Field::IsAudio),
             "is_book" => Ok(Field::IsBook),
             "is_doc" => Ok(Field::IsDoc),
             "is_font" => Ok(Field::IsFont),
             "is_image" => Ok(Field::IsImage),
+            "is_corrupted_image" => Ok(Field::IsCorruptedImage),
+            "is_encrypted" => Ok(Field::IsEncrypted),
             "is_source" => Ok(Field::IsSource),
+            "is_script" => Ok(Field::IsScript),
             "is_video" => Ok(Field::IsVideo),
             "sha1" => Ok(Field::Sha1),
             "sha2_256" | "sha256" => Ok(Field::Sha256),
             "sha2_512" | "sha512" => Ok(Field::Sha512),
             "sha3_512" | "sha3" => Ok(Field::Sha3),
+            "trash_original_path" | "original_path" => Ok(Field::TrashOriginalPath),
+            "trash_deleted" | "deleted" => Ok(Field::TrashDeleted),
             _ => {
                 let err = String::from("Unknown field ") + &field;
                 Err(err)
@@ -208,11 +490,19 @@ impl Serialize for Field {
 impl Field {
     #[rustfmt::skip]
     pub fn is_numeric_field(&self) -> bool {
-        matches!(self, Field::Size | Field::FormattedSize
-            | Field::Uid | Field::Gid
+        #[cfg(feature = "image-colors")]
+        if self == &Field::Brightness {
+            return true;
+        }
+
+        matches!(self, Field::Size | Field::FormattedSize | Field::DirSize | Field::SizeShare
+            | Field::Uid | Field::Gid | Field::MountId
+            | Field::ChildCount | Field::SubdirCount | Field::FileCount
             | Field::Width | Field::Height
-            | Field::LineCount
+            | Field::LineCount | Field::CodeLines | Field::CommentLines | Field::BlankLines
             | Field::Duration
+            | Field::AudioTracks | Field::SubtitleTracks
+            | Field::TorrentPieceCount | Field::TorrentTotalSize | Field::TorrentFilesCount
             | Field::Bitrate | Field::Freq | Field::Year
             | Field::ExifGpsLatitude | Field::ExifGpsLongitude | Field::ExifGpsAltitude)
     }
@@ -220,7 +510,14 @@ impl Field {
     pub fn is_datetime_field(&self) -> bool {
         matches!(
             self,
-            Field::Created | Field::Accessed | Field::Modified | Field::ExifDateTime
+            Field::Created
+                | Field::Accessed
+                | Field::Modified
+                | Field::NewestChildModified
+                | Field::OldestChildModified
+                | Field::ExifDateTime
+                | Field::VideoCreated
+                | Field::TrashDeleted
         )
     }
 
@@ -249,18 +546,40 @@ impl Field {
                 | Field::IsBlockDevice
                 | Field::IsSocket
                 | Field::IsHidden
+                | Field::IsGitRoot
+                | Field::IsHgRoot
+                | Field::IsGenerated
+                | Field::IsVendored
+                | Field::HgTracked
+                | Field::HgModified
+                | Field::SvnVersioned
+                | Field::IsValidUtf8Name
+                | Field::HasWindowsReservedName
+                | Field::HasControlChars
                 | Field::HasXattrs
+                | Field::IsImmutable
+                | Field::IsAppendOnly
+                | Field::Dax
+                | Field::Verity
+                | Field::IsOpen
                 | Field::IsEmpty
+                | Field::IsRecursivelyEmpty
                 | Field::IsShebang
+                | Field::HasBirthTime
                 | Field::IsBinary
                 | Field::IsText
                 | Field::IsArchive
+                | Field::IsValidArchive
+                | Field::InArchive
                 | Field::IsAudio
                 | Field::IsBook
                 | Field::IsDoc
                 | Field::IsFont
                 | Field::IsImage
+                | Field::IsCorruptedImage
+                | Field::IsEncrypted
                 | Field::IsSource
+                | Field::IsScript
                 | Field::IsVideo
         )
     }
@@ -299,20 +618,156 @@ impl Field {
                 | Field::Suid
                 | Field::Sgid
                 | Field::IsHidden
+                | Field::IsValidUtf8Name
+                | Field::HasWindowsReservedName
+                | Field::HasControlChars
                 | Field::IsEmpty
+                | Field::Created
+                | Field::HasBirthTime
+                | Field::Accessed
                 | Field::Modified
                 | Field::IsArchive
+                | Field::InArchive
+                | Field::ArchivePath
                 | Field::IsAudio
                 | Field::IsBook
                 | Field::IsDoc
                 | Field::IsFont
                 | Field::IsImage
                 | Field::IsSource
+                | Field::IsScript
                 | Field::IsVideo
+                | Field::LineCount
+                | Field::Sha1
+                | Field::Sha256
+                | Field::Sha512
+                | Field::Sha3
         )
     }
 
+    /// Fields taking few distinct values over many files, their values are shared when the rows are buffered
+    pub fn has_few_values(&self) -> bool {
+        #[cfg(all(unix, feature = "users"))]
+        if matches!(self, Field::User | Field::Group) {
+            return true;
+        }
+
+        self.is_boolean_field()
+            || matches!(
+                self,
+                Field::Extension
+                    | Field::Mime
+                    | Field::Uid
+                    | Field::Gid
+                    | Field::Mode
+                    | Field::Device
+                    | Field::ProjectType
+                    | Field::License
+            )
+    }
+
+    /// Fields with values that change over time even for files that stay the same
+    pub fn is_volatile(&self) -> bool {
+        #[cfg(all(target_os = "linux", feature = "processes"))]
+        if matches!(self, Field::OpenByPids | Field::OpenByProcessNames) {
+            return true;
+        }
+
+        matches!(self, Field::IsOpen)
+    }
+
     pub fn is_colorized_field(&self) -> bool {
         matches!(self, Field::Name)
     }
+
+    /// Returns the type of the values of the field: numeric, datetime, boolean, or string
+    pub fn get_type(&self) -> &'static str {
+        if self.is_numeric_field() {
+            "numeric"
+        } else if self.is_datetime_field() {
+            "datetime"
+        } else if self.is_boolean_field() {
+            "boolean"
+        } else {
+            "string"
+        }
+    }
+
+    /// Returns the relative cost of getting the value, mostly by the group of the field
+    pub fn get_cost(&self) -> Cost {
+        match self {
+            Field::Name
+            | Field::Path
+            | Field::Extension
+            | Field::Directory
+            | Field::IsValidUtf8Name
+            | Field::HasWindowsReservedName
+            | Field::HasControlChars => return Cost::Name,
+            Field::Mime | Field::IsBinary | Field::IsText => return Cost::Mime,
+            Field::DirSize
+            | Field::SizeShare
+            | Field::NewestChildModified
+            | Field::OldestChildModified
+            | Field::IsShebang
+            | Field::IsGenerated
+            | Field::IsRecursivelyEmpty
+            | Field::ChildCount
+            | Field::SubdirCount
+            | Field::FileCount => return Cost::Content,
+            // The whole image is decoded to sample its colors
+            #[cfg(feature = "image-colors")]
+            Field::DominantColor | Field::Brightness => return Cost::Content,
+            _ => {}
+        }
+
+        match self.get_group() {
+            "image" | "audio" | "video" | "exif" | "torrent" => Cost::Dimensions,
+            "content" | "hash" => Cost::Content,
+            _ => Cost::Metadata,
+        }
+    }
+
+    /// Returns the group the field is listed in, e.g. `file` or `exif`
+    pub fn get_group(&self) -> &'static str {
+        FIELDS
+            .iter()
+            .find(|info| info.field == *self)
+            .map_or("", |info| info.group)
+    }
+
+    /// Returns the platform the field is limited to, if any
+    pub fn get_platform(&self) -> Option<&'static str> {
+        match self {
+            #[cfg(all(unix, feature = "users"))]
+            Field::User | Field::Group => Some("unix"),
+            Field::Device | Field::Inode | Field::Blocks | Field::Hardlinks | Field::HasXattrs => {
+                Some("unix")
+            }
+            Field::Capabilities
+            | Field::IsImmutable
+            | Field::IsAppendOnly
+            | Field::Dax
+            | Field::Verity
+            | Field::ImaHash
+            | Field::IsOpen
+            | Field::MountId => Some("linux"),
+            #[cfg(all(target_os = "linux", feature = "processes"))]
+            Field::OpenByPids | Field::OpenByProcessNames => Some("linux"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fields_names_parse() {
+        for info in FIELDS {
+            for name in info.names {
+                assert_eq!(Field::from_str(name), Ok(info.field), "{}", name);
+            }
+        }
+    }
 }
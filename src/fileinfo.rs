@@ -1,17 +1,70 @@
-use zip::DateTime;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Read;
+use std::rc::Rc;
+
+use chrono::NaiveDateTime;
+use zip::extra_fields::ExtraField;
+use zip::ZipArchive;
+
+use crate::util::{from_unix_timestamp, to_local_datetime};
+
+/// Zip archive shared by the files found within it, so their contents can be read on demand
+pub type Archive = Rc<RefCell<ZipArchive<File>>>;
 
 pub struct FileInfo {
     pub name: String,
     pub size: u64,
     pub mode: Option<u32>,
-    pub modified: Option<DateTime>,
+    pub modified: Option<NaiveDateTime>,
+    pub accessed: Option<NaiveDateTime>,
+    pub created: Option<NaiveDateTime>,
+    archive: Archive,
+    index: usize,
 }
 
-pub fn to_file_info(zipped_file: &zip::read::ZipFile) -> FileInfo {
+impl FileInfo {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/') || self.name.ends_with('\\')
+    }
+
+    /// Decompresses the file from the archive and passes its contents to the reader function.
+    /// Returns None for directories or when the file can't be extracted.
+    pub fn read_contents<T>(&self, read: impl FnOnce(&mut dyn Read) -> T) -> Option<T> {
+        if self.is_dir() {
+            return None;
+        }
+
+        let mut archive = self.archive.try_borrow_mut().ok()?;
+        let mut zipped_file = archive.by_index(self.index).ok()?;
+
+        Some(read(&mut zipped_file))
+    }
+}
+
+pub fn to_file_info(zipped_file: &zip::read::ZipFile, archive: &Archive, index: usize) -> FileInfo {
+    let mut modified = zipped_file.last_modified().and_then(|dt| to_local_datetime(&dt));
+    let mut accessed = None;
+    let mut created = None;
+
+    // The extended timestamp field is more precise than the DOS time and carries atime/ctime too
+    for extra_field in zipped_file.extra_data_fields() {
+        let ExtraField::ExtendedTimestamp(timestamp) = extra_field;
+        if let Some(mod_time) = timestamp.mod_time().and_then(|t| from_unix_timestamp(t as i64)) {
+            modified = Some(mod_time);
+        }
+        accessed = timestamp.ac_time().and_then(|t| from_unix_timestamp(t as i64));
+        created = timestamp.cr_time().and_then(|t| from_unix_timestamp(t as i64));
+    }
+
     FileInfo {
         name: zipped_file.name().to_string(),
         size: zipped_file.size(),
         mode: zipped_file.unix_mode(),
-        modified: zipped_file.last_modified(),
+        modified,
+        accessed,
+        created,
+        archive: Rc::clone(archive),
+        index,
     }
 }
@@ -1,7 +1,6 @@
 //! Functions for processing values in the query language.
 //! This module contains both the regular and aggregate functions used in the query language.
 
-use std::collections::HashMap;
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
@@ -10,6 +9,8 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::str::FromStr;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 use chrono::Datelike;
 use chrono::Local;
@@ -20,11 +21,18 @@ use serde::ser::{Serialize, Serializer};
 #[cfg(unix)]
 use xattr::FileExt;
 
+use crate::expr::Cost;
 use crate::fileinfo::FileInfo;
-use crate::util::{capitalize, error_exit, format_date, format_datetime};
-use crate::util::{parse_filesize, parse_datetime, str_to_bool};
-
-#[derive(Clone, Debug)]
+use crate::util::code_metrics::count_marked_lines;
+use crate::util::structured_data::{get_json_value, is_valid_json, is_valid_toml, is_valid_yaml};
+use crate::util::xpath::get_xpath_value;
+use crate::util::{
+    capitalize, format_date, format_datetime, get_file_hash_fn, is_valid_hex_digest,
+    open_content_reader, BufferedRow, DeadlineReader,
+};
+use crate::util::{parse_filesize, parse_datetime, parse_interval, str_to_bool};
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum VariantType {
     String,
     Int,
@@ -207,17 +215,12 @@ impl Variant {
         }
     }
 
-    pub fn to_datetime(&self) -> (NaiveDateTime, NaiveDateTime) {
-        if self.dt_from.is_none() {
-            match parse_datetime(&self.string_value) {
-                Ok((dt_from, dt_to)) => {
-                    return (dt_from, dt_to);
-                }
-                _ => error_exit("Can't parse datetime", &self.string_value),
-            }
+    /// Returns nothing for values that are not dates, the query checks the constant ones before the search
+    pub fn to_datetime(&self) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        match (self.dt_from, self.dt_to) {
+            (Some(dt_from), Some(dt_to)) => Some((dt_from, dt_to)),
+            _ => parse_datetime(&self.string_value).ok(),
         }
-
-        (self.dt_from.unwrap(), self.dt_to.unwrap())
     }
 }
 
@@ -243,6 +246,10 @@ pub enum Function {
     ToBase64,
     /// Read the value as base64
     FromBase64,
+    /// Transliterate the value into ASCII
+    Transliterate,
+    /// Make a lowercase ASCII slug of the value, keeping the file extension
+    Slugify,
 
     //  String manipulation functions
     /// Concatenate the value with the arguments
@@ -297,6 +304,10 @@ pub enum Function {
     FormatSize,
     /// Format a time duration in human-readable format
     FormatTime,
+    /// Format a number of seconds as hours, minutes, and seconds
+    FormatDuration,
+    /// Get the label of the size range the value falls into
+    SizeBucket,
 
     //  Date and time functions
     /// Get the current date
@@ -309,6 +320,8 @@ pub enum Function {
     Year,
     /// Get the day of the week from a date
     DayOfWeek,
+    /// Get the label of the age range the date falls into
+    AgeBucket,
 
     //  File functions
     #[cfg(all(unix, feature = "users"))]
@@ -326,6 +339,25 @@ pub enum Function {
 
     /// Checks if a file contains a substring
     Contains,
+    /// Checks if the file hash computed with the given algorithm equals the expected value
+    HashEquals,
+    /// Checks if the file has been written to within the given interval
+    GrewSince,
+    #[cfg(feature = "secrets")]
+    /// Checks if the file contains credentials or private keys
+    HasSecrets,
+    /// Counts the lines of the file with TODO, FIXME, or other markers
+    TodoCount,
+    /// Checks if the file is a well-formed JSON document
+    IsValidJson,
+    /// Checks if the file is a well-formed YAML document
+    IsValidYaml,
+    /// Checks if the file is a well-formed TOML document
+    IsValidToml,
+    /// Extracts the value at the JSONPath from the JSON file
+    JsonValue,
+    /// Extracts the text of the element or attribute at the path from the XML file
+    XPath,
 
     #[cfg(unix)]
     /// Check if the file has a specific extended attribute
@@ -366,8 +398,123 @@ pub enum Function {
     VarPop,
     /// Get the sample variance
     VarSamp,
+    /// Collect all values into an array
+    ArrayAgg,
+
+    // ===== Window functions =====
+    /// Get the sum of the values of the row and all the preceding rows
+    RunningSum,
+    /// Get the share of the value in the total of all rows, in percents
+    PercentOfTotal,
 }
 
+/// Name, aliases, group, and description of a function, as listed by `--functions`
+pub struct FunctionInfo {
+    pub function: Function,
+    /// Canonical name first, followed by the aliases
+    pub names: &'static [&'static str],
+    pub group: &'static str,
+    pub description: &'static str,
+}
+
+const fn describe(
+    function: Function,
+    names: &'static [&'static str],
+    group: &'static str,
+    description: &'static str,
+) -> FunctionInfo {
+    FunctionInfo {
+        function,
+        names,
+        group,
+        description,
+    }
+}
+
+#[rustfmt::skip]
+pub const FUNCTIONS: &[FunctionInfo] = &[
+    describe(Function::Lower, &["lower", "lowercase", "lcase"], "string", "Returns lowercase value"),
+    describe(Function::Upper, &["upper", "uppercase", "ucase"], "string", "Returns uppercase value"),
+    describe(Function::InitCap, &["initcap"], "string", "Returns first letter of each word uppercase, all other letters lowercase"),
+    describe(Function::Length, &["length", "len"], "string", "Returns length of string value"),
+    describe(Function::ToBase64, &["to_base64", "base64"], "string", "Returns Base64 digest of a value"),
+    describe(Function::FromBase64, &["from_base64"], "string", "Returns decoded value from a Base64 digest"),
+    describe(Function::Transliterate, &["transliterate", "translit"], "string", "Returns value transliterated into ASCII, kana is converted to romaji"),
+    describe(Function::Slugify, &["slugify", "slug"], "string", "Returns lowercase ASCII slug of a value with words separated by dashes, keeping the file extension"),
+    describe(Function::Concat, &["concat"], "other", "Returns concatenated string of expression values"),
+    describe(Function::ConcatWs, &["concat_ws"], "other", "Returns concatenated string of expression values with specified delimiter"),
+    describe(Function::Substring, &["substring", "substr"], "string", "Returns part of the string value"),
+    describe(Function::Replace, &["replace"], "string", "Returns string with substring replaced with another one"),
+    describe(Function::Trim, &["trim"], "string", "Returns string with whitespaces at the beginning and the end stripped"),
+    describe(Function::LTrim, &["ltrim"], "string", "Returns string with whitespaces at the beginning stripped"),
+    describe(Function::RTrim, &["rtrim"], "string", "Returns string with whitespaces at the end stripped"),
+    describe(Function::Bin, &["bin"], "other", "Returns binary representation of an integer value"),
+    describe(Function::Hex, &["hex"], "other", "Returns hexadecimal representation of an integer value"),
+    describe(Function::Oct, &["oct"], "other", "Returns octal representation of an integer value"),
+    describe(Function::Abs, &["abs"], "other", "Returns absolute value of the number"),
+    describe(Function::Power, &["power", "pow"], "other", "Raise the value to the specified power"),
+    describe(Function::Sqrt, &["sqrt"], "other", "Returns square root of the value"),
+    describe(Function::Log, &["log"], "other", "Returns logarithm of the value"),
+    describe(Function::Ln, &["ln"], "other", "Returns natural logarithm of the value"),
+    describe(Function::Exp, &["exp"], "other", "Returns e raised to the power of the value"),
+    describe(Function::ContainsJapanese, &["contains_japanese", "japanese"], "japanese", "Used to check if string value contains Japanese symbols"),
+    describe(Function::ContainsHiragana, &["contains_hiragana", "hiragana"], "japanese", "Used to check if string value contains hiragana symbols"),
+    describe(Function::ContainsKatakana, &["contains_katakana", "katakana"], "japanese", "Used to check if string value contains katakana symbols"),
+    describe(Function::ContainsKana, &["contains_kana", "kana"], "japanese", "Used to check if string value contains kana symbols"),
+    describe(Function::ContainsKanji, &["contains_kanji", "kanji"], "japanese", "Used to check if string value contains kanji symbols"),
+    describe(Function::FormatSize, &["format_size", "format_filesize"], "other", "Returns formatted size of a file"),
+    describe(Function::FormatTime, &["format_time", "pretty_time"], "other", "Returns human-readable durations of time in seconds"),
+    describe(Function::FormatDuration, &["format_duration"], "other", "Returns durations of time in seconds as hours, minutes, and seconds (h:mm:ss)"),
+    describe(Function::SizeBucket, &["size_bucket"], "other", "Returns the size range of the value among the given bounds, e.g. 1k-1m for SIZE_BUCKET(size, '1k,1m,1g')"),
+    describe(Function::CurrentDate, &["current_date", "cur_date", "curdate"], "date", "Returns current date"),
+    describe(Function::Day, &["day"], "date", "Returns day of the month"),
+    describe(Function::Month, &["month"], "date", "Returns month of the year"),
+    describe(Function::Year, &["year"], "date", "Returns year of the date"),
+    describe(Function::DayOfWeek, &["dow", "dayofweek"], "date", "Returns day of the week (1 - Sunday, 2 - Monday, etc.)"),
+    describe(Function::AgeBucket, &["age_bucket"], "date", "Returns the age range of the date among the given bounds, e.g. 7d-30d for AGE_BUCKET(modified, '1d,7d,30d')"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Function::CurrentUid, &["current_uid"], "user", "Returns the current real UID"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Function::CurrentUser, &["current_user"], "user", "Returns the current username"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Function::CurrentGid, &["current_gid"], "user", "Returns the current primary GID"),
+    #[cfg(all(unix, feature = "users"))]
+    describe(Function::CurrentGroup, &["current_group"], "user", "Returns the current primary groupname"),
+    describe(Function::Contains, &["contains"], "other", "Returns true, if file contains string, false if not (.gz and .zst files are decompressed)"),
+    describe(Function::HashEquals, &["hash_equals", "hash_eq"], "other", "Returns true, if file hash computed with the algorithm (sha1, sha256, sha512, sha3) equals the value"),
+    describe(Function::GrewSince, &["grew_since"], "other", "Returns true, if file has been written to within the interval (e.g. 10s, 5min, 1h), i.e. it's being actively written"),
+    describe(Function::IsValidJson, &["is_valid_json"], "other", "Returns true, if file is a well-formed JSON document"),
+    describe(Function::IsValidYaml, &["is_valid_yaml"], "other", "Returns true, if file is a well-formed YAML document"),
+    describe(Function::IsValidToml, &["is_valid_toml"], "other", "Returns true, if file is a well-formed TOML document"),
+    describe(Function::JsonValue, &["json_value"], "other", "Returns the value at the JSONPath like $.version from the JSON file"),
+    describe(Function::XPath, &["xpath"], "other", "Returns the text of the element or attribute at the path like /project/version from the XML file"),
+    describe(Function::TodoCount, &["todo_count"], "other", "Returns the number of lines with TODO, FIXME, HACK, or XXX markers, or with the comma-separated markers given"),
+    #[cfg(feature = "secrets")]
+    describe(Function::HasSecrets, &["has_secrets"], "other", "Returns true, if file contains AWS keys, private keys, or API tokens of well-known services"),
+    #[cfg(unix)]
+    describe(Function::HasXattr, &["has_xattr"], "xattr", "Used to check if xattr exists"),
+    #[cfg(unix)]
+    describe(Function::Xattr, &["xattr"], "xattr", "Returns value of xattr"),
+    #[cfg(target_os = "linux")]
+    describe(Function::HasCapabilities, &["has_capabilities", "has_caps"], "xattr", "Check if any Linux capability exists for the file"),
+    #[cfg(target_os = "linux")]
+    describe(Function::HasCapability, &["has_capability", "has_cap"], "xattr", "Check if given Linux capability exists for the file"),
+    describe(Function::Coalesce, &["coalesce"], "other", "Returns first nonempty expression value"),
+    describe(Function::Random, &["random", "rand"], "other", "Returns random integer (from zero to max int, from zero to arg, or from arg1 to arg2)"),
+    describe(Function::Min, &["min"], "aggregate", "Returns minimum value"),
+    describe(Function::Max, &["max"], "aggregate", "Returns maximum value"),
+    describe(Function::Avg, &["avg"], "aggregate", "Returns average of all values"),
+    describe(Function::Sum, &["sum"], "aggregate", "Returns sum of all values"),
+    describe(Function::Count, &["count"], "aggregate", "Returns number of all values"),
+    describe(Function::StdDevPop, &["stddev_pop", "stddev", "std"], "aggregate", "Population standard deviation, the square root of variance"),
+    describe(Function::StdDevSamp, &["stddev_samp"], "aggregate", "Sample standard deviation, the square root of sample variance"),
+    describe(Function::VarPop, &["var_pop", "variance"], "aggregate", "Population variance"),
+    describe(Function::VarSamp, &["var_samp"], "aggregate", "Sample variance"),
+    describe(Function::ArrayAgg, &["array_agg"], "aggregate", "Returns all values as an array (a JSON array with JSON output)"),
+    describe(Function::RunningSum, &["running_sum"], "window", "Returns sum of the values of the row and all the rows before it"),
+    describe(Function::PercentOfTotal, &["percent_of_total"], "window", "Returns percentage of the value in the total of all rows"),
+];
+
 impl FromStr for Function {
     type Err = String;
 
@@ -381,6 +528,8 @@ impl FromStr for Function {
             "initcap" => Ok(Function::InitCap),
             "to_base64" | "base64" => Ok(Function::ToBase64),
             "from_base64" => Ok(Function::FromBase64),
+            "transliterate" | "translit" => Ok(Function::Transliterate),
+            "slugify" | "slug" => Ok(Function::Slugify),
             "bin" => Ok(Function::Bin),
             "hex" => Ok(Function::Hex),
             "oct" => Ok(Function::Oct),
@@ -407,12 +556,15 @@ impl FromStr for Function {
             "coalesce" => Ok(Function::Coalesce),
             "format_size" | "format_filesize" => Ok(Function::FormatSize),
             "format_time" | "pretty_time" => Ok(Function::FormatTime),
+            "format_duration" => Ok(Function::FormatDuration),
 
             "current_date" | "cur_date" | "curdate" => Ok(Function::CurrentDate),
             "day" => Ok(Function::Day),
             "month" => Ok(Function::Month),
             "year" => Ok(Function::Year),
             "dayofweek" | "dow" => Ok(Function::DayOfWeek),
+            "age_bucket" => Ok(Function::AgeBucket),
+            "size_bucket" => Ok(Function::SizeBucket),
 
             #[cfg(all(unix, feature = "users"))]
             "current_uid" => Ok(Function::CurrentUid),
@@ -433,8 +585,19 @@ impl FromStr for Function {
             "stddev_samp" => Ok(Function::StdDevSamp),
             "var_pop" | "variance" => Ok(Function::VarPop),
             "var_samp" => Ok(Function::VarSamp),
+            "array_agg" => Ok(Function::ArrayAgg),
 
             "contains" => Ok(Function::Contains),
+            "hash_equals" | "hash_eq" => Ok(Function::HashEquals),
+            "grew_since" => Ok(Function::GrewSince),
+            #[cfg(feature = "secrets")]
+            "has_secrets" => Ok(Function::HasSecrets),
+            "todo_count" => Ok(Function::TodoCount),
+            "is_valid_json" => Ok(Function::IsValidJson),
+            "is_valid_yaml" => Ok(Function::IsValidYaml),
+            "is_valid_toml" => Ok(Function::IsValidToml),
+            "json_value" => Ok(Function::JsonValue),
+            "xpath" => Ok(Function::XPath),
 
             #[cfg(unix)]
             "has_xattr" => Ok(Function::HasXattr),
@@ -447,6 +610,9 @@ impl FromStr for Function {
 
             "rand" | "random" => Ok(Function::Random),
 
+            "running_sum" => Ok(Function::RunningSum),
+            "percent_of_total" => Ok(Function::PercentOfTotal),
+
             _ => {
                 let err = String::from("Unknown function ") + &function;
                 Err(err)
@@ -484,12 +650,30 @@ impl Function {
                 | Function::StdDevSamp
                 | Function::VarPop
                 | Function::VarSamp
+                | Function::ArrayAgg
+        )
+    }
+
+    /// Check if the function is computed over the whole ordered result set
+    pub fn is_window_function(&self) -> bool {
+        matches!(self, Function::RunningSum | Function::PercentOfTotal)
+    }
+
+    /// Check if the function returns different values over time or from call to call
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            Function::CurrentDate | Function::GrewSince | Function::AgeBucket | Function::Random
         )
     }
 
     /// Check if the function is a numeric function, i.e. it returns a numeric value.
     pub fn is_numeric_function(&self) -> bool {
         if self.is_aggregate_function() {
+            return self != &Function::ArrayAgg;
+        }
+
+        if self.is_window_function() {
             return true;
         }
 
@@ -506,6 +690,7 @@ impl Function {
                 | Function::Log
                 | Function::Ln
                 | Function::Exp
+                | Function::TodoCount
         )
     }
 
@@ -521,9 +706,19 @@ impl Function {
             return true;
         }
 
+        #[cfg(feature = "secrets")]
+        if self == &Function::HasSecrets {
+            return true;
+        }
+
         matches!(
             self,
             Function::Contains
+                | Function::HashEquals
+                | Function::GrewSince
+                | Function::IsValidJson
+                | Function::IsValidYaml
+                | Function::IsValidToml
                 | Function::ContainsHiragana
                 | Function::ContainsKatakana
                 | Function::ContainsKana
@@ -531,6 +726,116 @@ impl Function {
                 | Function::ContainsJapanese
         )
     }
+
+    /// Checks if the function reads the contents of the file, so that the content limits apply
+    pub fn reads_content(&self) -> bool {
+        #[cfg(feature = "secrets")]
+        if self == &Function::HasSecrets {
+            return true;
+        }
+
+        matches!(
+            self,
+            Function::Contains
+                | Function::HashEquals
+                | Function::TodoCount
+                | Function::IsValidJson
+                | Function::IsValidYaml
+                | Function::IsValidToml
+                | Function::JsonValue
+                | Function::XPath
+        )
+    }
+
+    /// Returns the relative cost of the function itself, not counting its arguments
+    pub fn get_cost(&self) -> Cost {
+        if self.reads_content() {
+            return Cost::Content;
+        }
+
+        match FUNCTIONS.iter().find(|info| info.function == *self).map(|info| info.group) {
+            Some("xattr") => Cost::Metadata,
+            _ if *self == Function::GrewSince => Cost::Metadata,
+            _ => Cost::Name,
+        }
+    }
+
+    /// Returns the type of the value returned by the function: numeric, boolean, or string
+    pub fn get_type(&self) -> &'static str {
+        if self.is_numeric_function() {
+            "numeric"
+        } else if self.is_boolean_function() {
+            "boolean"
+        } else {
+            "string"
+        }
+    }
+
+    /// Returns the platform the function is limited to, if any
+    pub fn get_platform(&self) -> Option<&'static str> {
+        match self {
+            #[cfg(all(unix, feature = "users"))]
+            Function::CurrentUid
+            | Function::CurrentUser
+            | Function::CurrentGid
+            | Function::CurrentGroup => Some("unix"),
+            #[cfg(unix)]
+            Function::HasXattr | Function::Xattr => Some("unix"),
+            #[cfg(target_os = "linux")]
+            Function::HasCapabilities | Function::HasCapability => Some("linux"),
+            _ => None,
+        }
+    }
+}
+
+/// Checks the arguments that are known before the search, i.e. given as constants,
+/// so that a query with unusable ones is refused instead of giving empty values for every file.
+///
+/// Args:
+///  function: The function to check the arguments for.
+///  function_arg: The first argument, None if it's computed for every file.
+///  function_args: Additional arguments, None for the ones computed for every file.
+pub fn check_args(
+    function: &Function,
+    function_arg: Option<&str>,
+    function_args: &[Option<&str>],
+) -> Result<(), String> {
+    let first_arg = function_args.first().copied().unwrap_or(Some(""));
+
+    match function {
+        Function::HashEquals => match function_arg {
+            Some(algorithm) if get_file_hash_fn(algorithm).is_none() => {
+                Err(format!("Unknown hash algorithm: {}", algorithm))
+            }
+            _ => Ok(()),
+        },
+        Function::GrewSince => match function_arg {
+            Some(interval) if parse_interval(interval).is_none() => {
+                Err(format!("Invalid interval: {}", interval))
+            }
+            _ => Ok(()),
+        },
+        Function::SizeBucket => match first_arg {
+            Some(bounds) if parse_size_bounds(bounds).is_none() => {
+                Err(format!("Invalid size buckets: {}", bounds))
+            }
+            _ => Ok(()),
+        },
+        Function::AgeBucket => match first_arg {
+            Some(bounds) if parse_age_bounds(bounds).is_none() => {
+                Err(format!("Invalid age buckets: {}", bounds))
+            }
+            _ => Ok(()),
+        },
+        Function::Random => match (function_arg, function_args.first()) {
+            (Some(function_arg), None) => get_random_range(function_arg, None).map(|_| ()),
+            (Some(function_arg), Some(Some(limit))) => {
+                get_random_range(function_arg, Some(&limit.to_string())).map(|_| ())
+            }
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
 }
 
 /// Applies a function to a value and returns the result.
@@ -551,6 +856,7 @@ pub fn get_value(
     function_args: Vec<String>,
     entry: Option<&DirEntry>,
     file_info: &Option<FileInfo>,
+    content_deadline: Option<Instant>,
 ) -> Variant {
     //* Refer to the Function enum for a list of available functions and their descriptions
     match function {
@@ -580,6 +886,12 @@ pub fn get_value(
                     .to_string(),
             )
         }
+        Some(Function::Transliterate) => {
+            Variant::from_string(&crate::util::transliteration::transliterate(&function_arg))
+        }
+        Some(Function::Slugify) => {
+            Variant::from_string(&crate::util::transliteration::slugify(&function_arg))
+        }
 
         // ===== String manipulation functions =====
         Some(Function::Concat) => {
@@ -709,7 +1021,8 @@ pub fn get_value(
                 return Variant::empty(VariantType::String);
             }
 
-            if let Ok(size) = function_arg.parse::<u64>() {
+            if let Ok(size) = function_arg.parse::<f64>() {
+                let size = size.max(0.0) as u64;
                 let modifier = match function_args.first() {
                     Some(modifier) => modifier,
                     _ => "",
@@ -725,9 +1038,30 @@ pub fn get_value(
                 return Variant::empty(VariantType::String);
             }
 
-            let seconds = function_arg.parse::<u64>().unwrap();
-            let formatted = Duration::from_secs(seconds).to_human_time_string();
-            Variant::from_string(&formatted)
+            match function_arg.parse::<f64>() {
+                Ok(seconds) => {
+                    let seconds = seconds.max(0.0) as u64;
+                    let formatted = Duration::from_secs(seconds).to_human_time_string();
+                    Variant::from_string(&formatted)
+                }
+                _ => Variant::empty(VariantType::String),
+            }
+        }
+        Some(Function::FormatDuration) => match function_arg.parse::<f64>() {
+            Ok(seconds) => Variant::from_string(&format_duration(seconds)),
+            _ => Variant::empty(VariantType::String),
+        },
+        Some(Function::SizeBucket) => {
+            let bounds = function_args.first().map(String::as_str).unwrap_or_default();
+            let bounds = match parse_size_bounds(bounds) {
+                Some(bounds) => bounds,
+                None => return Variant::empty(VariantType::String),
+            };
+
+            match function_arg.parse::<f64>() {
+                Ok(size) => Variant::from_string(&get_bucket(size as i64, &bounds)),
+                _ => Variant::empty(VariantType::String),
+            }
         }
 
         // ===== Datetime functions =====
@@ -751,6 +1085,21 @@ pub fn get_value(
             Ok(date) => Variant::from_int(date.0.weekday().number_from_sunday() as i64),
             _ => Variant::empty(VariantType::Int),
         },
+        Some(Function::AgeBucket) => {
+            let bounds = function_args.first().map(String::as_str).unwrap_or_default();
+            let bounds = match parse_age_bounds(bounds) {
+                Some(bounds) => bounds,
+                None => return Variant::empty(VariantType::String),
+            };
+
+            match parse_datetime(&function_arg) {
+                Ok(date) => {
+                    let age = Local::now().naive_local() - date.0;
+                    Variant::from_string(&get_bucket(age.num_seconds(), &bounds))
+                }
+                _ => Variant::empty(VariantType::String),
+            }
+        }
 
         // ===== File functions =====
         #[cfg(all(unix, feature = "users"))]
@@ -772,12 +1121,23 @@ pub fn get_value(
             }
         }
         Some(Function::Contains) => {
-            if file_info.is_some() {
-                return Variant::empty(VariantType::Bool);
+            if let Some(file_info) = file_info {
+                let contains = file_info.read_contents(|reader| {
+                    let mut contents = String::new();
+                    DeadlineReader::new(reader, content_deadline)
+                        .read_to_string(&mut contents)
+                        .ok()
+                        .map(|_| contents.contains(&function_arg))
+                });
+
+                return match contains.flatten() {
+                    Some(contains) => Variant::from_bool(contains),
+                    None => Variant::empty(VariantType::Bool),
+                };
             }
 
             if let Some(entry) = entry {
-                if let Ok(mut f) = File::open(entry.path()) {
+                if let Ok(mut f) = open_content_reader(&entry.path(), content_deadline) {
                     let mut contents = String::new();
                     if f.read_to_string(&mut contents).is_ok() {
                         if contents.contains(&function_arg) {
@@ -791,6 +1151,127 @@ pub fn get_value(
 
             Variant::empty(VariantType::Bool)
         }
+        Some(Function::HashEquals) => {
+            if file_info.is_some() {
+                return Variant::empty(VariantType::Bool);
+            }
+
+            let (hash_fn, digest_len) = match get_file_hash_fn(&function_arg) {
+                Some(hash) => hash,
+                None => return Variant::empty(VariantType::Bool),
+            };
+
+            let expected = function_args.first().map(|arg| arg.trim()).unwrap_or_default();
+            if !is_valid_hex_digest(expected, digest_len) {
+                return Variant::from_bool(false);
+            }
+
+            if let Some(entry) = entry {
+                let hash = hash_fn(entry, content_deadline);
+                if !hash.is_empty() {
+                    return Variant::from_bool(hash.eq_ignore_ascii_case(expected));
+                }
+            }
+
+            Variant::empty(VariantType::Bool)
+        }
+        #[cfg(feature = "secrets")]
+        Some(Function::HasSecrets) => {
+            let has_secrets = read_file_contents(entry, file_info, content_deadline, |reader| {
+                crate::util::secrets::has_secrets(reader)
+            });
+
+            match has_secrets {
+                Some(has_secrets) => Variant::from_bool(has_secrets),
+                None => Variant::empty(VariantType::Bool),
+            }
+        }
+        Some(Function::TodoCount) => {
+            let markers: Vec<&str> = function_arg
+                .split(',')
+                .map(|marker| marker.trim())
+                .filter(|marker| !marker.is_empty())
+                .collect();
+
+            let count = read_file_contents(entry, file_info, content_deadline, |reader| {
+                count_marked_lines(reader, &markers)
+            });
+
+            match count {
+                Some(count) => Variant::from_int(count as i64),
+                None => Variant::empty(VariantType::Int),
+            }
+        }
+        Some(Function::IsValidJson) => {
+            let is_valid =
+                read_file_contents(entry, file_info, content_deadline, |reader| Some(is_valid_json(reader)));
+
+            match is_valid {
+                Some(is_valid) => Variant::from_bool(is_valid),
+                None => Variant::empty(VariantType::Bool),
+            }
+        }
+        Some(Function::IsValidYaml) => {
+            let is_valid =
+                read_file_contents(entry, file_info, content_deadline, |reader| Some(is_valid_yaml(reader)));
+
+            match is_valid {
+                Some(is_valid) => Variant::from_bool(is_valid),
+                None => Variant::empty(VariantType::Bool),
+            }
+        }
+        Some(Function::IsValidToml) => {
+            let is_valid =
+                read_file_contents(entry, file_info, content_deadline, |reader| Some(is_valid_toml(reader)));
+
+            match is_valid {
+                Some(is_valid) => Variant::from_bool(is_valid),
+                None => Variant::empty(VariantType::Bool),
+            }
+        }
+        Some(Function::JsonValue) => {
+            let value = read_file_contents(entry, file_info, content_deadline, |reader| {
+                get_json_value(reader, &function_arg)
+            });
+
+            match value {
+                Some(value) => Variant::from_string(&value),
+                None => Variant::empty(VariantType::String),
+            }
+        }
+        Some(Function::XPath) => {
+            let value = read_file_contents(entry, file_info, content_deadline, |reader| {
+                get_xpath_value(reader, &function_arg)
+            });
+
+            match value {
+                Some(value) => Variant::from_string(&value),
+                None => Variant::empty(VariantType::String),
+            }
+        }
+        Some(Function::GrewSince) => {
+            if file_info.is_some() {
+                return Variant::empty(VariantType::Bool);
+            }
+
+            let interval = match parse_interval(&function_arg) {
+                Some(interval) => interval,
+                None => return Variant::empty(VariantType::Bool),
+            };
+
+            if let Some(entry) = entry {
+                if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                    // Modification times in the future count as recent writes too
+                    let recent = match SystemTime::now().duration_since(modified) {
+                        Ok(elapsed) => elapsed <= interval,
+                        Err(_) => true,
+                    };
+                    return Variant::from_bool(recent);
+                }
+            }
+
+            Variant::empty(VariantType::Bool)
+        }
         #[cfg(unix)]
         Some(Function::HasXattr) => {
             if let Some(entry) = entry {
@@ -856,32 +1337,16 @@ pub fn get_value(
 
             Variant::empty(VariantType::String)
         }
-        Some(Function::Random) => {
-            let mut rng = rand::rng();
-
-            if function_arg.is_empty() {
-                return Variant::from_int(rng.random_range(0..i64::MAX));
-            }
-
-            match function_arg.parse::<i64>() {
-                Ok(val) => {
-                    if function_args.is_empty() {
-                        Variant::from_int(rng.random_range(0..val))
-                    } else {
-                        let limit = function_args.first().unwrap();
-                        match limit.parse::<i64>() {
-                            Ok(limit) => Variant::from_int(rng.random_range(val..limit)),
-                            _ => error_exit(
-                                "Could not parse limit argument of RANDOM function",
-                                limit.as_str(),
-                            ),
-                        }
-                    }
-                }
-                _ => error_exit(
-                    "Could not parse an argument of RANDOM function",
-                    function_arg.as_str(),
-                ),
+        Some(Function::Random) => match get_random_range(&function_arg, function_args.first()) {
+            Ok(range) => Variant::from_int(rand::rng().random_range(range)),
+            Err(_) => Variant::empty(VariantType::Int),
+        },
+        // ===== Window functions =====
+        // The value of the row only, the searcher accounts for the other rows once all of them are known
+        Some(Function::RunningSum) | Some(Function::PercentOfTotal) => {
+            match function_arg.parse::<f64>() {
+                Ok(val) => Variant::from_float(val),
+                _ => Variant::empty(VariantType::Float),
             }
         }
         // If no function is specified, return the original value
@@ -901,29 +1366,23 @@ pub fn get_value(
 ///   A string representation of the aggregate value computed or the default value if no function is provided.
 pub fn get_aggregate_value(
     function: &Option<Function>,
-    raw_output_buffer: &Vec<HashMap<String, String>>,
+    raw_output_buffer: &[BufferedRow],
     buffer_key: String,
     default_value: &Option<String>,
 ) -> String {
     //* Refer to the Function enum for a list of available functions and their descriptions
     match function {
         Some(Function::Min) => {
-            let min = raw_output_buffer
-                .iter()
-                .filter_map(|item| item.get(&buffer_key)) // Get the value from the buffer
-                .filter_map(|value| value.parse::<i64>().ok()) // Parse the value and filter out errors
-                .min()
-                .unwrap_or(0); // If no items were found
+            let min = get_buffer_numbers(raw_output_buffer, &buffer_key) // Parse the values and filter out errors
+                .reduce(f64::min)
+                .unwrap_or(0.0); // If no items were found
 
             min.to_string()
         }
         Some(Function::Max) => {
-            let max = raw_output_buffer
-                .iter()
-                .filter_map(|item| item.get(&buffer_key)) // Get the values from the buffer
-                .filter_map(|value| value.parse::<i64>().ok()) // Parse the value and filter out errors
-                .max()
-                .unwrap_or(0); // If no items were found
+            let max = get_buffer_numbers(raw_output_buffer, &buffer_key) // Parse the values and filter out errors
+                .reduce(f64::max)
+                .unwrap_or(0.0); // If no items were found
 
             max.to_string()
         }
@@ -980,6 +1439,7 @@ pub fn get_aggregate_value(
 
             variance.to_string()
         }
+        Some(Function::ArrayAgg) => get_aggregate_values(raw_output_buffer, &buffer_key).join(", "),
 
         // If no function is specified, return the default value
         // If no default value was specified, return an empty string
@@ -992,9 +1452,27 @@ pub fn get_aggregate_value(
 
 /// Get the variance of all values in the buffer, based on the buffer key.
 /// If the value can't be parsed as usize, it will be ignored.
+/// Reads the contents of the file, or of the file within an archive, with the content deadline applied
+fn read_file_contents<T>(
+    entry: Option<&DirEntry>,
+    file_info: &Option<FileInfo>,
+    content_deadline: Option<Instant>,
+    read: impl FnOnce(&mut dyn Read) -> Option<T>,
+) -> Option<T> {
+    match file_info {
+        Some(file_info) => file_info
+            .read_contents(|reader| read(&mut DeadlineReader::new(reader, content_deadline)))
+            .flatten(),
+        None => {
+            let mut reader = open_content_reader(&entry?.path(), content_deadline).ok()?;
+            read(&mut reader)
+        }
+    }
+}
+
 fn get_variance(
-    raw_output_buffer: &Vec<HashMap<String, String>>,
-    buffer_key: &String,
+    raw_output_buffer: &[BufferedRow],
+    buffer_key: &str,
     n: usize,
 ) -> f64 {
     let avg = get_mean(raw_output_buffer, buffer_key);
@@ -1011,26 +1489,189 @@ fn get_variance(
     result
 }
 
+/// Get all values in the buffer for ARRAY_AGG, based on the buffer key.
+pub fn get_aggregate_values(
+    raw_output_buffer: &[BufferedRow],
+    buffer_key: &str,
+) -> Vec<String> {
+    raw_output_buffer
+        .iter()
+        .filter_map(|item| item.get(buffer_key))
+        .map(|value| value.to_string())
+        .collect()
+}
+
 /// Get the mean of all values in the buffer, based on the buffer key.
-/// If the value can't be parsed as usize, it will be ignored.
-fn get_mean(raw_output_buffer: &Vec<HashMap<String, String>>, buffer_key: &String) -> f64 {
+/// If the value can't be parsed as a number, it will be ignored.
+fn get_mean(raw_output_buffer: &[BufferedRow], buffer_key: &str) -> f64 {
     let sum = get_buffer_sum(raw_output_buffer, buffer_key);
     let size = raw_output_buffer.len();
 
-    (sum / size) as f64
+    sum / size as f64
 }
 
 /// Get the sum of all values in the buffer, based on the buffer key.
-/// If the value can't be parsed as usize, it will be ignored.
-fn get_buffer_sum(raw_output_buffer: &Vec<HashMap<String, String>>, buffer_key: &String) -> usize {
-    let mut sum = 0;
-    for value in raw_output_buffer {
-        if let Some(value) = value.get(buffer_key) {
-            if let Ok(value) = value.parse::<usize>() {
-                sum += value;
+/// If the value can't be parsed as a number, it will be ignored.
+fn get_buffer_sum(raw_output_buffer: &[BufferedRow], buffer_key: &str) -> f64 {
+    get_buffer_numbers(raw_output_buffer, buffer_key).sum()
+}
+
+/// Get all values in the buffer that can be parsed as numbers, based on the buffer key.
+fn get_buffer_numbers<'a>(
+    raw_output_buffer: &'a [BufferedRow],
+    buffer_key: &'a str,
+) -> impl Iterator<Item = f64> + 'a {
+    raw_output_buffer
+        .iter()
+        .filter_map(move |item| item.get(buffer_key))
+        .filter_map(|value| value.parse::<f64>().ok())
+}
+
+/// Format the number of seconds as `h:mm:ss`
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{}:{:02}:{:02}", total / 3600, total % 3600 / 60, total % 60)
+}
+
+fn parse_size_bounds(bounds: &str) -> Option<Vec<(String, i64)>> {
+    parse_bounds(bounds, |bound| parse_filesize(bound).map(|size| size as i64))
+}
+
+fn parse_age_bounds(bounds: &str) -> Option<Vec<(String, i64)>> {
+    parse_bounds(bounds, |bound| parse_interval(bound).map(|interval| interval.as_secs() as i64))
+}
+
+/// RANDOM takes the upper bound, or the lower and the upper one, which must not be the same
+fn get_random_range(function_arg: &str, limit: Option<&String>) -> Result<std::ops::Range<i64>, String> {
+    if function_arg.is_empty() {
+        return Ok(0..i64::MAX);
+    }
+
+    let val = function_arg
+        .parse::<i64>()
+        .map_err(|_| format!("Could not parse an argument of RANDOM function: {}", function_arg))?;
+    let range = match limit {
+        Some(limit) => {
+            let limit = limit
+                .parse::<i64>()
+                .map_err(|_| format!("Could not parse limit argument of RANDOM function: {}", limit))?;
+            val..limit
+        }
+        None => 0..val,
+    };
+
+    match range.is_empty() {
+        true => Err(format!("Empty range of RANDOM function: {}..{}", range.start, range.end)),
+        false => Ok(range),
+    }
+}
+
+/// Parses comma-separated bounds like `1d,7d,30d` into labels and values, in ascending order
+fn parse_bounds(bounds: &str, parse: impl Fn(&str) -> Option<i64>) -> Option<Vec<(String, i64)>> {
+    let bounds = bounds
+        .split(',')
+        .map(|bound| {
+            let bound = bound.trim();
+            parse(bound).map(|value| (bound.to_string(), value))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match bounds.windows(2).all(|pair| pair[0].1 < pair[1].1) {
+        true => Some(bounds),
+        false => None,
+    }
+}
+
+/// Labels the range the value falls into, e.g. `7d-30d`, `0-1d` below the first bound, or `365d+` past the last one
+fn get_bucket(value: i64, bounds: &[(String, i64)]) -> String {
+    let mut lower = String::from("0");
+
+    for (label, bound) in bounds {
+        if value < *bound {
+            return format!("{}-{}", lower, label);
+        }
+        lower = label.clone();
+    }
+
+    format!("{}+", lower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_functions_names_parse() {
+        for info in FUNCTIONS {
+            for name in info.names {
+                assert_eq!(Function::from_str(name), Ok(info.function.clone()), "{}", name);
             }
         }
     }
 
-    sum
+    fn buffer(values: &[&str]) -> Vec<BufferedRow> {
+        values
+            .iter()
+            .map(|value| HashMap::from([(Rc::from("duration"), Rc::from(*value))]))
+            .collect()
+    }
+
+    #[test]
+    fn test_age_bucket() {
+        let parse = |bound: &str| parse_interval(bound).map(|interval| interval.as_secs() as i64);
+        let bounds = parse_bounds("1d, 7d,30d,365d", parse).unwrap();
+        let day = 86400;
+
+        assert_eq!(get_bucket(3600, &bounds), "0-1d");
+        assert_eq!(get_bucket(-3600, &bounds), "0-1d");
+        assert_eq!(get_bucket(day, &bounds), "1d-7d");
+        assert_eq!(get_bucket(10 * day, &bounds), "7d-30d");
+        assert_eq!(get_bucket(400 * day, &bounds), "365d+");
+
+        assert_eq!(parse_bounds("7d,1d", parse), None);
+        assert_eq!(parse_bounds("1d,soon", parse), None);
+    }
+
+    #[test]
+    fn test_size_bucket() {
+        let bounds = parse_bounds("1k,1m,100m,1g", |bound| parse_filesize(bound).map(|size| size as i64)).unwrap();
+
+        assert_eq!(get_bucket(0, &bounds), "0-1k");
+        assert_eq!(get_bucket(1024, &bounds), "1k-1m");
+        assert_eq!(get_bucket(5 * 1024 * 1024, &bounds), "1m-100m");
+        assert_eq!(get_bucket(2 * 1024 * 1024 * 1024, &bounds), "1g+");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.0), "0:00:00");
+        assert_eq!(format_duration(59.9), "0:00:59");
+        assert_eq!(format_duration(3725.0), "1:02:05");
+        assert_eq!(format_duration(360000.0), "100:00:00");
+    }
+
+    #[test]
+    fn test_aggregates_with_floats() {
+        let buffer = buffer(&["1.5", "2", "", "3.5"]);
+        let key = String::from("duration");
+
+        assert_eq!(get_aggregate_value(&Some(Function::Sum), &buffer, key.clone(), &None), "7");
+        assert_eq!(get_aggregate_value(&Some(Function::Min), &buffer, key.clone(), &None), "1.5");
+        assert_eq!(get_aggregate_value(&Some(Function::Max), &buffer, key, &None), "3.5");
+    }
+
+    #[test]
+    fn test_array_agg() {
+        let buffer = buffer(&["a.mp3", "b.mp3", ""]);
+        let key = String::from("duration");
+
+        assert_eq!(get_aggregate_values(&buffer, &key), vec!["a.mp3", "b.mp3", ""]);
+        assert_eq!(
+            get_aggregate_value(&Some(Function::ArrayAgg), &buffer, key, &None),
+            "a.mp3, b.mp3, "
+        );
+        assert!(!Function::ArrayAgg.is_numeric_function());
+    }
 }
@@ -28,6 +28,8 @@ pub enum Lexem {
     DescendingOrder,
     Limit,
     Into,
+    /// Separates a column from its display format, e.g. `size :: fsize('%.1 ')`
+    DoubleColon,
 }
 
 #[derive(Debug, PartialEq)]
@@ -42,6 +44,7 @@ enum LexingMode {
     BackticksQuotedString,
     Open,
     Close,
+    DoubleColon,
 }
 
 pub struct Lexer {
@@ -52,6 +55,8 @@ pub struct Lexer {
     possible_search_root: bool,
     after_open: bool,
     after_where: bool,
+    /// Within `group by`, `order by`, or the output format options, which take expressions like the columns do
+    after_by: bool,
     after_operator: bool,
 }
 
@@ -65,10 +70,19 @@ impl Lexer {
             possible_search_root: false,
             after_open: false,
             after_where: false,
+            after_by: false,
             after_operator: false,
         }
     }
 
+    /// Reads the input as if it followed a search root, for the root options alone
+    pub fn after_root(input: Vec<String>) -> Lexer {
+        Lexer {
+            before_from: false,
+            ..Lexer::new(input)
+        }
+    }
+
     pub fn next_lexem(&mut self) -> Option<Lexem> {
         let mut s = String::new();
         let mut mode = LexingMode::Undefined;
@@ -95,7 +109,10 @@ impl Lexer {
             }
             
             match mode {
-                LexingMode::Comma | LexingMode::Open | LexingMode::Close => break,
+                LexingMode::Comma
+                | LexingMode::Open
+                | LexingMode::Close
+                | LexingMode::DoubleColon => break,
                 LexingMode::SingleQuotedString => {
                     self.char_index += 1;
                     if c == '\'' {
@@ -129,6 +146,10 @@ impl Lexer {
                     break;
                 }
                 LexingMode::RawString => {
+                    if self.is_double_colon(input_part, self.char_index) {
+                        break;
+                    }
+
                     let is_date = c == '-' && looks_like_date(&s);
                     if !is_date {
                         if self.is_arithmetic_op_char(c) {
@@ -154,6 +175,10 @@ impl Lexer {
                         '"' => mode = LexingMode::DoubleQuotedString,
                         '`' => mode = LexingMode::BackticksQuotedString,
                         ',' => mode = LexingMode::Comma,
+                        ':' if self.is_double_colon(input_part, self.char_index - 1) => {
+                            self.char_index += 1;
+                            mode = LexingMode::DoubleColon
+                        }
                         '(' | '{' => {
                             s.push(c);
                             mode = LexingMode::Open
@@ -186,6 +211,7 @@ impl Lexer {
             LexingMode::Operator => Some(Lexem::Operator(s)),
             LexingMode::ArithmeticOperator => Some(Lexem::ArithmeticOperator(s)),
             LexingMode::Comma => Some(Lexem::Comma),
+            LexingMode::DoubleColon => Some(Lexem::DoubleColon),
             LexingMode::Open if &s == "(" => {
                 s.clear();
                 Some(Lexem::Open)
@@ -216,11 +242,25 @@ impl Lexer {
                 "and" => Some(Lexem::And),
                 "not" if self.after_where => Some(Lexem::Not),
                 "order" => Some(Lexem::Order),
-                "by" => Some(Lexem::By),
+                "by" => {
+                    self.after_by = true;
+                    Some(Lexem::By)
+                }
                 "asc" => self.next_lexem(),
                 "desc" => Some(Lexem::DescendingOrder),
-                "limit" => Some(Lexem::Limit),
-                "into" => Some(Lexem::Into),
+                "limit" => {
+                    self.after_by = false;
+                    Some(Lexem::Limit)
+                }
+                "into" => {
+                    self.after_by = true;
+                    Some(Lexem::Into)
+                }
+                // Actions come last and may be followed by unquoted paths
+                "extract" | "restore" | "purge" | "update" | "duplicates" if self.after_by => {
+                    self.after_by = false;
+                    Some(Lexem::RawString(s))
+                }
                 "eq" | "ne" | "gt" | "lt" | "ge" | "le" | "gte" | "lte" | "regexp" | "rx"
                 | "like" | "between" => Some(Lexem::Operator(s)),
                 "mul" | "div" | "mod" | "plus" | "minus" => Some(Lexem::ArithmeticOperator(s)),
@@ -230,7 +270,7 @@ impl Lexer {
         };
 
         self.possible_search_root = matches!(lexem, Some(Lexem::From))
-                || (matches!(lexem, Some(Lexem::Comma)) && !self.after_where);
+                || (matches!(lexem, Some(Lexem::Comma)) && !self.after_where && !self.after_by);
         self.after_operator = matches!(lexem, Some(Lexem::Operator(_)));
 
         lexem
@@ -238,16 +278,28 @@ impl Lexer {
 
     fn is_arithmetic_op_char(&self, c: char) -> bool {
         match c {
-            '+' | '-' => self.before_from || self.after_where,
+            '+' | '-' => self.before_from || self.after_where || self.after_by,
             '*' | '/' | '%' => {
-                (self.before_from || self.after_where) && !self.after_open && !self.after_operator
+                (self.before_from || self.after_where || self.after_by)
+                    && !self.after_open
+                    && !self.after_operator
             }
             _ => false,
         }
     }
 
+    /// Display formats are only given to the columns, so `::` elsewhere stays a part of the string
+    fn is_double_colon(&self, input_part: &str, char_index: isize) -> bool {
+        if !self.before_from || char_index < 0 {
+            return false;
+        }
+
+        let mut chars = input_part.chars().skip(char_index as usize);
+        chars.next() == Some(':') && chars.next() == Some(':')
+    }
+
     fn is_op_char(&self, c: char) -> bool {
-        if !self.before_from && !self.after_where {
+        if !self.before_from && !self.after_where && !self.after_by {
             return false;
         }
 
@@ -311,6 +363,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn group_by_expressions() {
+        let mut lexer = lexer!("name, count(*) from /test group by size / 1000, year(modified) order by size desc extract thumbnail to /tmp/thumbs");
+
+        let lexems: Vec<Lexem> = std::iter::from_fn(|| lexer.next_lexem()).collect();
+        assert_eq!(
+            &lexems[10..],
+            &[
+                Lexem::RawString(String::from("size")),
+                Lexem::ArithmeticOperator(String::from("/")),
+                Lexem::RawString(String::from("1000")),
+                Lexem::Comma,
+                Lexem::RawString(String::from("year")),
+                Lexem::Open,
+                Lexem::RawString(String::from("modified")),
+                Lexem::Close,
+                Lexem::Order,
+                Lexem::By,
+                Lexem::RawString(String::from("size")),
+                Lexem::DescendingOrder,
+                Lexem::RawString(String::from("extract")),
+                Lexem::RawString(String::from("thumbnail")),
+                Lexem::RawString(String::from("to")),
+                Lexem::RawString(String::from("/tmp/thumbs")),
+            ]
+        );
+    }
+
+    #[test]
+    fn column_formats() {
+        let mut lexer = lexer!("size::fsize('%.1 '), modified :: date('%Y-%m-%d') from C:\\test::dir");
+
+        let lexems: Vec<Lexem> = std::iter::from_fn(|| lexer.next_lexem()).collect();
+        assert_eq!(
+            lexems,
+            vec![
+                Lexem::RawString(String::from("size")),
+                Lexem::DoubleColon,
+                Lexem::RawString(String::from("fsize")),
+                Lexem::Open,
+                Lexem::String(String::from("%.1 ")),
+                Lexem::Close,
+                Lexem::Comma,
+                Lexem::RawString(String::from("modified")),
+                Lexem::DoubleColon,
+                Lexem::RawString(String::from("date")),
+                Lexem::Open,
+                Lexem::String(String::from("%Y-%m-%d")),
+                Lexem::Close,
+                Lexem::From,
+                Lexem::RawString(String::from("C:\\test::dir")),
+            ]
+        );
+    }
+
     #[test]
     fn looks_like_date_test() {
         assert!(looks_like_date("2018"));
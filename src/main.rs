@@ -9,18 +9,22 @@ extern crate uzers;
 extern crate xattr;
 
 use std::env;
-use std::io::{stdout, IsTerminal};
-use std::path::PathBuf;
+use std::io::{stdout, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Instant;
 #[cfg(feature = "update-notifications")]
 use std::time::Duration;
 
+use env_logger::{Env, WriteStyle};
+use log::debug;
 use nu_ansi_term::Color::*;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 #[cfg(feature = "update-notifications")]
 use update_informer::{registry, Check};
 
+mod cache;
 mod config;
 mod expr;
 mod field;
@@ -34,12 +38,17 @@ mod output;
 mod parser;
 mod query;
 mod searcher;
+mod server;
 mod util;
 
 use crate::config::Config;
+use crate::field::{Field, FIELDS};
+use crate::function::FUNCTIONS;
+use crate::output::sink::open_sink;
 use crate::parser::Parser;
 use crate::searcher::Searcher;
 use crate::util::error_message;
+use crate::util::format_filesize;
 use crate::util::str_to_bool;
 
 fn main() -> ExitCode {
@@ -95,11 +104,65 @@ fn main() -> ExitCode {
         return ExitCode::SUCCESS;
     }
 
+    if first_arg == "--fields" || first_arg == "--functions" {
+        let json = args
+            .get(1)
+            .is_some_and(|arg| arg.eq_ignore_ascii_case("json"));
+
+        if first_arg == "--fields" {
+            print_fields(json);
+        } else {
+            print_functions(json);
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
     let mut interactive = false;
+    let mut verbose = false;
+    let mut use_cache = false;
+    let mut show_stats = false;
+    let mut show_summary = false;
+    let mut serve_address = None;
+    #[cfg(feature = "http")]
+    let mut http_address = None;
 
     loop {
         if first_arg.contains("nocolor") || first_arg.contains("no-color") {
             no_color = true;
+        } else if first_arg == "--verbose" {
+            verbose = true;
+        } else if first_arg == "--cache" {
+            use_cache = true;
+        } else if first_arg == "--stats" {
+            show_stats = true;
+        } else if first_arg == "--summary" {
+            show_summary = true;
+        } else if first_arg == "--serve" {
+            if args.len() < 2 {
+                error_message("serve", "socket path or localhost address expected");
+                return ExitCode::from(2);
+            }
+
+            serve_address = Some(args[1].clone());
+            args.remove(0);
+        } else if first_arg == "--http" {
+            #[cfg(feature = "http")]
+            {
+                if args.len() < 2 {
+                    error_message("http", "address to listen on expected");
+                    return ExitCode::from(2);
+                }
+
+                http_address = Some(args[1].clone());
+                args.remove(0);
+            }
+
+            #[cfg(not(feature = "http"))]
+            {
+                error_message("http", "fselect was built without http feature");
+                return ExitCode::from(2);
+            }
         } else if first_arg.starts_with("-i")
             || first_arg.starts_with("--i")
             || first_arg.starts_with("/i")
@@ -126,7 +189,12 @@ fn main() -> ExitCode {
         args.remove(0);
 
         if args.is_empty() {
-            if !interactive {
+            #[cfg(feature = "http")]
+            let serving = serve_address.is_some() || http_address.is_some();
+            #[cfg(not(feature = "http"))]
+            let serving = serve_address.is_some();
+
+            if !interactive && !serving {
                 short_usage_info(no_color);
                 help_hint();
                 return ExitCode::SUCCESS;
@@ -138,9 +206,26 @@ fn main() -> ExitCode {
         first_arg = args[0].to_ascii_lowercase();
     }
 
+    init_logger(verbose || config.debug, no_color);
+
     let mut exit_value = None::<u8>;
 
-    if interactive {
+    #[cfg(feature = "http")]
+    if let Some(address) = http_address {
+        if let Err(err) = server::http::serve_http(&address, &config, &default_config, use_cache) {
+            error_message("http", &err);
+            return ExitCode::from(2);
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(address) = serve_address {
+        if let Err(err) = server::serve(&address, &config, &default_config, use_cache) {
+            error_message("serve", &err);
+            exit_value = Some(2);
+        }
+    } else if interactive {
         match DefaultEditor::new() {
             Ok(mut rl) => loop {
                 let readline = rl.readline("query> ");
@@ -151,9 +236,23 @@ fn main() -> ExitCode {
                     {
                         break
                     }
+                    Ok(cmd) if cmd.split_whitespace().next() == Some("\\set") => {
+                        let _ = rl.add_history_entry(cmd.as_str());
+                        if let Err(err) = config.set_variable(&cmd.trim_start()[4..]) {
+                            error_message("set", &err);
+                        }
+                    }
                     Ok(query) => {
                         let _ = rl.add_history_entry(query.as_str());
-                        exec_search(vec![query], &mut config, &default_config, no_color);
+                        exec_search(
+                            vec![query],
+                            &mut config,
+                            &default_config,
+                            no_color,
+                            use_cache,
+                            show_stats,
+                            show_summary,
+                        );
                     }
                     Err(ReadlineError::Interrupted) => {
                         println!("CTRL-C");
@@ -176,7 +275,15 @@ fn main() -> ExitCode {
             }
         }
     } else {
-        exit_value = Some(exec_search(args, &mut config, &default_config, no_color));
+        exit_value = Some(exec_search(
+            args,
+            &mut config,
+            &default_config,
+            no_color,
+            use_cache,
+            show_stats,
+            show_summary,
+        ));
     }
 
     config.save();
@@ -200,26 +307,185 @@ fn main() -> ExitCode {
     ExitCode::SUCCESS
 }
 
-fn exec_search(query: Vec<String>, config: &mut Config, default_config: &Config, no_color: bool) -> u8 {
-    if config.debug {
-        dbg!(&query);
-    }
+fn exec_search(
+    query: Vec<String>,
+    config: &mut Config,
+    default_config: &Config,
+    no_color: bool,
+    use_cache: bool,
+    show_stats: bool,
+    show_summary: bool,
+) -> u8 {
+    debug!(target: "parser", "query arguments: {:?}", query);
 
     let mut p = Parser::new();
-    let query = p.parse(query, config.debug);
-
-    if config.debug {
-        dbg!(&query);
+    if let Some(ref default_root_options) = config.default_root_options {
+        if let Err(err) = p.set_default_root_options(default_root_options) {
+            error_message("config", &err);
+            return 2;
+        }
+    }
+    if let Some(ref star_columns) = config.star_columns {
+        if let Err(err) = p.set_star_columns(star_columns) {
+            error_message("config", &err);
+            return 2;
+        }
     }
 
+    let query = match query
+        .iter()
+        .map(|arg| config.expand_constants(arg))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(query) => query,
+        Err(err) => {
+            error_message("query", &err);
+            return 2;
+        }
+    };
+
+    let query = p.parse(query);
+
+    debug!(target: "parser", "parsed query: {:?}", query);
+
     match query {
-        Ok(query) => {
-            let is_terminal = stdout().is_terminal();
+        Ok(mut query) => {
+            query.apply_output_headers(config);
+            if let Err(err) = query.apply_output_profile(config) {
+                error_message("output", &err);
+                return 2;
+            }
+
+            query.reorder_conditions();
+            if query.explain {
+                print!("{}", query.explain(config));
+                return 0;
+            }
+
+            let plan = match query.compile() {
+                Ok(plan) => plan,
+                Err(err) => {
+                    error_message("query", &err);
+                    return 2;
+                }
+            };
+
+            // Partitioned results are written to their own files by the searcher
+            let output_sink = match query.partition {
+                Some(_) => Ok(None),
+                None => open_sink(
+                    &query.output_sink,
+                    &query.output_format,
+                    &query.output_options,
+                ),
+            };
+            let mut output_sink = match output_sink {
+                Ok(output_sink) => output_sink,
+                Err(err) => {
+                    let path = query.output_sink.path.as_deref().unwrap_or(Path::new("output"));
+                    error_message(&path.to_string_lossy(), &err.to_string());
+                    return 2;
+                }
+            };
+
+            let is_terminal =
+                output_sink.is_none() && query.partition.is_none() && stdout().is_terminal();
             let use_colors = !no_color && is_terminal;
 
+            let cache_key = match use_cache {
+                true => cache::get_cache_key(&query, config, use_colors),
+                false => None,
+            };
+
+            let cache_ttl = cache::get_cache_ttl(config, default_config);
+            if let Some(results) = cache_key.as_deref().and_then(|key| cache::get_cached_results(key, cache_ttl)) {
+                let _ = match output_sink {
+                    Some(ref mut output_sink) => output_sink.write_all(&results),
+                    None => stdout().write_all(&results),
+                };
+                return 0;
+            }
+
+            let is_redirected = output_sink.is_some() || query.partition.is_some();
             let mut searcher = Searcher::new(&query, config, default_config, use_colors);
+            searcher.set_plan(&plan);
+            if use_colors {
+                if let Some(ref color_rules) = config.color_rules {
+                    if let Err(err) = searcher.set_color_rules(color_rules) {
+                        error_message("config", &err);
+                        return 2;
+                    }
+                }
+            }
+            if let Some(output_sink) = output_sink {
+                searcher.redirect_output(output_sink);
+            }
+            if cache_key.is_some() {
+                searcher.capture_output();
+            }
+            let started = Instant::now();
             searcher.list_search_results().unwrap();
 
+            // The footer follows the results, unless they went to files
+            if show_summary {
+                let mut summary = format!("{} file(s)", searcher.found);
+                if query.get_all_fields().contains(&Field::Size) {
+                    summary += &format!(", {} in total", format_filesize(searcher.total_size, ""));
+                }
+                summary += &format!(", found in {:.2?}", started.elapsed());
+
+                match is_redirected {
+                    true => eprintln!("{}", summary),
+                    false => println!("{}", summary),
+                }
+            }
+
+            if show_stats {
+                eprintln!(
+                    "Found {} file(s) in {:?} with {} error(s) and {} retried I/O operation(s)",
+                    searcher.found,
+                    started.elapsed(),
+                    searcher.error_count,
+                    searcher.io_retry_count
+                );
+                eprintln!(
+                    "Resolved {} path(s), {} more taken from the cached paths of their directories",
+                    searcher.path_lookup_count,
+                    searcher.path_lookups_saved
+                );
+                if searcher.pruned_dir_count > 0 {
+                    eprintln!(
+                        "Skipped {} directories sorting after the last of the limited results",
+                        searcher.pruned_dir_count
+                    );
+                }
+                let buffered_count = searcher.get_buffered_count();
+                if buffered_count > 0 {
+                    eprintln!(
+                        "Buffered {} row(s) in about {}, sharing {} distinct value(s) between them",
+                        buffered_count,
+                        format_filesize(searcher.get_buffered_memory() as u64, ""),
+                        searcher.get_interned_count()
+                    );
+                }
+            }
+
+            // Incomplete results are not worth reusing
+            if let Some(cache_key) = cache_key {
+                if searcher.error_count == 0 && searcher.content_skipped_count == 0 {
+                    if let Some(results) = searcher.take_captured_output() {
+                        cache::store_results(&cache_key, &results);
+                    }
+                }
+            }
+
+            if searcher.content_skipped_count > 0 {
+                eprintln!(
+                    "Skipped content fields of {} file(s) due to max_content_size or content_timeout",
+                    searcher.content_skipped_count
+                );
+            }
+
             let error_count = searcher.error_count;
             match error_count {
                 0 => 0,
@@ -257,7 +523,77 @@ fn short_usage_info(no_color: bool) {
     }
 
     println!();
-    println!("Usage: fselect [ARGS] COLUMN[, COLUMN...] [from PATH[, PATH...]] [where EXPR] [group by COLUMN, ...] [order by COLUMN (asc|desc), ...] [limit N] [into FORMAT]");
+    println!("Usage: fselect [ARGS] [distinct] COLUMN[, COLUMN...] [from PATH[, PATH...]] [where EXPR] [group by COLUMN, ...] [order by COLUMN (asc|desc), ...] [limit N [offset M]] [into FORMAT ['PATH' | file PATH | to PATH] [partition by EXPR]] [extract thumbnail to PATH [overwrite] | restore [to PATH] | purge | replace duplicates with LINKS | update set FLAG = BOOL | delete [into trash] [dry run]]");
+}
+
+#[derive(Serialize)]
+struct Description {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    #[serde(rename = "type")]
+    value_type: &'static str,
+    description: &'static str,
+    group: &'static str,
+    platform: Option<&'static str>,
+}
+
+fn print_fields(json: bool) {
+    let fields = FIELDS.iter().map(|info| Description {
+        name: info.names[0],
+        aliases: &info.names[1..],
+        value_type: info.field.get_type(),
+        description: info.description,
+        group: info.group,
+        platform: info.field.get_platform(),
+    });
+
+    print_descriptions(fields.collect(), json);
+}
+
+fn print_functions(json: bool) {
+    let functions = FUNCTIONS.iter().map(|info| Description {
+        name: info.names[0],
+        aliases: &info.names[1..],
+        value_type: info.function.get_type(),
+        description: info.description,
+        group: info.group,
+        platform: info.function.get_platform(),
+    });
+
+    print_descriptions(functions.collect(), json);
+}
+
+fn print_descriptions(descriptions: Vec<Description>, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&descriptions).unwrap());
+        return;
+    }
+
+    for item in descriptions {
+        let mut names = vec![item.name];
+        names.extend(item.aliases);
+
+        match item.platform {
+            Some(platform) => println!("{:<40}{} ({}-only)", names.join(" | "), item.description, platform),
+            None => println!("{:<40}{}", names.join(" | "), item.description),
+        }
+    }
+}
+
+/// Sets up logging to stderr.
+/// The `FSELECT_LOG` environment variable takes precedence and accepts the usual
+/// `level` or `target=level` directives, e.g. `FSELECT_LOG=searcher=debug,extractors=trace`.
+fn init_logger(verbose: bool, no_color: bool) {
+    let default_level = if verbose { "debug" } else { "warn" };
+
+    let mut builder = env_logger::Builder::from_env(Env::new().filter_or("FSELECT_LOG", default_level));
+    builder.format_timestamp_millis();
+
+    if no_color {
+        builder.write_style(WriteStyle::Never);
+    }
+
+    let _ = builder.try_init();
 }
 
 fn help_hint() {
@@ -298,6 +634,10 @@ fn usage_info(config: Config, default_config: Config, no_color: bool) {
         .is_source
         .unwrap_or(default_config.is_source.unwrap())
         .join(", ");
+    let is_script = config
+        .is_script
+        .unwrap_or(default_config.is_script.unwrap())
+        .join(", ");
     let is_video = config
         .is_video
         .unwrap_or(default_config.is_video.unwrap())
@@ -311,12 +651,14 @@ Files Detected as Document: {is_doc}
 Files Detected as Fonts: {is_font}
 Files Detected as Image: {is_image}
 Files Detected as Source Code: {is_source}
+Files Detected as Scripts: {is_script}
 Files Detected as Video: {is_video}
 
 Path Options:
     mindepth N 	                    Minimum search depth. Default is unlimited. Depth 1 means skip one directory level and search further.
     maxdepth N | depth N 	        Maximum search depth. Default is unlimited. Depth 1 means search the mentioned directory only. Depth 2 means search mentioned directory and its subdirectories.
-    symlinks | sym                  If specified, search process will follow symlinks. Default is not to follow.
+    symlinks | sym [POLICY]         Follow symlinks: never, files-only, dirs-only, or always (default if POLICY is omitted). Default is not to follow.
+    symlink_depth N | symdepth N    Maximum number of links resolved in a chain. Default is 40.
     archives | arc                  Search within archives. Only zip archives are supported. Default is not to include archived content into the search results.
     gitignore | git                 Search respects .gitignore files found.
     hgignore | hg                   Search respects .hgignore files found.
@@ -326,12 +668,19 @@ Path Options:
     nodockerignore | nodocker       Disable .dockerignore parsing during the search.
     dfs 	                        Depth-first search mode.
     bfs 	                        Breadth-first search mode. This is the default.
+    dfs-post | dfs_post             Depth-first search mode, the contents of a directory come before the directory itself.
     regexp | rx                     Use regular expressions to search within multiple roots.
+    sorted                          Read the entries of every directory in lexicographic order, so the output is deterministic.
+    verbatim                        Windows only. Keep the \\\\?\\ long path prefix in the output paths. By default the prefix is stripped.
+    trash://                        Search the trash of the current user instead of a directory
+    as csv(FIELD, ...) | as json    Read files from a CSV or JSON manifest at the path instead of searching a directory. Columns map onto the listed fields or are named by the header row or keys.
 
 Regex syntax:
     {}
 
 Column Options:
+    *                               Returns the columns listed in star_columns of the config, or mode, user, group, size, modified, and path
+    **                              Returns all columns read from the metadata of the file
     name                            Returns the name (with extension) of the file
     extension | ext                 Returns the extension of the file
     path                            Returns the path of the file
@@ -340,12 +689,17 @@ Column Options:
     absdir                          Returns the absolute directory of the file
     size                            Returns the size of the file in bytes
     fsize | hsize                   Returns the size of the file accompanied with the unit
+    dir_size                        Returns the total size of all files within the directory tree in bytes
+    size_share                      Returns the fraction of the parent directory's total size taken by the file or directory
     uid                             Returns the UID of the owner
     gid                             Returns the GID of the owner's group
 
     accessed                        Returns the time the file was last accessed (YYYY-MM-DD HH:MM:SS)
     created                         Returns the file creation date (YYYY-MM-DD HH:MM:SS)
+    has_birth_time                  Returns a boolean signifying whether the file system records the creation time of the file
     modified                        Returns the time the file was last modified (YYYY-MM-DD HH:MM:SS)
+    newest_child_modified           Returns the modification time of the most recently modified entry within the directory tree
+    oldest_child_modified           Returns the modification time of the least recently modified entry within the directory tree
 
     is_dir                          Returns a boolean signifying whether the file path is a directory
     is_file                         Returns a boolean signifying whether the file path is a file
@@ -355,10 +709,30 @@ Column Options:
     is_block                        Returns a boolean signifying whether the file path is a block or block special file
     is_socket                       Returns a boolean signifying whether the file path is a socket file
     is_hidden                       Returns a boolean signifying whether the file is a hidden file (e.g., files that start with a dot on *nix)
+    is_valid_utf8_name              Returns a boolean signifying whether the file name is valid UTF-8
+    has_windows_reserved_name       Returns a boolean signifying whether the file name is reserved on Windows or ends with a dot or a space
+    has_control_chars               Returns a boolean signifying whether the file name contains control characters
+    is_git_root                     Returns a boolean signifying whether the directory is the root of a Git repository
+    is_hg_root                      Returns a boolean signifying whether the directory is the root of a Mercurial repository
+    is_generated                    Returns a boolean signifying whether the file is generated according to .gitattributes or linguist heuristics
+    is_vendored                     Returns a boolean signifying whether the file is vendored according to .gitattributes or linguist heuristics
+    hg_tracked                      Returns a boolean signifying whether the file is tracked by Mercurial
+    hg_modified                     Returns a boolean signifying whether the file tracked by Mercurial has uncommitted changes
+    svn_versioned                   Returns a boolean signifying whether the file is versioned in a Subversion working copy
+    project_type | project          Returns the type of the project in the directory (cargo, npm, maven, or python)
     has_xattrs                      Returns a boolean signifying whether the file has extended attributes
     capabilities | caps             Returns a string describing Linux capabilities assigned to a file
+    is_immutable                    Returns a boolean signifying whether the file has the immutable flag set (chattr +i)
+    is_append_only                  Returns a boolean signifying whether the file has the append-only flag set (chattr +a)
+    dax                             Returns a boolean signifying whether the file is accessed directly, bypassing the page cache (DAX)
+    verity | has_verity             Returns a boolean signifying whether the file is protected with fs-verity
+    ima_hash                        Returns the IMA measurement of the file as ALGO:DIGEST, or ALGO:signed for IMA signatures
+    is_open                         Returns a boolean signifying whether the file is currently open by any process
+    open_by_pids                    Returns the comma-separated IDs of the processes holding the file open
+    open_by_process_names           Returns the comma-separated names of the processes holding the file open
 
     device (Linux only)             Returns the code of device the file is stored on
+    mount_id (Linux only)           Returns the ID of the mount the file is found on, as in /proc/self/mountinfo
     inode (Linux only)              Returns the number of inode
     blocks (Linux only)             Returns the number of blocks (256 bytes) the file occupies
     hardlinks (Linux only)          Returns the number of hardlinks of the file
@@ -387,11 +761,17 @@ Column Options:
 
     width                           Returns the number of pixels along the width of the photo or MP4 file
     height                          Returns the number of pixels along the height of the photo or MP4 file
+    dominant_color                  Returns the most common color of the image as #rrggbb (with image-colors feature)
+    brightness                      Returns the average brightness of the image from 0 (black) to 100 (white) (with image-colors feature)
 
     mime                            Returns MIME type of the file
     is_binary                       Returns a boolean signifying whether the file has binary contents
     is_text                         Returns a boolean signifying whether the file has text contents
-    line_count                      Returns a number of lines in a text file
+    line_count                      Returns a number of lines in a text file (.gz and .zst files are decompressed)
+    code_lines                      Returns a number of lines of code in a source file, without comments and blank lines
+    comment_lines                   Returns a number of comment lines in a source file
+    blank_lines                     Returns a number of blank lines in a source file
+    license                         Returns the SPDX identifier of the license found in the SPDX tag of the file, or in LICENSE and README files
 
     exif_datetime                   Returns date and time of taken photo
     exif_altitude | exif_alt        Returns GPS altitude of taken photo
@@ -411,21 +791,45 @@ Column Options:
     mp3_bitrate | bitrate           Returns the bitrate of the audio file in kbps
     duration                        Returns the duration of audio file in seconds
 
+    video_title                     Returns the title of the MKV or MP4 video file taken from the file's metadata
+    encoder | video_encoder         Returns the name of the application that encoded the MKV or MP4 video file
+    video_created                   Returns the creation date embedded into the MKV or MP4 video file
+    audio_tracks                    Returns the number of audio tracks in the MKV or MP4 video file
+    subtitle_tracks | subtitles     Returns the number of subtitle tracks in the MKV or MP4 video file
+    subtitle_langs                  Returns the comma-separated languages of subtitle tracks in the MKV or MP4 video file
+
+    torrent_info_hash               Returns the SHA-1 info hash of the .torrent file
+    torrent_piece_count             Returns the number of pieces described by the .torrent file
+    torrent_total_size              Returns the total size in bytes of the payload described by the .torrent file
+    torrent_files_count             Returns the number of payload files described by the .torrent file
+
     is_shebang                      Returns a boolean signifying whether the file starts with a shebang (#!)
     is_empty                        Returns a boolean signifying whether the file is empty or the directory is empty
+    is_recursively_empty            Returns a boolean signifying whether the directory contains nothing but empty directories
+    child_count | children          Returns the number of entries in the directory, without recursion
+    subdir_count | subdirs          Returns the number of subdirectories in the directory, without recursion
+    file_count | files              Returns the number of regular files in the directory, without recursion
     is_archive                      Returns a boolean signifying whether the file is an archival file
+    is_valid_archive                Returns a boolean signifying whether the archive can be read through and its checksums match
+    in_archive                      Returns a boolean signifying whether the file is found within an archive
+    archive_path                    Returns the path of the archive containing the file
     is_audio                        Returns a boolean signifying whether the file is an audio file
     is_book                         Returns a boolean signifying whether the file is a book
     is_doc                          Returns a boolean signifying whether the file is a document
     is_font                         Returns a boolean signifying whether the file is a font file
     is_image                        Returns a boolean signifying whether the file is an image
+    is_corrupted_image              Returns a boolean signifying whether the JPEG or PNG file is truncated or malformed
+    is_encrypted                    Returns a boolean signifying whether the file is encrypted: PGP, age, LUKS, zip, or Office
     is_source                       Returns a boolean signifying whether the file is source code
+    is_script                       Returns a boolean signifying whether the file is a script, by extension or shebang
     is_video                        Returns a boolean signifying whether the file is a video file
 
     sha1                            Returns SHA-1 digest of a file
     sha2_256 | sha256               Returns SHA2-256 digest of a file
     sha2_512 | sha512               Returns SHA2-512 digest of a file
     sha3_512 | sha3                 Returns SHA-3 digest of a file
+    trash_original_path | original_path   Returns the path the trashed file was deleted from
+    trash_deleted | deleted         Returns the time the file was moved to the trash
 
 Functions:
     Aggregate:
@@ -438,6 +842,10 @@ Functions:
         STDDEV_SAMP                 Sample standard deviation, the square root of sample variance
         VAR_POP | VARIANCE          Population variance
         VAR_SAMP                    Sample variance
+        ARRAY_AGG                   Returns all values as a list (a JSON array with JSON output)
+    Window:
+        RUNNING_SUM                 Returns sum of the values of the row and all the rows before it
+        PERCENT_OF_TOTAL            Returns percentage of the value in the total of all rows
     Date:
         CURRENT_DATE | CUR_DATE |
         CURDATE                     Returns current date
@@ -445,6 +853,7 @@ Functions:
         MONTH                       Returns month of the year
         YEAR                        Returns year of the date
         DOW | DAYOFWEEK             Returns day of the week (1 - Sunday, 2 - Monday, etc.)
+        AGE_BUCKET                  Returns age range of the date among the bounds (e.g. 7d-30d for '1d,7d,30d')
     User:
         CURRENT_USER                Returns the current username (unix-only)
         CURRENT_UID                 Returns the current real UID (unix-only)
@@ -462,6 +871,8 @@ Functions:
         INITCAP                     Returns first letter of each word uppercase, all other letters lowercase
         TO_BASE64 | BASE64          Returns Base64 digest of a value
         FROM_BASE64                 Returns decoded value from a Base64 digest
+        TRANSLITERATE | TRANSLIT    Returns value transliterated into ASCII, kana is converted to romaji
+        SLUGIFY | SLUG              Returns lowercase ASCII slug of a value with words separated by dashes, keeping the file extension
         SUBSTRING | SUBSTR          Returns part of the string value
         REPLACE                     Returns string with substring replaced with another one
         TRIM                        Returns string with whitespaces at the beginning and the end stripped
@@ -483,12 +894,23 @@ Functions:
         LOG                         Returns logarithm of the value
         LN                          Returns natural logarithm of the value
         EXP                         Returns e raised to the power of the value
-        CONTAINS                    Returns true, if file contains string, false if not
+        CONTAINS                    Returns true, if file contains string, false if not (.gz and .zst files are decompressed)
+        HASH_EQUALS | HASH_EQ       Returns true, if file hash computed with the algorithm (sha1, sha256, sha512, sha3) equals the value
+        GREW_SINCE                  Returns true, if file has been written to within the interval (e.g. 10s, 5min, 1h)
+        IS_VALID_JSON               Returns true, if file is a well-formed JSON document
+        IS_VALID_YAML               Returns true, if file is a well-formed YAML document
+        IS_VALID_TOML               Returns true, if file is a well-formed TOML document
+        JSON_VALUE                  Returns the value at the JSONPath like $.version from the JSON file
+        XPATH                       Returns the text of the element or attribute at the path like /project/version from the XML file
+        TODO_COUNT                  Returns the number of lines with TODO, FIXME, HACK, or XXX markers, or with the markers given
+        HAS_SECRETS                 Returns true, if file contains AWS keys, private keys, or API tokens (with secrets feature)
         COALESCE                    Returns first nonempty expression value
         CONCAT                      Returns concatenated string of expression values
         CONCAT_WS                   Returns concatenated string of expression values with specified delimiter
         FORMAT_SIZE                 Returns formatted size of a file
         FORMAT_TIME | PRETTY_TIME   Returns human-readable durations of time in seconds
+        FORMAT_DURATION             Returns durations of time in seconds as hours, minutes, and seconds (h:mm:ss)
+        SIZE_BUCKET                 Returns size range of the value among the bounds (e.g. 1k-1m for '1k,1m,1g')
         RANDOM | RAND               Returns random integer (from zero to max int, from zero to arg, or from arg1 to arg2)
 
 Expressions:
@@ -509,13 +931,53 @@ Expressions:
     Logical Operators:
         and                         Used as an AND operator for two conditions made with the above operators
         or                          Used as an OR operator for two conditions made with the above operators
+    Conditions are evaluated from the cheapest: name, metadata, mime, dimensions, content.
+    Prefix the query with explain to print the evaluation order instead of searching.
+
+Column format (COLUMN :: FORMAT):
+    fsize('MODIFIER')               Outputs the size in bytes with the units, as FORMAT_SIZE does
+    date('FORMAT')                  Outputs the date and time with the chrono format specifiers
+    time                            Outputs the duration in seconds as human-readable time
 
 Format:
     tabs (default)                  Outputs each file with its column value(s) on a line with each column value delimited by a tab
+    tsv                             Outputs tab-separated values with tabs, line breaks, and backslashes in them escaped
     lines                           Outputs each column value on a new line
+    tabs(headers) | tsv(headers) | lines(headers) | list(headers)
+                                    Outputs the column names before the first row, always if output_headers is set in the config
     list                            Outputs entire output onto a single line for xargs
     csv                             Outputs each file with its column value(s) on a line with each column value delimited by a comma
+    csv(DELIMITER, header, quote=STYLE)
+                                    Sets the delimiter, writes the column names first, and quotes ALWAYS, NECESSARY, NONNUMERIC, or NEVER
     json                            Outputs a JSON array with JSON objects holding the column value(s) of each file
-    html                            Outputs HTML document with table
+    ndjson | jsonl                  Outputs a JSON object holding the column value(s) of each file per line
+    html                            Outputs HTML document with table, paths link to the files
+    html(sortable)                  Outputs HTML table sorted by clicking the column names
+    histogram                       Outputs a bar chart of the last column value per the other columns, or of counts of the values of a single column
+    chart                           Outputs the column values with proportional bars of the last numeric column (e.g. sizes or counts)
+    xlsx                            Outputs Excel workbook with numbers and dates stored as such (with xlsx feature)
+    arrow                           Outputs Apache Arrow IPC stream with numbers, booleans, and dates stored as such (with arrow feature)
+    table | table(box)              Outputs the columns aligned under their names, with borders drawn for table(box)
+    sqlite                          Creates the results table with the columns in the SQLite database file (with sqlite feature), or outputs SQL statements without a file
+    pivot(rows=EXPR, cols=EXPR[, value=AGGREGATE])
+                                    Outputs a matrix of the aggregate (count(*) by default) with the rows and columns grouped by the expressions
+    profile(NAME)                   Outputs with the format, headers, and delimiter of the [output.NAME] profile from the config file
+    FORMAT.gz | FORMAT.zst          Compresses the output with gzip or zstd, also chosen by the extension of the file
+    FORMAT ('PATH' | file PATH | to PATH)
+                                    Writes the output to the file instead of stdout, replacing the file once complete
+    FORMAT ['PATH'] partition by EXPR
+                                    Writes a file per value of the expression, e.g. report_jpg.csv and report_png.csv
+
+Action:
+    extract thumbnail to PATH [overwrite]
+                                    Writes EXIF thumbnails of images and first keyframes of videos (requires ffmpeg) to PATH, {{column}} placeholders are substituted, existing files are kept unless overwrite is given
+    restore [to PATH]               Moves items of the trash:// root back to where they were deleted from, or into PATH
+    purge                           Deletes items of the trash:// root permanently
+    replace duplicates with (hardlinks | symlinks) [dry run]
+                                    Replaces files with the same contents as an earlier match with links to it
+    update set (immutable | append_only) = (true | false)[, ...]
+                                    Sets or clears the immutable and append-only flags, Linux only
+    delete [dry run]                Deletes directories containing nothing but empty directories, never files
+    delete into trash [dry run]     Moves files and directories into the trash, not supported on Windows
     ", Cyan.underline().paint("https://docs.rs/regex/1.10.2/regex/#syntax"));
 }
@@ -1,5 +1,7 @@
 //! Defines the arithmetic operators used in the query language
 
+use std::fmt::{Display, Error, Formatter};
+
 use crate::function::Variant;
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Serialize)]
@@ -26,6 +28,38 @@ pub enum Op {
     NotBetween,
 }
 
+impl Display for LogicalOp {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            LogicalOp::And => write!(f, "and"),
+            LogicalOp::Or => write!(f, "or"),
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let op = match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Eeq => "===",
+            Op::Ene => "!==",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::Rx => "~=",
+            Op::NotRx => "!~=",
+            Op::Like => "like",
+            Op::NotLike => "not like",
+            Op::Between => "between",
+            Op::NotBetween => "not between",
+        };
+
+        write!(f, "{}", op)
+    }
+}
+
 impl Op {
     pub fn from(text: String) -> Option<Op> {
         match text.to_lowercase().as_str() {
@@ -107,3 +141,17 @@ impl ArithmeticOp {
         Variant::from_float(result)
     }
 }
+
+impl Display for ArithmeticOp {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let op = match self {
+            ArithmeticOp::Add => "+",
+            ArithmeticOp::Subtract => "-",
+            ArithmeticOp::Multiply => "*",
+            ArithmeticOp::Divide => "/",
+            ArithmeticOp::Modulo => "%",
+        };
+
+        write!(f, "{}", op)
+    }
+}
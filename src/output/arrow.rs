@@ -0,0 +1,200 @@
+//! Handles export of results as Apache Arrow IPC streams.
+//! Rows come as CSV with the column names first, the stream is written when the writer is finished.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampSecondArray,
+};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use chrono::NaiveDateTime;
+
+use crate::function::VariantType;
+use crate::output::sink::{read_rows, AtomicFile};
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Collects the rows and writes the stream to the file, or to stdout, when dropped
+pub struct ArrowWriter {
+    path: Option<PathBuf>,
+    column_types: Vec<VariantType>,
+    contents: Vec<u8>,
+}
+
+impl ArrowWriter {
+    pub fn new(path: Option<PathBuf>, column_types: Vec<VariantType>) -> ArrowWriter {
+        ArrowWriter {
+            path,
+            column_types,
+            contents: vec![],
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), ArrowError> {
+        let rows = read_rows(&self.contents);
+        self.contents.clear();
+
+        let (schema, batch) = build_batch(&rows, &self.column_types)?;
+
+        match self.path {
+            Some(ref path) => write_stream(AtomicFile::create(path)?, &schema, batch),
+            None => write_stream(io::stdout(), &schema, batch),
+        }
+    }
+}
+
+impl Write for ArrowWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// The types of the columns depend on all of their values, so nothing is written before the end
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ArrowWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish() {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+fn write_stream<W: Write>(
+    output: W,
+    schema: &Schema,
+    batch: Option<RecordBatch>,
+) -> Result<(), ArrowError> {
+    let mut writer = StreamWriter::try_new(output, schema)?;
+    if let Some(ref batch) = batch {
+        writer.write(batch)?;
+    }
+    writer.finish()
+}
+
+/// Nothing but the empty schema is written if there are no rows, as even the column names are unknown then
+fn build_batch(
+    rows: &[Vec<String>],
+    column_types: &[VariantType],
+) -> Result<(Schema, Option<RecordBatch>), ArrowError> {
+    let Some((header, rows)) = rows.split_first() else {
+        return Ok((Schema::empty(), None));
+    };
+
+    let mut fields = vec![];
+    let mut columns = vec![];
+
+    for (col, name) in header.iter().enumerate() {
+        let values: Vec<&str> = rows
+            .iter()
+            .map(|row| row.get(col).map(String::as_str).unwrap_or_default())
+            .collect();
+        let (data_type, column) = build_column(column_types.get(col), &values);
+        fields.push(Field::new(name, data_type, true));
+        columns.push(column);
+    }
+
+    let schema = Schema::new(fields);
+    let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+
+    Ok((schema, Some(batch)))
+}
+
+/// Columns get the type of their expression if all of their values fit it, and become strings otherwise.
+/// Empty values are nulls, except in the strings.
+fn build_column(value_type: Option<&VariantType>, values: &[&str]) -> (DataType, ArrayRef) {
+    let all_values = |is_valid: fn(&str) -> bool| {
+        values.iter().filter(|value| !value.is_empty()).all(|value| is_valid(value))
+    };
+
+    match value_type {
+        Some(VariantType::Int) if all_values(|value| value.parse::<i64>().is_ok()) => {
+            let values: Vec<Option<i64>> = values.iter().map(|value| value.parse().ok()).collect();
+            (DataType::Int64, Arc::new(Int64Array::from(values)))
+        }
+        Some(VariantType::Int) | Some(VariantType::Float)
+            if all_values(|value| value.parse::<f64>().is_ok()) =>
+        {
+            let values: Vec<Option<f64>> = values.iter().map(|value| value.parse().ok()).collect();
+            (DataType::Float64, Arc::new(Float64Array::from(values)))
+        }
+        Some(VariantType::Bool) if all_values(|value| value == "true" || value == "false") => {
+            let values: Vec<Option<bool>> = values.iter().map(|value| value.parse().ok()).collect();
+            (DataType::Boolean, Arc::new(BooleanArray::from(values)))
+        }
+        Some(VariantType::DateTime)
+            if all_values(|value| NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).is_ok()) =>
+        {
+            let values: Vec<Option<i64>> = values
+                .iter()
+                .map(|value| {
+                    NaiveDateTime::parse_from_str(value, DATETIME_FORMAT)
+                        .ok()
+                        .map(|dt| dt.and_utc().timestamp())
+                })
+                .collect();
+            (
+                DataType::Timestamp(TimeUnit::Second, None),
+                Arc::new(TimestampSecondArray::from(values)),
+            )
+        }
+        _ => (DataType::Utf8, Arc::new(StringArray::from(values.to_vec()))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_column_types() {
+        let (data_type, column) = build_column(Some(&VariantType::Int), &["1024", "", "7"]);
+        assert_eq!(data_type, DataType::Int64);
+        assert_eq!(column.null_count(), 1);
+
+        let (data_type, _) = build_column(Some(&VariantType::Int), &["1024", "0.5"]);
+        assert_eq!(data_type, DataType::Float64);
+
+        let (data_type, _) = build_column(Some(&VariantType::Int), &["1.5 KiB"]);
+        assert_eq!(data_type, DataType::Utf8);
+
+        let (data_type, _) = build_column(Some(&VariantType::Bool), &["true", "false"]);
+        assert_eq!(data_type, DataType::Boolean);
+
+        let (data_type, _) = build_column(Some(&VariantType::DateTime), &["2024-02-29 10:00:00"]);
+        assert_eq!(data_type, DataType::Timestamp(TimeUnit::Second, None));
+
+        let (data_type, _) = build_column(None, &["1024"]);
+        assert_eq!(data_type, DataType::Utf8);
+    }
+
+    #[test]
+    fn test_stream() {
+        let rows = vec![strings(&["name", "size"]), strings(&["foo.txt", "10"]), strings(&["bar.txt", ""])];
+        let (schema, batch) = build_batch(&rows, &[VariantType::String, VariantType::Int]).unwrap();
+
+        let mut buffer = vec![];
+        write_stream(&mut buffer, &schema, batch).unwrap();
+
+        let batches: Vec<RecordBatch> = StreamReader::try_new(buffer.as_slice(), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field(1).data_type(), &DataType::Int64);
+    }
+}
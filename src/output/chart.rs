@@ -0,0 +1,165 @@
+//! Handles rendering of results as rows with proportional bars in the terminal
+
+use crate::output::ResultsFormatter;
+use crate::util::parse_filesize;
+
+const BAR_WIDTH: usize = 40;
+
+const PARTIAL_BLOCKS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// Draws the bar of the value relative to the maximum one, with eighths of a block precision.
+/// Tiny but non-zero values are still visible.
+pub fn draw_bar(value: f64, max_value: f64) -> String {
+    if value <= 0.0 || max_value <= 0.0 {
+        return String::new();
+    }
+
+    let eighths = ((value / max_value) * (BAR_WIDTH * 8) as f64).round().max(1.0) as usize;
+
+    format!("{}{}", "█".repeat(eighths / 8), PARTIAL_BLOCKS[eighths % 8])
+}
+
+/// Puts the value after the bar, if there is any
+pub fn join_bar(bar: &str, value: &str) -> String {
+    match bar.is_empty() {
+        true => value.to_string(),
+        false => format!("{} {}", bar, value),
+    }
+}
+
+/// Rows keep their order, the last numeric column gets the bar and the other columns are labels
+#[derive(Default)]
+pub struct ChartFormatter {
+    row: Vec<String>,
+    rows: Vec<ChartRow>,
+}
+
+struct ChartRow {
+    labels: Vec<String>,
+    value: String,
+    amount: f64,
+}
+
+impl ChartFormatter {
+    fn render(&self) -> String {
+        let label_count = self.rows.iter().map(|row| row.labels.len()).max().unwrap_or(0);
+        let label_widths: Vec<usize> = (0..label_count)
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.labels.get(i))
+                    .map(|label| label.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let max_amount = self.rows.iter().map(|row| row.amount).fold(0.0, f64::max);
+
+        let mut result = String::new();
+        for row in &self.rows {
+            for (i, width) in label_widths.iter().enumerate() {
+                let label = row.labels.get(i).map(String::as_str).unwrap_or_default();
+                result.push_str(&format!("{:<width$}  ", label));
+            }
+
+            let bar = draw_bar(row.amount, max_amount);
+            result.push_str(&format!("| {}\n", join_bar(&bar, &row.value)));
+        }
+
+        result
+    }
+}
+
+/// Reads plain numbers and sizes with units, e.g. as formatted with `fsize`
+fn parse_amount(value: &str) -> Option<f64> {
+    let value = value.trim();
+
+    value
+        .parse::<f64>()
+        .ok()
+        .or_else(|| parse_filesize(value).map(|size| size as f64))
+}
+
+impl ResultsFormatter for ChartFormatter {
+    fn header(&mut self) -> Option<String> {
+        None
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, _: &str, record: &str, _is_last: bool) -> Option<String> {
+        self.row.push(record.to_owned());
+        None
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        let mut labels = std::mem::take(&mut self.row);
+
+        let row = match labels.iter().rposition(|value| parse_amount(value).is_some()) {
+            Some(pos) => {
+                let value = labels.remove(pos);
+                ChartRow {
+                    amount: parse_amount(&value).unwrap_or_default(),
+                    labels,
+                    value,
+                }
+            }
+            None => ChartRow {
+                labels,
+                value: String::new(),
+                amount: 0.0,
+            },
+        };
+        self.rows.push(row);
+
+        None
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        Some(self.render())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::chart::{draw_bar, ChartFormatter};
+    use crate::output::test::write_test_items;
+    use crate::output::ResultsFormatter;
+
+    #[test]
+    fn test() {
+        let result = write_test_items(&mut ChartFormatter::default());
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(lines[0], "foo_value  BAR value  | ");
+        assert!(lines[1].starts_with("                      | █"));
+        assert!(lines[1].ends_with("█ 123"));
+    }
+
+    #[test]
+    fn test_chart() {
+        let mut formatter = ChartFormatter::default();
+        for (size, path) in [("4096", "/home/user/big"), ("1k", "/home/user/small"), ("3", "/tmp")] {
+            formatter.format_element("size", size, false);
+            formatter.format_element("path", path, true);
+            formatter.row_ended();
+        }
+
+        assert_eq!(
+            formatter.footer().unwrap(),
+            "/home/user/big    | ████████████████████████████████████████ 4096\n\
+             /home/user/small  | ██████████ 1k\n\
+             /tmp              | ▏ 3\n"
+        );
+    }
+
+    #[test]
+    fn test_draw_bar() {
+        assert_eq!(draw_bar(0.0, 10.0), "");
+        assert_eq!(draw_bar(1.0, 0.0), "");
+        assert_eq!(draw_bar(1.0, 1.0).chars().count(), 40);
+        assert_eq!(draw_bar(1.0, 320.0), "▏");
+        assert_eq!(draw_bar(9.0, 80.0), "████▌");
+    }
+}
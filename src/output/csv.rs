@@ -1,11 +1,24 @@
 //! Handles export of results in CSV format
 
 use crate::output::ResultsFormatter;
+use crate::query::CsvQuoting;
 use crate::util::WritableBuffer;
 
 #[derive(Default)]
 pub struct CsvFormatter {
     records: Vec<String>,
+    delimiter: Option<u8>,
+    quoting: Option<CsvQuoting>,
+}
+
+impl CsvFormatter {
+    pub fn new(delimiter: Option<u8>, quoting: Option<CsvQuoting>) -> CsvFormatter {
+        CsvFormatter {
+            records: vec![],
+            delimiter,
+            quoting,
+        }
+    }
 }
 
 impl ResultsFormatter for CsvFormatter {
@@ -25,7 +38,19 @@ impl ResultsFormatter for CsvFormatter {
     fn row_ended(&mut self) -> Option<String> {
         let mut csv_output = WritableBuffer::new();
         {
-            let mut csv_writer = csv::Writer::from_writer(&mut csv_output);
+            let mut builder = csv::WriterBuilder::new();
+            if let Some(delimiter) = self.delimiter {
+                builder.delimiter(delimiter);
+            }
+            if let Some(quoting) = self.quoting {
+                builder.quote_style(match quoting {
+                    CsvQuoting::Always => csv::QuoteStyle::Always,
+                    CsvQuoting::Necessary => csv::QuoteStyle::Necessary,
+                    CsvQuoting::NonNumeric => csv::QuoteStyle::NonNumeric,
+                    CsvQuoting::Never => csv::QuoteStyle::Never,
+                });
+            }
+            let mut csv_writer = builder.from_writer(&mut csv_output);
             let _ = csv_writer.write_record(&self.records);
             self.records.clear();
         }
@@ -41,10 +66,27 @@ impl ResultsFormatter for CsvFormatter {
 mod test {
     use crate::output::csv::CsvFormatter;
     use crate::output::test::write_test_items;
+    use crate::query::CsvQuoting;
 
     #[test]
     fn test() {
         let result = write_test_items(&mut CsvFormatter::default());
         assert_eq!("foo_value,BAR value\n123,\n", result);
     }
+
+    #[test]
+    fn test_delimited() {
+        let result = write_test_items(&mut CsvFormatter::new(Some(b';'), None));
+        assert_eq!("foo_value;BAR value\n123;\n", result);
+    }
+
+    #[test]
+    fn test_quoting() {
+        let mut formatter = CsvFormatter::new(Some(b'\t'), Some(CsvQuoting::NonNumeric));
+        let result = write_test_items(&mut formatter);
+        assert_eq!("\"foo_value\"\t\"BAR value\"\n123\t\"\"\n", result);
+
+        let result = write_test_items(&mut CsvFormatter::new(None, Some(CsvQuoting::Always)));
+        assert_eq!("\"foo_value\",\"BAR value\"\n\"123\",\"\"\n", result);
+    }
 }
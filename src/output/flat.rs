@@ -22,6 +22,16 @@ pub struct FlatWriter {
     line_separator: Option<char>,
 }
 
+impl FlatWriter {
+    /// Tab-separated lines with another separator of the records
+    pub fn delimited(record_separator: char) -> FlatWriter {
+        FlatWriter {
+            record_separator,
+            line_separator: Some('\n'),
+        }
+    }
+}
+
 impl ResultsFormatter for FlatWriter {
     fn header(&mut self) -> Option<String> {
         None
@@ -50,7 +60,7 @@ impl ResultsFormatter for FlatWriter {
 #[cfg(test)]
 mod test {
     #![allow(const_item_mutation)]
-    use crate::output::flat::{LINES_FORMATTER, LIST_FORMATTER, TABS_FORMATTER};
+    use crate::output::flat::{FlatWriter, LINES_FORMATTER, LIST_FORMATTER, TABS_FORMATTER};
     use crate::output::test::write_test_items;
 
     #[test]
@@ -70,4 +80,10 @@ mod test {
         let result = write_test_items(&mut TABS_FORMATTER);
         assert_eq!("foo_value\tBAR value\n123\t\n", result);
     }
+
+    #[test]
+    fn test_delimited() {
+        let result = write_test_items(&mut FlatWriter::delimited('|'));
+        assert_eq!("foo_value|BAR value\n123|\n", result);
+    }
 }
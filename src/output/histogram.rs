@@ -0,0 +1,155 @@
+//! Handles rendering of results as a bar chart in the terminal
+
+use std::cmp::Ordering;
+
+use crate::output::chart::{draw_bar, join_bar};
+use crate::output::ResultsFormatter;
+use crate::util::{parse_filesize, parse_interval};
+
+/// The last column of each row is the value of the bar, the rest make its label.
+/// Rows of a single column are counted instead, so plain values turn into a distribution.
+#[derive(Default)]
+pub struct HistogramFormatter {
+    row: Vec<String>,
+    bars: Vec<(String, f64)>,
+}
+
+impl HistogramFormatter {
+    fn add(&mut self, label: String, value: f64) {
+        match self.bars.iter_mut().find(|(bar_label, _)| *bar_label == label) {
+            Some((_, bar_value)) => *bar_value += value,
+            None => self.bars.push((label, value)),
+        }
+    }
+
+    fn render(&mut self) -> String {
+        // Bucket labels like `1k-1m` or `30d+` go by their lower bounds, anything else alphabetically
+        match lower_bounds(&self.bars) {
+            Some(bounds) => {
+                let mut bars: Vec<_> = bounds.into_iter().zip(self.bars.drain(..)).collect();
+                bars.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+                self.bars = bars.into_iter().map(|(_, bar)| bar).collect();
+            }
+            None => self.bars.sort_by(|a, b| a.0.cmp(&b.0)),
+        }
+
+        let label_width = self.bars.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+        let max_value = self.bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+
+        let mut result = String::new();
+        for (label, value) in &self.bars {
+            let bar = draw_bar(*value, max_value);
+            result.push_str(&format!("{:<label_width$} | {}\n", label, join_bar(&bar, &value.to_string())));
+        }
+
+        result
+    }
+}
+
+/// Lower bounds of all the bucket labels, read either as sizes or as intervals, so `1m` is never both
+fn lower_bounds(bars: &[(String, f64)]) -> Option<Vec<f64>> {
+    let parse_size = |s: &str| parse_filesize(s).map(|size| size as f64);
+    let parse_secs = |s: &str| parse_interval(s).map(|interval| interval.as_secs_f64());
+
+    [&parse_size as &dyn Fn(&str) -> Option<f64>, &parse_secs]
+        .iter()
+        .find_map(|parse| {
+            bars.iter()
+                .map(|(label, _)| {
+                    let label = label.strip_suffix('+').unwrap_or(label);
+                    let lower = label.split('-').next()?.trim();
+                    lower.parse::<f64>().ok().or_else(|| parse(lower))
+                })
+                .collect()
+        })
+}
+
+impl ResultsFormatter for HistogramFormatter {
+    fn header(&mut self) -> Option<String> {
+        None
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, _: &str, record: &str, _is_last: bool) -> Option<String> {
+        self.row.push(record.to_owned());
+        None
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        let mut row = std::mem::take(&mut self.row);
+
+        if row.len() > 1 {
+            let value = row.pop().unwrap_or_default().trim().parse::<f64>().unwrap_or(0.0);
+            self.add(row.join(" "), value);
+        } else if let Some(label) = row.pop() {
+            self.add(label, 1.0);
+        }
+
+        None
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        Some(self.render())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::histogram::HistogramFormatter;
+    use crate::output::test::write_test_items;
+    use crate::output::ResultsFormatter;
+
+    #[test]
+    fn test() {
+        let result = write_test_items(&mut HistogramFormatter::default());
+        assert_eq!("123       | 0\nfoo_value | 0\n", result);
+    }
+
+    #[test]
+    fn test_buckets() {
+        let mut formatter = HistogramFormatter::default();
+        for (bucket, count) in [("1m+", "1"), ("0-1k", "4"), ("1k-1m", "2")] {
+            formatter.format_element("size_bucket", bucket, false);
+            formatter.format_element("count", count, true);
+            formatter.row_ended();
+        }
+
+        assert_eq!(
+            formatter.footer().unwrap(),
+            "0-1k  | ████████████████████████████████████████ 4\n\
+             1k-1m | ████████████████████ 2\n\
+             1m+   | ██████████ 1\n"
+        );
+    }
+
+    #[test]
+    fn test_age_buckets() {
+        let mut formatter = HistogramFormatter::default();
+        for (bucket, count) in [("2h+", "1"), ("30m-2h", "1"), ("0-30m", "1")] {
+            formatter.format_element("age_bucket", bucket, false);
+            formatter.format_element("count", count, true);
+            formatter.row_ended();
+        }
+
+        let result = formatter.footer().unwrap();
+        let labels: Vec<&str> = result.lines().map(|line| line.split(' ').next().unwrap()).collect();
+        assert_eq!(labels, vec!["0-30m", "30m-2h", "2h+"]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut formatter = HistogramFormatter::default();
+        for ext in ["rs", "md", "rs"] {
+            formatter.format_element("ext", ext, true);
+            formatter.row_ended();
+        }
+
+        assert_eq!(
+            formatter.footer().unwrap(),
+            "md | ████████████████████ 1\nrs | ████████████████████████████████████████ 2\n"
+        );
+    }
+}
@@ -1,39 +1,174 @@
-//! Handles export of results in HTML format
+//! Handles export of results in HTML format.
+//! The first row is held back until its column names are known, so they go to the table head.
 
 use crate::output::ResultsFormatter;
 
-pub struct HtmlFormatter;
+const STYLE: &str = "table{border-collapse:collapse;font-family:sans-serif;font-size:14px}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#eee}tr:nth-child(even) td{background:#f8f8f8}";
+
+const SORTING_STYLE: &str = "th{cursor:pointer;user-select:none}";
+
+/// Sorts the rows by the clicked column, numbers by their values, clicking again reverses the order
+const SORTING_SCRIPT: &str = "document.querySelectorAll('th').forEach(function(th,col){\
+th.addEventListener('click',function(){\
+var body=th.closest('table').tBodies[0];if(!body)return;\
+var asc=th.dataset.order!=='asc';th.dataset.order=asc?'asc':'desc';\
+var rows=Array.prototype.slice.call(body.rows);\
+rows.sort(function(a,b){\
+var x=a.cells[col].textContent,y=b.cells[col].textContent;\
+var r=(x!==''&&y!==''&&!isNaN(x)&&!isNaN(y))?x-y:x.localeCompare(y);\
+return asc?r:-r;});\
+rows.forEach(function(row){body.appendChild(row);});});});";
+
+/// Columns whose values link to the files
+const PATH_COLUMNS: [&str; 4] = ["Path", "AbsPath", "Directory", "AbsDir"];
+
+#[derive(Default)]
+pub struct HtmlFormatter {
+    sortable: bool,
+    /// Column names and cells of the first row, until it's complete
+    columns: Vec<String>,
+    cells: Vec<String>,
+    head_written: bool,
+}
+
+impl HtmlFormatter {
+    /// Lets the rows be sorted by clicking the column names
+    pub fn sortable() -> HtmlFormatter {
+        HtmlFormatter {
+            sortable: true,
+            ..HtmlFormatter::default()
+        }
+    }
+}
 
 impl ResultsFormatter for HtmlFormatter {
     fn header(&mut self) -> Option<String> {
-        Some("<html><body><table>".to_owned())
+        let sorting_style = if self.sortable { SORTING_STYLE } else { "" };
+
+        Some(format!(
+            "<html><head><meta charset=\"utf-8\"><style>{}{}</style></head><body><table>",
+            STYLE, sorting_style
+        ))
     }
 
     fn row_started(&mut self) -> Option<String> {
-        Some("<tr>".to_owned())
+        match self.head_written {
+            true => Some("<tr>".to_owned()),
+            false => None,
+        }
     }
 
-    fn format_element(&mut self, _: &str, record: &str, _is_last: bool) -> Option<String> {
-        Some(format!("<td>{}</td>", record))
+    fn format_element(&mut self, name: &str, record: &str, _is_last: bool) -> Option<String> {
+        let value = escape(record);
+        let cell = match PATH_COLUMNS.contains(&name) && !record.is_empty() {
+            true => format!("<td><a href=\"{}\">{}</a></td>", escape(&to_file_url(record)), value),
+            false => format!("<td>{}</td>", value),
+        };
+
+        if self.head_written {
+            return Some(cell);
+        }
+
+        self.columns.push(name.to_owned());
+        self.cells.push(cell);
+        None
     }
 
     fn row_ended(&mut self) -> Option<String> {
-        Some("</tr>".to_owned())
+        if self.head_written {
+            return Some("</tr>".to_owned());
+        }
+
+        self.head_written = true;
+        let head: String = self
+            .columns
+            .drain(..)
+            .map(|name| format!("<th>{}</th>", escape(&name)))
+            .collect();
+        let cells: String = self.cells.drain(..).collect();
+
+        Some(format!("<thead><tr>{}</tr></thead><tbody><tr>{}</tr>", head, cells))
     }
 
     fn footer(&mut self) -> Option<String> {
-        Some("</table></body></html>".to_owned())
+        let body_end = if self.head_written { "</tbody>" } else { "" };
+        let script = match self.sortable {
+            true => format!("<script>{}</script>", SORTING_SCRIPT),
+            false => String::new(),
+        };
+
+        Some(format!("{}</table>{}</body></html>", body_end, script))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Absolute paths become `file://` URLs, relative ones stay relative to the report
+fn to_file_url(path: &str) -> String {
+    let path = path.replace('\\', "/");
+
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    if encoded.starts_with('/') {
+        format!("file://{}", encoded)
+    } else if encoded.as_bytes().get(1) == Some(&b':') {
+        // Windows drive letter
+        format!("file:///{}", encoded)
+    } else {
+        encoded
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::output::html::HtmlFormatter;
+    use crate::output::html::{to_file_url, HtmlFormatter, STYLE};
     use crate::output::test::write_test_items;
+    use crate::output::ResultsFormatter;
 
     #[test]
     fn test() {
-        let result = write_test_items(&mut HtmlFormatter);
-        assert_eq!("<html><body><table><tr><td>foo_value</td><td>BAR value</td></tr><tr><td>123</td><td></td></tr></table></body></html>", result);
+        let result = write_test_items(&mut HtmlFormatter::default());
+        assert_eq!(
+            format!(
+                "<html><head><meta charset=\"utf-8\"><style>{}</style></head><body><table>\
+                <thead><tr><th>foo</th><th>bar</th></tr></thead>\
+                <tbody><tr><td>foo_value</td><td>BAR value</td></tr><tr><td>123</td><td></td></tr></tbody>\
+                </table></body></html>",
+                STYLE
+            ),
+            result
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let mut formatter = HtmlFormatter::sortable();
+        formatter.header();
+        formatter.row_started();
+        formatter.format_element("Path", "/home/user/a b#1.txt", false);
+        formatter.format_element("Name", "<a>.txt", true);
+        let row = formatter.row_ended().unwrap();
+        assert!(row.contains("<td><a href=\"file:///home/user/a%20b%231.txt\">/home/user/a b#1.txt</a></td>"));
+        assert!(row.contains("<td>&lt;a&gt;.txt</td>"));
+        assert!(formatter.footer().unwrap().contains("<script>"));
+
+        assert_eq!(to_file_url("C:\\Users\\x.txt"), "file:///C:/Users/x.txt");
+        assert_eq!(to_file_url("docs/a.txt"), "docs/a.txt");
     }
 }
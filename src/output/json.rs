@@ -1,16 +1,68 @@
-//! Handles export of results in JSON format
+//! Handles export of results in JSON and newline-delimited JSON formats
 
+use crate::function::VariantType;
 use crate::output::ResultsFormatter;
+use serde_json::{Number, Value};
 use std::collections::BTreeMap;
 
 #[derive(Default)]
 pub struct JsonFormatter {
-    file_map: BTreeMap<String, String>,
+    file_map: BTreeMap<String, Value>,
+    newline_delimited: bool,
+    /// Types of the columns by their positions, values of unknown types are written as strings
+    column_types: Vec<VariantType>,
+    /// Position of the next column in the row
+    column: usize,
+}
+
+impl JsonFormatter {
+    /// Outputs each object on a separate line instead of wrapping them into an array
+    pub fn ndjson() -> JsonFormatter {
+        JsonFormatter {
+            newline_delimited: true,
+            ..Default::default()
+        }
+    }
+
+    /// Writes numbers, booleans, and empty values of the columns as native JSON values
+    pub fn typed(self, column_types: Vec<VariantType>) -> JsonFormatter {
+        JsonFormatter {
+            column_types,
+            ..self
+        }
+    }
+}
+
+/// Values not matching the type of the column, like formatted sizes, stay strings
+fn to_json_value(value_type: Option<&VariantType>, record: &str) -> Value {
+    let value = match value_type {
+        None | Some(VariantType::String) => None,
+        Some(_) if record.is_empty() => Some(Value::Null),
+        Some(VariantType::Int) | Some(VariantType::Float) => match record.parse::<i64>() {
+            Ok(number) => Some(Value::from(number)),
+            Err(_) => record
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+        },
+        Some(VariantType::Bool) => match record {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Some(VariantType::DateTime) => None,
+    };
+
+    value.unwrap_or_else(|| Value::String(record.to_owned()))
 }
 
 impl ResultsFormatter for JsonFormatter {
     fn header(&mut self) -> Option<String> {
-        Some("[".to_owned())
+        match self.newline_delimited {
+            true => None,
+            false => Some("[".to_owned()),
+        }
     }
 
     fn row_started(&mut self) -> Option<String> {
@@ -18,29 +70,50 @@ impl ResultsFormatter for JsonFormatter {
     }
 
     fn format_element(&mut self, name: &str, record: &str, _is_last: bool) -> Option<String> {
-        self.file_map.insert(name.to_owned(), record.to_owned());
+        let value = to_json_value(self.column_types.get(self.column), record);
+        self.file_map.insert(name.to_owned(), value);
+        self.column += 1;
+        None
+    }
+
+    fn format_array(&mut self, name: &str, records: &[String], _is_last: bool) -> Option<String> {
+        let values = records.iter().cloned().map(Value::String).collect();
+        self.file_map.insert(name.to_owned(), Value::Array(values));
+        self.column += 1;
         None
     }
 
     fn row_ended(&mut self) -> Option<String> {
-        let result = serde_json::to_string(&self.file_map).unwrap();
+        let mut result = serde_json::to_string(&self.file_map).unwrap();
         self.file_map.clear();
+        self.column = 0;
+        if self.newline_delimited {
+            result.push('\n');
+        }
         Some(result)
     }
 
     fn footer(&mut self) -> Option<String> {
-        Some("]".to_owned())
+        match self.newline_delimited {
+            true => None,
+            false => Some("]".to_owned()),
+        }
     }
 
     fn row_separator(&self) -> Option<String> {
-        Some(",".to_owned())
+        match self.newline_delimited {
+            true => None,
+            false => Some(",".to_owned()),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::function::VariantType;
     use crate::output::json::JsonFormatter;
     use crate::output::test::write_test_items;
+    use crate::output::ResultsFormatter;
 
     #[test]
     fn test() {
@@ -50,4 +123,51 @@ mod test {
             result
         );
     }
+
+    #[test]
+    fn test_ndjson() {
+        let result = write_test_items(&mut JsonFormatter::ndjson());
+        assert_eq!(
+            "{\"bar\":\"BAR value\",\"foo\":\"foo_value\"}\n{\"bar\":\"\",\"foo\":\"123\"}\n",
+            result
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        let mut formatter = JsonFormatter::default();
+        formatter.format_element("ext", "mp3", false);
+        formatter.format_array("array_agg(name)", &[String::from("a.mp3"), String::from("b.mp3")], true);
+
+        assert_eq!(
+            r#"{"array_agg(name)":["a.mp3","b.mp3"],"ext":"mp3"}"#,
+            formatter.row_ended().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_typed() {
+        let types = vec![VariantType::Int, VariantType::Bool];
+        let result = write_test_items(&mut JsonFormatter::ndjson().typed(types));
+        assert_eq!(
+            "{\"bar\":\"BAR value\",\"foo\":\"foo_value\"}\n{\"bar\":null,\"foo\":123}\n",
+            result
+        );
+
+        let mut formatter = JsonFormatter::default().typed(vec![
+            VariantType::String,
+            VariantType::Float,
+            VariantType::Bool,
+            VariantType::DateTime,
+        ]);
+        formatter.format_element("name", "007", false);
+        formatter.format_element("size / 2", "1.5", false);
+        formatter.format_element("is_dir", "false", false);
+        formatter.format_element("modified", "2024-02-29 10:00:00", true);
+
+        assert_eq!(
+            r#"{"is_dir":false,"modified":"2024-02-29 10:00:00","name":"007","size / 2":1.5}"#,
+            formatter.row_ended().unwrap()
+        );
+    }
 }
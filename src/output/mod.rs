@@ -1,19 +1,53 @@
+use crate::output::chart::ChartFormatter;
 use crate::output::csv::CsvFormatter;
-use crate::output::flat::{LINES_FORMATTER, LIST_FORMATTER, TABS_FORMATTER};
+use crate::output::flat::{FlatWriter, LINES_FORMATTER, LIST_FORMATTER, TABS_FORMATTER};
+use crate::output::histogram::HistogramFormatter;
 use crate::output::html::HtmlFormatter;
 use crate::output::json::JsonFormatter;
-use crate::query::OutputFormat;
+use crate::output::pivot::PivotFormatter;
+use crate::output::sqlite::SqliteFormatter;
+use crate::output::table::TableFormatter;
+use crate::output::tsv::TsvFormatter;
+use crate::query::{ColumnFormat, OutputFormat, OutputOptions};
+use crate::util::{format_filesize, parse_datetime};
+use human_time::ToHumanTimeString;
 use std::io::Write;
+use std::time::Duration;
 
+#[cfg(feature = "arrow")]
+mod arrow;
+mod chart;
 mod csv;
 mod flat;
+mod histogram;
 mod html;
 mod json;
+pub mod partition;
+mod pivot;
+pub mod sink;
+mod sqlite;
+mod table;
+mod tsv;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+/// Value of a single column of the result row
+pub enum Record {
+    Value(String),
+    /// Values collected with ARRAY_AGG
+    Array(Vec<String>),
+}
 
 pub trait ResultsFormatter {
     fn header(&mut self) -> Option<String>;
     fn row_started(&mut self) -> Option<String>;
     fn format_element(&mut self, name: &str, record: &str, is_last: bool) -> Option<String>;
+
+    /// Formats are free to represent arrays natively, by default the values are joined
+    fn format_array(&mut self, name: &str, records: &[String], is_last: bool) -> Option<String> {
+        self.format_element(name, &records.join(", "), is_last)
+    }
+
     fn row_ended(&mut self) -> Option<String>;
     fn footer(&mut self) -> Option<String>;
 
@@ -24,12 +58,20 @@ pub trait ResultsFormatter {
 
 pub struct ResultsWriter {
     formatter: Box<dyn ResultsFormatter>,
+    /// Column names are still to be written before the first row
+    pending_headers: bool,
+    column_formats: Vec<Option<ColumnFormat>>,
+    column_names: Vec<Option<String>>,
 }
 
 impl ResultsWriter {
-    pub fn new(format: &OutputFormat) -> ResultsWriter {
+    pub fn new(format: &OutputFormat, options: &OutputOptions) -> ResultsWriter {
         ResultsWriter {
-            formatter: select_formatter(format),
+            formatter: select_formatter(format, options),
+            pending_headers: (options.headers && (*format == OutputFormat::Csv || format.is_flat()))
+                || matches!(format, OutputFormat::Xlsx | OutputFormat::Arrow),
+            column_formats: options.column_formats.clone(),
+            column_names: options.column_names.clone(),
         }
     }
 
@@ -50,10 +92,45 @@ impl ResultsWriter {
         writer: &mut dyn Write,
         values: Vec<(String, String)>,
     ) -> std::io::Result<()> {
+        let records = values
+            .into_iter()
+            .map(|(name, value)| (name, Record::Value(value)))
+            .collect();
+        self.write_records(writer, records)
+    }
+
+    pub fn write_records(
+        &mut self,
+        writer: &mut dyn Write,
+        mut records: Vec<(String, Record)>,
+    ) -> std::io::Result<()> {
+        // Aliased columns go by their aliases in the headers and the keys of the values
+        for (alias, (name, _)) in self.column_names.iter().zip(records.iter_mut()) {
+            if let Some(alias) = alias {
+                name.clone_from(alias);
+            }
+        }
+
+        if self.pending_headers {
+            self.pending_headers = false;
+            let names = records
+                .iter()
+                .map(|(name, _)| (name.clone(), Record::Value(name.clone())))
+                .collect();
+            self.write_records(writer, names)?;
+        }
+
+        // Applied in place, values of columns without a format are kept
+        for (format, (_, record)) in self.column_formats.iter().zip(records.iter_mut()) {
+            if let (Some(format), Record::Value(value)) = (format, record) {
+                *value = format_value(format, std::mem::take(value));
+            }
+        }
+
         self.write_row_start(writer)?;
-        let len = values.len();
-        for (pos, (name, value)) in values.iter().enumerate() {
-            self.write_row_item(writer, name, value, pos == len - 1)?;
+        let len = records.len();
+        for (pos, (name, record)) in records.iter().enumerate() {
+            self.write_row_item(writer, name, record, pos == len - 1)?;
         }
         self.write_row_end(writer)
     }
@@ -73,12 +150,15 @@ impl ResultsWriter {
         &mut self,
         writer: &mut dyn Write,
         name: &str,
-        value: &str,
+        record: &Record,
         is_last: bool,
     ) -> std::io::Result<()> {
-        self.formatter
-            .format_element(name, value, is_last)
-            .map_or(Ok(()), |value| write!(writer, "{}", value))
+        let formatted = match record {
+            Record::Value(value) => self.formatter.format_element(name, value, is_last),
+            Record::Array(values) => self.formatter.format_array(name, values, is_last),
+        };
+
+        formatted.map_or(Ok(()), |value| write!(writer, "{}", value))
     }
 
     fn write_row_end(&mut self, writer: &mut dyn Write) -> std::io::Result<()> {
@@ -88,20 +168,83 @@ impl ResultsWriter {
     }
 }
 
-fn select_formatter(format: &OutputFormat) -> Box<dyn ResultsFormatter> {
+/// Applies the display format of the column, values not fitting it are kept as they are
+fn format_value(format: &ColumnFormat, value: String) -> String {
+    let formatted = match format {
+        ColumnFormat::Size(modifier) => value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|size| format_filesize(size.max(0.0) as u64, modifier)),
+        ColumnFormat::Date(pattern) => parse_datetime(&value).ok().and_then(|(dt, _)| {
+            // Invalid specifiers make chrono fail instead of printing anything
+            let mut result = String::new();
+            std::fmt::write(&mut result, format_args!("{}", dt.format(pattern)))
+                .ok()
+                .map(|_| result)
+        }),
+        ColumnFormat::Time => value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|seconds| Duration::from_secs(seconds.max(0.0) as u64).to_human_time_string()),
+    };
+
+    formatted.unwrap_or(value)
+}
+
+fn select_formatter(format: &OutputFormat, options: &OutputOptions) -> Box<dyn ResultsFormatter> {
     match format {
-        OutputFormat::Tabs => Box::new(TABS_FORMATTER),
+        OutputFormat::Tabs => match options.delimiter {
+            Some(delimiter) => Box::new(FlatWriter::delimited(delimiter)),
+            None => Box::new(TABS_FORMATTER),
+        },
+        OutputFormat::Tsv => Box::<TsvFormatter>::default(),
         OutputFormat::Lines => Box::new(LINES_FORMATTER),
         OutputFormat::List => Box::new(LIST_FORMATTER),
-        OutputFormat::Csv => Box::<CsvFormatter>::default(),
-        OutputFormat::Json => Box::<JsonFormatter>::default(),
-        OutputFormat::Html => Box::new(HtmlFormatter),
+        OutputFormat::Csv => Box::new(CsvFormatter::new(
+            options.delimiter.map(|delimiter| delimiter as u8),
+            options.quoting,
+        )),
+        OutputFormat::Json => {
+            Box::new(JsonFormatter::default().typed(options.column_types.clone()))
+        }
+        OutputFormat::Ndjson => Box::new(JsonFormatter::ndjson().typed(options.column_types.clone())),
+        OutputFormat::Html => match options.sortable {
+            true => Box::new(HtmlFormatter::sortable()),
+            false => Box::<HtmlFormatter>::default(),
+        },
+        OutputFormat::Histogram => Box::<HistogramFormatter>::default(),
+        OutputFormat::Chart => Box::<ChartFormatter>::default(),
+        OutputFormat::Table { borders: false } => Box::<TableFormatter>::default(),
+        OutputFormat::Table { borders: true } => Box::new(TableFormatter::with_borders()),
+        OutputFormat::Pivot(_) => Box::<PivotFormatter>::default(),
+        OutputFormat::Sqlite => Box::<SqliteFormatter>::default(),
+        // Workbooks and Arrow streams are built from CSV by the sink
+        OutputFormat::Xlsx | OutputFormat::Arrow => Box::<CsvFormatter>::default(),
+        // Profiles are resolved from the config before the search
+        OutputFormat::Profile(_) => Box::new(TABS_FORMATTER),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::output::ResultsFormatter;
+    use crate::output::{format_value, ResultsFormatter};
+    use crate::query::ColumnFormat;
+
+    #[test]
+    fn test_column_formats() {
+        let size = ColumnFormat::Size(String::from("%.1 "));
+        assert_eq!(format_value(&size, String::from("1536")), "1.5 KiB");
+        assert_eq!(format_value(&size, String::new()), "");
+
+        let date = ColumnFormat::Date(String::from("%d.%m.%Y"));
+        assert_eq!(format_value(&date, String::from("2024-02-29 10:00:00")), "29.02.2024");
+        assert_eq!(format_value(&date, String::from("not a date")), "not a date");
+
+        let invalid = ColumnFormat::Date(String::from("%Q"));
+        assert_eq!(format_value(&invalid, String::from("2024-02-29")), "2024-02-29");
+    }
 
     pub(crate) fn write_test_items<T: ResultsFormatter>(under_test: &mut T) -> String {
         let mut result = String::from("");
@@ -0,0 +1,290 @@
+//! Splits the results into files by the value of the partition expression,
+//! e.g. `report_jpg.csv` and `report_png.csv` for `into csv 'report.csv' partition by ext`
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::output::sink::open_sink;
+use crate::output::{Record, ResultsWriter};
+use crate::query::{Compression, OutputFormat, OutputOptions, OutputSink};
+
+pub struct PartitionedWriter {
+    format: OutputFormat,
+    options: OutputOptions,
+    sink: OutputSink,
+    /// Partitions by the path of their file, as different values could be sanitized to the same name
+    partitions: BTreeMap<PathBuf, Partition>,
+}
+
+/// Rows of a single partition with its own header and separators.
+/// They are kept in memory until the end, so that there are never more files open than one,
+/// however many distinct values there are.
+struct Partition {
+    results_writer: ResultsWriter,
+    output: Vec<u8>,
+    rows: usize,
+}
+
+impl PartitionedWriter {
+    pub fn new(format: &OutputFormat, options: &OutputOptions, sink: &OutputSink) -> PartitionedWriter {
+        PartitionedWriter {
+            format: format.clone(),
+            options: options.clone(),
+            sink: sink.clone(),
+            partitions: BTreeMap::new(),
+        }
+    }
+
+    pub fn write_row(&mut self, values: Vec<(String, String)>) -> io::Result<()> {
+        let records = values
+            .into_iter()
+            .map(|(name, value)| (name, Record::Value(value)))
+            .collect();
+        self.write_records(records)
+    }
+
+    /// Adds the row to its partition, which is chosen by the value of the last record
+    pub fn write_records(&mut self, mut records: Vec<(String, Record)>) -> io::Result<()> {
+        let key = match records.pop() {
+            Some((_, Record::Value(value))) => value,
+            Some((_, Record::Array(values))) => values.join(", "),
+            None => String::new(),
+        };
+
+        let path = partition_path(&self.sink, &self.format, &key);
+        let partition = match self.partitions.get_mut(&path) {
+            Some(partition) => partition,
+            None => {
+                let mut results_writer = ResultsWriter::new(&self.format, &self.options);
+                let mut output = Vec::new();
+                results_writer.write_header(&mut output)?;
+                let partition = Partition {
+                    results_writer,
+                    output,
+                    rows: 0,
+                };
+                self.partitions.entry(path).or_insert(partition)
+            }
+        };
+
+        if partition.rows > 0 {
+            partition.results_writer.write_row_separator(&mut partition.output)?;
+        }
+        partition.results_writer.write_records(&mut partition.output, records)?;
+        partition.rows += 1;
+
+        Ok(())
+    }
+
+    /// Writes the files of the partitions one after another.
+    /// Every partition is tried, the first error is returned.
+    pub fn write_footers(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+
+        for (path, partition) in self.partitions.iter_mut() {
+            let written = partition
+                .results_writer
+                .write_footer(&mut partition.output)
+                .and_then(|_| {
+                    write_partition(
+                        path,
+                        &self.format,
+                        &self.options,
+                        self.sink.compression,
+                        &partition.output,
+                    )
+                });
+            partition.output = Vec::new();
+
+            if let Err(e) = written {
+                if result.is_ok() {
+                    result = Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+fn write_partition(
+    path: &Path,
+    format: &OutputFormat,
+    options: &OutputOptions,
+    compression: Option<Compression>,
+    contents: &[u8],
+) -> io::Result<()> {
+    let sink = OutputSink {
+        path: Some(path.to_path_buf()),
+        compression,
+    };
+    let mut output =
+        open_sink(&sink, format, options)?.unwrap_or_else(|| Box::new(io::stdout()));
+    output.write_all(contents)?;
+    output.flush()
+}
+
+/// The value goes after the name of the file, rows without it are written to the file itself
+fn partition_path(sink: &OutputSink, format: &OutputFormat, value: &str) -> PathBuf {
+    let path = match sink.path {
+        Some(ref path) => path.clone(),
+        None => PathBuf::from(default_file_name(format, sink.compression)),
+    };
+
+    if value.is_empty() {
+        return path;
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let (stem, extensions) = match file_name.split_once('.') {
+        Some((stem, extensions)) => (stem, format!(".{}", extensions)),
+        None => (file_name.as_str(), String::new()),
+    };
+
+    path.with_file_name(format!("{}_{}{}", stem, sanitize(value), extensions))
+}
+
+fn default_file_name(format: &OutputFormat, compression: Option<Compression>) -> String {
+    let extension = match format {
+        OutputFormat::Csv => "csv",
+        OutputFormat::Json => "json",
+        OutputFormat::Ndjson => "ndjson",
+        OutputFormat::Html => "html",
+        OutputFormat::Tabs | OutputFormat::Tsv => "tsv",
+        OutputFormat::Sqlite => "db",
+        OutputFormat::Xlsx => "xlsx",
+        OutputFormat::Arrow => "arrows",
+        _ => "txt",
+    };
+
+    match compression {
+        Some(Compression::Gzip) => format!("output.{}.gz", extension),
+        Some(Compression::Zstd) => format!("output.{}.zst", extension),
+        None => format!("output.{}", extension),
+    }
+}
+
+/// Values are paths sometimes, they must not point anywhere else
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '-' || c == '.' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::output::partition::{partition_path, PartitionedWriter};
+    use crate::query::{Compression, OutputFormat, OutputOptions, OutputSink};
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_partition_path() {
+        let sink = OutputSink {
+            path: Some(PathBuf::from("/tmp/report.csv.gz")),
+            compression: Some(Compression::Gzip),
+        };
+        assert_eq!(
+            partition_path(&sink, &OutputFormat::Csv, "jpg"),
+            PathBuf::from("/tmp/report_jpg.csv.gz")
+        );
+        assert_eq!(
+            partition_path(&sink, &OutputFormat::Csv, ""),
+            PathBuf::from("/tmp/report.csv.gz")
+        );
+        assert_eq!(
+            partition_path(&sink, &OutputFormat::Csv, "../etc/passwd"),
+            PathBuf::from("/tmp/report_.._etc_passwd.csv.gz")
+        );
+        assert_eq!(
+            partition_path(&OutputSink::default(), &OutputFormat::Json, "png"),
+            PathBuf::from("output_png.json")
+        );
+    }
+
+    #[test]
+    fn test_partitions() {
+        let dir = TempDir::new("partitions");
+
+        let sink = OutputSink {
+            path: Some(dir.join("report.json")),
+            compression: None,
+        };
+        let mut writer = PartitionedWriter::new(&OutputFormat::Json, &OutputOptions::default(), &sink);
+        for (name, ext) in [("a.jpg", "jpg"), ("b.png", "png"), ("c.jpg", "jpg")] {
+            let row = vec![
+                (String::from("name"), String::from(name)),
+                (String::from("ext"), String::from(ext)),
+            ];
+            writer.write_row(row).unwrap();
+        }
+        writer.write_footers().unwrap();
+        drop(writer);
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report_jpg.json")).unwrap(),
+            "[{\"name\":\"a.jpg\"},{\"name\":\"c.jpg\"}]"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report_png.json")).unwrap(),
+            "[{\"name\":\"b.png\"}]"
+        );
+    }
+
+    #[test]
+    fn test_partitions_sharing_file() {
+        let dir = TempDir::new("partitions_sharing_file");
+
+        let sink = OutputSink {
+            path: Some(dir.join("report.csv")),
+            compression: None,
+        };
+        let mut writer = PartitionedWriter::new(&OutputFormat::Csv, &OutputOptions::default(), &sink);
+        for (name, dir_name) in [("a", "x/y"), ("b", "x_y"), ("c", "x/y")] {
+            let row = vec![
+                (String::from("name"), String::from(name)),
+                (String::from("dir"), String::from(dir_name)),
+            ];
+            writer.write_row(row).unwrap();
+        }
+        writer.write_footers().unwrap();
+        drop(writer);
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report_x_y.csv")).unwrap(),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_many_partitions() {
+        let dir = TempDir::new("many_partitions");
+
+        let sink = OutputSink {
+            path: Some(dir.join("report.csv")),
+            compression: None,
+        };
+        let mut writer = PartitionedWriter::new(&OutputFormat::Csv, &OutputOptions::default(), &sink);
+        for i in 0..2000 {
+            let row = vec![
+                (String::from("name"), i.to_string()),
+                (String::from("key"), i.to_string()),
+            ];
+            writer.write_row(row).unwrap();
+        }
+        writer.write_footers().unwrap();
+        drop(writer);
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2000);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("report_1999.csv")).unwrap(),
+            "1999\n"
+        );
+    }
+}
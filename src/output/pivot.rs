@@ -0,0 +1,134 @@
+//! Handles rendering of grouped results as a matrix, with the values of the first key as rows
+//! and the values of the second key as columns
+
+use std::cmp::Ordering;
+
+use crate::output::ResultsFormatter;
+
+#[derive(Default)]
+pub struct PivotFormatter {
+    /// Name of the rows key, the first column of the header
+    rows_name: String,
+    row: Vec<String>,
+    /// Row key, column key, and value of each group
+    cells: Vec<(String, String, String)>,
+}
+
+impl PivotFormatter {
+    fn render(&self) -> String {
+        let mut row_keys: Vec<&String> = self.cells.iter().map(|(row, _, _)| row).collect();
+        let mut col_keys: Vec<&String> = self.cells.iter().map(|(_, col, _)| col).collect();
+        for keys in [&mut row_keys, &mut col_keys] {
+            keys.sort_by(|a, b| compare_keys(a, b));
+            keys.dedup();
+        }
+
+        let mut table = vec![];
+
+        let mut header = vec![self.rows_name.clone()];
+        header.extend(col_keys.iter().map(|col| col.to_string()));
+        table.push(header);
+
+        for row in &row_keys {
+            let mut line = vec![row.to_string()];
+            for col in &col_keys {
+                let value = self
+                    .cells
+                    .iter()
+                    .find(|(r, c, _)| r == *row && c == *col)
+                    .map(|(_, _, value)| value.clone())
+                    .unwrap_or_default();
+                line.push(value);
+            }
+            table.push(line);
+        }
+
+        let widths: Vec<usize> = (0..=col_keys.len())
+            .map(|i| table.iter().map(|line| line[i].chars().count()).max().unwrap_or(0))
+            .collect();
+
+        let mut result = String::new();
+        for line in &table {
+            let formatted: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (value, width))| match i {
+                    0 => format!("{:<width$}", value),
+                    _ => format!("{:>width$}", value),
+                })
+                .collect();
+            result.push_str(formatted.join("  ").trim_end());
+            result.push('\n');
+        }
+
+        result
+    }
+}
+
+/// Numbers like years go in numeric order, anything else alphabetically
+fn compare_keys(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+impl ResultsFormatter for PivotFormatter {
+    fn header(&mut self) -> Option<String> {
+        None
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, name: &str, record: &str, _is_last: bool) -> Option<String> {
+        if self.row.is_empty() {
+            self.rows_name = name.to_owned();
+        }
+        self.row.push(record.to_owned());
+        None
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        let mut row = std::mem::take(&mut self.row).into_iter();
+
+        if let (Some(row_key), Some(col_key), Some(value)) = (row.next(), row.next(), row.next()) {
+            self.cells.push((row_key, col_key, value));
+        }
+
+        None
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        Some(self.render())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::pivot::PivotFormatter;
+    use crate::output::ResultsFormatter;
+
+    #[test]
+    fn test() {
+        let mut formatter = PivotFormatter::default();
+        for (ext, year, count) in [("rs", "2024", "10"), ("md", "2023", "2"), ("rs", "2023", "120"), ("", "2024", "1")] {
+            formatter.format_element("extension", ext, false);
+            formatter.format_element("year(modified)", year, false);
+            formatter.format_element("count(*)", count, true);
+            formatter.row_ended();
+        }
+
+        assert_eq!(
+            formatter.footer().unwrap(),
+            concat!(
+                "extension  2023  2024\n",
+                "                    1\n",
+                "md            2\n",
+                "rs          120    10\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Opens the destination of the results, compressing them as they are written
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sqlite")]
+use rusqlite::Connection;
+
+#[cfg(feature = "arrow")]
+use crate::output::arrow::ArrowWriter;
+#[cfg(feature = "xlsx")]
+use crate::output::xlsx::XlsxWriter;
+use crate::query::{Compression, OutputFormat, OutputOptions, OutputSink};
+
+/// Returns nothing if the results go to stdout as they are.
+/// Compressed streams are finished when the writer is dropped.
+/// SQLite results are executed against the database file instead of being written to it,
+/// Excel workbooks and Arrow streams are built from the results when the writer is dropped.
+/// Files replace their destinations only when complete.
+#[cfg_attr(not(feature = "arrow"), allow(unused_variables))]
+pub fn open_sink(
+    sink: &OutputSink,
+    format: &OutputFormat,
+    options: &OutputOptions,
+) -> io::Result<Option<Box<dyn Write>>> {
+    match format {
+        #[cfg(feature = "xlsx")]
+        OutputFormat::Xlsx => return Ok(Some(Box::new(XlsxWriter::new(sink.path.clone())))),
+        #[cfg(not(feature = "xlsx"))]
+        OutputFormat::Xlsx => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "fselect was built without xlsx feature",
+            ));
+        }
+        #[cfg(feature = "arrow")]
+        OutputFormat::Arrow => {
+            let writer = ArrowWriter::new(sink.path.clone(), options.column_types.clone());
+            return Ok(Some(Box::new(writer)));
+        }
+        #[cfg(not(feature = "arrow"))]
+        OutputFormat::Arrow => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "fselect was built without arrow feature",
+            ));
+        }
+        _ => {}
+    }
+
+    let writer: Box<dyn Write> = match sink.path {
+        #[cfg(feature = "sqlite")]
+        Some(ref path) if *format == OutputFormat::Sqlite => {
+            return Ok(Some(Box::new(SqliteWriter::open(path)?)));
+        }
+        #[cfg(not(feature = "sqlite"))]
+        Some(_) if *format == OutputFormat::Sqlite => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "fselect was built without sqlite feature",
+            ));
+        }
+        Some(ref path) => Box::new(AtomicFile::create(path)?),
+        None if sink.compression.is_some() => Box::new(io::stdout()),
+        None => return Ok(None),
+    };
+
+    let writer: Box<dyn Write> = match sink.compression {
+        Some(Compression::Gzip) => Box::new(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        )),
+        Some(Compression::Zstd) => Box::new(zstd::Encoder::new(writer, 0)?.auto_finish()),
+        None => writer,
+    };
+
+    Ok(Some(Box::new(BufWriter::new(writer))))
+}
+
+/// Workbooks and Arrow streams are built from the rows written as CSV
+#[cfg(any(feature = "xlsx", feature = "arrow"))]
+pub(super) fn read_rows(contents: &[u8]) -> Vec<Vec<String>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(contents)
+        .records()
+        .filter_map(|record| record.ok())
+        .map(|record| record.iter().map(String::from).collect())
+        .collect()
+}
+
+/// Writes into a temporary file next to the destination, which is renamed to it when dropped,
+/// so partial results never appear under the name of the file
+pub(super) struct AtomicFile {
+    file: Option<File>,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    failed: bool,
+}
+
+impl AtomicFile {
+    pub(super) fn create(path: &Path) -> io::Result<AtomicFile> {
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Output path doesn't name a file")
+        })?;
+        let tmp_path = path.with_file_name(format!(
+            ".{}.tmp{}",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+
+        Ok(AtomicFile {
+            file: Some(File::create(&tmp_path)?),
+            path: path.to_path_buf(),
+            tmp_path,
+            failed: false,
+        })
+    }
+
+    fn persist(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(file) => {
+                file.sync_all()?;
+                drop(file);
+                fs::rename(&self.tmp_path, &self.path)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = match self.file {
+            Some(ref mut file) => file.write(buf),
+            None => Err(io::Error::other("Output file is already closed")),
+        };
+
+        self.failed |= result.is_err();
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file {
+            Some(ref mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AtomicFile {
+    /// Incomplete files are removed, the destination keeps its previous contents
+    fn drop(&mut self) {
+        if !self.failed {
+            match self.persist() {
+                Ok(()) => return,
+                Err(err) => eprintln!("{}: {}", self.path.display(), err),
+            }
+        }
+
+        self.file = None;
+        let _ = fs::remove_file(&self.tmp_path);
+    }
+}
+
+/// Collects the SQL statements and executes them when flushed or dropped
+#[cfg(feature = "sqlite")]
+struct SqliteWriter {
+    connection: Connection,
+    statements: Vec<u8>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteWriter {
+    fn open(path: &Path) -> io::Result<SqliteWriter> {
+        let connection = Connection::open(path).map_err(io::Error::other)?;
+
+        Ok(SqliteWriter {
+            connection,
+            statements: vec![],
+        })
+    }
+
+    fn execute(&mut self) -> rusqlite::Result<()> {
+        if self.statements.is_empty() {
+            return Ok(());
+        }
+
+        let statements = String::from_utf8_lossy(&self.statements).to_string();
+        self.statements.clear();
+
+        self.connection.execute_batch(&statements)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Write for SqliteWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.statements.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// The results are flushed after the complete rows, so the statements are never cut
+    fn flush(&mut self) -> io::Result<()> {
+        self.execute().map_err(io::Error::other)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Drop for SqliteWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.execute() {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use crate::output::sink::open_sink;
+    use crate::query::{Compression, OutputFormat, OutputOptions, OutputSink};
+    use crate::util::open_content_reader;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_compressed_files() {
+        let dir = TempDir::new("sink");
+        let options = OutputOptions::default();
+
+        for (name, compression) in [
+            ("out.json.gz", Some(Compression::Gzip)),
+            ("out.json.zst", Some(Compression::Zstd)),
+            ("out.json", None),
+        ] {
+            let path = dir.join(name);
+            let sink = OutputSink {
+                path: Some(path.clone()),
+                compression,
+            };
+
+            {
+                let mut writer = open_sink(&sink, &OutputFormat::Json, &options).unwrap().unwrap();
+                writer.write_all(b"[{\"name\":\"foo\"}]\n").unwrap();
+            }
+
+            let mut contents = String::new();
+            open_content_reader(&path, None)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            assert_eq!(contents, "[{\"name\":\"foo\"}]\n");
+        }
+
+        assert!(open_sink(&OutputSink::default(), &OutputFormat::Json, &options).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_atomic_file() {
+        let dir = TempDir::new("atomic");
+        let options = OutputOptions::default();
+
+        let path = dir.join("report.csv");
+        std::fs::write(&path, "old\n").unwrap();
+        let sink = OutputSink {
+            path: Some(path.clone()),
+            compression: None,
+        };
+
+        {
+            let mut writer = open_sink(&sink, &OutputFormat::Csv, &options).unwrap().unwrap();
+            writer.write_all(b"foo,1\n").unwrap();
+            writer.flush().unwrap();
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\n");
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "foo,1\n");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_sqlite_database() {
+        let dir = TempDir::new("sqlite");
+        let options = OutputOptions::default();
+
+        let path = dir.join("results.db");
+        let sink = OutputSink {
+            path: Some(path.clone()),
+            compression: None,
+        };
+
+        for _ in 0..2 {
+            let mut writer = open_sink(&sink, &OutputFormat::Sqlite, &options).unwrap().unwrap();
+            writer
+                .write_all(b"BEGIN;\nDROP TABLE IF EXISTS results;\nCREATE TABLE results (\"name\", \"size\");\n")
+                .unwrap();
+            writer.write_all(b"INSERT INTO results VALUES ('foo', 1024);\nCOMMIT;\n").unwrap();
+        }
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let size: i64 = connection
+            .query_row("SELECT sum(size) FROM results WHERE name = 'foo'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(size, 1024);
+    }
+}
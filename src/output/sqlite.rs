@@ -0,0 +1,129 @@
+//! Handles export of results as SQL statements filling the `results` table of a SQLite database
+
+use crate::output::ResultsFormatter;
+
+const TABLE_NAME: &str = "results";
+
+#[derive(Default)]
+pub struct SqliteFormatter {
+    columns: Vec<String>,
+    values: Vec<String>,
+    /// The table is created when the column names are known, i.e. with the first row
+    table_created: bool,
+}
+
+impl ResultsFormatter for SqliteFormatter {
+    fn header(&mut self) -> Option<String> {
+        Some(String::from("BEGIN;\n"))
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, name: &str, record: &str, _is_last: bool) -> Option<String> {
+        if !self.table_created {
+            self.columns.push(unique_column_name(&self.columns, name));
+        }
+        self.values.push(to_sql_literal(record));
+        None
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        let mut result = String::new();
+
+        if !self.table_created {
+            self.table_created = true;
+            let columns = self
+                .columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            result.push_str(&format!("DROP TABLE IF EXISTS {};\n", TABLE_NAME));
+            result.push_str(&format!("CREATE TABLE {} ({});\n", TABLE_NAME, columns));
+        }
+
+        result.push_str(&format!(
+            "INSERT INTO {} VALUES ({});\n",
+            TABLE_NAME,
+            self.values.join(", ")
+        ));
+        self.values.clear();
+
+        Some(result)
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        Some(String::from("COMMIT;\n"))
+    }
+}
+
+/// Columns selected twice, like `name, name`, get a numbered suffix
+fn unique_column_name(columns: &[String], name: &str) -> String {
+    let mut column = name.to_string();
+    let mut suffix = 1;
+
+    while columns.contains(&column) {
+        suffix += 1;
+        column = format!("{}_{}", name, suffix);
+    }
+
+    column
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Numbers are stored as such, so that they are compared and summed as numbers.
+/// Empty values become NULL.
+fn to_sql_literal(value: &str) -> String {
+    if value.is_empty() {
+        return String::from("NULL");
+    }
+
+    // Leading zeros and plus signs are kept in text, like in permissions or phone numbers
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let has_leading_zero = digits.starts_with('0') && digits.len() > 1 && !digits.starts_with("0.");
+    let is_integer = value.parse::<i64>().is_ok_and(|n| n.to_string() == value);
+    let is_real = !has_leading_zero
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '-')
+        && value.parse::<f64>().is_ok_and(|n| n.is_finite());
+
+    if is_integer || is_real {
+        return value.to_string();
+    }
+
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::sqlite::{to_sql_literal, SqliteFormatter};
+    use crate::output::test::write_test_items;
+
+    #[test]
+    fn test() {
+        let result = write_test_items(&mut SqliteFormatter::default());
+        assert_eq!(
+            result,
+            "BEGIN;\nDROP TABLE IF EXISTS results;\nCREATE TABLE results (\"foo\", \"bar\");\n\
+            INSERT INTO results VALUES ('foo_value', 'BAR value');\n\
+            INSERT INTO results VALUES (123, NULL);\nCOMMIT;\n"
+        );
+    }
+
+    #[test]
+    fn test_sql_literals() {
+        assert_eq!(to_sql_literal("1024"), "1024");
+        assert_eq!(to_sql_literal("-1.5"), "-1.5");
+        assert_eq!(to_sql_literal("0644"), "'0644'");
+        assert_eq!(to_sql_literal("0.5"), "0.5");
+        assert_eq!(to_sql_literal("-0.5"), "-0.5");
+        assert_eq!(to_sql_literal("0"), "0");
+        assert_eq!(to_sql_literal("1.2.3"), "'1.2.3'");
+        assert_eq!(to_sql_literal("it's"), "'it''s'");
+        assert_eq!(to_sql_literal("NaN"), "'NaN'");
+    }
+}
@@ -0,0 +1,207 @@
+//! Handles rendering of results as an aligned table, like psql does
+
+use crate::output::ResultsFormatter;
+
+/// Characters the table is drawn with
+struct TableStyle {
+    vertical: &'static str,
+    horizontal: &'static str,
+    /// Left, middle, and right junctions of the top, header separator, and bottom lines
+    top: Option<[&'static str; 3]>,
+    separator: [&'static str; 3],
+    bottom: Option<[&'static str; 3]>,
+}
+
+const PLAIN: TableStyle = TableStyle {
+    vertical: "|",
+    horizontal: "-",
+    top: None,
+    separator: ["", "+", ""],
+    bottom: None,
+};
+
+const BOX: TableStyle = TableStyle {
+    vertical: "│",
+    horizontal: "─",
+    top: Some(["┌", "┬", "┐"]),
+    separator: ["├", "┼", "┤"],
+    bottom: Some(["└", "┴", "┘"]),
+};
+
+/// Rows are buffered to fit the widths of the columns to the longest values.
+/// Numbers are aligned to the right.
+#[derive(Default)]
+pub struct TableFormatter {
+    borders: bool,
+    columns: Vec<String>,
+    row: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl TableFormatter {
+    /// Draws the table with box-drawing characters, including the outer borders
+    pub fn with_borders() -> TableFormatter {
+        TableFormatter {
+            borders: true,
+            ..Default::default()
+        }
+    }
+
+    fn render(&self) -> String {
+        let style = match self.borders {
+            true => &BOX,
+            false => &PLAIN,
+        };
+
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .chain(std::iter::once(column))
+                    .map(|value| value.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let numeric: Vec<bool> = (0..self.columns.len())
+            .map(|i| {
+                let values: Vec<&String> = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                !values.is_empty() && values.iter().all(|value| value.trim().parse::<f64>().is_ok())
+            })
+            .collect();
+
+        let mut result = String::new();
+
+        if let Some(junctions) = style.top {
+            result.push_str(&render_line(style, &widths, junctions));
+        }
+
+        let header: Vec<String> = widths
+            .iter()
+            .zip(&self.columns)
+            .map(|(width, column)| format!("{:^width$}", column))
+            .collect();
+        result.push_str(&render_row(style, self.borders, &header));
+        result.push_str(&render_line(style, &widths, style.separator));
+
+        for row in &self.rows {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    let value = row.get(i).map(String::as_str).unwrap_or_default();
+                    match numeric[i] {
+                        true => format!("{:>width$}", value),
+                        false => format!("{:<width$}", value),
+                    }
+                })
+                .collect();
+            result.push_str(&render_row(style, self.borders, &cells));
+        }
+
+        if let Some(junctions) = style.bottom {
+            result.push_str(&render_line(style, &widths, junctions));
+        }
+
+        result.push_str(&match self.rows.len() {
+            1 => String::from("(1 row)\n"),
+            count => format!("({} rows)\n", count),
+        });
+
+        result
+    }
+}
+
+fn render_row(style: &TableStyle, borders: bool, cells: &[String]) -> String {
+    let inner = cells
+        .iter()
+        .map(|cell| format!(" {} ", cell))
+        .collect::<Vec<_>>()
+        .join(style.vertical);
+
+    match borders {
+        true => format!("{}{}{}\n", style.vertical, inner, style.vertical),
+        false => format!("{}\n", inner.trim_end()),
+    }
+}
+
+fn render_line(style: &TableStyle, widths: &[usize], [left, middle, right]: [&str; 3]) -> String {
+    let inner = widths
+        .iter()
+        .map(|width| style.horizontal.repeat(width + 2))
+        .collect::<Vec<_>>()
+        .join(middle);
+
+    format!("{}{}{}\n", left, inner, right)
+}
+
+impl ResultsFormatter for TableFormatter {
+    fn header(&mut self) -> Option<String> {
+        None
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, name: &str, record: &str, _is_last: bool) -> Option<String> {
+        if self.rows.is_empty() && self.row.len() == self.columns.len() {
+            self.columns.push(name.to_owned());
+        }
+        self.row.push(record.to_owned());
+        None
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        self.rows.push(std::mem::take(&mut self.row));
+        None
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        if self.rows.is_empty() {
+            return Some(String::from("(0 rows)\n"));
+        }
+
+        Some(self.render())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::table::TableFormatter;
+    use crate::output::test::write_test_items;
+
+    #[test]
+    fn test() {
+        let result = write_test_items(&mut TableFormatter::default());
+        assert_eq!(
+            result,
+            "    foo    |    bar\n-----------+-----------\n foo_value | BAR value\n 123       |\n(2 rows)\n"
+        );
+    }
+
+    #[test]
+    fn test_borders() {
+        let result = write_test_items(&mut TableFormatter::with_borders());
+        assert_eq!(
+            result,
+            "┌───────────┬───────────┐\n\
+             │    foo    │    bar    │\n\
+             ├───────────┼───────────┤\n\
+             │ foo_value │ BAR value │\n\
+             │ 123       │           │\n\
+             └───────────┴───────────┘\n\
+             (2 rows)\n"
+        );
+    }
+}
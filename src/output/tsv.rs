@@ -0,0 +1,75 @@
+//! Handles export of results in TSV format.
+//! Tabs, line breaks, and backslashes inside the values are escaped with backslashes,
+//! so every line is a row and every tab separates its columns whatever the file names are.
+
+use crate::output::ResultsFormatter;
+
+#[derive(Default)]
+pub struct TsvFormatter;
+
+impl ResultsFormatter for TsvFormatter {
+    fn header(&mut self) -> Option<String> {
+        None
+    }
+
+    fn row_started(&mut self) -> Option<String> {
+        None
+    }
+
+    fn format_element(&mut self, _: &str, record: &str, is_last: bool) -> Option<String> {
+        let value = escape(record);
+        match is_last {
+            true => Some(value),
+            false => Some(format!("{}\t", value)),
+        }
+    }
+
+    fn row_ended(&mut self) -> Option<String> {
+        Some(String::from("\n"))
+    }
+
+    fn footer(&mut self) -> Option<String> {
+        None
+    }
+}
+
+fn escape(value: &str) -> String {
+    if !value.contains(['\\', '\t', '\n', '\r']) {
+        return value.to_string();
+    }
+
+    let mut result = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::output::test::write_test_items;
+    use crate::output::tsv::TsvFormatter;
+    use crate::output::ResultsFormatter;
+
+    #[test]
+    fn test() {
+        let result = write_test_items(&mut TsvFormatter);
+        assert_eq!("foo_value\tBAR value\n123\t\n", result);
+    }
+
+    #[test]
+    fn test_escaping() {
+        let mut formatter = TsvFormatter;
+        assert_eq!(
+            formatter.format_element("name", "a\tb\nc\r\\d", false),
+            Some(String::from("a\\tb\\nc\\r\\\\d\t"))
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Handles export of results into Excel workbooks.
+//! Rows come as CSV with the column names first, the workbook is built when the writer is finished.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+use crate::output::sink::{read_rows, AtomicFile};
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ColumnType {
+    Number,
+    DateTime,
+    Text,
+}
+
+/// Collects the rows and writes the workbook to the file, or to stdout, when dropped
+pub struct XlsxWriter {
+    path: Option<PathBuf>,
+    contents: Vec<u8>,
+}
+
+impl XlsxWriter {
+    pub fn new(path: Option<PathBuf>) -> XlsxWriter {
+        XlsxWriter {
+            path,
+            contents: vec![],
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), XlsxError> {
+        let rows = read_rows(&self.contents);
+        self.contents.clear();
+
+        let buffer = build_workbook(&rows)?.save_to_buffer()?;
+
+        match self.path {
+            Some(ref path) => AtomicFile::create(path)?.write_all(&buffer)?,
+            None => io::stdout().write_all(&buffer)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for XlsxWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Workbooks can't be appended to, so nothing is written before the end
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for XlsxWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish() {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+fn build_workbook(rows: &[Vec<String>]) -> Result<Workbook, XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+
+    let header_format = Format::new().set_bold();
+    let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+    let Some((header, rows)) = rows.split_first() else {
+        return Ok(workbook);
+    };
+
+    for (col, name) in header.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, name, &header_format)?;
+    }
+
+    let column_types = (0..header.len())
+        .map(|col| get_column_type(rows.iter().filter_map(|row| row.get(col))))
+        .collect::<Vec<_>>();
+
+    for (pos, row) in rows.iter().enumerate() {
+        let row_num = pos as u32 + 1;
+        for (col, value) in row.iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+
+            let col_num = col as u16;
+            match column_types.get(col).copied().unwrap_or(ColumnType::Text) {
+                ColumnType::Number => {
+                    let number = value.parse::<f64>().unwrap_or_default();
+                    worksheet.write_number(row_num, col_num, number)?;
+                }
+                ColumnType::DateTime => {
+                    let dt = NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).unwrap_or_default();
+                    worksheet.write_datetime_with_format(row_num, col_num, dt, &datetime_format)?;
+                }
+                ColumnType::Text => {
+                    worksheet.write_string(row_num, col_num, value)?;
+                }
+            }
+        }
+    }
+
+    worksheet.autofit();
+
+    Ok(workbook)
+}
+
+/// Columns are numbers or dates if all of their values are, empty values don't count
+fn get_column_type<'a>(values: impl Iterator<Item = &'a String>) -> ColumnType {
+    let mut column_type = None;
+
+    for value in values.filter(|value| !value.is_empty()) {
+        let value_type = if is_number(value) {
+            ColumnType::Number
+        } else if NaiveDateTime::parse_from_str(value, DATETIME_FORMAT).is_ok() {
+            ColumnType::DateTime
+        } else {
+            return ColumnType::Text;
+        };
+
+        match column_type {
+            Some(column_type) if column_type != value_type => return ColumnType::Text,
+            _ => column_type = Some(value_type),
+        }
+    }
+
+    column_type.unwrap_or(ColumnType::Text)
+}
+
+/// Leading zeros are kept in text, like in permissions or phone numbers
+fn is_number(value: &str) -> bool {
+    let is_integer = value.parse::<i64>().is_ok_and(|n| n.to_string() == value);
+    let is_real = value.contains('.')
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '-')
+        && value.parse::<f64>().is_ok_and(|n| n.is_finite());
+
+    is_integer || is_real
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_column_types() {
+        let sizes = strings(&["1024", "", "0.5"]);
+        assert_eq!(get_column_type(sizes.iter()), ColumnType::Number);
+
+        let dates = strings(&["2024-02-29 10:00:00", "2023-01-01 00:00:00"]);
+        assert_eq!(get_column_type(dates.iter()), ColumnType::DateTime);
+
+        let mixed = strings(&["1024", "2024-02-29 10:00:00"]);
+        assert_eq!(get_column_type(mixed.iter()), ColumnType::Text);
+
+        let modes = strings(&["0644", "0755"]);
+        assert_eq!(get_column_type(modes.iter()), ColumnType::Text);
+
+        assert_eq!(get_column_type(strings(&["", ""]).iter()), ColumnType::Text);
+    }
+
+    #[test]
+    fn test_read_rows() {
+        let rows = read_rows(b"name,size\n\"a, b.txt\",10\n");
+        assert_eq!(rows, vec![strings(&["name", "size"]), strings(&["a, b.txt", "10"])]);
+        assert!(build_workbook(&rows).is_ok());
+    }
+}
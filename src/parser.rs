@@ -13,9 +13,15 @@ use crate::operators::LogicalOp;
 use crate::operators::Op;
 use crate::query::Query;
 use crate::query::Root;
-use crate::query::TraversalMode::{Bfs, Dfs};
-use crate::query::{OutputFormat, RootOptions};
+use crate::query::TraversalMode::{Bfs, Dfs, DfsPost};
+use crate::query::{
+    Action, ColumnFormat, Compression, CsvQuoting, LinkKind, Manifest, ManifestFormat, OutputFormat,
+    OutputOptions, OutputSink, Pivot, RootOptions, SymlinkPolicy, DEFAULT_SYMLINK_DEPTH,
+};
+use crate::util::{get_extension, str_to_bool};
+use crate::util::trash::is_trash_root;
 use directories::UserDirs;
+use log::{debug, trace};
 use std::path::PathBuf;
 
 pub struct Parser {
@@ -23,6 +29,10 @@ pub struct Parser {
     index: usize,
     roots_parsed: bool,
     where_parsed: bool,
+    /// Options of the roots without their own
+    default_root_options: RootOptions,
+    /// Columns `*` expands to
+    star_columns: Vec<Field>,
 }
 
 impl Parser {
@@ -32,10 +42,111 @@ impl Parser {
             index: 0,
             roots_parsed: false,
             where_parsed: false,
+            default_root_options: RootOptions::new(),
+            star_columns: Self::get_default_star_columns(),
         }
     }
 
-    pub fn parse(&mut self, query: Vec<String>, debug: bool) -> Result<Query, String> {
+    /// Replaces the columns `*` expands to with the fields of the list
+    pub fn set_star_columns(&mut self, columns: &[String]) -> Result<(), String> {
+        if columns.is_empty() {
+            return Err(String::from("List of star columns is empty"));
+        }
+
+        self.star_columns = columns
+            .iter()
+            .map(|column| {
+                Field::from_str(column).map_err(|_| format!("Unknown field {} in star columns", column))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(())
+    }
+
+    fn get_default_star_columns() -> Vec<Field> {
+        let mut columns = vec![];
+
+        #[cfg(unix)]
+        {
+            columns.push(Field::Mode);
+            #[cfg(feature = "users")]
+            columns.push(Field::User);
+            #[cfg(feature = "users")]
+            columns.push(Field::Group);
+        }
+
+        columns.extend([Field::Size, Field::Modified, Field::Path]);
+        columns
+    }
+
+    /// Columns of `**`, all of them come from the metadata of the file without reading its contents
+    fn get_metadata_columns() -> Vec<Field> {
+        let mut columns = vec![
+            Field::Name,
+            Field::Path,
+            Field::Extension,
+            Field::Directory,
+            Field::Size,
+            Field::IsDir,
+            Field::IsFile,
+            Field::IsSymlink,
+            Field::IsHidden,
+            Field::Created,
+            Field::Accessed,
+            Field::Modified,
+        ];
+
+        #[cfg(unix)]
+        {
+            columns.push(Field::Mode);
+            columns.push(Field::Uid);
+            columns.push(Field::Gid);
+            #[cfg(feature = "users")]
+            columns.push(Field::User);
+            #[cfg(feature = "users")]
+            columns.push(Field::Group);
+            columns.extend([Field::Device, Field::Inode, Field::Blocks, Field::Hardlinks]);
+        }
+
+        columns
+    }
+
+    /// Parses the options given to every root lacking explicit ones, e.g. `gitignore dfs maxdepth 10`
+    pub fn set_default_root_options(&mut self, options: &str) -> Result<(), String> {
+        if options.trim().is_empty() {
+            return Ok(());
+        }
+
+        let mut parser = Parser::new();
+        let mut lexer = Lexer::after_root(vec![options.to_string()]);
+        while let Some(lexem) = lexer.next_lexem() {
+            parser.lexems.push(lexem);
+        }
+
+        match parser.parse_root_options() {
+            Some(root_options) if !parser.there_are_remaining_lexems() => {
+                self.default_root_options = root_options;
+                Ok(())
+            }
+            _ => Err(format!("Could not parse default root options: {}", options)),
+        }
+    }
+
+    /// Parses a condition in the syntax of `where` standing on its own, e.g. `size > 1gb`
+    pub fn parse_condition(&mut self, condition: &str) -> Result<Expr, String> {
+        let mut lexer = Lexer::after_root(vec![format!("where {}", condition)]);
+        while let Some(lexem) = lexer.next_lexem() {
+            self.lexems.push(lexem);
+        }
+        self.roots_parsed = true;
+
+        match self.parse_where() {
+            Ok(Some(expr)) if !self.there_are_remaining_lexems() => Ok(expr),
+            _ => Err(format!("Could not parse condition: {}", condition)),
+        }
+    }
+
+    pub fn parse(&mut self, query: Vec<String>) -> Result<Query, String> {
         let mut lexer = Lexer::new(query);
         while let Some(lexem) = lexer.next_lexem() {
             match lexem {
@@ -44,41 +155,90 @@ impl Parser {
             }            
         }
 
-        if debug {
-            dbg!(&self.lexems);
-        }
+        trace!(target: "parser", "lexems: {:?}", self.lexems);
 
-        let fields = self.parse_fields()?;
-        let mut roots = self.parse_roots();
+        let explain = self.parse_explain();
+        let distinct = self.parse_distinct();
+        let (fields, column_formats, column_names) = self.parse_fields()?;
+        let mut roots = self.parse_roots()?;
         let root_options = self.parse_root_options();
         self.roots_parsed = true;
         let expr = self.parse_where()?;
         self.where_parsed = true;
-        let grouping_fields = self.parse_group_by()?;
-        let (ordering_fields, ordering_asc) = self.parse_order_by(&fields)?;
-        let mut limit = self.parse_limit()?;
-        let output_format = self.parse_output_format()?;
+        let grouping_fields = self.parse_group_by(&fields, &column_names)?;
+        let (ordering_fields, ordering_asc) = self.parse_order_by(&fields, &column_names)?;
+        let (mut limit, offset) = self.parse_limit()?;
+        let (output_format, output_options, output_sink) = self.parse_output_format()?;
+        let partition = self.parse_partition()?;
+        let action = self.parse_action()?;
 
         if roots.is_empty() {
-            roots = self.parse_roots();
+            roots = self.parse_roots()?;
         }
 
         if roots.is_empty() {
-            roots.push(Root::default(root_options));
+            let root_options = root_options.unwrap_or_else(|| self.default_root_options.clone());
+            roots.push(Root::default(Some(root_options)));
         }
 
-        if self.there_are_remaining_lexems() {
-            if debug {
-                dbg!(&fields);
-                dbg!(&roots);
+        // The pivot is made of grouped rows of its keys and the value
+        let (fields, grouping_fields, column_formats, column_names) = match output_format {
+            OutputFormat::Pivot(ref pivot) => (
+                vec![pivot.rows.clone(), pivot.cols.clone(), pivot.value.clone()],
+                vec![pivot.rows.clone(), pivot.cols.clone()],
+                vec![],
+                vec![],
+            ),
+            _ => (fields, grouping_fields, column_formats, column_names),
+        };
+
+        let fields = match partition {
+            Some(ref partition) => [fields, vec![partition.clone()]].concat(),
+            None => fields,
+        };
+
+        if let Some(Action::Restore(_) | Action::Purge) = action {
+            if !roots.iter().all(|root| is_trash_root(&root.path)) {
+                return Err(String::from(
+                    "Error parsing action, RESTORE and PURGE are only allowed for the trash:// root",
+                ));
+            }
+        }
+
+        if let Some(Action::Delete { into_trash: true, .. }) = action {
+            if roots.iter().any(|root| is_trash_root(&root.path)) {
+                return Err(String::from(
+                    "Error parsing action, items of the trash:// root are already in the trash, use PURGE",
+                ));
             }
+        }
+
+        // Buffered rows are written only after the search, while the actions are taken on the files as they are found
+        if action.is_some()
+            && (!grouping_fields.is_empty()
+                || !ordering_fields.is_empty()
+                || fields
+                    .iter()
+                    .any(|expr| expr.has_aggregate_function() || expr.is_window_function()))
+        {
+            return Err(String::from(
+                "Error parsing action, actions can't be combined with ORDER BY, GROUP BY, aggregate or window functions",
+            ));
+        }
+
+        if self.there_are_remaining_lexems() {
+            debug!(target: "parser", "fields: {:?}", fields);
+            debug!(target: "parser", "roots: {:?}", roots);
+            debug!(target: "parser", "remaining lexems: {:?}", &self.lexems[self.index..]);
 
             return Err(String::from(
                 "Could not parse tokens at the end of the query",
             ));
         }
 
+        // Constant columns are the same for every file, unless they are aggregated by groups
         if limit == 0
+            && grouping_fields.is_empty()
             && fields
                 .iter()
                 .all(|expr| expr.get_required_fields().is_empty())
@@ -86,20 +246,68 @@ impl Parser {
             limit = 1;
         }
 
+        let column_types = fields.iter().map(Expr::get_value_type).collect();
+
         Ok(Query {
             fields,
+            distinct,
             roots,
             expr,
             grouping_fields: Rc::new(grouping_fields),
             ordering_fields: Rc::new(ordering_fields),
             ordering_asc: Rc::new(ordering_asc),
             limit,
+            offset,
             output_format,
+            output_options: OutputOptions {
+                column_formats,
+                column_names,
+                column_types,
+                ..output_options
+            },
+            output_sink,
+            partition,
+            action,
+            explain,
         })
     }
 
-    fn parse_fields(&mut self) -> Result<Vec<Expr>, String> {
+    fn parse_explain(&mut self) -> bool {
+        match self.next_lexem() {
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("explain") => true,
+            _ => {
+                self.drop_lexem();
+                false
+            }
+        }
+    }
+
+    /// Consumes `DISTINCT`, with or without `SELECT` before it
+    fn parse_distinct(&mut self) -> bool {
+        let index = self.index;
+
+        let lexem = match self.next_lexem() {
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("select") => self.next_lexem(),
+            lexem => lexem,
+        };
+
+        match lexem {
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("distinct") => true,
+            _ => {
+                self.index = index;
+                false
+            }
+        }
+    }
+
+    /// Parses the columns along with their display formats and aliases, both by the positions of the columns
+    #[allow(clippy::type_complexity)]
+    fn parse_fields(
+        &mut self,
+    ) -> Result<(Vec<Expr>, Vec<Option<ColumnFormat>>, Vec<Option<String>>), String> {
         let mut fields = vec![];
+        let mut column_formats = vec![];
+        let mut column_names = vec![];
 
         loop {
             let lexem = self.next_lexem();
@@ -107,23 +315,34 @@ impl Parser {
                 Some(Lexem::Comma) => {
                     // skip
                 }
+                Some(Lexem::RawString(ref s))
+                    if s.eq_ignore_ascii_case("as") && !fields.is_empty() =>
+                {
+                    column_names.resize(fields.len() - 1, None);
+                    column_names.push(Some(self.parse_column_alias()?));
+                }
                 Some(Lexem::String(ref s))
                 | Some(Lexem::RawString(ref s))
                 | Some(Lexem::ArithmeticOperator(ref s)) => {
                     if s.to_ascii_lowercase() != "select" {
-                        if s == "*" {
-                            #[cfg(unix)]
-                            {
-                                fields.push(Expr::field(Field::Mode));
-                                #[cfg(feature = "users")]
-                                fields.push(Expr::field(Field::User));
-                                #[cfg(feature = "users")]
-                                fields.push(Expr::field(Field::Group));
-                            }
+                        if s == "*" || s == "**" {
+                            // `**` comes as two separate operators
+                            let all_columns = match s.as_str() {
+                                "**" => true,
+                                _ => match self.next_lexem() {
+                                    Some(Lexem::ArithmeticOperator(ref next)) if next == "*" => true,
+                                    _ => {
+                                        self.drop_lexem();
+                                        false
+                                    }
+                                },
+                            };
 
-                            fields.push(Expr::field(Field::Size));
-                            fields.push(Expr::field(Field::Modified));
-                            fields.push(Expr::field(Field::Path));
+                            let columns = match all_columns {
+                                true => Self::get_metadata_columns(),
+                                false => self.star_columns.clone(),
+                            };
+                            fields.extend(columns.into_iter().map(Expr::field));
                         } else {
                             if s.to_lowercase() == "group" {
                                 if let Some(Lexem::By) = self.next_lexem() {
@@ -137,7 +356,8 @@ impl Parser {
 
                             self.drop_lexem();
 
-                            if Self::is_root_option_keyword(s) {
+                            // Fields like `archive_path` look like the options of the default root
+                            if Self::is_root_option_keyword(s) && Field::from_str(s).is_err() {
                                 break;
                             }
 
@@ -153,6 +373,10 @@ impl Parser {
                         fields.push(field);
                     }
                 }
+                Some(Lexem::DoubleColon) if !fields.is_empty() => {
+                    column_formats.resize(fields.len() - 1, None);
+                    column_formats.push(Some(self.parse_column_format()?));
+                }
                 _ => {
                     self.drop_lexem();
                     break;
@@ -164,14 +388,45 @@ impl Parser {
             return Err(String::from("Error parsing fields, no selector found"));
         }
 
-        Ok(fields)
+        column_formats.resize(fields.len(), None);
+        column_names.resize(fields.len(), None);
+
+        Ok((fields, column_formats, column_names))
+    }
+
+    fn parse_column_alias(&mut self) -> Result<String, String> {
+        match self.next_lexem() {
+            Some(Lexem::String(alias)) | Some(Lexem::RawString(alias)) => Ok(alias),
+            _ => Err(String::from("Error parsing column alias, expected its name after AS")),
+        }
+    }
+
+    fn parse_column_format(&mut self) -> Result<ColumnFormat, String> {
+        let name = match self.next_lexem() {
+            Some(Lexem::RawString(name)) => name,
+            _ => return Err(String::from("Error parsing column format, expected its name after ::")),
+        };
+
+        let arg = match self.next_lexem() {
+            Some(Lexem::Open) => match (self.next_lexem(), self.next_lexem()) {
+                (Some(Lexem::String(arg)) | Some(Lexem::RawString(arg)), Some(Lexem::Close)) => Some(arg),
+                _ => return Err(format!("Error parsing column format {}, expected a single argument", name)),
+            },
+            _ => {
+                self.drop_lexem();
+                None
+            }
+        };
+
+        ColumnFormat::from(&name, arg).ok_or_else(|| format!("Unknown column format {}", name))
     }
 
-    fn parse_roots(&mut self) -> Vec<Root> {
+    fn parse_roots(&mut self) -> Result<Vec<Root>, String> {
         enum RootParsingMode {
             Unknown,
             From,
             Root,
+            Manifest,
             Comma,
         }
 
@@ -191,7 +446,7 @@ impl Parser {
 
         if let RootParsingMode::From = mode {
             let mut path: String = String::from("");
-            let mut root_options = RootOptions::new();
+            let mut root_options = self.default_root_options.clone();
 
             loop {
                 let lexem = self.next_lexem();
@@ -210,16 +465,26 @@ impl Parser {
                                 }
                                 mode = RootParsingMode::Root;
                             }
+                            RootParsingMode::Root if s.eq_ignore_ascii_case("as") => {
+                                let manifest = self.parse_manifest()?;
+                                roots.push(Root::with_manifest(path, manifest));
+                                path = String::from("");
+                                mode = RootParsingMode::Manifest;
+                            }
                             RootParsingMode::Root => {
                                 self.drop_lexem();
                                 match self.parse_root_options() {
                                     Some(options) => root_options = options,
                                     None => {
-                                        roots.push(Root::new(path, RootOptions::new()));
+                                        roots.push(Root::new(path, self.default_root_options.clone()));
                                         break
                                     }
                                 }
                             }
+                            RootParsingMode::Manifest => {
+                                self.drop_lexem();
+                                break;
+                            }
                             _ => {}
                         },
                         Lexem::Comma => {
@@ -227,8 +492,10 @@ impl Parser {
                                 roots.push(Root::new(path, root_options));
 
                                 path = String::from("");
-                                root_options = RootOptions::new();
+                                root_options = self.default_root_options.clone();
 
+                                mode = RootParsingMode::Comma;
+                            } else if let RootParsingMode::Manifest = mode {
                                 mode = RootParsingMode::Comma;
                             } else {
                                 self.drop_lexem();
@@ -254,7 +521,42 @@ impl Parser {
             }
         }
 
-        roots
+        Ok(roots)
+    }
+
+    /// Parses the manifest format and optional column list after AS, e.g. `csv(path, size)`
+    fn parse_manifest(&mut self) -> Result<Manifest, String> {
+        let format = match self.next_lexem() {
+            Some(Lexem::String(ref s)) | Some(Lexem::RawString(ref s)) => {
+                match s.to_ascii_lowercase().as_str() {
+                    "csv" => ManifestFormat::Csv,
+                    "json" => ManifestFormat::Json,
+                    _ => return Err(format!("Unknown manifest format: {}", s)),
+                }
+            }
+            _ => return Err(String::from("Error parsing roots, manifest format expected after AS")),
+        };
+
+        let mut fields = vec![];
+
+        match self.next_lexem() {
+            Some(Lexem::Open) => loop {
+                match self.next_lexem() {
+                    Some(Lexem::String(ref s)) | Some(Lexem::RawString(ref s)) => {
+                        let field = Field::from_str(s)
+                            .map_err(|_| format!("Unknown field in manifest columns: {}", s))?;
+                        fields.push(field);
+                    }
+                    Some(Lexem::Comma) => {}
+                    Some(Lexem::Close) => break,
+                    _ => return Err(String::from("Error parsing roots, unmatched parenthesis in manifest columns")),
+                }
+            },
+            Some(_) => self.drop_lexem(),
+            None => {}
+        }
+
+        Ok(Manifest { format, fields })
     }
 
     fn parse_root_options(&mut self) -> Option<RootOptions> {
@@ -263,6 +565,9 @@ impl Parser {
             Options,
             MinDepth,
             Depth,
+            /// After `symlinks`, which may be followed by the policy
+            Symlinks,
+            SymlinkDepth,
         }
 
         let mut mode = RootParsingMode::Unknown;
@@ -270,30 +575,45 @@ impl Parser {
         let mut min_depth: u32 = 0;
         let mut max_depth: u32 = 0;
         let mut archives = false;
-        let mut symlinks = false;
+        let mut symlinks = SymlinkPolicy::Never;
+        let mut symlink_depth = DEFAULT_SYMLINK_DEPTH;
         let mut gitignore = None;
         let mut hgignore = None;
         let mut dockerignore = None;
         let mut traversal = Bfs;
         let mut regexp = false;
+        let mut verbatim = false;
+        let mut sorted = false;
 
         loop {
             let lexem = self.next_lexem();
             match lexem {
                 Some(ref lexem) => match lexem {
                     Lexem::String(ref s) | Lexem::RawString(ref s) => match mode {
-                        RootParsingMode::Unknown | RootParsingMode::Options => {
+                        RootParsingMode::Unknown
+                        | RootParsingMode::Options
+                        | RootParsingMode::Symlinks => {
                             let s = s.to_ascii_lowercase();
-                            if s == "mindepth" {
+                            let policy = match mode {
+                                RootParsingMode::Symlinks => SymlinkPolicy::from(&s),
+                                _ => None,
+                            };
+
+                            if let Some(policy) = policy {
+                                symlinks = policy;
+                                mode = RootParsingMode::Options;
+                            } else if s == "mindepth" {
                                 mode = RootParsingMode::MinDepth;
                             } else if s == "maxdepth" || s == "depth" {
                                 mode = RootParsingMode::Depth;
                             } else if s.starts_with("arc") {
                                 archives = true;
                                 mode = RootParsingMode::Options;
+                            } else if s == "symlink_depth" || s == "symdepth" {
+                                mode = RootParsingMode::SymlinkDepth;
                             } else if s.starts_with("sym") {
-                                symlinks = true;
-                                mode = RootParsingMode::Options;
+                                symlinks = SymlinkPolicy::Always;
+                                mode = RootParsingMode::Symlinks;
                             } else if s.starts_with("git") {
                                 gitignore = Some(true);
                                 mode = RootParsingMode::Options;
@@ -318,9 +638,18 @@ impl Parser {
                             } else if s == "dfs" {
                                 traversal = Dfs;
                                 mode = RootParsingMode::Options;
+                            } else if s == "dfs-post" || s == "dfs_post" {
+                                traversal = DfsPost;
+                                mode = RootParsingMode::Options;
                             } else if s.starts_with("regex") {
                                 regexp = true;
                                 mode = RootParsingMode::Options;
+                            } else if s == "verbatim" {
+                                verbatim = true;
+                                mode = RootParsingMode::Options;
+                            } else if s == "sorted" {
+                                sorted = true;
+                                mode = RootParsingMode::Options;
                             } else {
                                 self.drop_lexem();
                                 break;
@@ -352,6 +681,19 @@ impl Parser {
                                 }
                             }
                         }
+                        RootParsingMode::SymlinkDepth => {
+                            let d: Result<u32, _> = s.parse();
+                            match d {
+                                Ok(d) => {
+                                    symlink_depth = d;
+                                    mode = RootParsingMode::Options;
+                                }
+                                _ => {
+                                    self.drop_lexem();
+                                    break;
+                                }
+                            }
+                        }
                     },
                     Lexem::Operator(s) if s.eq("rx") => {
                         regexp = true;
@@ -375,11 +717,14 @@ impl Parser {
                 max_depth,
                 archives,
                 symlinks,
+                symlink_depth,
                 gitignore,
                 hgignore,
                 dockerignore,
                 traversal,
                 regexp,
+                verbatim,
+                sorted,
             }),
         }
     }
@@ -398,7 +743,10 @@ impl Parser {
             || s.starts_with("nodock")
             || s == "bfs"
             || s == "dfs"
+            || s == "dfs-post"
+            || s == "dfs_post"
             || s.starts_with("regex")
+            || s == "sorted"
     }
 
     /*
@@ -574,12 +922,12 @@ impl Parser {
                 }
             } else if let Some(function) = expr.function {
                 if expr.right.is_none()
-                    && (expr.args.is_none() || expr.args.unwrap().is_empty())
                     && function.is_boolean_function()
                     && self.roots_parsed
                     && !self.where_parsed
                 {
-                    let func_expr = Expr::function_left(function, expr.left);
+                    let mut func_expr = Expr::function_left(function, expr.left);
+                    func_expr.args = expr.args;
                     result = Ok(Some(Expr::op(
                         func_expr,
                         Op::Eq,
@@ -789,7 +1137,11 @@ impl Parser {
         }
     }
 
-    fn parse_group_by(&mut self) -> Result<Vec<Expr>, String> {
+    fn parse_group_by(
+        &mut self,
+        fields: &[Expr],
+        column_names: &[Option<String>],
+    ) -> Result<Vec<Expr>, String> {
         let mut group_by_fields: Vec<Expr> = vec![];
 
         if let Some(Lexem::RawString(s)) = self.next_lexem() {
@@ -798,9 +1150,20 @@ impl Parser {
                     loop {
                         match self.next_lexem() {
                             Some(Lexem::Comma) => {}
-                            Some(Lexem::RawString(_)) => {
+                            // Actions may follow right after the grouping
+                            Some(Lexem::RawString(ref s)) if Self::is_keyword_after_format(s) => {
                                 self.drop_lexem();
-                                let group_field = self.parse_expr().unwrap().unwrap();
+                                break;
+                            }
+                            Some(Lexem::RawString(ref group_field)) => {
+                                let alias = Self::get_column_by_alias(fields, column_names, group_field);
+                                let group_field = match alias {
+                                    Some(expr) => expr,
+                                    None => {
+                                        self.drop_lexem();
+                                        self.parse_expr().unwrap().unwrap()
+                                    }
+                                };
                                 group_by_fields.push(group_field);
                             }
                             _ => {
@@ -822,7 +1185,11 @@ impl Parser {
         Ok(group_by_fields)
     }
 
-    fn parse_order_by(&mut self, fields: &[Expr]) -> Result<(Vec<Expr>, Vec<bool>), String> {
+    fn parse_order_by(
+        &mut self,
+        fields: &[Expr],
+        column_names: &[Option<String>],
+    ) -> Result<(Vec<Expr>, Vec<bool>), String> {
         let mut order_by_fields: Vec<Expr> = vec![];
         let mut order_by_directions: Vec<bool> = vec![];
 
@@ -831,12 +1198,43 @@ impl Parser {
                 loop {
                     match self.next_lexem() {
                         Some(Lexem::Comma) => {}
+                        // Actions may follow right after the ordering
+                        Some(Lexem::RawString(ref s)) if Self::is_keyword_after_format(s) => {
+                            self.drop_lexem();
+                            break;
+                        }
                         Some(Lexem::RawString(ref ordering_field)) => {
-                            let actual_field = match ordering_field.parse::<usize>() {
-                                Ok(idx) => fields[idx - 1].clone(),
+                            // Expressions missing from the columns are evaluated only for ordering
+                            let alias = Self::get_column_by_alias(fields, column_names, ordering_field);
+                            let actual_field = match (ordering_field.parse::<usize>(), alias) {
+                                (Ok(pos), _) => Self::get_column_by_position(fields, pos, false)?,
+                                (_, Some(expr)) => expr,
                                 _ => {
                                     self.drop_lexem();
-                                    self.parse_expr().unwrap().unwrap()
+                                    match self.parse_expr()? {
+                                        Some(expr) => expr,
+                                        None => {
+                                            return Err(String::from(
+                                                "Error parsing order by, expected a column or an expression",
+                                            ))
+                                        }
+                                    }
+                                }
+                            };
+                            order_by_fields.push(actual_field);
+                            order_by_directions.push(true);
+                        }
+                        Some(Lexem::ArithmeticOperator(ref minus)) if minus == "-" => {
+                            let pos = match self.next_lexem() {
+                                Some(Lexem::RawString(pos)) => pos.parse::<usize>().ok(),
+                                _ => None,
+                            };
+                            let actual_field = match pos {
+                                Some(pos) => Self::get_column_by_position(fields, pos, true)?,
+                                None => {
+                                    return Err(String::from(
+                                        "Error parsing order by, expected a column number after -",
+                                    ))
                                 }
                             };
                             order_by_fields.push(actual_field);
@@ -862,18 +1260,60 @@ impl Parser {
         Ok((order_by_fields, order_by_directions))
     }
 
-    fn parse_limit(&mut self) -> Result<u32, &str> {
+    /// Aliases stand for the expressions of their columns, so the values are shared with them
+    fn get_column_by_alias(
+        fields: &[Expr],
+        column_names: &[Option<String>],
+        name: &str,
+    ) -> Option<Expr> {
+        column_names
+            .iter()
+            .position(|alias| alias.as_ref().is_some_and(|alias| alias.eq_ignore_ascii_case(name)))
+            .map(|pos| fields[pos].clone())
+    }
+
+    /// Columns are numbered from 1, or from the last one if counted from the end
+    fn get_column_by_position(fields: &[Expr], pos: usize, from_end: bool) -> Result<Expr, String> {
+        if pos == 0 || pos > fields.len() {
+            return Err(format!(
+                "Error parsing order by, column {}{} is out of range",
+                if from_end { "-" } else { "" },
+                pos
+            ));
+        }
+
+        match from_end {
+            true => Ok(fields[fields.len() - pos].clone()),
+            false => Ok(fields[pos - 1].clone()),
+        }
+    }
+
+    /// Parses `limit N [offset M]`, returning the limit and the number of rows to skip
+    fn parse_limit(&mut self) -> Result<(u32, u32), &str> {
         let lexem = self.next_lexem();
         match lexem {
             Some(Lexem::Limit) => {
                 let lexem = self.next_lexem();
                 match lexem {
                     Some(Lexem::RawString(s)) | Some(Lexem::String(s)) => {
-                        if let Ok(limit) = s.parse() {
-                            return Ok(limit);
-                        } else {
+                        let Ok(limit) = s.parse() else {
                             return Err("Error parsing limit");
+                        };
+
+                        if !self.next_keyword_is("offset") {
+                            return Ok((limit, 0));
                         }
+
+                        return match self.next_lexem() {
+                            Some(Lexem::RawString(s)) | Some(Lexem::String(s)) => match s.parse() {
+                                Ok(offset) => Ok((limit, offset)),
+                                Err(_) => Err("Error parsing offset"),
+                            },
+                            _ => {
+                                self.drop_lexem();
+                                Err("Error parsing offset, offset value not found")
+                            }
+                        };
                     }
                     _ => {
                         self.drop_lexem();
@@ -886,20 +1326,74 @@ impl Parser {
             }
         }
 
-        Ok(0)
+        Ok((0, 0))
     }
 
-    fn parse_output_format(&mut self) -> Result<OutputFormat, &str> {
+    /// Parses `into FORMAT['.gz' | '.zst'] [FILE] ['PATH']`, the compression is also taken from the extension of the path
+    fn parse_output_format(
+        &mut self,
+    ) -> Result<(OutputFormat, OutputOptions, OutputSink), &'static str> {
         let lexem = self.next_lexem();
-        match lexem {
+        let mut compression = None;
+        let mut output_options = OutputOptions::default();
+        let output_format = match lexem {
             Some(Lexem::Into) => {
                 let lexem = self.next_lexem();
                 match lexem {
+                    Some(Lexem::RawString(s)) if s.eq_ignore_ascii_case("pivot") => {
+                        OutputFormat::Pivot(Box::new(self.parse_pivot()?))
+                    }
+                    Some(Lexem::RawString(s)) if s.eq_ignore_ascii_case("profile") => {
+                        match (self.next_lexem(), self.next_lexem(), self.next_lexem()) {
+                            (
+                                Some(Lexem::Open),
+                                Some(Lexem::RawString(name)) | Some(Lexem::String(name)),
+                                Some(Lexem::Close),
+                            ) => OutputFormat::Profile(name),
+                            _ => return Err("Error parsing output profile, expected profile(NAME)"),
+                        }
+                    }
+                    Some(Lexem::RawString(s)) if s.eq_ignore_ascii_case("table") => {
+                        match self.next_lexem() {
+                            Some(Lexem::Open) => match (self.next_lexem(), self.next_lexem()) {
+                                (Some(Lexem::RawString(style)), Some(Lexem::Close))
+                                    if style.eq_ignore_ascii_case("box") =>
+                                {
+                                    OutputFormat::Table { borders: true }
+                                }
+                                _ => return Err("Error parsing table format, expected table(box)"),
+                            },
+                            _ => {
+                                self.drop_lexem();
+                                OutputFormat::Table { borders: false }
+                            }
+                        }
+                    }
                     Some(Lexem::RawString(s)) | Some(Lexem::String(s)) => {
-                        return match OutputFormat::from(&s) {
-                            Some(output_format) => Ok(output_format),
-                            None => Err("Unknown output format"),
+                        let name = match s.rsplit_once('.') {
+                            Some((name, ext)) if Compression::from_extension(ext).is_some() => {
+                                compression = Compression::from_extension(ext);
+                                name
+                            }
+                            _ => &s,
                         };
+
+                        match OutputFormat::from(name) {
+                            Some(OutputFormat::Csv) => {
+                                output_options = self.parse_csv_dialect()?;
+                                OutputFormat::Csv
+                            }
+                            Some(output_format) if output_format.is_flat() => {
+                                output_options = self.parse_flat_options()?;
+                                output_format
+                            }
+                            Some(OutputFormat::Html) => {
+                                output_options = self.parse_html_options()?;
+                                OutputFormat::Html
+                            }
+                            Some(output_format) => output_format,
+                            None => return Err("Unknown output format"),
+                        }
                     }
                     _ => {
                         self.drop_lexem();
@@ -909,77 +1403,438 @@ impl Parser {
             }
             _ => {
                 self.drop_lexem();
+                return Ok((OutputFormat::Tabs, output_options, OutputSink::default()));
+            }
+        };
+
+        // Unquoted paths need `FILE` or `TO` before them, so a mistyped keyword is never taken for a file name
+        let path = match self.next_lexem() {
+            Some(Lexem::String(path)) => Some(path),
+            Some(Lexem::RawString(keyword))
+                if keyword.eq_ignore_ascii_case("file") || keyword.eq_ignore_ascii_case("to") =>
+            {
+                match self.next_lexem() {
+                    Some(Lexem::String(path)) | Some(Lexem::RawString(path)) => Some(path),
+                    _ => return Err("Error parsing output file, expected a path after FILE or TO"),
+                }
+            }
+            Some(Lexem::RawString(word)) if !Self::is_keyword_after_format(&word) => {
+                return Err("Error parsing output format, quote the output path or put FILE or TO before it");
+            }
+            _ => {
+                self.drop_lexem();
+                None
             }
+        };
+
+        if let Some(ref path) = path {
+            compression = compression.or_else(|| Compression::from_extension(&get_extension(path)));
         }
 
-        Ok(OutputFormat::Tabs)
+        Ok((
+            output_format,
+            output_options,
+            OutputSink {
+                path: path.map(PathBuf::from),
+                compression,
+            },
+        ))
     }
 
-    fn there_are_remaining_lexems(&mut self) -> bool {
-        let result = self.next_lexem().is_some();
-        if result {
+    /// Parses the optional `(headers)` argument of `tabs`, `tsv`, `lines`, and `list`
+    fn parse_flat_options(&mut self) -> Result<OutputOptions, &'static str> {
+        let mut options = OutputOptions::default();
+
+        if self.next_lexem() != Some(Lexem::Open) {
             self.drop_lexem();
+            return Ok(options);
         }
 
-        result
+        match (self.next_lexem(), self.next_lexem()) {
+            (Some(Lexem::RawString(s)), Some(Lexem::Close))
+                if s.eq_ignore_ascii_case("header") || s.eq_ignore_ascii_case("headers") =>
+            {
+                options.headers = true;
+            }
+            _ => return Err("Error parsing output format, expected FORMAT(headers)"),
+        }
+
+        Ok(options)
     }
 
-    fn next_lexem(&mut self) -> Option<Lexem> {
-        let lexem = self.lexems.get(self.index);
-        self.index += 1;
+    /// Parses the optional `(sortable)` argument of `html`
+    fn parse_html_options(&mut self) -> Result<OutputOptions, &'static str> {
+        let mut options = OutputOptions::default();
 
-        lexem.cloned()
-    }
+        if self.next_lexem() != Some(Lexem::Open) {
+            self.drop_lexem();
+            return Ok(options);
+        }
 
-    fn drop_lexem(&mut self) {
-        self.index -= 1;
+        match (self.next_lexem(), self.next_lexem()) {
+            (Some(Lexem::RawString(s)), Some(Lexem::Close)) if s.eq_ignore_ascii_case("sortable") => {
+                options.sortable = true;
+            }
+            _ => return Err("Error parsing output format, expected html(sortable)"),
+        }
+
+        Ok(options)
     }
 
-    fn negate_expr_op(expr: &Expr) -> Expr {
-        let mut result = expr.clone();
+    /// Parses the optional `csv(DELIMITER, header, quote=STYLE)` arguments, all of them in any order
+    fn parse_csv_dialect(&mut self) -> Result<OutputOptions, &'static str> {
+        let mut options = OutputOptions::default();
 
-        if let Some(left) = &expr.left {
-            result.left = Some(Box::from(Self::negate_expr_op(left)));
+        if self.next_lexem() != Some(Lexem::Open) {
+            self.drop_lexem();
+            return Ok(options);
         }
 
-        if let &Some(op) = &expr.op {
-            result.op = Some(Op::negate(op));
-        }
+        loop {
+            match self.next_lexem() {
+                Some(Lexem::String(delimiter)) => {
+                    options.delimiter = match delimiter.as_str() {
+                        "\\t" => Some('\t'),
+                        _ => {
+                            let mut chars = delimiter.chars();
+                            match (chars.next(), chars.next()) {
+                                (Some(c), None) if c.is_ascii() => Some(c),
+                                _ => return Err("CSV delimiter must be a single ASCII character"),
+                            }
+                        }
+                    };
+                }
+                Some(Lexem::RawString(s)) if s.eq_ignore_ascii_case("tab") => {
+                    options.delimiter = Some('\t');
+                }
+                Some(Lexem::RawString(s))
+                    if s.eq_ignore_ascii_case("header") || s.eq_ignore_ascii_case("headers") =>
+                {
+                    options.headers = true;
+                }
+                Some(Lexem::RawString(s)) if s.eq_ignore_ascii_case("quote") => {
+                    match (self.next_lexem(), self.next_lexem()) {
+                        (
+                            Some(Lexem::Operator(op)) | Some(Lexem::RawString(op)),
+                            Some(Lexem::RawString(style)),
+                        ) if op == "=" => match CsvQuoting::from(&style) {
+                            Some(quoting) => options.quoting = Some(quoting),
+                            None => {
+                                return Err("Unknown CSV quoting, expected ALWAYS, NECESSARY, NONNUMERIC, or NEVER")
+                            }
+                        },
+                        _ => return Err("Error parsing CSV quoting, expected quote=STYLE"),
+                    }
+                }
+                _ => {
+                    return Err("Error parsing CSV format, expected a delimiter, HEADER, or QUOTE=STYLE")
+                }
+            }
 
-        if let Some(right) = &expr.right {
-            result.right = Some(Box::from(Self::negate_expr_op(right)));
+            match self.next_lexem() {
+                Some(Lexem::Comma) => continue,
+                Some(Lexem::Close) => break,
+                _ => return Err("Error parsing CSV format, expected , or )"),
+            }
         }
 
-        result
+        Ok(options)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn is_keyword_after_format(s: &str) -> bool {
+        ["partition", "extract", "restore", "purge", "update", "replace", "delete"]
+            .iter()
+            .any(|keyword| s.eq_ignore_ascii_case(keyword))
+    }
 
-    #[test]
-    fn simple_query() {
-        let query = "select name, path ,size , fsize from /";
-        let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+    /// Parses `partition by EXPR` following the output format
+    fn parse_partition(&mut self) -> Result<Option<Expr>, String> {
+        if !self.next_keyword_is("partition") {
+            return Ok(None);
+        }
 
-        assert_eq!(
-            query.fields,
-            vec![
-                Expr::field(Field::Name),
-                Expr::field(Field::Path),
-                Expr::field(Field::Size),
-                Expr::field(Field::FormattedSize),
-            ]
-        );
-    }
+        if self.next_lexem() != Some(Lexem::By) {
+            return Err(String::from("Error parsing partition, expected BY"));
+        }
+
+        match self.parse_expr()? {
+            Some(expr) if expr.has_aggregate_function() => Err(String::from(
+                "Error parsing partition, aggregate functions are not allowed",
+            )),
+            Some(expr) => Ok(Some(expr)),
+            None => Err(String::from("Error parsing partition, expected an expression")),
+        }
+    }
+
+    /// Parses `pivot(rows=EXPR, cols=EXPR[, value=AGGREGATE])`, the value is `count(*)` by default
+    fn parse_pivot(&mut self) -> Result<Pivot, &'static str> {
+        if self.next_lexem() != Some(Lexem::Open) {
+            return Err("Error parsing pivot, expected (");
+        }
+
+        let mut rows = None;
+        let mut cols = None;
+        let mut value = None;
+
+        loop {
+            let key = match self.next_lexem() {
+                Some(Lexem::RawString(key)) => key.to_lowercase(),
+                Some(Lexem::Comma) => continue,
+                Some(Lexem::Close) => break,
+                _ => return Err("Error parsing pivot, expected ROWS, COLS, or VALUE"),
+            };
+
+            match self.next_lexem() {
+                Some(Lexem::Operator(op)) | Some(Lexem::RawString(op)) if op == "=" => {}
+                _ => return Err("Error parsing pivot, expected ="),
+            }
+
+            let expr = match self.parse_expr() {
+                Ok(Some(expr)) => expr,
+                _ => return Err("Error parsing pivot expression"),
+            };
+
+            match key.as_str() {
+                "rows" | "row" => rows = Some(expr),
+                "cols" | "columns" | "col" => cols = Some(expr),
+                "value" => value = Some(expr),
+                _ => return Err("Error parsing pivot, expected ROWS, COLS, or VALUE"),
+            }
+        }
+
+        let value = value.unwrap_or_else(|| {
+            Expr::function_left(Function::Count, Some(Box::new(Expr::value(String::from("*")))))
+        });
+
+        if !value.has_aggregate_function() {
+            return Err("Error parsing pivot, VALUE must be an aggregate like count(*) or sum(size)");
+        }
+
+        match (rows, cols) {
+            (Some(rows), Some(cols)) => Ok(Pivot { rows, cols, value }),
+            _ => Err("Error parsing pivot, both ROWS and COLS are required"),
+        }
+    }
+
+    fn parse_action(&mut self) -> Result<Option<Action>, &str> {
+        let lexem = self.next_lexem();
+        match lexem {
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("extract") => {
+                if !self.next_keyword_is("thumbnail") {
+                    return Err("Error parsing action, unknown kind of extraction");
+                }
+
+                if !self.next_keyword_is("to") {
+                    return Err("Error parsing action, expected TO");
+                }
+
+                let lexem = self.next_lexem();
+                match lexem {
+                    Some(Lexem::RawString(template)) | Some(Lexem::String(template)) => {
+                        let overwrite = self.next_keyword_is("overwrite");
+                        return Ok(Some(Action::ExtractThumbnail { template, overwrite }));
+                    }
+                    _ => {
+                        self.drop_lexem();
+                        return Err("Error parsing action, destination path not found");
+                    }
+                }
+            }
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("restore") => {
+                if !self.next_keyword_is("to") {
+                    return Ok(Some(Action::Restore(None)));
+                }
+
+                let lexem = self.next_lexem();
+                match lexem {
+                    Some(Lexem::RawString(s)) | Some(Lexem::String(s)) => {
+                        return Ok(Some(Action::Restore(Some(s))));
+                    }
+                    _ => {
+                        self.drop_lexem();
+                        return Err("Error parsing action, destination path not found");
+                    }
+                }
+            }
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("purge") => {
+                return Ok(Some(Action::Purge));
+            }
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("update") => {
+                if !self.next_keyword_is("set") {
+                    return Err("Error parsing action, expected SET");
+                }
+
+                if cfg!(not(target_os = "linux")) {
+                    return Err("Error parsing action, UPDATE SET is supported on Linux only");
+                }
+
+                let mut immutable = None;
+                let mut append_only = None;
+
+                loop {
+                    let flag = match self.next_lexem() {
+                        Some(Lexem::RawString(ref s)) => match s.to_ascii_lowercase().as_str() {
+                            "immutable" | "is_immutable" => &mut immutable,
+                            "append_only" | "is_append_only" => &mut append_only,
+                            _ => return Err("Error parsing action, unknown flag to update"),
+                        },
+                        _ => return Err("Error parsing action, flag to update expected"),
+                    };
+
+                    match self.next_lexem() {
+                        Some(Lexem::Operator(ref s)) | Some(Lexem::RawString(ref s)) if s == "=" => {}
+                        _ => return Err("Error parsing action, expected ="),
+                    }
+
+                    *flag = match self.next_lexem() {
+                        Some(Lexem::RawString(ref s)) | Some(Lexem::String(ref s)) => {
+                            match str_to_bool(s) {
+                                Some(value) => Some(value),
+                                None => return Err("Error parsing action, boolean value expected"),
+                            }
+                        }
+                        _ => return Err("Error parsing action, boolean value expected"),
+                    };
+
+                    match self.next_lexem() {
+                        Some(Lexem::Comma) => {}
+                        _ => {
+                            self.drop_lexem();
+                            break;
+                        }
+                    }
+                }
+
+                return Ok(Some(Action::UpdateFlags { immutable, append_only }));
+            }
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("replace") => {
+                if !self.next_keyword_is("duplicates") || !self.next_keyword_is("with") {
+                    return Err("Error parsing action, expected REPLACE DUPLICATES WITH");
+                }
+
+                let kind = match self.next_lexem() {
+                    Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("hardlinks") => LinkKind::Hard,
+                    Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("symlinks") => LinkKind::Symbolic,
+                    _ => {
+                        self.drop_lexem();
+                        return Err("Error parsing action, expected HARDLINKS or SYMLINKS");
+                    }
+                };
+
+                let dry_run = self.next_keyword_is("dry");
+                if dry_run && !self.next_keyword_is("run") {
+                    return Err("Error parsing action, expected DRY RUN");
+                }
+
+                return Ok(Some(Action::ReplaceDuplicates { kind, dry_run }));
+            }
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case("delete") => {
+                let into_trash = match self.next_lexem() {
+                    Some(Lexem::Into) if self.next_keyword_is("trash") => true,
+                    Some(Lexem::Into) => return Err("Error parsing action, expected INTO TRASH"),
+                    _ => {
+                        self.drop_lexem();
+                        false
+                    }
+                };
+
+                if into_trash && cfg!(windows) {
+                    return Err("Error parsing action, DELETE INTO TRASH is not supported on Windows");
+                }
+
+                let dry_run = self.next_keyword_is("dry");
+                if dry_run && !self.next_keyword_is("run") {
+                    return Err("Error parsing action, expected DRY RUN");
+                }
+
+                return Ok(Some(Action::Delete { into_trash, dry_run }));
+            }
+            _ => {
+                self.drop_lexem();
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn next_keyword_is(&mut self, keyword: &str) -> bool {
+        match self.next_lexem() {
+            Some(Lexem::RawString(ref s)) if s.eq_ignore_ascii_case(keyword) => true,
+            _ => {
+                self.drop_lexem();
+                false
+            }
+        }
+    }
+
+    fn there_are_remaining_lexems(&mut self) -> bool {
+        let result = self.next_lexem().is_some();
+        if result {
+            self.drop_lexem();
+        }
+
+        result
+    }
+
+    fn next_lexem(&mut self) -> Option<Lexem> {
+        let lexem = self.lexems.get(self.index);
+        self.index += 1;
+
+        lexem.cloned()
+    }
+
+    fn drop_lexem(&mut self) {
+        self.index -= 1;
+    }
+
+    fn negate_expr_op(expr: &Expr) -> Expr {
+        let mut result = expr.clone();
+
+        if let Some(left) = &expr.left {
+            result.left = Some(Box::from(Self::negate_expr_op(left)));
+        }
+
+        if let &Some(op) = &expr.op {
+            result.op = Some(Op::negate(op));
+        }
+
+        if let Some(right) = &expr.right {
+            result.right = Some(Box::from(Self::negate_expr_op(right)));
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::expr::Cost;
+    use crate::function::VariantType;
+
+    #[test]
+    fn simple_query() {
+        let query = "select name, path ,size , fsize from /";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.fields,
+            vec![
+                Expr::field(Field::Name),
+                Expr::field(Field::Path),
+                Expr::field(Field::Size),
+                Expr::field(Field::FormattedSize),
+            ]
+        );
+    }
 
     #[test]
     fn query() {
         let query = "select name, path ,size , fsize from /test depth 2, /test2 archives,/test3 depth 3 archives , /test4 ,'/test5' gitignore , /test6 mindepth 3, /test7 archives DFS, /test8 dfs where name != 123 AND ( size gt 456 or fsize lte 758) or name = 'xxx' order by 2, size desc limit 50";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(
             query.fields,
@@ -996,35 +1851,35 @@ mod tests {
             vec![
                 Root::new(
                     String::from("/test"),
-                    RootOptions::from(0, 2, false, false, None, None, None, Bfs, false)
+                    RootOptions { max_depth: 2, ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test2"),
-                    RootOptions::from(0, 0, true, false, None, None, None, Bfs, false)
+                    RootOptions { archives: true, ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test3"),
-                    RootOptions::from(0, 3, true, false, None, None, None, Bfs, false)
+                    RootOptions { max_depth: 3, archives: true, ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test4"),
-                    RootOptions::from(0, 0, false, false, None, None, None, Bfs, false)
+                    RootOptions::new()
                 ),
                 Root::new(
                     String::from("/test5"),
-                    RootOptions::from(0, 0, false, false, Some(true), None, None, Bfs, false)
+                    RootOptions { gitignore: Some(true), ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test6"),
-                    RootOptions::from(3, 0, false, false, None, None, None, Bfs, false)
+                    RootOptions { min_depth: 3, ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test7"),
-                    RootOptions::from(0, 0, true, false, None, None, None, Dfs, false)
+                    RootOptions { archives: true, traversal: Dfs, ..RootOptions::new() }
                 ),
                 Root::new(
                     String::from("/test8"),
-                    RootOptions::from(0, 0, false, false, None, None, None, Dfs, false)
+                    RootOptions { traversal: Dfs, ..RootOptions::new() }
                 ),
             ]
         );
@@ -1068,11 +1923,110 @@ mod tests {
         assert_eq!(query.limit, 50);
     }
 
+    #[test]
+    fn query_with_symlink_policies() {
+        let query = "select name from /test1 symlinks, /test2 sym files-only depth 3, /test3 symlinks dirs_only symlink_depth 5, /test4 sym never, /test5";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let policies: Vec<(SymlinkPolicy, u32, u32)> = query
+            .roots
+            .iter()
+            .map(|root| (root.options.symlinks, root.options.symlink_depth, root.options.max_depth))
+            .collect();
+
+        assert_eq!(
+            policies,
+            vec![
+                (SymlinkPolicy::Always, DEFAULT_SYMLINK_DEPTH, 0),
+                (SymlinkPolicy::FilesOnly, DEFAULT_SYMLINK_DEPTH, 3),
+                (SymlinkPolicy::DirsOnly, 5, 0),
+                (SymlinkPolicy::Never, DEFAULT_SYMLINK_DEPTH, 0),
+                (SymlinkPolicy::Never, DEFAULT_SYMLINK_DEPTH, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn query_with_sorted_roots() {
+        let query = "select name from /test1 sorted, /test2 depth 2 sorted dfs, /test3";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let sorted: Vec<bool> = query.roots.iter().map(|root| root.options.sorted).collect();
+        assert_eq!(sorted, vec![true, true, false]);
+        assert_eq!(query.roots[1].options.max_depth, 2);
+        assert_eq!(query.roots[1].options.traversal, Dfs);
+    }
+
+    #[test]
+    fn query_with_post_order_traversal() {
+        let query = "select name from /test1 dfs-post, /test2 depth 3 dfs_post archives, /test3 dfs";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let traversals: Vec<_> = query.roots.iter().map(|root| root.options.traversal).collect();
+        assert_eq!(traversals, vec![DfsPost, DfsPost, Dfs]);
+        assert!(query.roots[1].options.archives);
+    }
+
+    #[test]
+    fn query_with_default_root_options() {
+        let query = "select name from /test1, /test2 depth 3 archives where size gt 0";
+        let mut p = Parser::new();
+        p.set_default_root_options("gitignore dfs maxdepth 10").unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.roots,
+            vec![
+                Root::new(
+                    String::from("/test1"),
+                    RootOptions { max_depth: 10, gitignore: Some(true), traversal: Dfs, ..RootOptions::new() }
+                ),
+                Root::new(
+                    String::from("/test2"),
+                    RootOptions { max_depth: 3, archives: true, ..RootOptions::new() }
+                ),
+            ]
+        );
+
+        let query = "select name where size gt 0";
+        let mut p = Parser::new();
+        p.set_default_root_options("sym files-only").unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.roots[0].path, ".");
+        assert_eq!(query.roots[0].options.symlinks, SymlinkPolicy::FilesOnly);
+
+        let mut p = Parser::new();
+        assert!(p.set_default_root_options("depth ten").is_err());
+        assert!(p.set_default_root_options("gitignore where").is_err());
+    }
+
+    #[test]
+    fn query_with_archive_fields() {
+        let query = "select path, in_archive, archive_path from /test archives where in_archive";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.fields,
+            vec![
+                Expr::field(Field::Path),
+                Expr::field(Field::InArchive),
+                Expr::field(Field::ArchivePath),
+            ]
+        );
+        assert_eq!(query.roots.len(), 1);
+        assert!(query.roots[0].options.archives);
+    }
+
     #[test]
     fn query_with_not() {
         let query = "select name from /test where name not like '%.tmp'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(query.fields, vec![Expr::field(Field::Name)]);
 
@@ -1080,7 +2034,7 @@ mod tests {
             query.roots,
             vec![Root::new(
                 String::from("/test"),
-                RootOptions::from(0, 0, false, false, None, None, None, Bfs, false)
+                RootOptions::new()
             ),]
         );
 
@@ -1097,7 +2051,7 @@ mod tests {
     fn query_with_single_not() {
         let query = "select name from /test where not name like '%.tmp'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(query.fields, vec![Expr::field(Field::Name)]);
 
@@ -1105,7 +2059,7 @@ mod tests {
             query.roots,
             vec![Root::new(
                 String::from("/test"),
-                RootOptions::from(0, 0, false, false, None, None, None, Bfs, false)
+                RootOptions::new()
             ),]
         );
 
@@ -1122,7 +2076,7 @@ mod tests {
     fn query_with_multiple_not() {
         let query = "select name from /test where not name like '%.tmp' and not name like '%.tst'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let left = Expr::op(
             Expr::field(Field::Name),
@@ -1144,7 +2098,7 @@ mod tests {
         let query =
             "select name from /test where (not name like '%.tmp') and (not name like '%.tst')";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let left = Expr::op(
             Expr::field(Field::Name),
@@ -1165,7 +2119,7 @@ mod tests {
     fn query_double_not() {
         let query = "select name from /test where not not name like '%.tmp'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let expr = Expr::op(
             Expr::field(Field::Name),
@@ -1180,7 +2134,7 @@ mod tests {
     fn query_triple_not() {
         let query = "select name from /test where not not not name like '%.tmp'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let expr = Expr::op(
             Expr::field(Field::Name),
@@ -1195,7 +2149,7 @@ mod tests {
     fn broken_query() {
         let query = "select name, path ,size , fsize from / where name != 'foobar' order by size desc limit 10 into csv this is unexpected";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false);
+        let query = p.parse(vec![query.to_string()]);
 
         assert!(query.is_err());
     }
@@ -1204,13 +2158,13 @@ mod tests {
     fn path_with_spaces() {
         let query = "select name from '/opt/Some Cool Dir/Test This'";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(
             query.roots,
             vec![Root::new(
                 String::from("/opt/Some Cool Dir/Test This"),
-                RootOptions::from(0, 0, false, false, None, None, None, Bfs, false)
+                RootOptions::new()
             ),]
         );
     }
@@ -1219,11 +2173,11 @@ mod tests {
     fn simple_boolean_syntax() {
         let query = "select name from /home/user where is_audio or is_video";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select name from /home/user where is_audio = true or is_video = true";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
@@ -1232,11 +2186,24 @@ mod tests {
     fn simple_boolean_function_syntax() {
         let query = "select name from /home/user where CONTAINS('foobar') or CONTAINS('bazz')";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select name from /home/user where CONTAINS('foobar') = true or CONTAINS('bazz') = true";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
+
+        assert_eq!(query.expr, query2.expr);
+    }
+
+    #[test]
+    fn simple_boolean_function_with_args_syntax() {
+        let query = "select name from /home/user where HASH_EQUALS('sha1', 'da39a3ee5e6b4b0d3255bfef95601890afd80709')";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let query2 = "select name from /home/user where HASH_EQUALS('sha1', 'da39a3ee5e6b4b0d3255bfef95601890afd80709') = true";
+        let mut p2 = Parser::new();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
@@ -1246,11 +2213,11 @@ mod tests {
     fn simple_function_without_args_syntax_in_where() {
         let query = "select name, caps from /home/user where HAS_CAPS()";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select name, caps from /home/user where HAS_CAPS";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
@@ -1259,11 +2226,11 @@ mod tests {
     fn simple_function_without_args_syntax() {
         let query = "select CURDATE()";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select CURDATE";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
@@ -1272,7 +2239,7 @@ mod tests {
     fn from_at_the_end_of_the_query() {
         let query = "select name where not name like '%.tmp' from /test gitignore mindepth 2";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(query.fields, vec![Expr::field(Field::Name)]);
 
@@ -1280,7 +2247,7 @@ mod tests {
             query.roots,
             vec![Root::new(
                 String::from("/test"),
-                RootOptions::from(2, 0, false, false, Some(true), None, None, Bfs, false)
+                RootOptions { min_depth: 2, gitignore: Some(true), ..RootOptions::new() }
             ),]
         );
 
@@ -1297,7 +2264,7 @@ mod tests {
     fn query_with_implicit_root() {
         let query = "select name, size";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(
             query.roots,
@@ -1309,13 +2276,13 @@ mod tests {
     fn query_with_implicit_root_and_root_options() {
         let query = "select name, size depth 2";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(
             query.roots,
             vec![Root::new(
                 String::from("."),
-                RootOptions::from(0, 2, false, false, None, None, None, Bfs, false)
+                RootOptions { max_depth: 2, ..RootOptions::new() }
             ),]
         );
     }
@@ -1324,11 +2291,11 @@ mod tests {
     fn use_curly_braces() {
         let query = "select name, (1 + 2) from /home/user limit 1";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select name, {1 + 2} from /home/user limit 1";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
@@ -1337,7 +2304,7 @@ mod tests {
     fn query_with_group_by() {
         let query = "select AVG(size) from /test group by mime";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         assert_eq!(
             query.fields,
@@ -1351,7 +2318,7 @@ mod tests {
             query.roots,
             vec![Root::new(
                 String::from("/test"),
-                RootOptions::from(0, 0, false, false, None, None, None, Bfs, false)
+                RootOptions::new()
             ),]
         );
 
@@ -1365,12 +2332,856 @@ mod tests {
     fn query_with_between() {
         let query = "select name, size from /test where size between 5mb and 6mb";
         let mut p = Parser::new();
-        let query = p.parse(vec![query.to_string()], false).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
 
         let query2 = "select name, size from /test where size gte 5mb and size lte 6mb";
         let mut p2 = Parser::new();
-        let query2 = p2.parse(vec![query2.to_string()], false).unwrap();
+        let query2 = p2.parse(vec![query2.to_string()]).unwrap();
 
         assert_eq!(query.expr, query2.expr);
     }
+
+    #[test]
+    fn query_with_windows_long_and_unc_roots() {
+        let query = r"select name from '\\?\C:\deep' verbatim, '\\server\share' depth 2";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.roots,
+            vec![
+                Root::new(
+                    String::from(r"\\?\C:\deep"),
+                    RootOptions { verbatim: true, ..RootOptions::new() }
+                ),
+                Root::new(
+                    String::from(r"\\server\share"),
+                    RootOptions { max_depth: 2, ..RootOptions::new() }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn query_with_expression_group_keys() {
+        let query = "select lower(ext), size_bucket(size, '1k'), count(*) from /test group by LOWER(ext), SIZE_BUCKET(size, '1k'), size_bucket(size, '1m'), size + 1, size - 1";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let keys: Vec<String> = query.grouping_fields.iter().map(|key| key.to_string()).collect();
+        assert_eq!(
+            keys,
+            vec!["Lower(Extension)", "SizeBucket(Size, 1k)", "SizeBucket(Size, 1m)", "Size + 1", "Size - 1"]
+        );
+        assert_eq!(query.fields[0].to_string(), keys[0]);
+        assert_eq!(query.fields[1].to_string(), keys[1]);
+    }
+
+    #[test]
+    fn query_with_pivot() {
+        let query = "select name from /test where size > 0 into pivot(rows=ext, cols=YEAR(modified), value=sum(size))";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        let fields: Vec<String> = query.fields.iter().map(|field| field.to_string()).collect();
+        assert_eq!(fields, vec!["Extension", "Year(Modified)", "Sum(Size)"]);
+        assert_eq!(query.grouping_fields.len(), 2);
+        assert!(matches!(query.output_format, OutputFormat::Pivot(_)));
+
+        let query = "select name from /test into pivot(rows=ext, cols=year(modified))";
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.fields[2].to_string(), "Count(*)");
+
+        let query = "select name from /test into pivot(rows=ext, cols=year(modified), value=size)";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_column_formats() {
+        let query = "select name, size :: fsize('%.1 '), modified::date('%Y-%m-%d'), duration :: time from /test";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.fields.len(), 4);
+        assert_eq!(query.fields[1].to_string(), "Size");
+        assert_eq!(
+            query.output_options.column_formats,
+            vec![
+                None,
+                Some(ColumnFormat::Size(String::from("%.1 "))),
+                Some(ColumnFormat::Date(String::from("%Y-%m-%d"))),
+                Some(ColumnFormat::Time),
+            ]
+        );
+
+        let query = "select sum(size) :: fsize, name from /test";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(
+            query.output_options.column_formats,
+            vec![Some(ColumnFormat::Size(String::new())), None]
+        );
+
+        let query = "select name, size :: unknown from /test";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_column_aliases() {
+        let query = "select size as bytes, upper(name) AS filename, path from /test order by bytes desc";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.fields.len(), 3);
+        assert_eq!(
+            query.output_options.column_names,
+            vec![Some(String::from("bytes")), Some(String::from("filename")), None]
+        );
+        assert_eq!(query.ordering_fields[0].to_string(), query.fields[0].to_string());
+        assert_eq!(query.ordering_asc, Rc::new(vec![false]));
+
+        let query = "select ext as kind, count(*) as 'total files' from /test group by kind order by 2";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.grouping_fields[0].to_string(), query.fields[0].to_string());
+        assert_eq!(query.output_options.column_names[1], Some(String::from("total files")));
+
+        let query = "select size::fsize as bytes, name from /test";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_options.column_formats[0], Some(ColumnFormat::Size(String::new())));
+        assert_eq!(query.output_options.column_names, vec![Some(String::from("bytes")), None]);
+
+        let query = "select name as from /test";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_output_sink() {
+        let query = "select name, size from /test into json.gz 'out.json.gz'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Json);
+        assert_eq!(
+            query.output_sink,
+            OutputSink {
+                path: Some(PathBuf::from("out.json.gz")),
+                compression: Some(Compression::Gzip),
+            }
+        );
+
+        let query = "select name from /test into csv to out.csv.zst";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Csv);
+        assert_eq!(query.output_sink.compression, Some(Compression::Zstd));
+
+        let query = "select name from /test into lines.gz";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Lines);
+        assert_eq!(query.output_sink.path, None);
+        assert_eq!(query.output_sink.compression, Some(Compression::Gzip));
+
+        let query = "select name from /test into json 'out.json'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_sink.compression, None);
+
+        let query = "select name from /test into csv.bz2";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_ndjson_output() {
+        let query = "select name, size from /test into ndjson";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Ndjson);
+
+        let query = "select name, size from /test into jsonl.gz 'out.jsonl.gz'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Ndjson);
+        assert_eq!(query.output_sink.compression, Some(Compression::Gzip));
+    }
+
+    #[test]
+    fn query_with_sqlite_output() {
+        let query = "select name, size from /test into sqlite 'results.db'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Sqlite);
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("results.db")));
+    }
+
+    #[test]
+    fn query_with_table_output() {
+        let query = "select name, size from /test into table";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Table { borders: false });
+
+        let query = "select name, size from /test into table(box) 'out.txt'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Table { borders: true });
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("out.txt")));
+
+        let query = "select name, size from /test into table(round)";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_xlsx_output() {
+        let query = "select name, size from /test into xlsx 'report.xlsx'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Xlsx);
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("report.xlsx")));
+    }
+
+    #[test]
+    fn query_with_arrow_output() {
+        let query = "select name, size, modified from /test into arrow '/tmp/files.arrows'";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Arrow);
+        assert_eq!(
+            query.output_options.column_types,
+            vec![VariantType::String, VariantType::Int, VariantType::DateTime]
+        );
+    }
+
+    #[test]
+    fn query_with_partition() {
+        let query = "select name, size from /test into csv 'report.csv' partition by ext";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Csv);
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("report.csv")));
+        assert_eq!(query.partition.as_ref().map(|expr| expr.to_string()), Some(String::from("Extension")));
+
+        let fields: Vec<String> = query.fields.iter().map(|field| field.to_string()).collect();
+        assert_eq!(fields, vec!["Name", "Size", "Extension"]);
+
+        let query = "select name from /test into json partition by lower(ext)";
+        let query = Parser::new().parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_sink.path, None);
+        assert_eq!(query.fields[1].to_string(), "Lower(Extension)");
+
+        let query = "select name from /test into csv partition by count(*)";
+        assert!(Parser::new().parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_order_by_positions_and_expressions() {
+        let query = "select path, size, modified from /test order by -1 desc, 2";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(
+            *query.ordering_fields,
+            vec![Expr::field(Field::Modified), Expr::field(Field::Size)]
+        );
+        assert_eq!(*query.ordering_asc, vec![false, true]);
+
+        let query = "select path from /test order by LOWER(ext), size desc";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.fields, vec![Expr::field(Field::Path)]);
+        assert_eq!(
+            *query.ordering_fields,
+            vec![
+                Expr::function_left(Function::Lower, Some(Box::new(Expr::field(Field::Extension)))),
+                Expr::field(Field::Size),
+            ]
+        );
+        assert_eq!(*query.ordering_asc, vec![true, false]);
+
+        for query in [
+            "select path, size from /test order by 3",
+            "select path, size from /test order by 0",
+            "select path, size from /test order by -3",
+        ] {
+            let mut p = Parser::new();
+            assert!(p.parse(vec![query.to_string()]).is_err());
+        }
+    }
+
+    #[test]
+    fn standalone_condition() {
+        let mut p = Parser::new();
+        let expr = p.parse_condition("size > 1gb and name like '%.iso'").unwrap();
+        assert_eq!(expr.logical_op, Some(LogicalOp::And));
+        assert_eq!(expr.left.unwrap().op, Some(Op::Gt));
+
+        assert!(Parser::new().parse_condition("size >").is_err());
+        assert!(Parser::new().parse_condition("size > 1gb limit 5").is_err());
+    }
+
+    #[test]
+    fn query_with_star_columns() {
+        let query = "select * from /test";
+        let mut p = Parser::new();
+        p.set_star_columns(&[String::from("name"), String::from("fsize")]).unwrap();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.fields, vec![Expr::field(Field::Name), Expr::field(Field::FormattedSize)]);
+
+        let mut p = Parser::new();
+        assert!(p.set_star_columns(&[String::from("nonexistent")]).is_err());
+        assert!(p.set_star_columns(&[]).is_err());
+
+        let query = "select **, sha1 from /test";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.fields.first(), Some(&Expr::field(Field::Name)));
+        assert!(query.fields.contains(&Expr::field(Field::Modified)));
+        assert_eq!(query.fields.last(), Some(&Expr::field(Field::Sha1)));
+    }
+
+    #[test]
+    fn query_with_csv_dialect() {
+        let query = "select name, size from /test into csv(';', header, quote=nonnumeric) '/tmp/out.csv'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Csv);
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("/tmp/out.csv")));
+        assert_eq!(
+            query.output_options,
+            OutputOptions {
+                headers: true,
+                delimiter: Some(';'),
+                quoting: Some(CsvQuoting::NonNumeric),
+                sortable: false,
+                column_formats: vec![None, None],
+                column_names: vec![None, None],
+                column_types: vec![VariantType::String, VariantType::Int],
+            }
+        );
+
+        let query = "select name from /test into csv(tab)";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_options.delimiter, Some('\t'));
+        assert!(!query.output_options.headers);
+
+        for query in [
+            "select name from /test into csv('::')",
+            "select name from /test into csv(quote=sometimes)",
+            "select name from /test into csv(header",
+        ] {
+            let mut p = Parser::new();
+            assert!(p.parse(vec![query.to_string()]).is_err());
+        }
+    }
+
+    #[test]
+    fn query_with_conditions_ordered_by_cost() {
+        let query = "select path from /test where contains('error') and size > 1024 and name like '%.log'";
+        let mut p = Parser::new();
+        let mut query = p.parse(vec![query.to_string()]).unwrap();
+        query.reorder_conditions();
+        let expr = query.expr.unwrap();
+        assert_eq!(expr.logical_op, Some(LogicalOp::And));
+
+        let operands = expr.get_operands();
+        let costs: Vec<Cost> = operands.iter().map(|operand| operand.get_cost()).collect();
+        assert_eq!(costs, vec![Cost::Name, Cost::Metadata, Cost::Content]);
+        assert_eq!(operands[0].to_string(), "Name like %.log");
+
+        let query = "select path from /test where sha1 = '0000' or (width > 100 and is_archive = true) or ext = 'jpg'";
+        let mut p = Parser::new();
+        let mut query = p.parse(vec![query.to_string()]).unwrap();
+        query.reorder_conditions();
+        let expr = query.expr.unwrap();
+        let operands = expr.get_operands();
+        let costs: Vec<Cost> = operands.iter().map(|operand| operand.get_cost()).collect();
+        assert_eq!(costs, vec![Cost::Name, Cost::Dimensions, Cost::Content]);
+
+        let inner = operands[1].get_operands();
+        assert_eq!(inner[0].get_cost(), Cost::Metadata);
+        assert_eq!(inner[1].get_cost(), Cost::Dimensions);
+    }
+
+    #[test]
+    fn query_with_explain() {
+        let query = "explain select name from /test where mime = 'text/plain' and name like '%.txt' limit 5";
+        let mut p = Parser::new();
+        let mut query = p.parse(vec![query.to_string()]).unwrap();
+        assert!(query.explain);
+
+        query.reorder_conditions();
+        let plan = query.explain(&Config::default());
+        let name_line = plan.find("Name like %.txt [name]").unwrap();
+        let mime_line = plan.find("Mime = text/plain [mime]").unwrap();
+        assert!(name_line < mime_line);
+        assert!(plan.contains("Limit: 5"));
+        assert!(plan.contains("  /test [bfs]"));
+        assert!(plan.contains("  content: Mime"));
+
+        let query = "explain select name, sha256, width from /test dfs gitignore maxdepth 2";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        let plan = query.explain(&Config::default());
+        assert!(plan.contains("  /test [dfs, maxdepth 2, gitignore]"));
+        assert!(plan.contains("  hash: Sha256"));
+        assert!(plan.contains("  image: Width"));
+
+        let query = "explain select name, size from /test";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert!(query.explain(&Config::default()).contains("Reads: metadata only"));
+
+        let query = "select name from /test";
+        let mut p = Parser::new();
+        assert!(!p.parse(vec![query.to_string()]).unwrap().explain);
+    }
+
+    #[test]
+    fn query_with_pattern_set() {
+        let query = "select name from /test where name like '%.jpg' or name ~= '\\.png$' or name like '%.gif'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        let expr = query.expr.as_ref().unwrap();
+        let (column, patterns) = expr.get_pattern_set().unwrap();
+        assert_eq!(column, &Expr::field(Field::Name));
+        assert_eq!(patterns.len(), 3);
+        assert!(query.explain(&Config::default()).contains("regex set of 3 patterns on Name"));
+
+        for query in [
+            "select name from /test where name like '%.jpg' or path like '%.png'",
+            "select name from /test where name like '%.jpg' or size > 100",
+            "select name from /test where name like '%.jpg' and name like '%.png'",
+            "select name from /test where name like '%.jpg'",
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert!(query.expr.unwrap().get_pattern_set().is_none());
+        }
+    }
+
+    #[test]
+    fn compiled_query() {
+        let query = "select name, size from /test where name like '%.log' or name ~= '^err' or name = '*.txt'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        let plan = query.compile().unwrap();
+        assert_eq!(plan.fields.len(), 2);
+        assert!(plan.regexes["%.log"].is_match("app.log"));
+        assert!(plan.regexes["^err"].is_match("errors"));
+        assert!(plan.regexes["*.txt"].is_match("notes.txt"));
+        assert!(plan.pattern_sets.is_empty());
+
+        let query = "select name from /test where size > 0 and (name like '%.log' or name ~= '^err')";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        let plan = query.compile().unwrap();
+        let chain = query.expr.as_ref().unwrap().right.as_deref().unwrap();
+        let pattern_set = &plan.pattern_sets[&(chain as *const Expr)];
+        assert_eq!(pattern_set.column, Expr::field(Field::Name));
+        assert!(pattern_set.regex_set.is_match("errors"));
+        assert!(!pattern_set.regex_set.is_match("notes.txt"));
+
+        let query = "select name from /test where name ~= '(unclosed'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert!(query.compile().is_err());
+    }
+
+    #[test]
+    fn query_compile_checks_constants() {
+        for query in [
+            "select name from /test where hash_equals(sha256, 'abc')",
+            "select name, size_bucket(size, '1k,1m') from /test",
+            "select name, age_bucket(modified, '1d,7d') from /test",
+            "select name from /test where grew_since('10s')",
+            "select name, random(), random(10), random(-5, 5) from /test",
+            "select name from /test where modified gt '1 hour ago' and created lt 2024-01-01",
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert!(query.compile().is_ok(), "{:?}", query);
+        }
+
+        for query in [
+            "select name from /test where hash_equals(md5, 'abc')",
+            "select name, size_bucket(size, '1m,1k') from /test",
+            "select name, age_bucket(modified, 'soon') from /test",
+            "select name from /test where grew_since('forever')",
+            "select name, random(0) from /test",
+            "select name from /test where size > random(5, 5)",
+            "select name from /test where modified gt 'gibberish'",
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert!(query.compile().is_err(), "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn query_with_output_file() {
+        let query = "select name, size from /test into csv file '/tmp/report.csv'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Csv);
+        assert_eq!(query.output_sink.path, Some(PathBuf::from("/tmp/report.csv")));
+
+        let query = "select name from /test into json file";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+
+        // A mistyped keyword is an error rather than the name of the output file
+        let query = "select name from /test into csv limt 5";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_flat_headers() {
+        for (query, output_format) in [
+            ("select name, size from /test into tabs(headers)", OutputFormat::Tabs),
+            ("select name, size from /test into tsv(headers)", OutputFormat::Tsv),
+            ("select name, size from /test into lines(header) '/tmp/out.txt'", OutputFormat::Lines),
+            ("select name, size from /test into list(headers)", OutputFormat::List),
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert_eq!(query.output_format, output_format);
+            assert!(query.output_options.headers);
+        }
+
+        let query = "select name from /test into tabs(borders)";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_sortable_html() {
+        let query = "select path, size from /test into html(sortable)";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Html);
+        assert!(query.output_options.sortable);
+
+        let query = "select path, size from /test into html";
+        let mut p = Parser::new();
+        assert!(!p.parse(vec![query.to_string()]).unwrap().output_options.sortable);
+
+        let query = "select path from /test into html(headers)";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_offset() {
+        let query = "select name from /test order by name limit 10 offset 20 into json";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.limit, 10);
+        assert_eq!(query.offset, 20);
+        assert_eq!(query.get_search_limit(), 30);
+        assert_eq!(query.output_format, OutputFormat::Json);
+
+        let query = "select name from /test limit 10";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.offset, 0);
+        assert_eq!(query.get_search_limit(), 10);
+
+        let query = "select name from /test limit 10 offset";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+
+        let query = "select name from /test limit 10 offset many";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+
+        let query = "select count(*) from /test";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.limit, 1);
+
+        let query = "select count(*) from /test group by ext";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.limit, 0);
+    }
+
+    #[test]
+    fn query_limited_by_path() {
+        for (query, expected) in [
+            ("select name from /test order by path limit 10", true),
+            ("select name from /test order by path, size limit 10", true),
+            ("select name from /test order by path desc limit 10", false),
+            ("select name from /test order by size, path limit 10", false),
+            ("select name from /test order by path", false),
+            ("select ext, count(*) from /test group by ext order by path limit 10", false),
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert_eq!(query.is_limited_by_path(), expected, "{:?}", query.ordering_fields);
+        }
+    }
+
+    #[test]
+    fn query_with_distinct() {
+        for query in [
+            "select distinct ext from /test",
+            "distinct ext from /test",
+            "explain select distinct ext",
+        ] {
+            let mut p = Parser::new();
+            let query = p.parse(vec![query.to_string()]).unwrap();
+            assert!(query.distinct);
+            assert_eq!(query.fields, vec![Expr::field(Field::Extension)]);
+        }
+
+        let query = "select ext, size from /test";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert!(!query.distinct);
+        assert_eq!(query.fields.len(), 2);
+    }
+
+    #[test]
+    fn query_with_output_headers_from_config() {
+        let config: Config = toml::from_str("output_headers = true\n").unwrap();
+
+        for (query, headers) in [
+            ("select name from /test", true),
+            ("select name from /test into list", true),
+            ("select name from /test into json", false),
+        ] {
+            let mut p = Parser::new();
+            let mut query = p.parse(vec![query.to_string()]).unwrap();
+            query.apply_output_headers(&config);
+            assert_eq!(query.output_options.headers, headers);
+        }
+    }
+
+    #[test]
+    fn query_with_output_profile() {
+        let query = "select name, size from /test into profile(report)";
+        let mut p = Parser::new();
+        let mut query = p.parse(vec![query.to_string()]).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Profile(String::from("report")));
+
+        let config: Config = toml::from_str(
+            "[output.report]\nformat = \"csv\"\nheaders = true\ndelimiter = \";\"\n\n[output.bad]\ndelimiter = \"::\"\n",
+        )
+        .unwrap();
+        query.apply_output_profile(&config).unwrap();
+        assert_eq!(query.output_format, OutputFormat::Csv);
+        assert_eq!(
+            query.output_options,
+            OutputOptions {
+                headers: true,
+                delimiter: Some(';'),
+                quoting: None,
+                sortable: false,
+                column_formats: vec![None, None],
+                column_names: vec![None, None],
+                column_types: vec![VariantType::String, VariantType::Int],
+            }
+        );
+
+        for name in ["missing", "bad"] {
+            let query = format!("select name from /test into profile({})", name);
+            let mut query = p.parse(vec![query]).unwrap();
+            assert!(query.apply_output_profile(&config).is_err());
+        }
+
+        let query = "select name from /test into profile";
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_window_functions() {
+        let query = "select size, running_sum(size), percent_of_total(running_sum(size)) from /test order by size desc limit 20";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert!(query.has_window_column());
+        assert!(!query.has_aggregate_column());
+        assert!(!query.fields[0].is_window_function());
+        assert!(query.fields[1].is_window_function());
+        assert!(query.fields[2].is_window_function());
+
+        let query = "select format_size(running_sum(size)) from /test";
+        let query = p.parse(vec![query.to_string()]).unwrap();
+        assert!(!query.has_window_column());
+    }
+
+    #[test]
+    fn query_with_manifest_roots() {
+        let query = "select name, size from 'files.csv' as csv(path, size, modified), /test depth 1, inventory.json as json where size > 1mb";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.roots,
+            vec![
+                Root::with_manifest(
+                    String::from("files.csv"),
+                    Manifest {
+                        format: ManifestFormat::Csv,
+                        fields: vec![Field::Path, Field::Size, Field::Modified],
+                    }
+                ),
+                Root::new(
+                    String::from("/test"),
+                    RootOptions { max_depth: 1, ..RootOptions::new() }
+                ),
+                Root::with_manifest(
+                    String::from("inventory.json"),
+                    Manifest {
+                        format: ManifestFormat::Json,
+                        fields: vec![],
+                    }
+                ),
+            ]
+        );
+        assert!(query.expr.is_some());
+
+        let query = "select name from 'files.csv' as csv(path, bogus)";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_trash_actions() {
+        let query = "select name from trash:// where trash_deleted lt '30 days ago' restore to '/tmp/restored'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.roots, vec![Root::new(String::from("trash://"), RootOptions::new())]);
+        assert_eq!(
+            query.action,
+            Some(Action::Restore(Some(String::from("/tmp/restored"))))
+        );
+
+        let query = "select name from trash:// depth 1 restore";
+        let mut p = Parser::new();
+        assert_eq!(p.parse(vec![query.to_string()]).unwrap().action, Some(Action::Restore(None)));
+
+        let query = "select name from /home/user where size > 1gb purge";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+
+        let query = "select name where size > 1gb purge";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_replace_duplicates() {
+        let query = "select path from /test where size gt 1mb replace duplicates with hardlinks";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::ReplaceDuplicates { kind: LinkKind::Hard, dry_run: false })
+        );
+
+        let query = "select path from /test replace duplicates with symlinks dry run";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::ReplaceDuplicates { kind: LinkKind::Symbolic, dry_run: true })
+        );
+
+        let query = "select path from /test replace duplicates with copies";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_delete() {
+        let query = "select path from /test dfs-post where is_recursively_empty delete";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.action, Some(Action::Delete { into_trash: false, dry_run: false }));
+        assert_eq!(
+            query.expr,
+            Some(Expr::op(
+                Expr::field(Field::IsRecursivelyEmpty),
+                Op::Eq,
+                Expr::value(String::from("true")),
+            ))
+        );
+
+        let query = "select path from /test where is_empty delete dry run";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.action, Some(Action::Delete { into_trash: false, dry_run: true }));
+
+        let query = "select path from /test where is_empty limit 10 offset 10 into json delete";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.action, Some(Action::Delete { into_trash: false, dry_run: false }));
+        assert_eq!(query.output_format, OutputFormat::Json);
+
+        let query = "select path from /test where name like '%.bak' delete into trash";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.action, Some(Action::Delete { into_trash: true, dry_run: false }));
+
+        let query = "select path from /test into csv delete into trash dry run";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(query.action, Some(Action::Delete { into_trash: true, dry_run: true }));
+        assert_eq!(query.output_format, OutputFormat::Csv);
+
+        for query in [
+            "select path from /test delete into bin",
+            "select path from trash:// delete into trash",
+            "select path from /test where is_recursively_empty order by path limit 1 delete",
+            "select path from /test order by path delete dry run",
+            "select ext, count(*) from /test group by ext delete",
+            "select max(size) from /test delete dry run",
+            "select path, running_sum(size) from /test delete",
+        ] {
+            let mut p = Parser::new();
+            assert!(p.parse(vec![query.to_string()]).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn query_with_update_flags() {
+        let query = "select path from /etc where name = '*.conf' update set immutable = true, append_only = false";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::UpdateFlags { immutable: Some(true), append_only: Some(false) })
+        );
+
+        let query = "select path from /etc update set is_immutable = 0";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::UpdateFlags { immutable: Some(false), append_only: None })
+        );
+
+        let query = "select path from /etc update set nodump = true";
+        let mut p = Parser::new();
+        assert!(p.parse(vec![query.to_string()]).is_err());
+    }
+
+    #[test]
+    fn query_with_extract_thumbnail() {
+        let query = "select name from /test where is_image extract thumbnail to '/tmp/thumbs/{sha1}.jpg'";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::ExtractThumbnail { template: String::from("/tmp/thumbs/{sha1}.jpg"), overwrite: false })
+        );
+
+        let query = "select name from /test where is_video extract thumbnail to '/tmp/thumbs/{name}.jpg' overwrite";
+        let mut p = Parser::new();
+        let query = p.parse(vec![query.to_string()]).unwrap();
+
+        assert_eq!(
+            query.action,
+            Some(Action::ExtractThumbnail { template: String::from("/tmp/thumbs/{name}.jpg"), overwrite: true })
+        );
+    }
 }
@@ -1,11 +1,19 @@
 //! Defines the query struct and related types.
 //! Query parsing is handled in the `parser` module
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::PathBuf;
 use std::rc::Rc;
 
-use crate::expr::Expr;
+use regex::{Regex, RegexSet};
+
+use crate::config::Config;
+use crate::expr::{Cost, Expr};
 use crate::field::Field;
+use crate::function::{self, Function, VariantType};
+use crate::operators::Op;
+use crate::util::{convert_glob_to_pattern, convert_like_to_pattern, is_glob, parse_datetime};
 use crate::query::TraversalMode::Bfs;
 
 #[derive(Debug, Clone)]
@@ -14,6 +22,8 @@ use crate::query::TraversalMode::Bfs;
 pub struct Query {
     /// File fields to be selected
     pub fields: Vec<Expr>,
+    /// Repeated rows are written only once
+    pub distinct: bool,
     /// Root directories to search
     pub roots: Vec<Root>,
     /// "where" filter expression
@@ -26,8 +36,21 @@ pub struct Query {
     pub ordering_asc: Rc<Vec<bool>>,
     /// Max amount of results to return
     pub limit: u32,
+    /// Amount of results to skip before the returned ones
+    pub offset: u32,
     /// Output format
     pub output_format: OutputFormat,
+    /// Settings of the output format taken from the profile
+    pub output_options: OutputOptions,
+    /// File to write the results to instead of stdout, and their compression
+    pub output_sink: OutputSink,
+    /// Expression splitting the results into a file per its value.
+    /// It's also the last of the fields, so it's computed for every row, and removed before writing
+    pub partition: Option<Expr>,
+    /// Action to perform on each matching file
+    pub action: Option<Action>,
+    /// Print how the query is evaluated instead of running it
+    pub explain: bool,
 }
 
 impl Query {
@@ -41,6 +64,23 @@ impl Query {
         result
     }
 
+    /// Every expression of the query, the columns and the conditions alike
+    fn get_all_exprs(&self) -> impl Iterator<Item = &Expr> {
+        self.fields
+            .iter()
+            .chain(self.expr.iter())
+            .chain(self.grouping_fields.iter())
+            .chain(self.ordering_fields.iter())
+            .chain(self.partition.iter())
+    }
+
+    /// Results of such queries change over time or with the contents of the files,
+    /// even though the directories stay the same
+    pub fn is_volatile(&self) -> bool {
+        self.get_all_exprs()
+            .any(|expr| expr.is_time_relative() || expr.get_cost() == Cost::Content)
+    }
+
     pub fn is_ordered(&self) -> bool {
         !self.ordering_fields.is_empty()
     }
@@ -48,6 +88,376 @@ impl Query {
     pub fn has_aggregate_column(&self) -> bool {
         self.fields.iter().any(|ref f| f.has_aggregate_function())
     }
+
+    /// Number of results to find before the search is over, the skipped ones included
+    pub fn get_search_limit(&self) -> u32 {
+        match self.limit {
+            0 => 0,
+            limit => limit.saturating_add(self.offset),
+        }
+    }
+
+    pub fn has_window_column(&self) -> bool {
+        self.fields.iter().any(|f| f.is_window_function())
+    }
+
+    /// Results go by path with a limit, so files sorting after the last of the kept paths are not worth visiting.
+    /// Actions and window functions need every matching file, and so do aggregates.
+    pub fn is_limited_by_path(&self) -> bool {
+        self.limit > 0
+            && self.ordering_fields.first() == Some(&Expr::field(Field::Path))
+            && self.ordering_asc.first() == Some(&true)
+            && self.action.is_none()
+            && !self.has_aggregate_column()
+            && !self.has_window_column()
+    }
+
+    /// Compiles the patterns of the conditions and resolves the fields of the columns once,
+    /// so any number of searches could share them, whatever roots they go through.
+    /// Invalid patterns, dates, and arguments of the functions are reported here instead of stopping the search midway.
+    pub fn compile(&self) -> Result<QueryPlan, String> {
+        for expr in self.get_all_exprs() {
+            Self::check_constants(expr)?;
+        }
+
+        let mut regexes = HashMap::new();
+        if let Some(ref expr) = self.expr {
+            Self::compile_patterns(expr, &mut regexes)?;
+        }
+
+        let mut pattern_sets = HashMap::new();
+        if let Some(ref expr) = self.expr {
+            Self::compile_pattern_sets(expr, &mut pattern_sets)?;
+        }
+
+        Ok(QueryPlan {
+            fields: Rc::new(self.get_all_fields().into_iter().collect()),
+            regexes,
+            pattern_sets,
+        })
+    }
+
+    /// Constant arguments of the functions and dates compared with have to make sense for any file
+    fn check_constants(expr: &Expr) -> Result<(), String> {
+        let constant = |expr: &Expr| {
+            expr.val.as_ref().map(|val| match expr.minus {
+                true => format!("-{}", val),
+                false => val.clone(),
+            })
+        };
+
+        if let Some(ref function) = expr.function {
+            // The algorithm name of HASH_EQUALS is parsed as the hash field of the same name
+            let function_arg = match (function, expr.left.as_deref()) {
+                (Function::HashEquals, Some(Expr { field: Some(field), .. })) => Some(field.to_string()),
+                (_, Some(left)) => constant(left),
+                (_, None) => Some(String::new()),
+            };
+            let function_args: Vec<Option<String>> =
+                expr.args.iter().flatten().map(constant).collect();
+            let function_args: Vec<Option<&str>> =
+                function_args.iter().map(Option::as_deref).collect();
+            function::check_args(function, function_arg.as_deref(), &function_args)?;
+        }
+
+        if let (Some(_), Some(left), Some(right)) = (&expr.op, &expr.left, &expr.right) {
+            if left.get_value_type() == VariantType::DateTime {
+                if let Some(val) = constant(right) {
+                    parse_datetime(&val).map_err(|_| format!("Can't parse datetime: {}", val))?;
+                }
+            }
+        }
+
+        for operand in expr.left.iter().chain(expr.right.iter()) {
+            Self::check_constants(operand)?;
+        }
+        for arg in expr.args.iter().flatten() {
+            Self::check_constants(arg)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pattern sets are keyed by the OR nodes they're made of, so the plan only fits this very query
+    fn compile_pattern_sets(
+        expr: &Expr,
+        pattern_sets: &mut HashMap<*const Expr, Rc<PatternSet>>,
+    ) -> Result<(), String> {
+        if let Some((column, patterns)) = expr.get_pattern_set() {
+            let regex_set = RegexSet::new(&patterns)
+                .map_err(|_| format!("Incorrect regex expressions {}", patterns.join(", ")))?;
+            let pattern_set = PatternSet { column: column.clone(), regex_set };
+            pattern_sets.insert(expr as *const Expr, Rc::new(pattern_set));
+        }
+
+        for operand in expr.left.iter().chain(expr.right.iter()) {
+            Self::compile_pattern_sets(operand, pattern_sets)?;
+        }
+
+        Ok(())
+    }
+
+    /// Regexes are keyed by the values of the conditions, as the searcher looks them up
+    fn compile_patterns(expr: &Expr, regexes: &mut HashMap<String, Regex>) -> Result<(), String> {
+        let val = expr.right.as_ref().and_then(|right| right.val.as_ref());
+
+        if let (Some(op), Some(val)) = (&expr.op, val) {
+            if !regexes.contains_key(val) {
+                let regex = match op {
+                    Op::Rx | Op::NotRx => Some(
+                        Regex::new(val).map_err(|_| format!("Incorrect regex expression {}", val))?,
+                    ),
+                    Op::Like | Op::NotLike => Some(
+                        Regex::new(&convert_like_to_pattern(val))
+                            .map_err(|_| format!("Incorrect LIKE expression {}", val))?,
+                    ),
+                    // Globs that don't compile are compared as they are
+                    Op::Eq | Op::Ne if is_glob(val) => Regex::new(&convert_glob_to_pattern(val)).ok(),
+                    _ => None,
+                };
+
+                if let Some(regex) = regex {
+                    regexes.insert(val.clone(), regex);
+                }
+            }
+        }
+
+        for operand in expr.left.iter().chain(expr.right.iter()) {
+            Self::compile_patterns(operand, regexes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the cheapest conditions first, see `Expr::reorder_by_cost`
+    pub fn reorder_conditions(&mut self) {
+        self.expr = self.expr.take().map(Expr::reorder_by_cost);
+    }
+
+    /// Describes the query for `explain`: the conditions in the order they are evaluated,
+    /// how the roots are traversed, and what is read from the files besides their metadata
+    pub fn explain(&self, config: &Config) -> String {
+        let mut result = String::new();
+
+        let column_names = self.output_options.column_names.iter().chain(std::iter::repeat(&None));
+        let columns: Vec<String> = self
+            .fields
+            .iter()
+            .zip(column_names)
+            .map(|(field, alias)| match alias {
+                Some(alias) => format!("{} as {}", field, alias),
+                None => field.to_string(),
+            })
+            .collect();
+        let distinct = if self.distinct { ", distinct" } else { "" };
+        let _ = writeln!(result, "Columns: {}{}", columns.join(", "), distinct);
+
+        let _ = writeln!(result, "Roots:");
+        for root in &self.roots {
+            let _ = writeln!(result, "  {} [{}]", root.path, Self::explain_root_options(&root.options, config));
+        }
+
+        match self.expr {
+            Some(ref expr) => {
+                let _ = writeln!(result, "Conditions, in evaluation order:");
+                Self::explain_condition(expr, 1, &mut result);
+            }
+            None => {
+                let _ = writeln!(result, "Conditions: none");
+            }
+        }
+
+        if self.is_ordered() {
+            let ordering: Vec<String> = self
+                .ordering_fields
+                .iter()
+                .zip(self.ordering_asc.iter())
+                .map(|(field, asc)| format!("{} {}", field, if *asc { "asc" } else { "desc" }))
+                .collect();
+            let _ = writeln!(result, "Order by: {}", ordering.join(", "));
+        }
+
+        if self.limit > 0 {
+            let _ = match self.offset {
+                0 => writeln!(result, "Limit: {}", self.limit),
+                offset => writeln!(result, "Limit: {}, offset {}", self.limit, offset),
+            };
+        }
+
+        let mut extractors = BTreeMap::new();
+        let all_exprs = self.fields.iter()
+            .chain(self.expr.iter())
+            .chain(self.grouping_fields.iter())
+            .chain(self.ordering_fields.iter());
+        for expr in all_exprs {
+            Self::collect_extractors(expr, &mut extractors);
+        }
+
+        match extractors.is_empty() {
+            true => {
+                let _ = writeln!(result, "Reads: metadata only");
+            }
+            false => {
+                let _ = writeln!(result, "Reads:");
+                for (group, names) in extractors {
+                    let names: Vec<String> = names.into_iter().collect();
+                    let _ = writeln!(result, "  {}: {}", group, names.join(", "));
+                }
+            }
+        }
+
+        result
+    }
+
+    fn explain_root_options(options: &RootOptions, config: &Config) -> String {
+        let traversal = match options.traversal {
+            TraversalMode::Bfs => "bfs",
+            TraversalMode::Dfs => "dfs",
+            TraversalMode::DfsPost => "dfs-post",
+        };
+        let mut result = vec![traversal.to_string()];
+
+        if options.min_depth > 0 {
+            result.push(format!("mindepth {}", options.min_depth));
+        }
+        if options.max_depth > 0 {
+            result.push(format!("maxdepth {}", options.max_depth));
+        }
+        if options.archives {
+            result.push(String::from("archives"));
+        }
+        match options.symlinks {
+            SymlinkPolicy::Never => {}
+            SymlinkPolicy::FilesOnly => result.push(String::from("symlinks files-only")),
+            SymlinkPolicy::DirsOnly => result.push(String::from("symlinks dirs-only")),
+            SymlinkPolicy::Always => result.push(String::from("symlinks always")),
+        }
+
+        let ignore_files = [
+            ("gitignore", options.gitignore, config.gitignore),
+            ("hgignore", options.hgignore, config.hgignore),
+            ("dockerignore", options.dockerignore, config.dockerignore),
+        ];
+        for (name, option, default) in ignore_files {
+            if option.unwrap_or(default.unwrap_or(false)) {
+                result.push(name.to_string());
+            }
+        }
+
+        result.join(", ")
+    }
+
+    /// Fields and functions reading more than the metadata of the files, by their groups
+    fn collect_extractors(expr: &Expr, extractors: &mut BTreeMap<&'static str, BTreeSet<String>>) {
+        if let Some(field) = expr.field {
+            if field.get_cost() >= Cost::Mime {
+                extractors.entry(field.get_group()).or_default().insert(field.to_string());
+            }
+        }
+
+        if let Some(ref function) = expr.function {
+            if function.get_cost() == Cost::Content {
+                extractors.entry("content").or_default().insert(function.to_string());
+            }
+        }
+
+        for operand in expr.left.iter().chain(expr.right.iter()) {
+            Self::collect_extractors(operand, extractors);
+        }
+        for arg in expr.args.iter().flatten() {
+            Self::collect_extractors(arg, extractors);
+        }
+    }
+
+    fn explain_condition(expr: &Expr, depth: usize, result: &mut String) {
+        let indent = "  ".repeat(depth);
+        let _ = match (&expr.logical_op, expr.get_pattern_set()) {
+            (Some(_), Some((column, patterns))) => writeln!(
+                result,
+                "{}or [{}], regex set of {} patterns on {}",
+                indent,
+                expr.get_cost(),
+                patterns.len(),
+                column
+            ),
+            (Some(logical_op), None) => writeln!(result, "{}{} [{}]", indent, logical_op, expr.get_cost()),
+            (None, _) => writeln!(result, "{}{} [{}]", indent, expr, expr.get_cost()),
+        };
+
+        if expr.logical_op.is_some() {
+            for operand in expr.get_operands() {
+                Self::explain_condition(operand, depth + 1, result);
+            }
+        }
+    }
+
+    /// Flat formats name their columns if the config says so, unless a profile is selected
+    pub fn apply_output_headers(&mut self, config: &Config) {
+        if config.output_headers.unwrap_or(false) && self.output_format.is_flat() {
+            self.output_options.headers = true;
+        }
+    }
+
+    /// Replaces `profile(NAME)` with the output format and settings of the profile from the config
+    pub fn apply_output_profile(&mut self, config: &Config) -> Result<(), String> {
+        let OutputFormat::Profile(ref name) = self.output_format else {
+            return Ok(());
+        };
+
+        let profile = config
+            .output
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .ok_or_else(|| format!("Unknown output profile {}", name))?;
+
+        let format = profile.format.as_deref().unwrap_or("tabs");
+        let output_format = match OutputFormat::from(format) {
+            Some(OutputFormat::Profile(_)) | None => {
+                return Err(format!("Unknown output format {} in profile {}", format, name));
+            }
+            Some(output_format) => output_format,
+        };
+
+        let delimiter = match profile.delimiter.as_deref() {
+            Some(delimiter) => {
+                let mut chars = delimiter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii() => Some(c),
+                    _ => {
+                        return Err(format!(
+                            "Delimiter of profile {} must be a single ASCII character",
+                            name
+                        ));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        self.output_format = output_format;
+        self.output_options.headers = profile.headers.unwrap_or(false);
+        self.output_options.delimiter = delimiter;
+
+        Ok(())
+    }
+}
+
+/// Parts of the query prepared by `Query::compile`, they don't depend on the roots
+#[derive(Debug, Clone, Default)]
+pub struct QueryPlan {
+    /// Fields the columns need for every file
+    pub fields: Rc<Vec<Field>>,
+    /// Compiled patterns of the conditions by their values
+    pub regexes: HashMap<String, Regex>,
+    /// OR chains of patterns matched at once, by their nodes within the query
+    pub pattern_sets: HashMap<*const Expr, Rc<PatternSet>>,
+}
+
+/// Column and patterns of an OR chain of regex and LIKE conditions, see `Expr::get_pattern_set`
+#[derive(Debug)]
+pub struct PatternSet {
+    pub column: Expr,
+    pub regex_set: RegexSet,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -55,6 +465,22 @@ impl Query {
 pub struct Root {
     pub path: String,
     pub options: RootOptions,
+    /// Read file records from the manifest at the path instead of searching a directory
+    pub manifest: Option<Manifest>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An external list of files, e.g. a pre-collected inventory, used as a root.
+pub struct Manifest {
+    pub format: ManifestFormat,
+    /// Fields the columns map onto, empty to take them from the CSV header or JSON keys
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ManifestFormat {
+    Csv,
+    Json,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -66,8 +492,10 @@ pub struct RootOptions {
     pub max_depth: u32,
     /// Whether to search archives
     pub archives: bool,
-    /// Whether to follow symlinks
-    pub symlinks: bool,
+    /// Which symlinks to follow
+    pub symlinks: SymlinkPolicy,
+    /// Maximum number of links resolved in a chain, longer chains are not followed
+    pub symlink_depth: u32,
     /// Whether to respect .gitignore files
     pub gitignore: Option<bool>,
     /// Whether to respect .hgignore files
@@ -78,6 +506,10 @@ pub struct RootOptions {
     pub traversal: TraversalMode,
     /// Treat the path as a regular expression
     pub regexp: bool,
+    /// Keep the `\\?\` prefix of Windows long paths in the output
+    pub verbatim: bool,
+    /// Read the entries of each directory in lexicographic order
+    pub sorted: bool,
 }
 
 impl RootOptions {
@@ -86,50 +518,73 @@ impl RootOptions {
             min_depth: 0,
             max_depth: 0,
             archives: false,
-            symlinks: false,
+            symlinks: SymlinkPolicy::Never,
+            symlink_depth: DEFAULT_SYMLINK_DEPTH,
             gitignore: None,
             hgignore: None,
             dockerignore: None,
             traversal: Bfs,
             regexp: false,
+            verbatim: false,
+            sorted: false,
         }
     }
+}
 
-    #[cfg(test)]
-    pub fn from(
-        min_depth: u32,
-        max_depth: u32,
-        archives: bool,
-        symlinks: bool,
-        gitignore: Option<bool>,
-        hgignore: Option<bool>,
-        dockerignore: Option<bool>,
-        traversal: TraversalMode,
-        regexp: bool,
-    ) -> RootOptions {
-        RootOptions {
-            min_depth,
-            max_depth,
-            archives,
-            symlinks,
-            gitignore,
-            hgignore,
-            dockerignore,
-            traversal,
-            regexp,
+/// Same as the limit of Linux for resolving paths
+pub const DEFAULT_SYMLINK_DEPTH: u32 = 40;
+
+/// Which symlinks are followed, both for the metadata of the files and for descending into directories
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymlinkPolicy {
+    Never,
+    FilesOnly,
+    DirsOnly,
+    Always,
+}
+
+impl SymlinkPolicy {
+    pub fn from(s: &str) -> Option<SymlinkPolicy> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "never" => Some(SymlinkPolicy::Never),
+            "files-only" | "files" => Some(SymlinkPolicy::FilesOnly),
+            "dirs-only" | "dirs" => Some(SymlinkPolicy::DirsOnly),
+            "always" => Some(SymlinkPolicy::Always),
+            _ => None,
         }
     }
+
+    pub fn follows_files(&self) -> bool {
+        matches!(self, SymlinkPolicy::FilesOnly | SymlinkPolicy::Always)
+    }
+
+    pub fn follows_dirs(&self) -> bool {
+        matches!(self, SymlinkPolicy::DirsOnly | SymlinkPolicy::Always)
+    }
 }
 
 impl Root {
     pub fn new(path: String, options: RootOptions) -> Root {
-        Root { path, options }
+        Root {
+            path,
+            options,
+            manifest: None,
+        }
+    }
+
+    pub fn with_manifest(path: String, manifest: Manifest) -> Root {
+        Root {
+            path,
+            options: RootOptions::new(),
+            manifest: Some(manifest),
+        }
     }
 
     pub fn default(options: Option<RootOptions>) -> Root {
         Root {
             path: String::from("."),
             options: options.unwrap_or_else(RootOptions::new),
+            manifest: None,
         }
     }
 
@@ -145,19 +600,170 @@ impl Root {
 pub enum TraversalMode {
     Bfs,
     Dfs,
+    /// Depth-first, with the contents of a directory going before the directory itself
+    DfsPost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Extract an embedded image thumbnail or the first video frame to the templated path,
+    /// existing files are only replaced when overwriting is asked for
+    ExtractThumbnail { template: String, overwrite: bool },
+    /// Move trashed items back to where they were deleted from, or into the given directory
+    Restore(Option<String>),
+    /// Delete trashed items permanently
+    Purge,
+    /// Replace files with the same contents as an earlier match with links to it,
+    /// only reporting the expected savings when it's a dry run
+    ReplaceDuplicates { kind: LinkKind, dry_run: bool },
+    /// Set or clear the immutable and append-only flags, Linux only
+    UpdateFlags {
+        immutable: Option<bool>,
+        append_only: Option<bool>,
+    },
+    /// Delete directories with nothing but empty directories inside,
+    /// or move any files and directories into the trash of the user,
+    /// only listing them when it's a dry run
+    Delete { into_trash: bool, dry_run: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Hard,
+    Symbolic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A matrix of the aggregated value by the row and column keys, for `into pivot(...)`
+pub struct Pivot {
+    pub rows: Expr,
+    pub cols: Expr,
+    pub value: Expr,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OutputFormat {
     Tabs,
+    /// Tab-separated values with tabs and line breaks inside them escaped
+    Tsv,
     Lines,
     List,
     Csv,
     Json,
+    Ndjson,
     Html,
+    Histogram,
+    Chart,
+    /// Aligned columns with a header, drawn with box-drawing characters if there are borders
+    Table { borders: bool },
+    /// SQL statements creating and filling the results table, executed if there is a database file
+    Sqlite,
+    /// Excel workbook with numbers and dates in their own types
+    Xlsx,
+    /// Apache Arrow IPC stream with the columns typed after their expressions
+    Arrow,
+    Pivot(Box<Pivot>),
+    /// Named profile from the config, replaced with its format before the search
+    Profile(String),
+}
+
+/// Which values of the CSV output are enclosed in quotes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvQuoting {
+    Always,
+    /// Only values with delimiters, quotes, or line breaks
+    Necessary,
+    NonNumeric,
+    Never,
+}
+
+impl CsvQuoting {
+    pub fn from(s: &str) -> Option<CsvQuoting> {
+        match s.to_lowercase().as_str() {
+            "always" | "all" => Some(CsvQuoting::Always),
+            "necessary" | "minimal" => Some(CsvQuoting::Necessary),
+            "nonnumeric" => Some(CsvQuoting::NonNumeric),
+            "never" | "none" => Some(CsvQuoting::Never),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputOptions {
+    /// Write the column names before the first row
+    pub headers: bool,
+    /// Column separator instead of the default one of the format
+    pub delimiter: Option<char>,
+    /// Quoting of the CSV values instead of quoting only where necessary
+    pub quoting: Option<CsvQuoting>,
+    /// Sort the rows of the HTML table by clicking the column names
+    pub sortable: bool,
+    /// Display formats of the columns, by their positions
+    pub column_formats: Vec<Option<ColumnFormat>>,
+    /// Names given to the columns with AS, by their positions
+    pub column_names: Vec<Option<String>>,
+    /// Types of the values of the columns, by their positions
+    pub column_types: Vec<VariantType>,
+}
+
+/// Where the results are written, e.g. `into json.gz 'out.json.gz'`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputSink {
+    /// Stdout if there is none
+    pub path: Option<PathBuf>,
+    pub compression: Option<Compression>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_extension(ext: &str) -> Option<Compression> {
+        match ext.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// How a column is displayed, given after its expression like `size :: fsize('%.1 ')`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnFormat {
+    /// Sizes in bytes, with the same modifiers as FORMAT_SIZE
+    Size(String),
+    /// Dates and times, with chrono format specifiers
+    Date(String),
+    /// Durations in seconds as human-readable time, like FORMAT_TIME
+    Time,
+}
+
+impl ColumnFormat {
+    pub fn from(name: &str, arg: Option<String>) -> Option<ColumnFormat> {
+        match name.to_lowercase().as_str() {
+            "fsize" | "size" | "format_size" => Some(ColumnFormat::Size(arg.unwrap_or_default())),
+            "date" | "datetime" => Some(ColumnFormat::Date(
+                arg.unwrap_or_else(|| String::from("%Y-%m-%d %H:%M:%S")),
+            )),
+            "time" | "format_time" if arg.is_none() => Some(ColumnFormat::Time),
+            _ => None,
+        }
+    }
 }
 
 impl OutputFormat {
+    /// Plain text formats separating the values with a single character
+    pub fn is_flat(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Tabs | OutputFormat::Tsv | OutputFormat::Lines | OutputFormat::List
+        )
+    }
+
     pub fn from(s: &str) -> Option<OutputFormat> {
         let s = s.to_lowercase();
 
@@ -166,8 +772,16 @@ impl OutputFormat {
             "list" => Some(OutputFormat::List),
             "csv" => Some(OutputFormat::Csv),
             "json" => Some(OutputFormat::Json),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
             "tabs" => Some(OutputFormat::Tabs),
+            "tsv" => Some(OutputFormat::Tsv),
             "html" => Some(OutputFormat::Html),
+            "histogram" => Some(OutputFormat::Histogram),
+            "chart" => Some(OutputFormat::Chart),
+            "table" => Some(OutputFormat::Table { borders: false }),
+            "sqlite" | "sqlite3" => Some(OutputFormat::Sqlite),
+            "xlsx" | "excel" => Some(OutputFormat::Xlsx),
+            "arrow" | "ipc" => Some(OutputFormat::Arrow),
             _ => None,
         }
     }
@@ -1,20 +1,23 @@
 //! Handles directory traversal and file processing.
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-#[cfg(unix)]
-use std::fs::symlink_metadata;
-use std::fs::{DirEntry, FileType, Metadata};
+use std::fs::{DirEntry, Metadata};
 use std::io;
-use std::io::{ErrorKind, Write};
+use std::io::{BufWriter, ErrorKind, IsTerminal, Write};
+use std::mem::size_of;
 use std::ops::Add;
 #[cfg(unix)]
-use std::os::unix::fs::{DirEntryExt, MetadataExt};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::LazyLock;
+use std::time::{Instant, SystemTime};
 
 use chrono::{DateTime, Local};
 use git2::Repository;
+use log::{debug, info, trace};
 use lscolors::{LsColors, Style};
 use mp3_metadata::MP3Metadata;
 use regex::Regex;
@@ -23,25 +26,56 @@ use uzers::{Groups, Users, UsersCache};
 #[cfg(unix)]
 use xattr::FileExt;
 
-use crate::config::Config;
+use crate::config::{ColorRule, Config};
 use crate::expr::Expr;
 use crate::field::Field;
 use crate::fileinfo::{to_file_info, FileInfo};
 use crate::function;
-use crate::function::{Variant, VariantType};
+use crate::function::{Function, Variant, VariantType};
 use crate::ignore::docker::{
     matches_dockerignore_filter, search_upstream_dockerignore, DockerignoreFilter,
 };
 use crate::ignore::hg::{matches_hgignore_filter, search_upstream_hgignore, HgignoreFilter};
 use crate::mode;
 use crate::operators::{LogicalOp, Op};
-use crate::output::ResultsWriter;
+use crate::output::partition::PartitionedWriter;
+use crate::output::{Record, ResultsWriter};
+use crate::parser::Parser;
 use crate::query::TraversalMode::Bfs;
-use crate::query::{Query, Root, TraversalMode};
+use crate::query::{Action, Manifest, PatternSet, Query, QueryPlan, Root, SymlinkPolicy, TraversalMode};
+#[cfg(feature = "image-colors")]
+use crate::util::color::get_image_colors;
+use crate::util::code_metrics::{get_code_metrics, CodeMetrics};
 use crate::util::dimensions::get_dimensions;
+use crate::util::integrity;
+use crate::util::license::get_license;
+use crate::util::manifest::{get_row_value, read_manifest, ManifestRow};
+use crate::util::mime::*;
+#[cfg(target_os = "linux")]
+use crate::util::open_files::OpenFiles;
+use crate::util::linguist::{has_generated_marker, Linguist};
+use crate::util::project::{get_project_type, is_git_root, is_hg_root};
+use crate::util::vcs::VersionControl;
+#[cfg(target_os = "linux")]
+use crate::util::statx::{get_statx_info, StatxInfo};
+use crate::util::thumbnail::{extract_image_thumbnail, extract_video_frame};
+use crate::util::dedupe::{is_same_file, replace_with_link, Deduplicator};
 use crate::util::duration::get_duration;
+use crate::util::encryption;
+use crate::util::torrent::get_torrent_info;
+use crate::util::trash;
+use crate::util::trash::{
+    get_trash_dirs, get_trash_info, is_trash_metadata, is_trash_root, TrashDir, TrashInfo,
+};
+use crate::util::video::get_video_metadata;
 use crate::util::*;
 
+/// Buffer of the results written to stdout when it's not a terminal
+const STDOUT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// `{field}` placeholders of the paths the actions write to
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([a-zA-Z0-9_]+)\}").unwrap());
+
 struct FileMetadataState {
     file_metadata_set: bool,
     file_metadata: Option<Metadata>,
@@ -49,9 +83,20 @@ struct FileMetadataState {
     line_count_set: bool,
     line_count: Option<usize>,
 
+    mime_set: bool,
+    mime: Option<&'static str>,
+
+    code_metrics_set: bool,
+    code_metrics: Option<CodeMetrics>,
+
     dimensions_set: bool,
     dimensions: Option<Dimensions>,
 
+    #[cfg(feature = "image-colors")]
+    image_colors_set: bool,
+    #[cfg(feature = "image-colors")]
+    image_colors: Option<ImageColors>,
+
     duration_set: bool,
     duration: Option<Duration>,
 
@@ -60,6 +105,23 @@ struct FileMetadataState {
 
     exif_metadata_set: bool,
     exif_metadata: Option<HashMap<String, String>>,
+
+    video_metadata_set: bool,
+    video_metadata: Option<VideoMetadata>,
+
+    torrent_info_set: bool,
+    torrent_info: Option<TorrentInfo>,
+
+    dir_entry_counts_set: bool,
+    dir_entry_counts: Option<DirEntryCounts>,
+
+    trash_info_set: bool,
+    trash_info: Option<TrashInfo>,
+
+    #[cfg(target_os = "linux")]
+    statx_info_set: bool,
+    #[cfg(target_os = "linux")]
+    statx_info: Option<StatxInfo>,
 }
 
 impl FileMetadataState {
@@ -71,9 +133,20 @@ impl FileMetadataState {
             line_count_set: false,
             line_count: None,
 
+            mime_set: false,
+            mime: None,
+
+            code_metrics_set: false,
+            code_metrics: None,
+
             dimensions_set: false,
             dimensions: None,
 
+            #[cfg(feature = "image-colors")]
+            image_colors_set: false,
+            #[cfg(feature = "image-colors")]
+            image_colors: None,
+
             duration_set: false,
             duration: None,
 
@@ -82,6 +155,23 @@ impl FileMetadataState {
 
             exif_metadata_set: false,
             exif_metadata: None,
+
+            video_metadata_set: false,
+            video_metadata: None,
+
+            torrent_info_set: false,
+            torrent_info: None,
+
+            dir_entry_counts_set: false,
+            dir_entry_counts: None,
+
+            trash_info_set: false,
+            trash_info: None,
+
+            #[cfg(target_os = "linux")]
+            statx_info_set: false,
+            #[cfg(target_os = "linux")]
+            statx_info: None,
         }
     }
 
@@ -92,9 +182,21 @@ impl FileMetadataState {
         self.line_count_set = false;
         self.line_count = None;
 
+        self.mime_set = false;
+        self.mime = None;
+
+        self.code_metrics_set = false;
+        self.code_metrics = None;
+
         self.dimensions_set = false;
         self.dimensions = None;
 
+        #[cfg(feature = "image-colors")]
+        {
+            self.image_colors_set = false;
+            self.image_colors = None;
+        }
+
         self.duration_set = false;
         self.duration = None;
 
@@ -103,6 +205,24 @@ impl FileMetadataState {
 
         self.exif_metadata_set = false;
         self.exif_metadata = None;
+
+        self.video_metadata_set = false;
+        self.video_metadata = None;
+
+        self.torrent_info_set = false;
+        self.torrent_info = None;
+
+        self.dir_entry_counts_set = false;
+        self.dir_entry_counts = None;
+
+        self.trash_info_set = false;
+        self.trash_info = None;
+
+        #[cfg(target_os = "linux")]
+        {
+            self.statx_info_set = false;
+            self.statx_info = None;
+        }
     }
 
     fn update_file_metadata(&mut self, entry: &DirEntry, follow_symlinks: bool) {
@@ -112,31 +232,82 @@ impl FileMetadataState {
         }
     }
 
-    fn update_line_count(&mut self, entry: &DirEntry) {
+    #[cfg(target_os = "linux")]
+    fn update_statx_info(&mut self, entry: &DirEntry, follow_symlinks: bool) {
+        if !self.statx_info_set {
+            self.statx_info_set = true;
+            self.statx_info = traced("statx", entry, || get_statx_info(&entry.path(), follow_symlinks));
+        }
+    }
+
+    fn update_line_count(&mut self, entry: &DirEntry, deadline: Option<Instant>) {
         if !self.line_count_set {
             self.line_count_set = true;
-            self.line_count = get_line_count(entry);
+            self.line_count = traced("line count", entry, || get_line_count(entry, deadline));
+        }
+    }
+
+    fn update_code_metrics(&mut self, entry: &DirEntry, deadline: Option<Instant>) {
+        if !self.code_metrics_set {
+            self.code_metrics_set = true;
+            self.code_metrics = traced("code metrics", entry, || get_code_metrics(&entry.path(), deadline));
         }
     }
 
     fn update_mp3_metadata(&mut self, entry: &DirEntry) {
         if !self.mp3_metadata_set {
             self.mp3_metadata_set = true;
-            self.mp3_metadata = get_mp3_metadata(entry);
+            self.mp3_metadata = traced("mp3 metadata", entry, || get_mp3_metadata(entry));
         }
     }
 
     fn update_exif_metadata(&mut self, entry: &DirEntry) {
         if !self.exif_metadata_set {
             self.exif_metadata_set = true;
-            self.exif_metadata = get_exif_metadata(entry);
+            self.exif_metadata = traced("exif metadata", entry, || get_exif_metadata(entry));
+        }
+    }
+
+    fn update_video_metadata(&mut self, entry: &DirEntry) {
+        if !self.video_metadata_set {
+            self.video_metadata_set = true;
+            self.video_metadata = traced("video metadata", entry, || get_video_metadata(entry.path()));
+        }
+    }
+
+    fn update_torrent_info(&mut self, entry: &DirEntry) {
+        if !self.torrent_info_set {
+            self.torrent_info_set = true;
+            self.torrent_info = traced("torrent info", entry, || get_torrent_info(entry.path()));
+        }
+    }
+
+    fn update_dir_entry_counts(&mut self, entry: &DirEntry) {
+        if !self.dir_entry_counts_set {
+            self.dir_entry_counts_set = true;
+            self.dir_entry_counts = get_dir_entry_counts(entry.path());
+        }
+    }
+
+    fn update_trash_info(&mut self, entry: &DirEntry, trash_dirs: &[TrashDir]) {
+        if !self.trash_info_set {
+            self.trash_info_set = true;
+            self.trash_info = get_trash_info(trash_dirs, &entry.path());
         }
     }
 
     fn update_dimensions(&mut self, entry: &DirEntry) {
         if !self.dimensions_set {
             self.dimensions_set = true;
-            self.dimensions = get_dimensions(entry.path());
+            self.dimensions = traced("dimensions", entry, || get_dimensions(entry.path()));
+        }
+    }
+
+    #[cfg(feature = "image-colors")]
+    fn update_image_colors(&mut self, entry: &DirEntry) {
+        if !self.image_colors_set {
+            self.image_colors_set = true;
+            self.image_colors = traced("image colors", entry, || get_image_colors(entry.path()));
         }
     }
 
@@ -145,36 +316,176 @@ impl FileMetadataState {
             self.update_mp3_metadata(entry);
 
             self.duration_set = true;
-            self.duration = get_duration(entry.path(), &self.mp3_metadata);
+            self.duration = traced("duration", entry, || get_duration(entry.path(), &self.mp3_metadata));
+        }
+    }
+}
+
+/// Runs a metadata extractor, tracing how long it took to help find slow queries
+fn traced<T>(what: &str, entry: &DirEntry, extractor: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = extractor();
+    trace!(target: "extractors", "Read {} of {} in {:?}", what, entry.path().display(), started.elapsed());
+
+    result
+}
+
+/// Returns the name of the file, or of the archived file without its path inside the archive
+fn get_file_name(entry: &DirEntry, file_info: &Option<FileInfo>) -> String {
+    match file_info {
+        Some(ref file_info) => {
+            let name = file_info.name.trim_end_matches(['/', '\\']);
+            name.rsplit(['/', '\\']).next().unwrap_or(name).to_string()
+        }
+        _ => entry.file_name().to_string_lossy().to_string(),
+    }
+}
+
+/// Columns whose values are shared between the buffered rows: grouping keys and fields with few distinct values
+fn get_interned_columns(query: &Query) -> HashSet<String> {
+    let fields = query
+        .get_all_fields()
+        .into_iter()
+        .filter(|field| field.has_few_values())
+        .map(|field| field.to_string());
+    let grouping_fields = query.grouping_fields.iter().map(|expr| expr.to_string());
+
+    fields.chain(grouping_fields).collect()
+}
+
+/// Writes search results to stdout or to the redirected output,
+/// keeping a copy of everything written if the output is captured
+#[derive(Default)]
+struct SearchOutput<'a> {
+    redirected: Option<Box<dyn Write + 'a>>,
+    /// Stdout locked on the first write and kept locked until the search is over
+    stdout: Option<Box<dyn Write>>,
+    captured: Option<Vec<u8>>,
+}
+
+impl SearchOutput<'_> {
+    /// Rows go to the terminal line by line, but a pipe or a file takes them in large blocks
+    fn lock_stdout() -> Box<dyn Write> {
+        let stdout = std::io::stdout();
+        match stdout.is_terminal() {
+            true => Box::new(stdout.lock()),
+            false => Box::new(BufWriter::with_capacity(STDOUT_BUFFER_SIZE, stdout.lock())),
+        }
+    }
+}
+
+impl Write for SearchOutput<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = match self.redirected {
+            Some(ref mut output) => output.write(buf)?,
+            None => self.stdout.get_or_insert_with(Self::lock_stdout).write(buf)?,
+        };
+        if let Some(ref mut captured) = self.captured {
+            captured.extend_from_slice(&buf[..written]);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match (&mut self.redirected, &mut self.stdout) {
+            (Some(output), _) => output.flush(),
+            (None, Some(stdout)) => stdout.flush(),
+            (None, None) => std::io::stdout().flush(),
         }
     }
 }
 
+/// Style of the values of the column, applied if the file matches the condition
+struct ColumnStyle {
+    column: Expr,
+    condition: Option<Expr>,
+    style: Style,
+}
+
 pub struct Searcher<'a> {
     query: &'a Query,
     config: &'a Config,
     default_config: &'a Config,
     use_colors: bool,
     results_writer: ResultsWriter,
+    /// Takes the rows instead of the results writer if the query is partitioned
+    partitioned_writer: Option<PartitionedWriter>,
     #[cfg(all(unix, feature = "users"))]
     user_cache: UsersCache,
+    /// Fields the columns need, resolved once for all the files
+    column_fields: Rc<Vec<Field>>,
     regex_cache: HashMap<String, Regex>,
-    found: u32,
-    raw_output_buffer: Vec<HashMap<String, String>>,
-    partitioned_output_buffer: Rc<HashMap<Vec<String>, Vec<HashMap<String, String>>>>,
-    output_buffer: TopN<Criteria<String>, String>,
+    /// Column and patterns of OR chains matched at once by their nodes, taken from the plan
+    pattern_sets: HashMap<*const Expr, Rc<PatternSet>>,
+    children_modified_cache: HashMap<PathBuf, Option<ChildrenModified>>,
+    dir_size_cache: HashMap<PathBuf, u64>,
+    pub found: u32,
+    /// Sum of the sizes of the matched files, if the columns need the size
+    pub total_size: u64,
+    raw_output_buffer: Vec<BufferedRow>,
+    partitioned_output_buffer: Rc<HashMap<Vec<String>, Vec<BufferedRow>>>,
+    /// Rows kept for ordering, formatted only once all of them are known
+    output_buffer: TopN<Criteria<String>, OrderedRow>,
+    /// Shares the column names and the values of the columns with few distinct values between the buffered rows
+    interner: Interner,
+    interned_columns: HashSet<String>,
+    /// Rules of the .gitattributes files met so far
+    linguist: Linguist,
+    /// Mercurial dirstates and Subversion working copies met so far
+    version_control: VersionControl,
+    /// Values of the rows of SELECT DISTINCT seen so far
+    distinct_rows: HashSet<Vec<String>>,
+    /// Totals of the values of window function columns over all the rows, including the ones past the limit
+    window_totals: Vec<f64>,
     hgignore_filters: Vec<HgignoreFilter>,
     dockerignore_filters: Vec<DockerignoreFilter>,
+    #[cfg(not(unix))]
     visited_dirs: HashSet<PathBuf>,
+    /// Device and inode of the directories already visited, so bind mounts and links are descended only once
     #[cfg(unix)]
-    visited_inodes: HashSet<u64>,
+    visited_inodes: HashSet<(u64, u64)>,
+    /// Trash directories searched for the `trash://` root
+    trash_dirs: Vec<TrashDir>,
+    /// Directories restored or purged from the trash, not to be descended into
+    removed_dirs: HashSet<PathBuf>,
+    deduplicator: Deduplicator,
+    /// Files open by any process, collected on first use
+    #[cfg(target_os = "linux")]
+    open_files: Option<OpenFiles>,
     lscolors: LsColors,
+    /// Styles from the color rules of the config, checked before LS_COLORS
+    column_styles: Rc<Vec<ColumnStyle>>,
     dir_queue: Box<VecDeque<PathBuf>>,
+    current_symlinks: SymlinkPolicy,
+    current_symlink_depth: u32,
+    /// Whether the metadata of the current entry is read through the symlink
     current_follow_symlinks: bool,
+    current_verbatim: bool,
+    current_sorted: bool,
 
     fms: FileMetadataState,
+    /// Record of the manifest being searched instead of a directory entry
+    current_row: Option<ManifestRow>,
+    content_deadline: Option<Instant>,
+    content_skipped: bool,
+
+    output: SearchOutput<'a>,
 
     pub error_count: i32,
+    pub content_skipped_count: i32,
+    /// Reads of directories and metadata repeated after transient errors
+    pub io_retry_count: u32,
+    /// Results are ordered by path with a limit, so the paths past the kept ones are skipped
+    limited_by_path: bool,
+    /// Directories left unvisited, as their files would sort after the last of the kept results
+    pub pruned_dir_count: u32,
+    /// Canonical paths resolved with the file system
+    pub path_lookup_count: u32,
+    /// Canonical paths of entries made of the cached path of their directory instead
+    pub path_lookups_saved: u32,
+    /// Last directory whose canonical path was resolved, entries of the same directory reuse it
+    canonical_dir: Option<(PathBuf, String)>,
 }
 
 impl<'a> Searcher<'a> {
@@ -184,19 +495,34 @@ impl<'a> Searcher<'a> {
         default_config: &'a Config,
         use_colors: bool,
     ) -> Self {
-        let limit = query.limit;
+        let limit = query.get_search_limit();
 
-        let results_writer = ResultsWriter::new(&query.output_format);
+        set_noatime(
+            config
+                .noatime
+                .unwrap_or(default_config.noatime.unwrap_or(false)),
+        );
+
+        let results_writer = ResultsWriter::new(&query.output_format, &query.output_options);
+        let partitioned_writer = query.partition.as_ref().map(|_| {
+            PartitionedWriter::new(&query.output_format, &query.output_options, &query.output_sink)
+        });
         Searcher {
             query,
             config,
             default_config,
             use_colors,
             results_writer,
+            partitioned_writer,
             #[cfg(all(unix, feature = "users"))]
             user_cache: UsersCache::new(),
+            column_fields: Rc::new(query.get_all_fields().into_iter().collect()),
             regex_cache: HashMap::new(),
+            pattern_sets: HashMap::new(),
+            children_modified_cache: HashMap::new(),
+            dir_size_cache: HashMap::new(),
             found: 0,
+            total_size: 0,
             raw_output_buffer: vec![],
             partitioned_output_buffer: Rc::new(HashMap::new()),
             output_buffer: if limit == 0 {
@@ -204,23 +530,102 @@ impl<'a> Searcher<'a> {
             } else {
                 TopN::new(limit)
             },
+            window_totals: vec![0.0; query.fields.len()],
+            interner: Interner::default(),
+            linguist: Linguist::default(),
+            version_control: VersionControl::default(),
+            interned_columns: get_interned_columns(query),
+            distinct_rows: HashSet::new(),
             hgignore_filters: vec![],
             dockerignore_filters: vec![],
+            #[cfg(not(unix))]
             visited_dirs: HashSet::new(),
             #[cfg(unix)]
             visited_inodes: HashSet::new(),
+            trash_dirs: vec![],
+            removed_dirs: HashSet::new(),
+            deduplicator: Deduplicator::new(),
+            #[cfg(target_os = "linux")]
+            open_files: None,
             lscolors: LsColors::from_env().unwrap_or_default(),
+            column_styles: Rc::new(vec![]),
             dir_queue: Box::from(VecDeque::new()),
+            current_symlinks: SymlinkPolicy::Never,
+            current_symlink_depth: 0,
             current_follow_symlinks: false,
+            current_verbatim: false,
+            current_sorted: false,
 
             fms: FileMetadataState::new(),
+            current_row: None,
+            content_deadline: None,
+            content_skipped: false,
+
+            output: SearchOutput::default(),
 
             error_count: 0,
+            content_skipped_count: 0,
+            io_retry_count: 0,
+            limited_by_path: query.is_limited_by_path(),
+            pruned_dir_count: 0,
+            path_lookup_count: 0,
+            path_lookups_saved: 0,
+            canonical_dir: None,
         }
     }
 
+    /// Writes results to the given output instead of stdout
+    pub fn redirect_output(&mut self, output: Box<dyn Write + 'a>) {
+        self.output.redirected = Some(output);
+    }
+
+    /// Takes the fields and the compiled patterns from the plan instead of preparing them again
+    pub fn set_plan(&mut self, plan: &QueryPlan) {
+        self.column_fields = Rc::clone(&plan.fields);
+        self.regex_cache.extend(plan.regexes.clone());
+        self.pattern_sets = plan.pattern_sets.clone();
+    }
+
+    /// Parses the conditions and styles of the color rules, which only apply if the colors are used
+    pub fn set_color_rules(&mut self, color_rules: &[ColorRule]) -> Result<(), String> {
+        let column_styles = color_rules
+            .iter()
+            .map(|rule| {
+                let column = rule
+                    .column
+                    .parse::<Field>()
+                    .map_err(|_| format!("Unknown column {} in color rules", rule.column))?;
+                let condition = match rule.when {
+                    Some(ref condition) => Some(Parser::new().parse_condition(condition)?),
+                    None => None,
+                };
+                let style = Style::from_ansi_sequence(&rule.style).ok_or_else(|| {
+                    format!("Could not parse style {} of column {}", rule.style, rule.column)
+                })?;
+
+                Ok(ColumnStyle {
+                    column: Expr::field(column),
+                    condition,
+                    style,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        self.column_styles = Rc::new(column_styles);
+        Ok(())
+    }
+
+    /// Keeps a copy of everything written to the output, see `take_captured_output`
+    pub fn capture_output(&mut self) {
+        self.output.captured = Some(vec![]);
+    }
+
+    pub fn take_captured_output(&mut self) -> Option<Vec<u8>> {
+        self.output.captured.take()
+    }
+
     pub fn is_buffered(&self) -> bool {
-        self.has_ordering() || self.has_aggregate_column()
+        self.has_ordering() || self.has_aggregate_column() || self.query.has_window_column()
     }
 
     fn has_ordering(&self) -> bool {
@@ -233,11 +638,14 @@ impl<'a> Searcher<'a> {
 
     /// Searches directories based on configured query and outputs results to stdout.
     pub fn list_search_results(&mut self) -> io::Result<()> {
+        let started = Instant::now();
         let current_dir = std::env::current_dir().unwrap();
 
-        if let Err(e) = self.results_writer.write_header(&mut std::io::stdout()) {
-            if e.kind() == ErrorKind::BrokenPipe {
-                return Ok(());
+        if self.partitioned_writer.is_none() {
+            if let Err(e) = self.results_writer.write_header(&mut self.output) {
+                if e.kind() == ErrorKind::BrokenPipe {
+                    return Ok(());
+                }
             }
         }
 
@@ -245,7 +653,13 @@ impl<'a> Searcher<'a> {
 
         // ======== Process each root specified in the query =========
         for root in &self.query.roots {
-            if root.options.regexp {
+            if is_trash_root(&root.path) {
+                for trash_dir in get_trash_dirs() {
+                    let path = trash_dir.files.to_string_lossy().to_string();
+                    roots.push(Root::clone_with_path(path, root.clone()));
+                    self.trash_dirs.push(trash_dir);
+                }
+            } else if root.options.regexp {
                 let mut ext_roots: Vec<String> = vec![];
                 // Split the path into parts to process each segment as a regex
                 let parts = root.path.split('/').collect::<Vec<&str>>();
@@ -334,7 +748,16 @@ impl<'a> Searcher<'a> {
 
         // ======== Explore each root =========
         for root in roots {
-            self.current_follow_symlinks = root.options.symlinks;
+            if let Some(ref manifest) = root.manifest {
+                debug!(target: "searcher", "Searching manifest {} as {:?}", root.path, manifest);
+                self.search_manifest(&root.path, manifest)?;
+                continue;
+            }
+
+            self.current_symlinks = root.options.symlinks;
+            self.current_symlink_depth = root.options.symlink_depth;
+            self.current_verbatim = root.options.verbatim;
+            self.current_sorted = root.options.sorted;
 
             let root_dir = Path::new(&root.path);
             let min_depth = root.options.min_depth;
@@ -365,16 +788,9 @@ impl<'a> Searcher<'a> {
 
             self.dir_queue.clear();
 
-            #[cfg(unix)]
-            {
-                let metadata = match self.current_follow_symlinks {
-                    true => root_dir.metadata(),
-                    false => symlink_metadata(root_dir),
-                };
-                if let Ok(metadata) = metadata {
-                    self.visited_inodes.insert(metadata.ino());
-                }
-            }
+            debug!(target: "searcher", "Searching {} with {:?}", root.path, root.options);
+
+            self.ok_to_visit_dir(root_dir, None, None);
 
             let _result = self.visit_dir(
                 root_dir,
@@ -406,9 +822,12 @@ impl<'a> Searcher<'a> {
                     .collect();
                 let buffer_partitions = self.partitioned_output_buffer.clone();
 
+                // Only the distinct groups count towards the offset and the limit
+                let mut groups_found = 0;
+
                 buffer_partitions.iter().for_each(|f| {
                     let mut buf = WritableBuffer::new();
-                    let mut items: Vec<(String, String)> = Vec::new();
+                    let mut items: Vec<(String, Record)> = Vec::new();
 
                     let mut file_map = HashMap::new();
                     for (i, k) in group_keys.iter().enumerate() {
@@ -416,65 +835,97 @@ impl<'a> Searcher<'a> {
                     }
 
                     for column_expr in &self.query.fields {
-                        let record = format!(
-                            "{}",
-                            self.get_column_expr_value(
-                                None,
-                                &None,
-                                &mut file_map,
-                                Some(f.1),
-                                column_expr
-                            )
-                        );
+                        let record =
+                            self.get_aggregated_record(&mut file_map, Some(f.1), column_expr);
                         let field_name = column_expr.to_string().to_lowercase();
                         items.push((field_name, record));
                     }
 
-                    let _ = self.results_writer.write_row(&mut buf, items);
+                    let values = items
+                        .iter()
+                        .map(|(_, record)| match record {
+                            Record::Value(value) => value.clone(),
+                            Record::Array(values) => values.join(", "),
+                        })
+                        .collect();
+                    if self.is_repeated_row(values) {
+                        return;
+                    }
+
+                    groups_found += 1;
+                    if groups_found <= self.query.offset
+                        || (self.query.limit > 0
+                            && groups_found > self.query.get_search_limit())
+                    {
+                        return;
+                    }
+
+                    if self.partitioned_writer.is_some() {
+                        self.write_partitioned(|writer| writer.write_records(items));
+                        return;
+                    }
+
+                    if groups_found > self.query.offset + 1 {
+                        let _ = self.results_writer.write_row_separator(&mut buf);
+                    }
+                    let _ = self.results_writer.write_records(&mut buf, items);
 
-                    let _ = write!(std::io::stdout(), "{}", String::from(buf));
+                    let _ = write!(self.output, "{}", String::from(buf));
                 })
-            } else {
+            } else if self.query.offset == 0 {
+                // The only aggregated row is skipped by any offset
                 let mut buf = WritableBuffer::new();
-                let mut items: Vec<(String, String)> = Vec::new();
+                let mut items: Vec<(String, Record)> = Vec::new();
 
                 for column_expr in &self.query.fields {
-                    let record = format!(
-                        "{}",
-                        self.get_column_expr_value(
-                            None,
-                            &None,
-                            &mut HashMap::new(),
-                            None,
-                            column_expr
-                        )
-                    );
+                    let record = self.get_aggregated_record(&mut HashMap::new(), None, column_expr);
                     let field_name = column_expr.to_string().to_lowercase();
                     items.push((field_name, record));
                 }
 
-                self.results_writer.write_row(&mut buf, items)?;
+                if self.partitioned_writer.is_some() {
+                    self.write_partitioned(|writer| writer.write_records(items));
+                } else {
+                    self.results_writer.write_records(&mut buf, items)?;
 
-                if let Err(e) = write!(std::io::stdout(), "{}", String::from(buf)) {
-                    if e.kind() == ErrorKind::BrokenPipe {
-                        return Ok(());
+                    if let Err(e) = write!(self.output, "{}", String::from(buf)) {
+                        if e.kind() == ErrorKind::BrokenPipe {
+                            return Ok(());
+                        }
                     }
                 }
             }
         } else if self.is_buffered() {
             let mut first = true;
-            for piece in self.output_buffer.values() {
+            let mut running_sums = vec![0.0; self.query.fields.len()];
+            for (pos, items) in self.output_buffer.values().into_iter().enumerate() {
+                let mut items: Vec<(String, String)> = items
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect();
+                self.apply_window_functions(&mut items, &mut running_sums);
+
+                // Running sums go through the skipped rows too
+                if pos < self.query.offset as usize {
+                    continue;
+                }
+
+                if self.partitioned_writer.is_some() {
+                    self.write_partitioned(|writer| writer.write_row(items));
+                    continue;
+                }
+
                 if first {
                     first = false;
                 } else if let Err(e) = self
                     .results_writer
-                    .write_row_separator(&mut std::io::stdout())
+                    .write_row_separator(&mut self.output)
                 {
                     if e.kind() == ErrorKind::BrokenPipe {
                         return Ok(());
                     }
                 }
-                if let Err(e) = write!(std::io::stdout(), "{}", piece) {
+                if let Err(e) = self.results_writer.write_row(&mut self.output, items) {
                     if e.kind() == ErrorKind::BrokenPipe {
                         return Ok(());
                     }
@@ -482,7 +933,61 @@ impl<'a> Searcher<'a> {
             }
         }
 
-        self.results_writer.write_footer(&mut std::io::stdout())?;
+        match self.partitioned_writer {
+            Some(ref mut partitioned_writer) => partitioned_writer.write_footers()?,
+            None => self.results_writer.write_footer(&mut self.output)?,
+        }
+
+        if let Err(e) = self.output.flush() {
+            if e.kind() != ErrorKind::BrokenPipe {
+                return Err(e);
+            }
+        }
+
+        if let Some(Action::ReplaceDuplicates { dry_run, .. }) = self.query.action {
+            eprintln!(
+                "{} {} duplicate(s), reclaiming {}",
+                if dry_run { "Would replace" } else { "Replaced" },
+                self.deduplicator.replaced,
+                format_filesize(self.deduplicator.reclaimed, "")
+            );
+        }
+
+        info!(
+            target: "searcher",
+            "Found {} file(s) in {:?} with {} error(s)",
+            self.found,
+            started.elapsed(),
+            self.error_count
+        );
+
+        Ok(())
+    }
+
+    /// Checks the records of an external file list instead of walking a directory
+    fn search_manifest(&mut self, path: &str, manifest: &Manifest) -> io::Result<()> {
+        let rows = match read_manifest(Path::new(path), manifest) {
+            Ok(rows) => rows,
+            Err(err) => {
+                self.error_count += 1;
+                error_message(path, &err);
+                return Ok(());
+            }
+        };
+
+        for row in rows {
+            self.current_row = Some(row);
+            let checked = self.check_file(None, &None);
+            self.current_row = None;
+
+            if !checked? {
+                break;
+            }
+
+            if self.is_limit_reached() {
+                break;
+            }
+        }
 
         Ok(())
     }
@@ -503,13 +1008,12 @@ impl<'a> Searcher<'a> {
         traversal_mode: TraversalMode,
         process_queue: bool,
     ) -> io::Result<()> {
-        // Prevents infinite loops when following symlinks
-        if self.current_follow_symlinks {
-            if self.visited_dirs.contains(&dir.to_path_buf()) {
-                return Ok(());
-            } else {
-                self.visited_dirs.insert(dir.to_path_buf());
-            }
+        // Every file inside would sort after the last of the kept results
+        let contents = dir.join("");
+        if root_depth != 0 && self.is_past_path_bound(&contents, search_archives) {
+            trace!(target: "searcher", "Skipping {} past the limit", dir.display());
+            self.pruned_dir_count += 1;
+            return Ok(());
         }
 
         // Canonicalize the path to resolve symlinks and relative paths
@@ -535,12 +1039,23 @@ impl<'a> Searcher<'a> {
 
         let depth = canonical_depth - base_depth + 1;
 
+        trace!(target: "searcher", "Visiting {} at depth {}", dir.display(), depth);
+
         // Read the directory and process each entry
-        match fs::read_dir(dir) {
+        match self.retry_io(|| fs::read_dir(dir)) {
             Ok(entry_list) => {
+                // Makes the output independent of the order of the entries on the disk
+                let entry_list: Box<dyn Iterator<Item = io::Result<DirEntry>>> = match self.current_sorted {
+                    true => {
+                        let mut entries: Vec<_> = entry_list.collect();
+                        entries.sort_by_key(|entry| entry.as_ref().ok().map(|entry| entry.file_name()));
+                        Box::new(entries.into_iter())
+                    }
+                    false => Box::new(entry_list),
+                };
+
                 for entry in entry_list {
-                    if !self.is_buffered() && self.query.limit > 0 && self.query.limit <= self.found
-                    {
+                    if self.is_limit_reached() {
                         break;
                     }
 
@@ -550,7 +1065,7 @@ impl<'a> Searcher<'a> {
                             let mut canonical_path = path.clone();
 
                             if apply_gitignore || apply_hgignore || apply_dockerignore {
-                                if let Ok(canonicalized) = crate::util::canonical_path(&path) {
+                                if let Ok(canonicalized) = self.get_canonical_path(&entry) {
                                     canonical_path = PathBuf::from(canonicalized);
                                 }
                             }
@@ -573,55 +1088,44 @@ impl<'a> Searcher<'a> {
 
                             // If the path passes the filters, process it
                             if pass_gitignore && pass_hgignore && pass_dockerignore {
-                                if min_depth == 0 || depth >= min_depth {
-                                    let checked = self.check_file(&entry, &None)?;
-                                    if !checked {
-                                        return Ok(());
-                                    }
-
-                                    if search_archives
-                                        && self.is_zip_archive(&path.to_string_lossy())
-                                    {
-                                        if let Ok(file) = fs::File::open(&path) {
-                                            if let Ok(mut archive) = zip::ZipArchive::new(file) {
-                                                for i in 0..archive.len() {
-                                                    if self.query.limit > 0
-                                                        && self.query.limit <= self.found
-                                                    {
-                                                        break;
-                                                    }
-
-                                                    if let Ok(afile) = archive.by_index(i) {
-                                                        let file_info = to_file_info(&afile);
-                                                        let checked = self
-                                                            .check_file(&entry, &Some(file_info))?;
-                                                        if !checked {
-                                                            return Ok(());
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                                let check_entry = min_depth == 0 || depth >= min_depth;
+                                if check_entry
+                                    && traversal_mode != TraversalMode::DfsPost
+                                    && !self.check_entry(&entry, search_archives)?
+                                {
+                                    return Ok(());
                                 }
 
                                 // Recursively visit subdirectories if we're not too deep
                                 if max_depth == 0 || depth < max_depth {
-                                    let result = entry.file_type();
+                                    let result = self.retry_io(|| entry.file_type());
                                     if let Ok(file_type) = result {
                                         let mut ok = false;
+                                        let mut target = None;
 
                                         if file_type.is_symlink() {
-                                            if let Ok(resolved) = std::fs::read_link(&path) {
-                                                ok = true;
-                                                path = resolved;
+                                            if self.current_symlinks.follows_dirs() {
+                                                if let Some(resolved) =
+                                                    resolve_symlink(&path, self.current_symlink_depth)
+                                                {
+                                                    if let Ok(metadata) = fs::metadata(&resolved) {
+                                                        if metadata.is_dir() {
+                                                            ok = true;
+                                                            path = resolved;
+                                                            target = Some(metadata);
+                                                        }
+                                                    }
+                                                }
                                             }
                                         } else if file_type.is_dir() {
                                             ok = true;
                                         }
 
-                                        if ok && self.ok_to_visit_dir(&entry, file_type) {
-                                            if traversal_mode == TraversalMode::Dfs {
+                                        if ok
+                                            && self.ok_to_visit_dir(&path, Some(&entry), target)
+                                            && !self.removed_dirs.contains(&path)
+                                        {
+                                            if traversal_mode != TraversalMode::Bfs {
                                                 let repo;
                                                 let git_repository = match git_repository {
                                                     Some(repo) => Some(repo),
@@ -661,6 +1165,15 @@ impl<'a> Searcher<'a> {
                                         path_error_message(&path, result.err().unwrap());
                                     }
                                 }
+
+                                // Children go before the directory itself
+                                if check_entry
+                                    && traversal_mode == TraversalMode::DfsPost
+                                    && !self.is_limit_reached()
+                                    && !self.check_entry(&entry, search_archives)?
+                                {
+                                    return Ok(());
+                                }
                             }
                         }
                         Err(err) => {
@@ -712,38 +1225,196 @@ impl<'a> Searcher<'a> {
         Ok(())
     }
 
-    #[cfg(unix)]
-    fn ok_to_visit_dir(&mut self, entry: &DirEntry, file_type: FileType) -> bool {
-        let ino = entry.ino();
-        if self.visited_inodes.contains(&ino) {
+    /// Checks the entry and the contents of the archive it could be.
+    /// Returns false when the search is over.
+    fn check_entry(&mut self, entry: &DirEntry, search_archives: bool) -> io::Result<bool> {
+        if !self.check_file(Some(entry), &None)? {
+            return Ok(false);
+        }
+
+        let path = entry.path();
+        if search_archives && self.is_zip_archive(&path.to_string_lossy()) {
+            if let Ok(file) = fs::File::open(&path) {
+                if let Ok(archive) = zip::ZipArchive::new(file) {
+                    let archive_len = archive.len();
+                    let archive = Rc::new(RefCell::new(archive));
+                    for i in 0..archive_len {
+                        let limit = self.query.get_search_limit();
+                        if limit > 0 && limit <= self.found {
+                            break;
+                        }
+
+                        let file_info = match archive.borrow_mut().by_index(i) {
+                            Ok(afile) => to_file_info(&afile, &archive, i),
+                            Err(_) => continue,
+                        };
+                        if !self.check_file(Some(entry), &Some(file_info))? {
+                            return Ok(false);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the canonical path of the entry, so that the root of its repository is found among its parents
+    fn get_repository_path(&mut self, entry: &DirEntry) -> PathBuf {
+        match self.get_canonical_path(entry) {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => entry.path(),
+        }
+    }
+
+    /// Resolves the canonical path of the entry through the path of its directory,
+    /// which is looked up once for all of its entries. Symlinks are resolved on their own.
+    fn get_canonical_path(&mut self, entry: &DirEntry) -> Result<String, String> {
+        let path = entry.path();
+        let is_symlink = entry.file_type().map_or(true, |file_type| file_type.is_symlink());
+
+        match path.parent() {
+            Some(parent) if !is_symlink => {
+                let dir = self.get_canonical_dir(parent)?;
+                Ok(crate::util::format_absolute_path(&Path::new(&dir).join(entry.file_name())))
+            }
+            _ => {
+                self.path_lookup_count += 1;
+                crate::util::canonical_path(&path)
+            }
+        }
+    }
+
+    fn get_canonical_dir(&mut self, dir: &Path) -> Result<String, String> {
+        if let Some((ref cached_dir, ref canonical)) = self.canonical_dir {
+            if cached_dir == dir {
+                self.path_lookups_saved += 1;
+                return Ok(canonical.clone());
+            }
+        }
+
+        self.path_lookup_count += 1;
+        let canonical = crate::util::canonical_path(&dir.to_path_buf())?;
+        self.canonical_dir = Some((dir.to_path_buf(), canonical.clone()));
+
+        Ok(canonical)
+    }
+
+    /// With ORDER BY path and a LIMIT, the greatest of the kept paths once there are enough of them
+    fn get_path_bound(&self) -> Option<&String> {
+        match self.limited_by_path {
+            true => self.output_buffer.get_bound().and_then(|criteria| criteria.first_value()),
+            false => None,
+        }
+    }
+
+    /// Checks if the path and every path starting with it sort after the last of the kept results.
+    /// Archived files are named after their archives in brackets, so nothing is skipped if archives are searched.
+    fn is_past_path_bound(&self, path: &Path, search_archives: bool) -> bool {
+        if search_archives {
             return false;
-        } else {
-            self.visited_inodes.insert(ino);
         }
 
-        match self.current_follow_symlinks {
-            true => true,
-            false => !file_type.is_symlink(),
+        match self.get_path_bound() {
+            Some(bound) => self.format_output_path(path.to_string_lossy().to_string()) > *bound,
+            None => false,
+        }
+    }
+
+    fn is_limit_reached(&self) -> bool {
+        let limit = self.query.get_search_limit();
+        !self.is_buffered() && limit > 0 && limit <= self.found
+    }
+
+    /// Prevents infinite loops when following symlinks and visiting the same directory twice.
+    /// Subdirectories are identified by the metadata of their symlink target when it's already read,
+    /// otherwise by the metadata of the entry, which is looked up within the parent without resolving the whole path
+    #[cfg(unix)]
+    fn ok_to_visit_dir(&mut self, dir: &Path, entry: Option<&DirEntry>, target: Option<Metadata>) -> bool {
+        let metadata = match (target, entry) {
+            (Some(target), _) => Ok(target),
+            (None, Some(entry)) => entry.metadata(),
+            (None, None) => fs::metadata(dir),
+        };
+
+        match metadata {
+            Ok(metadata) => self.visited_inodes.insert((metadata.dev(), metadata.ino())),
+            Err(_) => true,
         }
     }
 
+    /// Plain subdirectories extend the canonical path of their parent, which is resolved once for all its entries
     #[cfg(not(unix))]
-    fn ok_to_visit_dir(&mut self, _: &DirEntry, file_type: FileType) -> bool {
-        match self.current_follow_symlinks {
-            true => true,
-            false => !file_type.is_symlink(),
+    fn ok_to_visit_dir(&mut self, dir: &Path, entry: Option<&DirEntry>, _: Option<Metadata>) -> bool {
+        let canonical = match entry {
+            Some(entry) => self.get_canonical_path(entry),
+            None => crate::util::canonical_path(&dir.to_path_buf()),
+        };
+
+        match canonical {
+            Ok(path) => self.visited_dirs.insert(PathBuf::from(path)),
+            Err(_) => true,
         }
     }
 
+    /// Whether the metadata of the entry is taken from the target of its symlink
+    fn follows_symlink(&self, entry: &DirEntry) -> bool {
+        if self.current_symlinks == SymlinkPolicy::Never {
+            return false;
+        }
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => {
+                match resolve_symlink(&entry.path(), self.current_symlink_depth) {
+                    Some(target) if target.is_dir() => self.current_symlinks.follows_dirs(),
+                    Some(_) => self.current_symlinks.follows_files(),
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Computes the value of the column for the aggregated output,
+    /// ARRAY_AGG columns keep their values as an array for the formats supporting that
+    fn get_aggregated_record(
+        &mut self,
+        file_map: &mut HashMap<String, String>,
+        buffer_data: Option<&Vec<BufferedRow>>,
+        column_expr: &Expr,
+    ) -> Record {
+        if let (Some(Function::ArrayAgg), Some(left_expr)) =
+            (&column_expr.function, &column_expr.left)
+        {
+            let _ = self.get_column_expr_value(None, &None, file_map, buffer_data, left_expr);
+            let buffer_key = left_expr.to_string();
+            let values = function::get_aggregate_values(
+                buffer_data.unwrap_or(&self.raw_output_buffer),
+                &buffer_key,
+            );
+            return Record::Array(values);
+        }
+
+        let value = self.get_column_expr_value(None, &None, file_map, buffer_data, column_expr);
+        Record::Value(value.to_string())
+    }
+
     fn get_column_expr_value(
         &mut self,
         entry: Option<&DirEntry>,
         file_info: &Option<FileInfo>,
         file_map: &mut HashMap<String, String>,
-        buffer_data: Option<&Vec<HashMap<String, String>>>,
+        buffer_data: Option<&Vec<BufferedRow>>,
         column_expr: &Expr,
     ) -> Variant {
-        if let Some(ref _function) = column_expr.function {
+        if let Some(ref function) = column_expr.function {
+            // Grouped rows carry the values of the grouping functions computed per file
+            if entry.is_none() && !function.is_aggregate_function() {
+                if let Some(val) = file_map.get(&column_expr.to_string()) {
+                    return Variant::from_string(val);
+                }
+            }
+
             let result =
                 self.get_function_value(entry, file_info, file_map, buffer_data, column_expr);
             file_map.insert(column_expr.to_string(), result.to_string());
@@ -751,12 +1422,22 @@ impl<'a> Searcher<'a> {
         }
 
         if let Some(ref field) = column_expr.field {
-            if entry.is_some() {
+            if let (None, Some(row)) = (entry, &self.current_row) {
+                let result = get_row_value(row, field);
+                file_map.insert(column_expr.to_string(), result.to_string());
+                return result;
+            } else if entry.is_some() {
                 let result = self.get_field_value(entry.unwrap(), file_info, field);
                 file_map.insert(column_expr.to_string(), result.to_string());
                 return result;
             } else if let Some(val) = file_map.get(&field.to_string()) {
                 return Variant::from_string(val);
+            } else if let Some(val) = buffer_data
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get(field.to_string().as_str()))
+            {
+                // Columns other than the grouping keys take the values of the first file of the group
+                return Variant::from_string(&val.to_string());
             } else {
                 return Variant::empty(VariantType::String);
             }
@@ -796,7 +1477,7 @@ impl<'a> Searcher<'a> {
         entry: Option<&DirEntry>,
         file_info: &Option<FileInfo>,
         file_map: &mut HashMap<String, String>,
-        buffer_data: Option<&Vec<HashMap<String, String>>>,
+        buffer_data: Option<&Vec<BufferedRow>>,
         column_expr: &Expr,
     ) -> Variant {
         let dummy = Expr::value(String::from(""));
@@ -820,8 +1501,15 @@ impl<'a> Searcher<'a> {
             );
             return Variant::from_string(&aggr_result);
         } else {
-            let function_arg =
-                self.get_column_expr_value(entry, file_info, file_map, buffer_data, left_expr);
+            // The algorithm name of HASH_EQUALS is parsed as the hash field of the same name,
+            // take the name as is instead of computing that hash
+            let function_arg = match (function, &left_expr.field) {
+                (Function::HashEquals, Some(field)) => Variant::from_string(&field.to_string()),
+                (Function::TodoCount, None) if column_expr.left.is_none() => {
+                    Variant::from_string(&self.get_todo_markers().join(","))
+                }
+                _ => self.get_column_expr_value(entry, file_info, file_map, buffer_data, left_expr),
+            };
             let mut function_args = vec![];
             if let Some(args) = &column_expr.args {
                 for arg in args {
@@ -830,32 +1518,142 @@ impl<'a> Searcher<'a> {
                     function_args.push(arg_value.to_string());
                 }
             }
+            let reads_content = entry.is_some() && function.reads_content();
+            if reads_content && !self.is_content_allowed(entry.unwrap(), file_info) {
+                return Variant::empty(column_expr.get_value_type());
+            }
+
             let result = function::get_value(
                 &column_expr.function,
                 function_arg.to_string(),
                 function_args,
                 entry,
                 file_info,
+                self.content_deadline,
             );
+            if reads_content && self.is_content_timed_out() {
+                return Variant::empty(column_expr.get_value_type());
+            }
             file_map.insert(column_expr.to_string(), result.to_string());
 
             return result;
         }
     }
 
-    fn partition_output_buffer(&self) -> HashMap<Vec<String>, Vec<HashMap<String, String>>> {
-        let group_fields: Vec<String> = self
-            .query
-            .grouping_fields
-            .iter()
+    /// Rows of partitioned results go to the files of their partitions instead of the output
+    fn write_partitioned(&mut self, write: impl FnOnce(&mut PartitionedWriter) -> io::Result<()>) {
+        if let Some(ref mut partitioned_writer) = self.partitioned_writer {
+            if let Err(e) = write(partitioned_writer) {
+                self.error_count += 1;
+                error_message("partition", &e.to_string());
+            }
+        }
+    }
+
+    /// Replaces the values of the row in window function columns with the ones computed over the result set
+    fn apply_window_functions(&self, items: &mut [(String, String)], running_sums: &mut [f64]) {
+        for (idx, field) in self.query.fields.iter().enumerate() {
+            let value = items[idx].1.parse::<f64>().unwrap_or(0.0);
+
+            match field.function {
+                Some(Function::RunningSum) => {
+                    running_sums[idx] += value;
+                    items[idx].1 = Variant::from_float(running_sums[idx]).to_string();
+                }
+                Some(Function::PercentOfTotal) => {
+                    // Cumulative percentage with PERCENT_OF_TOTAL(RUNNING_SUM(...))
+                    let cumulative = field
+                        .left
+                        .as_ref()
+                        .is_some_and(|left| left.function == Some(Function::RunningSum));
+                    let value = match cumulative {
+                        true => {
+                            running_sums[idx] += value;
+                            running_sums[idx]
+                        }
+                        false => value,
+                    };
+                    let total = self.window_totals[idx];
+                    items[idx].1 = match total != 0.0 {
+                        true => format!("{:.2}", value / total * 100.0),
+                        false => String::new(),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Rows of SELECT DISTINCT that were already written or buffered are left out
+    fn is_repeated_row(&mut self, values: Vec<String>) -> bool {
+        self.query.distinct && !self.distinct_rows.insert(values)
+    }
+
+    /// Column names are always shared, values only if the column has few distinct ones
+    fn intern_value(&mut self, name: String, value: String) -> (Rc<str>, Rc<str>) {
+        let value = match self.interned_columns.contains(&name) {
+            true => self.interner.intern(&value),
+            false => Rc::from(value),
+        };
+
+        (self.interner.intern(&name), value)
+    }
+
+    fn intern_row(&mut self, file_map: HashMap<String, String>) -> BufferedRow {
+        file_map
+            .into_iter()
+            .map(|(name, value)| self.intern_value(name, value))
+            .collect()
+    }
+
+    /// Estimates the memory taken by the rows kept for ordering or aggregation
+    pub fn get_buffered_memory(&self) -> usize {
+        let get_pair_size = |name: &Rc<str>, value: &Rc<str>| {
+            size_of::<(Rc<str>, Rc<str>)>()
+                + self.interner.get_size(name)
+                + self.interner.get_size(value)
+        };
+        let ordered = self
+            .output_buffer
+            .iter()
+            .flatten()
+            .map(|(name, value)| get_pair_size(name, value));
+        let aggregated = self
+            .raw_output_buffer
+            .iter()
+            .flatten()
+            .map(|(name, value)| get_pair_size(name, value));
+
+        self.interner.get_memory_usage() + ordered.chain(aggregated).sum::<usize>()
+    }
+
+    /// Number of the rows kept for ordering or aggregation
+    pub fn get_buffered_count(&self) -> usize {
+        self.output_buffer.iter().count() + self.raw_output_buffer.len()
+    }
+
+    /// Number of the distinct strings shared between the buffered rows
+    pub fn get_interned_count(&self) -> usize {
+        self.interner.count()
+    }
+
+    fn partition_output_buffer(&self) -> HashMap<Vec<String>, Vec<BufferedRow>> {
+        let group_fields: Vec<String> = self
+            .query
+            .grouping_fields
+            .iter()
             .map(|ref expr| expr.to_string())
             .collect();
-        let mut result: HashMap<Vec<String>, Vec<HashMap<String, String>>> = HashMap::new();
+        let mut result: HashMap<Vec<String>, Vec<BufferedRow>> = HashMap::new();
 
         self.raw_output_buffer.iter().for_each(|item| {
             let key: Vec<String> = group_fields
                 .iter()
-                .map(|f| item.get(f).unwrap_or(&String::new()).clone())
+                .map(|f| {
+                    item.get(f.as_str())
+                        .map(|value| value.to_string())
+                        .unwrap_or_default()
+                })
                 .collect();
             if result.contains_key(&key) {
                 result.get_mut(&key).unwrap().push(item.clone());
@@ -917,7 +1715,8 @@ impl<'a> Searcher<'a> {
                     ));
                 }
                 _ => {
-                    return Variant::from_string(&format!("{}", entry.path().to_string_lossy()));
+                    let path = entry.path().to_string_lossy().to_string();
+                    return Variant::from_string(&self.format_output_path(path));
                 }
             },
             Field::AbsPath => match file_info {
@@ -929,8 +1728,8 @@ impl<'a> Searcher<'a> {
                     ));
                 }
                 _ => {
-                    if let Ok(path) = crate::util::canonical_path(&entry.path()) {
-                        return Variant::from_string(&path);
+                    if let Ok(path) = self.get_canonical_path(entry) {
+                        return Variant::from_string(&self.format_output_path(path));
                     }
                 }
             },
@@ -941,7 +1740,12 @@ impl<'a> Searcher<'a> {
                 };
                 let pb = PathBuf::from(file_path);
                 if let Some(parent) = pb.parent() {
-                    return Variant::from_string(&parent.to_string_lossy().to_string());
+                    if file_info.is_some() {
+                        return Variant::from_string(&parent.to_string_lossy().to_string());
+                    }
+
+                    let path = parent.to_string_lossy().to_string();
+                    return Variant::from_string(&self.format_output_path(path));
                 }
             }
             Field::AbsDir => {
@@ -955,8 +1759,8 @@ impl<'a> Searcher<'a> {
                         return Variant::from_string(&parent.to_string_lossy().to_string());
                     }
 
-                    if let Ok(path) = crate::util::canonical_path(&parent.to_path_buf()) {
-                        return Variant::from_string(&path);
+                    if let Ok(path) = self.get_canonical_dir(parent) {
+                        return Variant::from_string(&self.format_output_path(path));
                     }
                 }
             }
@@ -1260,6 +2064,79 @@ impl<'a> Searcher<'a> {
                     ));
                 }
             },
+            Field::IsGitRoot => {
+                return Variant::from_bool(is_git_root(entry.path()));
+            }
+            Field::IsHgRoot => {
+                return Variant::from_bool(is_hg_root(entry.path()));
+            }
+            Field::IsGenerated => {
+                let path = self.get_repository_path(entry);
+                if let Some(generated) = self.linguist.is_generated_by_path(&path) {
+                    return Variant::from_bool(generated);
+                }
+
+                let generated = self.is_content_allowed(entry, file_info)
+                    && has_generated_marker(&entry.path());
+                return Variant::from_bool(generated);
+            }
+            Field::IsVendored => {
+                let path = self.get_repository_path(entry);
+                return Variant::from_bool(self.linguist.is_vendored(&path));
+            }
+            Field::HgTracked => {
+                let path = self.get_repository_path(entry);
+                let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                if let Some(tracked) = self.version_control.is_hg_tracked(&path, is_dir) {
+                    return Variant::from_bool(tracked);
+                }
+            }
+            Field::HgModified => {
+                let path = self.get_repository_path(entry);
+                self.fms
+                    .update_file_metadata(entry, self.current_follow_symlinks);
+
+                if let Some(ref metadata) = self.fms.file_metadata {
+                    if let Some(modified) = self.version_control.is_hg_modified(&path, metadata) {
+                        return Variant::from_bool(modified);
+                    }
+                }
+            }
+            Field::SvnVersioned => {
+                let path = self.get_repository_path(entry);
+                if let Some(versioned) = self.version_control.is_svn_versioned(&path) {
+                    return Variant::from_bool(versioned);
+                }
+            }
+            Field::ProjectType => {
+                if let Some(project_type) = get_project_type(entry.path()) {
+                    return Variant::from_string(&project_type.to_string());
+                }
+            }
+            Field::IsValidUtf8Name => match file_info {
+                Some(_) => {
+                    return Variant::from_bool(true);
+                }
+                _ => {
+                    return Variant::from_bool(entry.file_name().to_str().is_some());
+                }
+            },
+            Field::HasWindowsReservedName => {
+                return Variant::from_bool(has_windows_reserved_name(&get_file_name(entry, file_info)));
+            }
+            Field::HasControlChars => {
+                return Variant::from_bool(has_control_chars(&get_file_name(entry, file_info)));
+            }
+            Field::DirSize => {
+                if let Some(size) = get_dir_size(&entry.path(), &mut self.dir_size_cache) {
+                    return Variant::from_int(size as i64);
+                }
+            }
+            Field::SizeShare => {
+                if let Some(share) = self.get_size_share(entry) {
+                    return Variant::from_float(share);
+                }
+            }
             Field::Uid => {
                 self.fms
                     .update_file_metadata(entry, self.current_follow_symlinks);
@@ -1310,33 +2187,60 @@ impl<'a> Searcher<'a> {
                     }
                 }
             }
-            Field::Created => {
-                self.fms
-                    .update_file_metadata(entry, self.current_follow_symlinks);
+            Field::Created => match file_info {
+                Some(ref file_info) => {
+                    if let Some(created) = file_info.created {
+                        return Variant::from_datetime(created);
+                    }
+                }
+                _ => {
+                    self.fms
+                        .update_file_metadata(entry, self.current_follow_symlinks);
 
-                if let Some(ref attrs) = self.fms.file_metadata {
-                    if let Ok(sdt) = attrs.created() {
-                        let dt: DateTime<Local> = DateTime::from(sdt);
+                    if let Some(created) = self.get_birth_time(entry) {
+                        let dt: DateTime<Local> = DateTime::from(created);
                         return Variant::from_datetime(dt.naive_local());
                     }
+
+                    debug!(
+                        target: "searcher",
+                        "No creation time is recorded for {}",
+                        entry.path().display()
+                    );
+
+                    return Variant::empty(VariantType::DateTime);
                 }
-            }
-            Field::Accessed => {
-                self.fms
-                    .update_file_metadata(entry, self.current_follow_symlinks);
+            },
+            Field::HasBirthTime => match file_info {
+                Some(ref file_info) => {
+                    return Variant::from_bool(file_info.created.is_some());
+                }
+                _ => {
+                    return Variant::from_bool(self.get_birth_time(entry).is_some());
+                }
+            },
+            Field::Accessed => match file_info {
+                Some(ref file_info) => {
+                    if let Some(accessed) = file_info.accessed {
+                        return Variant::from_datetime(accessed);
+                    }
+                }
+                _ => {
+                    self.fms
+                        .update_file_metadata(entry, self.current_follow_symlinks);
 
-                if let Some(ref attrs) = self.fms.file_metadata {
-                    if let Ok(sdt) = attrs.accessed() {
-                        let dt: DateTime<Local> = DateTime::from(sdt);
-                        return Variant::from_datetime(dt.naive_local());
+                    if let Some(ref attrs) = self.fms.file_metadata {
+                        if let Ok(sdt) = attrs.accessed() {
+                            let dt: DateTime<Local> = DateTime::from(sdt);
+                            return Variant::from_datetime(dt.naive_local());
+                        }
                     }
                 }
-            }
+            },
             Field::Modified => match file_info {
                 Some(ref file_info) => {
-                    if let Some(file_info_modified) = &file_info.modified {
-                        let dt = to_local_datetime(file_info_modified);
-                        return Variant::from_datetime(dt);
+                    if let Some(modified) = file_info.modified {
+                        return Variant::from_datetime(modified);
                     }
                 }
                 _ => {
@@ -1351,6 +2255,24 @@ impl<'a> Searcher<'a> {
                     }
                 }
             },
+            Field::NewestChildModified => {
+                let children_modified =
+                    get_children_modified(&entry.path(), &mut self.children_modified_cache);
+
+                if let Some(children_modified) = children_modified {
+                    let dt: DateTime<Local> = DateTime::from(children_modified.newest);
+                    return Variant::from_datetime(dt.naive_local());
+                }
+            }
+            Field::OldestChildModified => {
+                let children_modified =
+                    get_children_modified(&entry.path(), &mut self.children_modified_cache);
+
+                if let Some(children_modified) = children_modified {
+                    let dt: DateTime<Local> = DateTime::from(children_modified.oldest);
+                    return Variant::from_datetime(dt.naive_local());
+                }
+            }
             Field::HasXattrs => {
                 #[cfg(unix)]
                 {
@@ -1381,6 +2303,109 @@ impl<'a> Searcher<'a> {
 
                 return Variant::empty(VariantType::String);
             }
+            Field::MountId => {
+                #[cfg(target_os = "linux")]
+                {
+                    self.fms
+                        .update_statx_info(entry, self.current_follow_symlinks);
+
+                    if let Some(mount_id) = self.fms.statx_info.and_then(|info| info.mount_id) {
+                        return Variant::from_int(mount_id as i64);
+                    }
+                }
+
+                return Variant::empty(VariantType::Int);
+            }
+            Field::Dax | Field::Verity => {
+                #[cfg(target_os = "linux")]
+                {
+                    self.fms
+                        .update_statx_info(entry, self.current_follow_symlinks);
+
+                    if let Some(ref statx_info) = self.fms.statx_info {
+                        return Variant::from_bool(match field {
+                            Field::Dax => statx_info.dax,
+                            _ => statx_info.verity,
+                        });
+                    }
+                }
+
+                return Variant::empty(VariantType::Bool);
+            }
+            Field::ImaHash => {
+                #[cfg(target_os = "linux")]
+                {
+                    if let Ok(Some(ima_xattr)) = xattr::get(entry.path(), "security.ima") {
+                        if let Some(ima_hash) = crate::util::ima::parse_ima_xattr(&ima_xattr) {
+                            return Variant::from_string(&ima_hash);
+                        }
+                    }
+                }
+
+                return Variant::empty(VariantType::String);
+            }
+            Field::IsOpen => {
+                #[cfg(target_os = "linux")]
+                {
+                    self.fms
+                        .update_file_metadata(entry, self.current_follow_symlinks);
+
+                    if let Some(ref attrs) = self.fms.file_metadata {
+                        let open_files = self.open_files.get_or_insert_with(OpenFiles::scan);
+                        return Variant::from_bool(open_files.is_open(attrs.dev(), attrs.ino()));
+                    }
+                }
+
+                return Variant::from_bool(false);
+            }
+            #[cfg(all(target_os = "linux", feature = "processes"))]
+            Field::OpenByPids => {
+                self.fms
+                    .update_file_metadata(entry, self.current_follow_symlinks);
+
+                if let Some(ref attrs) = self.fms.file_metadata {
+                    let open_files = self.open_files.get_or_insert_with(OpenFiles::scan);
+                    let pids = open_files.get_pids(attrs.dev(), attrs.ino());
+                    let pids: Vec<String> = pids.iter().map(|pid| pid.to_string()).collect();
+                    return Variant::from_string(&pids.join(", "));
+                }
+            }
+            #[cfg(all(target_os = "linux", feature = "processes"))]
+            Field::OpenByProcessNames => {
+                self.fms
+                    .update_file_metadata(entry, self.current_follow_symlinks);
+
+                if let Some(ref attrs) = self.fms.file_metadata {
+                    let open_files = self.open_files.get_or_insert_with(OpenFiles::scan);
+                    let names = open_files.get_process_names(attrs.dev(), attrs.ino());
+                    return Variant::from_string(&names.join(", "));
+                }
+            }
+            Field::IsImmutable | Field::IsAppendOnly => {
+                // Only regular files and directories have the flags, like lsattr shows them
+                let file_type = match entry.file_type() {
+                    Ok(file_type) if file_info.is_none() && (file_type.is_file() || file_type.is_dir()) => {
+                        file_type
+                    }
+                    _ => return Variant::empty(VariantType::Bool),
+                };
+
+                #[cfg(target_os = "linux")]
+                {
+                    let flag = match field {
+                        Field::IsImmutable => crate::util::file_flags::FS_IMMUTABLE_FL,
+                        _ => crate::util::file_flags::FS_APPEND_FL,
+                    };
+                    if let Ok(flags) = crate::util::file_flags::get_file_flags(&entry.path(), file_type) {
+                        return Variant::from_bool(flags & flag != 0);
+                    }
+                }
+
+                #[cfg(not(target_os = "linux"))]
+                let _ = file_type;
+
+                return Variant::from_bool(false);
+            }
             Field::IsShebang => {
                 return Variant::from_bool(is_shebang(&entry.path()));
             }
@@ -1403,6 +2428,40 @@ impl<'a> Searcher<'a> {
                     }
                 }
             },
+            Field::IsRecursivelyEmpty => {
+                if file_info.is_some() {
+                    return Variant::from_bool(false);
+                }
+
+                return match entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => match is_dir_recursively_empty(&entry.path()) {
+                        Some(result) => Variant::from_bool(result),
+                        None => Variant::empty(VariantType::Bool),
+                    },
+                    _ => Variant::from_bool(false),
+                };
+            }
+            Field::ChildCount => {
+                self.fms.update_dir_entry_counts(entry);
+
+                if let Some(DirEntryCounts { children, .. }) = self.fms.dir_entry_counts {
+                    return Variant::from_int(children as i64);
+                }
+            }
+            Field::SubdirCount => {
+                self.fms.update_dir_entry_counts(entry);
+
+                if let Some(DirEntryCounts { subdirs, .. }) = self.fms.dir_entry_counts {
+                    return Variant::from_int(subdirs as i64);
+                }
+            }
+            Field::FileCount => {
+                self.fms.update_dir_entry_counts(entry);
+
+                if let Some(DirEntryCounts { files, .. }) = self.fms.dir_entry_counts {
+                    return Variant::from_int(files as i64);
+                }
+            }
             Field::Width => {
                 self.fms.update_dimensions(entry);
 
@@ -1417,6 +2476,30 @@ impl<'a> Searcher<'a> {
                     return Variant::from_int(height as i64);
                 }
             }
+            #[cfg(feature = "image-colors")]
+            Field::DominantColor => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::String);
+                }
+
+                self.fms.update_image_colors(entry);
+
+                if let Some(ImageColors { ref dominant_color, .. }) = self.fms.image_colors {
+                    return Variant::from_string(dominant_color);
+                }
+            }
+            #[cfg(feature = "image-colors")]
+            Field::Brightness => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Int);
+                }
+
+                self.fms.update_image_colors(entry);
+
+                if let Some(ImageColors { brightness, .. }) = self.fms.image_colors {
+                    return Variant::from_int(brightness as i64);
+                }
+            }
             Field::Duration => {
                 self.fms.update_duration(entry);
 
@@ -1483,6 +2566,76 @@ impl<'a> Searcher<'a> {
                     }
                 }
             }
+            Field::VideoTitle => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(VideoMetadata { title: Some(ref title), .. }) = self.fms.video_metadata {
+                    return Variant::from_string(title);
+                }
+            }
+            Field::Encoder => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(VideoMetadata { encoder: Some(ref encoder), .. }) = self.fms.video_metadata {
+                    return Variant::from_string(encoder);
+                }
+            }
+            Field::VideoCreated => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(VideoMetadata { creation_date: Some(creation_date), .. }) = self.fms.video_metadata {
+                    return Variant::from_datetime(creation_date);
+                }
+            }
+            Field::AudioTracks => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(ref video_metadata) = self.fms.video_metadata {
+                    return Variant::from_int(video_metadata.audio_tracks as i64);
+                }
+            }
+            Field::SubtitleTracks => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(ref video_metadata) = self.fms.video_metadata {
+                    return Variant::from_int(video_metadata.subtitle_tracks as i64);
+                }
+            }
+            Field::SubtitleLangs => {
+                self.fms.update_video_metadata(entry);
+
+                if let Some(ref video_metadata) = self.fms.video_metadata {
+                    return Variant::from_string(&video_metadata.subtitle_langs.join(","));
+                }
+            }
+            Field::TorrentInfoHash => {
+                self.fms.update_torrent_info(entry);
+
+                if let Some(ref torrent_info) = self.fms.torrent_info {
+                    return Variant::from_string(&torrent_info.info_hash);
+                }
+            }
+            Field::TorrentPieceCount => {
+                self.fms.update_torrent_info(entry);
+
+                if let Some(ref torrent_info) = self.fms.torrent_info {
+                    return Variant::from_int(torrent_info.piece_count as i64);
+                }
+            }
+            Field::TorrentTotalSize => {
+                self.fms.update_torrent_info(entry);
+
+                if let Some(ref torrent_info) = self.fms.torrent_info {
+                    return Variant::from_int(torrent_info.total_size as i64);
+                }
+            }
+            Field::TorrentFilesCount => {
+                self.fms.update_torrent_info(entry);
+
+                if let Some(ref torrent_info) = self.fms.torrent_info {
+                    return Variant::from_int(torrent_info.files_count as i64);
+                }
+            }
             Field::ExifDateTime => {
                 self.fms.update_exif_metadata(entry);
 
@@ -1558,14 +2711,62 @@ impl<'a> Searcher<'a> {
                 }
             }
             Field::LineCount => {
-                self.fms.update_line_count(entry);
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Int);
+                }
+
+                let line_count = match file_info {
+                    Some(file_info) => {
+                        let deadline = self.content_deadline;
+                        file_info
+                            .read_contents(|reader| count_lines(DeadlineReader::new(reader, deadline)))
+                            .flatten()
+                    }
+                    None => {
+                        self.fms.update_line_count(entry, self.content_deadline);
+                        self.fms.line_count
+                    }
+                };
+
+                if self.is_content_timed_out() {
+                    return Variant::empty(VariantType::Int);
+                }
 
-                if let Some(line_count) = self.fms.line_count {
+                if let Some(line_count) = line_count {
                     return Variant::from_int(line_count as i64);
                 }
             }
+            Field::CodeLines | Field::CommentLines | Field::BlankLines => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Int);
+                }
+
+                self.fms.update_code_metrics(entry, self.content_deadline);
+
+                if self.is_content_timed_out() {
+                    return Variant::empty(VariantType::Int);
+                }
+
+                if let Some(code_metrics) = self.fms.code_metrics {
+                    let lines = match field {
+                        Field::CodeLines => code_metrics.code_lines,
+                        Field::CommentLines => code_metrics.comment_lines,
+                        _ => code_metrics.blank_lines,
+                    };
+                    return Variant::from_int(lines as i64);
+                }
+            }
+            Field::License => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::String);
+                }
+
+                if let Some(license) = get_license(&entry.path()) {
+                    return Variant::from_string(&license);
+                }
+            }
             Field::Mime => {
-                if let Some(mime) = tree_magic_mini::from_filepath(&entry.path()) {
+                if let Some(mime) = self.get_mime(entry, file_info) {
                     return Variant::from_string(&String::from(mime));
                 }
 
@@ -1581,7 +2782,7 @@ impl<'a> Searcher<'a> {
                     }
                 }
 
-                if let Some(mime) = tree_magic_mini::from_filepath(&entry.path()) {
+                if let Some(mime) = self.get_mime(entry, file_info) {
                     let is_binary = !is_text_mime(mime);
                     return Variant::from_bool(is_binary);
                 }
@@ -1598,7 +2799,7 @@ impl<'a> Searcher<'a> {
                     }
                 }
 
-                if let Some(mime) = tree_magic_mini::from_filepath(&entry.path()) {
+                if let Some(mime) = self.get_mime(entry, file_info) {
                     let is_text = is_text_mime(mime);
                     return Variant::from_bool(is_text);
                 }
@@ -1606,88 +2807,203 @@ impl<'a> Searcher<'a> {
                 return Variant::from_bool(false);
             }
             Field::IsArchive => {
-                let is_archive = match file_info {
-                    Some(file_info) => self.is_archive(&file_info.name),
-                    None => self.is_archive(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_archive) = self.classify_by_mime(entry, file_info, is_archive_mime) {
+                    return Variant::from_bool(is_archive);
+                }
+
+                let is_archive = self.is_of_category(entry, file_info, |config| &config.is_archive);
 
                 return Variant::from_bool(is_archive);
             }
+            Field::IsValidArchive => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Bool);
+                }
+
+                let is_zip = self.is_zip_archive(&entry.file_name().to_string_lossy());
+                let is_valid = is_valid_archive(&entry.path(), is_zip, self.content_deadline);
+
+                if self.is_content_timed_out() {
+                    return Variant::empty(VariantType::Bool);
+                }
+
+                if let Some(is_valid) = is_valid {
+                    return Variant::from_bool(is_valid);
+                }
+
+                return Variant::empty(VariantType::Bool);
+            }
+            Field::InArchive => {
+                return Variant::from_bool(file_info.is_some());
+            }
+            Field::ArchivePath => {
+                if file_info.is_some() {
+                    let path = entry.path().to_string_lossy().to_string();
+                    return Variant::from_string(&self.format_output_path(path));
+                }
+            }
             Field::IsAudio => {
-                let is_audio = match file_info {
-                    Some(file_info) => self.is_audio(&file_info.name),
-                    None => self.is_audio(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_audio) = self.classify_by_mime(entry, file_info, is_audio_mime) {
+                    return Variant::from_bool(is_audio);
+                }
+
+                let is_audio = self.is_of_category(entry, file_info, |config| &config.is_audio);
 
                 return Variant::from_bool(is_audio);
             }
             Field::IsBook => {
-                let is_book = match file_info {
-                    Some(file_info) => self.is_book(&file_info.name),
-                    None => self.is_book(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_book) = self.classify_by_mime(entry, file_info, is_book_mime) {
+                    return Variant::from_bool(is_book);
+                }
+
+                let is_book = self.is_of_category(entry, file_info, |config| &config.is_book);
 
                 return Variant::from_bool(is_book);
             }
             Field::IsDoc => {
-                let is_doc = match file_info {
-                    Some(file_info) => self.is_doc(&file_info.name),
-                    None => self.is_doc(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_doc) = self.classify_by_mime(entry, file_info, is_doc_mime) {
+                    return Variant::from_bool(is_doc);
+                }
+
+                let is_doc = self.is_of_category(entry, file_info, |config| &config.is_doc);
 
                 return Variant::from_bool(is_doc);
             }
             Field::IsFont => {
-                let is_font = match file_info {
-                    Some(file_info) => self.is_font(&file_info.name),
-                    None => self.is_font(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_font) = self.classify_by_mime(entry, file_info, is_font_mime) {
+                    return Variant::from_bool(is_font);
+                }
+
+                let is_font = self.is_of_category(entry, file_info, |config| &config.is_font);
 
                 return Variant::from_bool(is_font);
             }
             Field::IsImage => {
-                let is_image = match file_info {
-                    Some(file_info) => self.is_image(&file_info.name),
-                    None => self.is_image(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_image) = self.classify_by_mime(entry, file_info, is_image_mime) {
+                    return Variant::from_bool(is_image);
+                }
+
+                let is_image = self.is_of_category(entry, file_info, |config| &config.is_image);
 
                 return Variant::from_bool(is_image);
             }
+            Field::IsCorruptedImage => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Bool);
+                }
+
+                if let Some(is_corrupted) = integrity::is_corrupted_image(&entry.path()) {
+                    return Variant::from_bool(is_corrupted);
+                }
+            }
+            Field::IsEncrypted => {
+                if !self.is_content_allowed(entry, file_info) {
+                    return Variant::empty(VariantType::Bool);
+                }
+
+                if let Some(is_encrypted) = encryption::is_encrypted(&entry.path()) {
+                    return Variant::from_bool(is_encrypted);
+                }
+            }
             Field::IsSource => {
-                let is_source = match file_info {
-                    Some(file_info) => self.is_source(&file_info.name),
-                    None => self.is_source(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_source) = self.classify_by_mime(entry, file_info, is_source_mime) {
+                    return Variant::from_bool(is_source);
+                }
+
+                let is_source = self.is_of_category(entry, file_info, |config| &config.is_source);
 
                 return Variant::from_bool(is_source);
             }
+            Field::IsScript => {
+                let is_script = self.is_of_category(entry, file_info, |config| &config.is_script);
+
+                return Variant::from_bool(is_script);
+            }
             Field::IsVideo => {
-                let is_video = match file_info {
-                    Some(file_info) => self.is_video(&file_info.name),
-                    None => self.is_video(&entry.file_name().to_string_lossy()),
-                };
+                if let Some(is_video) = self.classify_by_mime(entry, file_info, is_video_mime) {
+                    return Variant::from_bool(is_video);
+                }
+
+                let is_video = self.is_of_category(entry, file_info, |config| &config.is_video);
 
                 return Variant::from_bool(is_video);
             }
             Field::Sha1 => {
-                return Variant::from_string(&crate::util::get_sha1_file_hash(entry));
+                return self.get_content_hash(
+                    entry,
+                    file_info,
+                    crate::util::get_sha1_file_hash,
+                    crate::util::get_sha1_hash,
+                );
             }
             Field::Sha256 => {
-                return Variant::from_string(&crate::util::get_sha256_file_hash(entry));
+                return self.get_content_hash(
+                    entry,
+                    file_info,
+                    crate::util::get_sha256_file_hash,
+                    crate::util::get_sha256_hash,
+                );
             }
             Field::Sha512 => {
-                return Variant::from_string(&crate::util::get_sha512_file_hash(entry));
+                return self.get_content_hash(
+                    entry,
+                    file_info,
+                    crate::util::get_sha512_file_hash,
+                    crate::util::get_sha512_hash,
+                );
             }
             Field::Sha3 => {
-                return Variant::from_string(&crate::util::get_sha3_512_file_hash(entry));
+                return self.get_content_hash(
+                    entry,
+                    file_info,
+                    crate::util::get_sha3_512_file_hash,
+                    crate::util::get_sha3_512_hash,
+                );
+            }
+            Field::TrashOriginalPath => {
+                self.fms.update_trash_info(entry, &self.trash_dirs);
+
+                if let Some(ref trash_info) = self.fms.trash_info {
+                    if let Some(ref original_path) = trash_info.original_path {
+                        return Variant::from_string(&original_path.to_string_lossy().to_string());
+                    }
+                }
+            }
+            Field::TrashDeleted => {
+                self.fms.update_trash_info(entry, &self.trash_dirs);
+
+                if let Some(ref trash_info) = self.fms.trash_info {
+                    if let Some(deleted) = trash_info.deleted {
+                        return Variant::from_datetime(deleted);
+                    }
+                }
             }
         };
 
         return Variant::empty(VariantType::String);
     }
 
-    fn check_file(&mut self, entry: &DirEntry, file_info: &Option<FileInfo>) -> io::Result<bool> {
+    /// Checks the entry, or the current manifest record when there's none, and outputs it if matched
+    fn check_file(&mut self, entry: Option<&DirEntry>, file_info: &Option<FileInfo>) -> io::Result<bool> {
+        if let Some(entry) = entry {
+            if !self.trash_dirs.is_empty() && is_trash_metadata(&self.trash_dirs, &entry.path()) {
+                return Ok(true);
+            }
+        }
+
         self.fms.clear();
+        if let Some(entry) = entry {
+            self.current_follow_symlinks = self.follows_symlink(entry);
+        }
+        self.content_deadline = self.get_content_timeout().map(|timeout| Instant::now() + timeout);
+        self.content_skipped = false;
+
+        // Files sorting after the last of the kept results can't make it to them anyway
+        if let (Some(entry), None) = (entry, file_info) {
+            if self.is_past_path_bound(&entry.path(), false) {
+                return Ok(true);
+            }
+        }
 
         if let Some(ref expr) = self.query.expr {
             let result = self.conforms(entry, file_info, expr);
@@ -1698,30 +3014,39 @@ impl<'a> Searcher<'a> {
 
         self.found += 1;
 
+        if let Some(entry) = entry {
+            trace!(target: "searcher", "Matched {}", entry.path().display());
+        }
+
         let mut file_map = HashMap::new();
 
-        let mut buf = WritableBuffer::new();
         let mut criteria = vec!["".to_string(); self.query.ordering_fields.len()];
 
-        for field in self.query.get_all_fields() {
-            file_map.insert(
-                field.to_string(),
-                self.get_field_value(entry, file_info, &field).to_string(),
-            );
-        }
-
-        if !self.is_buffered() && self.found > 1 {
-            self.results_writer.write_row_separator(&mut buf)?;
+        let column_fields = Rc::clone(&self.column_fields);
+        for field in column_fields.iter() {
+            let value = match (entry, &self.current_row) {
+                (Some(entry), _) => self.get_field_value(entry, file_info, field),
+                (None, Some(row)) => get_row_value(row, field),
+                (None, None) => Variant::empty(VariantType::String),
+            };
+            if *field == Field::Size {
+                self.total_size += value.to_int().max(0) as u64;
+            }
+            file_map.insert(field.to_string(), value.to_string());
         }
 
         let mut items: Vec<(String, String)> = Vec::new();
+        let mut values = vec![];
 
         for field in self.query.fields.iter() {
             let record =
-                self.get_column_expr_value(Some(entry), file_info, &mut file_map, None, field);
+                self.get_column_expr_value(entry, file_info, &mut file_map, None, field);
+            if self.query.distinct {
+                values.push(record.to_string());
+            }
 
-            let value = match self.use_colors && field.contains_colorized() {
-                true => self.colorize(&record.to_string()),
+            let value = match self.use_colors {
+                true => self.colorize_column(entry, file_info, field, record.to_string()),
                 false => record.to_string(),
             };
             items.push((field.to_string(), value));
@@ -1729,7 +3054,7 @@ impl<'a> Searcher<'a> {
 
         for field in self.query.grouping_fields.iter() {
             if file_map.get(&field.to_string()).is_none() {
-                self.get_column_expr_value(Some(entry), file_info, &mut file_map, None, field);
+                self.get_column_expr_value(entry, file_info, &mut file_map, None, field);
             }
         }
 
@@ -1737,27 +3062,69 @@ impl<'a> Searcher<'a> {
             criteria[idx] = match file_map.get(&field.to_string()) {
                 Some(record) => record.clone(),
                 None => self
-                    .get_column_expr_value(Some(entry), file_info, &mut file_map, None, field)
+                    .get_column_expr_value(entry, file_info, &mut file_map, None, field)
                     .to_string(),
             }
         }
 
-        self.results_writer.write_row(&mut buf, items)?;
+        // Only the distinct rows count towards the limit, aggregated ones are compared when they are written
+        if !self.has_aggregate_column() && self.is_repeated_row(values) {
+            self.found -= 1;
+            return Ok(true);
+        }
+
+        // Aggregated results are only written at the end, formats collecting rows must not see single files
+        if self.has_aggregate_column() {
+            let row = self.intern_row(file_map);
+            self.raw_output_buffer.push(row);
+            return Ok(true);
+        }
 
         if self.is_buffered() {
+            for (idx, field) in self.query.fields.iter().enumerate() {
+                if field.is_window_function() {
+                    self.window_totals[idx] += items[idx].1.parse::<f64>().unwrap_or(0.0);
+                }
+            }
+
+            let items = items
+                .into_iter()
+                .map(|(name, value)| self.intern_value(name, value))
+                .collect();
             self.output_buffer.insert(
                 Criteria::new(
                     self.query.ordering_fields.clone(),
                     criteria,
                     self.query.ordering_asc.clone(),
                 ),
-                String::from(buf),
+                items,
             );
+            return Ok(true);
+        }
 
-            if self.has_aggregate_column() {
-                self.raw_output_buffer.push(file_map);
-            }
-        } else if let Err(e) = write!(std::io::stdout(), "{}", String::from(buf)) {
+        // Rows before the offset are only counted
+        if self.found <= self.query.offset {
+            return Ok(true);
+        }
+
+        // Only the files of the rows being written are acted upon, the parser rejects actions on buffered rows
+        if let (Some(entry), Some(action)) = (entry, &self.query.action) {
+            self.perform_action(entry, file_info, action);
+        }
+
+        if self.partitioned_writer.is_some() {
+            self.write_partitioned(|writer| writer.write_row(items));
+            return Ok(true);
+        }
+
+        // Unordered rows go straight to the output, which buffers them itself
+        let written = match self.found - self.query.offset {
+            1 => Ok(()),
+            _ => self.results_writer.write_row_separator(&mut self.output),
+        }
+        .and_then(|_| self.results_writer.write_row(&mut self.output, items));
+
+        if let Err(e) = written {
             if e.kind() == ErrorKind::BrokenPipe {
                 return Ok(false);
             }
@@ -1766,6 +3133,420 @@ impl<'a> Searcher<'a> {
         Ok(true)
     }
 
+    fn get_content_hash(
+        &mut self,
+        entry: &DirEntry,
+        file_info: &Option<FileInfo>,
+        file_hash_fn: FileHashFn,
+        hash_fn: HashFn,
+    ) -> Variant {
+        if !self.is_content_allowed(entry, file_info) {
+            return Variant::empty(VariantType::String);
+        }
+
+        let hash = match file_info {
+            Some(file_info) => {
+                let deadline = self.content_deadline;
+                file_info
+                    .read_contents(|reader| hash_fn(&mut DeadlineReader::new(reader, deadline)))
+                    .unwrap_or_default()
+            }
+            None => file_hash_fn(entry, self.content_deadline),
+        };
+
+        if self.is_content_timed_out() {
+            return Variant::empty(VariantType::String);
+        }
+
+        Variant::from_string(&hash)
+    }
+
+    /// Checks if the contents of the file may be read within the configured limits.
+    /// Devices, FIFOs, and sockets are never read as they could block forever.
+    /// Files within archives are limited by their uncompressed size.
+    fn is_content_allowed(&mut self, entry: &DirEntry, file_info: &Option<FileInfo>) -> bool {
+        let (is_file, size) = match file_info {
+            Some(file_info) => (!file_info.is_dir(), file_info.size),
+            None => match fs::metadata(entry.path()) {
+                Ok(metadata) => (metadata.is_file(), metadata.len()),
+                Err(_) => return false,
+            },
+        };
+
+        if !is_file {
+            return false;
+        }
+
+        if let Some(max_content_size) = self.get_max_content_size() {
+            if size > max_content_size {
+                debug!(
+                    target: "searcher",
+                    "Skipping contents of {}: {} bytes exceed max_content_size",
+                    entry.path().display(),
+                    size
+                );
+                self.skip_content();
+                return false;
+            }
+        }
+
+        !self.is_content_timed_out()
+    }
+
+    fn is_content_timed_out(&mut self) -> bool {
+        match self.content_deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                if !self.content_skipped {
+                    debug!(target: "searcher", "Reached content_timeout, skipping remaining content fields");
+                }
+                self.skip_content();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn skip_content(&mut self) {
+        if !self.content_skipped {
+            self.content_skipped = true;
+            self.content_skipped_count += 1;
+        }
+    }
+
+    fn get_todo_markers(&self) -> &Vec<String> {
+        self.config
+            .todo_markers
+            .as_ref()
+            .unwrap_or(self.default_config.todo_markers.as_ref().unwrap())
+    }
+
+    fn get_max_content_size(&self) -> Option<u64> {
+        let max_content_size = self
+            .config
+            .max_content_size
+            .as_ref()
+            .unwrap_or(self.default_config.max_content_size.as_ref().unwrap());
+
+        parse_filesize(max_content_size).filter(|&size| size > 0)
+    }
+
+    /// Repeats the operation after transient errors as configured with `io_retries` and `io_retry_delay`
+    fn retry_io<T>(&mut self, operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let retries = self
+            .config
+            .io_retries
+            .unwrap_or(self.default_config.io_retries.unwrap());
+        let delay = self
+            .config
+            .io_retry_delay
+            .unwrap_or(self.default_config.io_retry_delay.unwrap());
+
+        let (result, retry_count) = retry_io(retries, std::time::Duration::from_millis(delay), operation);
+        self.io_retry_count += retry_count;
+
+        result
+    }
+
+    fn get_content_timeout(&self) -> Option<std::time::Duration> {
+        let content_timeout = self
+            .config
+            .content_timeout
+            .unwrap_or(self.default_config.content_timeout.unwrap());
+
+        match content_timeout {
+            0 => None,
+            seconds => Some(std::time::Duration::from_secs(seconds)),
+        }
+    }
+
+    fn perform_action(&mut self, entry: &DirEntry, file_info: &Option<FileInfo>, action: &Action) {
+        match action {
+            Action::ExtractThumbnail { template, overwrite } => {
+                if file_info.is_some() {
+                    return;
+                }
+
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let is_video = self.is_video(&file_name);
+                if !is_video && !self.is_image(&file_name) {
+                    return;
+                }
+
+                let destination = PathBuf::from(self.expand_template(template, entry, file_info));
+                if !overwrite && destination.exists() {
+                    self.error_count += 1;
+                    error_message(&destination.to_string_lossy(), "already exists, not overwritten");
+                    return;
+                }
+
+                debug!(
+                    target: "searcher",
+                    "Extracting thumbnail of {} to {}",
+                    entry.path().display(),
+                    destination.display()
+                );
+                let result = match is_video {
+                    true => extract_video_frame(&entry.path(), &destination, *overwrite),
+                    false => extract_image_thumbnail(&entry.path(), &destination, *overwrite),
+                };
+
+                if let Err(e) = result {
+                    self.error_count += 1;
+                    path_error_message(&entry.path(), e);
+                }
+            }
+            Action::ReplaceDuplicates { kind, dry_run } => {
+                // Symlinks are never replaced, nor do they count as originals
+                if file_info.is_some() || !entry.file_type().is_ok_and(|t| t.is_file()) {
+                    return;
+                }
+
+                self.fms.update_file_metadata(entry, false);
+                let size = match self.fms.file_metadata {
+                    Some(ref metadata) if metadata.len() > 0 => metadata.len(),
+                    _ => return,
+                };
+
+                let duplicate = entry.path();
+                let original = match self.deduplicator.find_original(&duplicate, size) {
+                    Some(original) if !is_same_file(&original, &duplicate) => original,
+                    _ => return,
+                };
+
+                if *dry_run {
+                    eprintln!(
+                        "Would replace {} with a link to {}",
+                        duplicate.display(),
+                        original.display()
+                    );
+                } else {
+                    debug!(
+                        target: "searcher",
+                        "Replacing {} with a link to {}",
+                        duplicate.display(),
+                        original.display()
+                    );
+                    if let Err(e) = replace_with_link(&duplicate, &original, *kind) {
+                        self.error_count += 1;
+                        path_error_message(&duplicate, e);
+                        return;
+                    }
+                }
+
+                self.deduplicator.replaced += 1;
+                self.deduplicator.reclaimed += size;
+            }
+            Action::UpdateFlags { immutable, append_only } => {
+                if file_info.is_some() {
+                    return;
+                }
+
+                #[cfg(target_os = "linux")]
+                {
+                    use crate::util::file_flags::{update_file_flags, FS_APPEND_FL, FS_IMMUTABLE_FL};
+
+                    let mut set = 0;
+                    let mut clear = 0;
+                    for (value, flag) in [(immutable, FS_IMMUTABLE_FL), (append_only, FS_APPEND_FL)] {
+                        match value {
+                            Some(true) => set |= flag,
+                            Some(false) => clear |= flag,
+                            None => {}
+                        }
+                    }
+
+                    debug!(target: "searcher", "Updating flags of {}", entry.path().display());
+                    let result = entry
+                        .file_type()
+                        .and_then(|file_type| update_file_flags(&entry.path(), file_type, set, clear));
+                    if let Err(e) = result {
+                        self.error_count += 1;
+                        path_error_message(&entry.path(), e);
+                    }
+                }
+
+                #[cfg(not(target_os = "linux"))]
+                let _ = (immutable, append_only);
+            }
+            Action::Restore(_) | Action::Purge => {
+                if file_info.is_some() {
+                    return;
+                }
+
+                self.fms.update_trash_info(entry, &self.trash_dirs);
+                let trash_info = match self.fms.trash_info {
+                    Some(ref trash_info) => trash_info.clone(),
+                    None => return,
+                };
+
+                // Trashed items are restored or purged as a whole, along with the files nested within
+                if trash_info.item != entry.path() {
+                    return;
+                }
+
+                let result = match action {
+                    Action::Restore(destination) => {
+                        debug!(target: "searcher", "Restoring {}", entry.path().display());
+                        trash::restore(&trash_info, destination.as_ref().map(Path::new))
+                    }
+                    _ => {
+                        debug!(target: "searcher", "Purging {}", entry.path().display());
+                        trash::purge(&trash_info)
+                    }
+                };
+
+                match result {
+                    Ok(()) => {
+                        self.removed_dirs.insert(entry.path());
+                    }
+                    Err(e) => {
+                        self.error_count += 1;
+                        path_error_message(&entry.path(), e);
+                    }
+                }
+            }
+            Action::Delete { into_trash: true, dry_run } => {
+                if file_info.is_some() {
+                    return;
+                }
+
+                let path = entry.path();
+
+                // Nothing within a directory moved to the trash is visited, it goes there along with it
+                if *dry_run {
+                    eprintln!("Would move {} to the trash", path.display());
+                    self.removed_dirs.insert(path);
+                    return;
+                }
+
+                debug!(target: "searcher", "Moving {} to the trash", path.display());
+                match trash::move_to_trash(&path) {
+                    Ok(_) => {
+                        self.removed_dirs.insert(path);
+                    }
+                    Err(e) => {
+                        self.error_count += 1;
+                        path_error_message(&path, e);
+                    }
+                }
+            }
+            Action::Delete { into_trash: false, dry_run } => {
+                if file_info.is_some() {
+                    return;
+                }
+
+                let path = entry.path();
+
+                // Files are never deleted, neither are directories with files left anywhere within
+                if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    self.error_count += 1;
+                    error_message(&path.to_string_lossy(), "not a directory, not deleted");
+                    return;
+                }
+
+                if is_dir_recursively_empty(&path) != Some(true) {
+                    self.error_count += 1;
+                    error_message(&path.to_string_lossy(), "directory is not empty, not deleted");
+                    return;
+                }
+
+                if *dry_run {
+                    eprintln!("Would delete {}", path.display());
+                    return;
+                }
+
+                debug!(target: "searcher", "Deleting {}", path.display());
+                match remove_empty_dirs(&path) {
+                    Ok(()) => {
+                        self.removed_dirs.insert(path);
+                    }
+                    Err(e) => {
+                        self.error_count += 1;
+                        path_error_message(&path, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the fraction of the parent directory's total size taken by the file or directory,
+    /// rounded to four decimal places
+    fn get_size_share(&mut self, entry: &DirEntry) -> Option<f64> {
+        let path = entry.path();
+        let parent_size = get_dir_size(path.parent()?, &mut self.dir_size_cache)?;
+        if parent_size == 0 {
+            return None;
+        }
+
+        let metadata = entry.metadata().ok()?;
+        let size = match metadata.is_dir() {
+            true => get_dir_size(&path, &mut self.dir_size_cache)?,
+            false => metadata.len(),
+        };
+
+        Some((size as f64 / parent_size as f64 * 10000.0).round() / 10000.0)
+    }
+
+    /// Strips the `\\?\` prefix of Windows long paths from the path in the output,
+    /// or adds it to absolute paths when the root has the `verbatim` option
+    fn format_output_path(&self, path: String) -> String {
+        if !cfg!(windows) {
+            return path;
+        }
+
+        match self.current_verbatim {
+            true => to_verbatim_path(&path),
+            false => strip_verbatim_prefix(&path),
+        }
+    }
+
+    /// Replaces `{field}` placeholders with the values of the corresponding fields
+    fn expand_template(&mut self, template: &str, entry: &DirEntry, file_info: &Option<FileInfo>) -> String {
+        let mut result = String::new();
+        let mut last = 0;
+
+        for captures in PLACEHOLDER_REGEX.captures_iter(template) {
+            let whole = captures.get(0).unwrap();
+            if let Ok(field) = captures[1].parse::<Field>() {
+                result.push_str(&template[last..whole.start()]);
+                result.push_str(&self.get_field_value(entry, file_info, &field).to_string());
+                last = whole.end();
+            }
+        }
+
+        result.push_str(&template[last..]);
+        result
+    }
+
+    /// Styles the value with the first color rule of the column matching the file,
+    /// paths without such rules are styled with LS_COLORS
+    fn colorize_column(
+        &mut self,
+        entry: Option<&DirEntry>,
+        file_info: &Option<FileInfo>,
+        column: &Expr,
+        value: String,
+    ) -> String {
+        let column_styles = Rc::clone(&self.column_styles);
+
+        for column_style in column_styles.iter().filter(|style| style.column == *column) {
+            let matches = match column_style.condition {
+                Some(ref condition) => self.conforms(entry, file_info, condition),
+                None => true,
+            };
+
+            if matches {
+                let ansi_style = column_style.style.to_nu_ansi_term_style();
+                return format!("{}", ansi_style.paint(value));
+            }
+        }
+
+        match column.contains_colorized() {
+            true => self.colorize(&value),
+            false => value,
+        }
+    }
+
     fn colorize(&mut self, value: &str) -> String {
         let style;
 
@@ -1808,9 +3589,38 @@ impl<'a> Searcher<'a> {
         Variant::from_bool(false)
     }
 
-    fn conforms(&mut self, entry: &DirEntry, file_info: &Option<FileInfo>, expr: &Expr) -> bool {
+    /// Matches an OR chain of regex and LIKE conditions on the same column in one pass,
+    /// nothing if the chain isn't made of such conditions or the column isn't a string
+    fn conforms_regex_set(
+        &mut self,
+        entry: Option<&DirEntry>,
+        file_info: &Option<FileInfo>,
+        expr: &Expr,
+    ) -> Option<bool> {
+        let pattern_set = Rc::clone(self.pattern_sets.get(&(expr as *const Expr))?);
+        let value =
+            self.get_column_expr_value(entry, file_info, &mut HashMap::new(), None, &pattern_set.column);
+
+        match value.get_type() {
+            VariantType::String => Some(pattern_set.regex_set.is_match(&value.to_string())),
+            _ => None,
+        }
+    }
+
+    fn conforms(
+        &mut self,
+        entry: Option<&DirEntry>,
+        file_info: &Option<FileInfo>,
+        expr: &Expr,
+    ) -> bool {
         let mut result = false;
 
+        if expr.logical_op == Some(LogicalOp::Or) {
+            if let Some(result) = self.conforms_regex_set(entry, file_info, expr) {
+                return result;
+            }
+        }
+
         if let Some(ref logical_op) = expr.logical_op {
             let mut left_result = false;
             let mut right_result = false;
@@ -1848,20 +3658,28 @@ impl<'a> Searcher<'a> {
             }
         } else if let Some(ref op) = expr.op {
             let field_value = self.get_column_expr_value(
-                Some(entry),
+                entry,
                 file_info,
                 &mut HashMap::new(),
                 None,
                 expr.left.as_ref().unwrap(),
             );
             let value = self.get_column_expr_value(
-                Some(entry),
+                entry,
                 file_info,
                 &mut HashMap::new(),
                 None,
                 expr.right.as_ref().unwrap(),
             );
 
+            // Archived entries without their own timestamps don't match any date
+            if file_info.is_some()
+                && field_value.to_string().is_empty()
+                && expr.left.as_ref().unwrap().contains_datetime()
+            {
+                return false;
+            }
+
             result = match field_value.get_type() {
                 VariantType::String => {
                     let val = value.to_string();
@@ -2032,10 +3850,15 @@ impl<'a> Searcher<'a> {
                     }
                 }
                 VariantType::DateTime => {
-                    let (start, finish) = value.to_datetime();
+                    // Values computed for the file that are not dates never match
+                    let (Some((start, finish)), Some((dt, _))) =
+                        (value.to_datetime(), field_value.to_datetime())
+                    else {
+                        return false;
+                    };
                     let start = start.and_utc().timestamp();
                     let finish = finish.and_utc().timestamp();
-                    let dt = field_value.to_datetime().0.and_utc().timestamp();
+                    let dt = dt.and_utc().timestamp();
                     match op {
                         Op::Eeq => dt == start,
                         Op::Ene => dt != start,
@@ -2054,93 +3877,111 @@ impl<'a> Searcher<'a> {
         result
     }
 
+    /// Returns the creation time of the file, on Linux it's taken from `statx`
+    fn get_birth_time(&mut self, entry: &DirEntry) -> Option<SystemTime> {
+        #[cfg(target_os = "linux")]
+        {
+            self.fms
+                .update_statx_info(entry, self.current_follow_symlinks);
+
+            if let Some(ref statx_info) = self.fms.statx_info {
+                return statx_info.birth_time;
+            }
+        }
+
+        self.fms
+            .update_file_metadata(entry, self.current_follow_symlinks);
+
+        self.fms
+            .file_metadata
+            .as_ref()
+            .and_then(|attrs| attrs.created().ok())
+    }
+
     fn is_zip_archive(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_zip_archive
-                .as_ref()
-                .unwrap_or(self.default_config.is_zip_archive.as_ref().unwrap()),
-        )
-    }
-
-    fn is_archive(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_archive
-                .as_ref()
-                .unwrap_or(self.default_config.is_archive.as_ref().unwrap()),
-        )
-    }
-
-    fn is_audio(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_audio
-                .as_ref()
-                .unwrap_or(self.default_config.is_audio.as_ref().unwrap()),
-        )
-    }
-
-    fn is_book(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_book
-                .as_ref()
-                .unwrap_or(self.default_config.is_book.as_ref().unwrap()),
-        )
-    }
-
-    fn is_doc(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_doc
-                .as_ref()
-                .unwrap_or(self.default_config.is_doc.as_ref().unwrap()),
-        )
-    }
-
-    fn is_font(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_font
-                .as_ref()
-                .unwrap_or(self.default_config.is_font.as_ref().unwrap()),
-        )
+        has_extension(file_name, self.get_extensions(|config| &config.is_zip_archive))
     }
 
-    fn is_image(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_image
-                .as_ref()
-                .unwrap_or(self.default_config.is_image.as_ref().unwrap()),
-        )
+    /// Classifies the file by its detected MIME type if `classify_by = "mime"` is configured.
+    /// Returns None to fall back to the extension lists, e.g. for archived files
+    /// or when the detected type is too generic to tell anything.
+    fn classify_by_mime(
+        &mut self,
+        entry: &DirEntry,
+        file_info: &Option<FileInfo>,
+        is_mime_of_kind: fn(&str) -> bool,
+    ) -> Option<bool> {
+        let classify_by = self
+            .config
+            .classify_by
+            .as_ref()
+            .unwrap_or(self.default_config.classify_by.as_ref().unwrap());
+
+        if !classify_by.eq_ignore_ascii_case("mime") || file_info.is_some() {
+            return None;
+        }
+
+        self.get_mime(entry, file_info)
+            .filter(|mime| !is_generic_mime(mime))
+            .map(is_mime_of_kind)
+    }
+
+    /// Detects the MIME type from the contents once per file, within the content limits.
+    /// Directories are told without reading, files within archives are not detected.
+    fn get_mime(&mut self, entry: &DirEntry, file_info: &Option<FileInfo>) -> Option<&'static str> {
+        if file_info.is_some() {
+            return None;
+        }
+
+        if !self.fms.mime_set {
+            self.fms.mime_set = true;
+
+            self.fms
+                .update_file_metadata(entry, self.current_follow_symlinks);
+            let is_dir = self.fms.file_metadata.as_ref().is_some_and(|meta| meta.is_dir());
+
+            if is_dir || self.is_content_allowed(entry, file_info) {
+                self.fms.mime = traced("mime", entry, || tree_magic_mini::from_filepath(&entry.path()));
+            }
+        }
+
+        self.fms.mime
     }
 
-    fn is_source(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_source
-                .as_ref()
-                .unwrap_or(self.default_config.is_source.as_ref().unwrap()),
-        )
+    fn is_image(&self, file_name: &str) -> bool {
+        has_extension(file_name, self.get_extensions(|config| &config.is_image))
     }
 
     fn is_video(&self, file_name: &str) -> bool {
-        has_extension(
-            file_name,
-            self.config
-                .is_video
-                .as_ref()
-                .unwrap_or(self.default_config.is_video.as_ref().unwrap()),
-        )
+        has_extension(file_name, self.get_extensions(|config| &config.is_video))
+    }
+
+    /// Returns the extensions of the file category like `is_image` from the config, or the default ones
+    fn get_extensions(&self, category: fn(&Config) -> &Option<Vec<String>>) -> &Vec<String> {
+        category(self.config)
+            .as_ref()
+            .unwrap_or_else(|| category(self.default_config).as_ref().unwrap())
+    }
+
+    /// Checks the file name against the extensions of the category.
+    /// Categories listing `#!` among the extensions also take in the files starting with a shebang,
+    /// so that scripts without extensions are caught.
+    fn is_of_category(
+        &self,
+        entry: &DirEntry,
+        file_info: &Option<FileInfo>,
+        category: fn(&Config) -> &Option<Vec<String>>,
+    ) -> bool {
+        let extensions = self.get_extensions(category);
+
+        let has_extension = match file_info {
+            Some(file_info) => has_extension(&file_info.name, extensions),
+            None => has_extension(&entry.file_name().to_string_lossy(), extensions),
+        };
+
+        has_extension
+            || (file_info.is_none()
+                && extensions.iter().any(|ext| ext == SHEBANG_MARKER)
+                && is_shebang(&entry.path()))
     }
 }
@@ -0,0 +1,186 @@
+//! Handles the HTTP mode started with `--http`, available with the `http` feature.
+//!
+//! `POST /query` with a JSON body like `{"query": "..."}` runs the query and responds with a JSON array of the results.
+//! Requiring JSON keeps web pages from sending queries through the browser, as forms can't post it.
+//! `GET /query?q=...` is not served for the same reason, any page could make the browser send it.
+//! The `Host` header has to name the loopback address, so pages of other sites resolving to it
+//! after DNS rebinding are refused as well.
+
+use std::net::{IpAddr, SocketAddr};
+use std::thread;
+
+use log::{debug, info};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::config::Config;
+use crate::query::OutputFormat;
+use crate::server::{check_loopback, run_query};
+
+/// Listens on the localhost address, answering HTTP requests until killed
+pub fn serve_http(
+    address: &str,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) -> Result<(), String> {
+    let socket_addr = address
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("{} is not a HOST:PORT address", address))?;
+    check_loopback(&socket_addr)?;
+
+    let server = Server::http(socket_addr).map_err(|err| err.to_string())?;
+    info!(target: "server", "Listening on http://{}", address);
+
+    for request in server.incoming_requests() {
+        let config = config.clone();
+        let default_config = default_config.clone();
+
+        let port = socket_addr.port();
+
+        thread::spawn(move || answer_request(request, port, &config, &default_config, use_cache));
+    }
+
+    Ok(())
+}
+
+fn answer_request(
+    mut request: Request,
+    port: u16,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) {
+    debug!(target: "server", "{} {}", request.method(), request.url());
+
+    let path = request.url().split('?').next().unwrap_or_default().to_string();
+    let host = get_header(&request, "Host");
+
+    let (status, body) = if !is_loopback_host(host.as_deref(), port) {
+        (403, error_body("Host header has to name the loopback address and the port"))
+    } else if path != "/query" {
+        (404, error_body("not found"))
+    } else if request.method() != &Method::Post {
+        (405, error_body("only POST is supported"))
+    } else {
+        let content_type = get_header(&request, "Content-Type");
+
+        let mut request_body = String::new();
+        let query = match request.as_reader().read_to_string(&mut request_body) {
+            Ok(_) => get_query(content_type.as_deref(), &request_body),
+            Err(err) => Err(err.to_string()),
+        };
+
+        match query {
+            Ok(query) => {
+                let mut results = vec![];
+                match run_query(
+                    &query,
+                    OutputFormat::Json,
+                    &mut results,
+                    config,
+                    default_config,
+                    use_cache,
+                ) {
+                    Ok(()) => (200, results),
+                    Err(err) => (400, error_body(&err)),
+                }
+            }
+            Err(err) => (400, error_body(&err)),
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(content_type);
+
+    if let Err(err) = request.respond(response) {
+        debug!(target: "server", "Could not send response: {}", err);
+    }
+}
+
+fn get_header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str().to_string())
+}
+
+/// Checks that the `Host` header is `localhost` or a loopback IP with the port listened on
+fn is_loopback_host(host: Option<&str>, port: u16) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) if !host_port.contains(']') => (name, host_port.parse::<u16>().ok()),
+        _ => (host, Some(80)),
+    };
+    if host_port != Some(port) {
+        return false;
+    }
+
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn error_body(err: &str) -> Vec<u8> {
+    serde_json::json!({ "error": err }).to_string().into_bytes()
+}
+
+/// Takes the query from the JSON body, other content types are refused
+/// since browsers send them from any web page without asking
+fn get_query(content_type: Option<&str>, body: &str) -> Result<String, String> {
+    let is_json = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Err(String::from("Content-Type application/json expected"));
+    }
+
+    let body: serde_json::Value = serde_json::from_str(body).map_err(|err| err.to_string())?;
+    match body.get("query").and_then(|query| query.as_str()) {
+        Some(query) => Ok(query.to_string()),
+        None => Err(String::from("query expected in the request body")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_query() {
+        assert_eq!(
+            get_query(Some("application/json; charset=utf-8"), r#"{"query": "name, size from /tmp"}"#),
+            Ok(String::from("name, size from /tmp"))
+        );
+        assert!(get_query(Some("text/plain"), r#"{"query": "name from /tmp"}"#).is_err());
+        assert!(get_query(Some("application/x-www-form-urlencoded"), "query=name").is_err());
+        assert!(get_query(None, r#"{"query": "name from /tmp"}"#).is_err());
+        assert!(get_query(Some("application/json"), r#"{"q": "name from /tmp"}"#).is_err());
+    }
+
+    #[test]
+    fn test_loopback_host() {
+        assert!(is_loopback_host(Some("127.0.0.1:8080"), 8080));
+        assert!(is_loopback_host(Some("localhost:8080"), 8080));
+        assert!(is_loopback_host(Some("[::1]:8080"), 8080));
+        assert!(is_loopback_host(Some("localhost"), 80));
+        assert!(!is_loopback_host(Some("localhost"), 8080));
+        assert!(!is_loopback_host(Some("127.0.0.1:8081"), 8080));
+        assert!(!is_loopback_host(Some("attacker.example:8080"), 8080));
+        assert!(!is_loopback_host(Some("[::1]"), 8080));
+        assert!(!is_loopback_host(None, 8080));
+    }
+
+    #[test]
+    fn test_loopback_only() {
+        let config = Config::default();
+        assert!(serve_http("0.0.0.0:0", &config, &config, false).is_err());
+        assert!(serve_http("localhost", &config, &config, false).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Handles the server mode started with `--serve`.
+//!
+//! Every connection sends a single query terminated with a newline
+//! and gets the results back as newline-delimited JSON, then the connection is closed.
+//! Every query is searched from scratch, only the results cache of `--cache` is shared between them.
+//! The HTTP mode started with `--http` lives in the `http` submodule.
+
+#[cfg(feature = "http")]
+pub mod http;
+
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+
+use log::{debug, info};
+
+use crate::cache;
+use crate::config::Config;
+use crate::parser::Parser;
+use crate::query::{OutputFormat, OutputSink};
+use crate::searcher::Searcher;
+
+/// Listens on the localhost TCP address or on the unix socket path, answering queries until killed
+pub fn serve(
+    address: &str,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) -> Result<(), String> {
+    if let Ok(socket_addr) = address.parse::<SocketAddr>() {
+        check_loopback(&socket_addr)?;
+
+        let listener = TcpListener::bind(socket_addr).map_err(|err| err.to_string())?;
+        info!(target: "server", "Listening on {}", address);
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => spawn_handler(stream, config, default_config, use_cache),
+                Err(err) => debug!(target: "server", "Could not accept connection: {}", err),
+            }
+        }
+
+        return Ok(());
+    }
+
+    serve_unix_socket(address, config, default_config, use_cache)
+}
+
+/// Queries reveal the files and their contents, so they are answered on the local machine only
+fn check_loopback(socket_addr: &SocketAddr) -> Result<(), String> {
+    match socket_addr.ip().is_loopback() {
+        true => Ok(()),
+        false => Err(format!("{} is not a loopback address", socket_addr)),
+    }
+}
+
+#[cfg(unix)]
+fn serve_unix_socket(
+    path: &str,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) -> Result<(), String> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // A socket left over by a server that is gone would make binding fail
+    let is_socket = std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket());
+    if is_socket {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("{} is already in use", path));
+        }
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    let listener = UnixListener::bind(path).map_err(|err| err.to_string())?;
+
+    // Content functions reveal what is inside the files, so only the owner may connect
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|err| err.to_string())?;
+    info!(target: "server", "Listening on {}", path);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => spawn_handler(stream, config, default_config, use_cache),
+            Err(err) => debug!(target: "server", "Could not accept connection: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix_socket(
+    path: &str,
+    _config: &Config,
+    _default_config: &Config,
+    _use_cache: bool,
+) -> Result<(), String> {
+    Err(format!(
+        "{} is not a TCP address, unix sockets are not supported on this platform",
+        path
+    ))
+}
+
+fn spawn_handler<S: Read + Write + Send + 'static>(
+    stream: S,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) {
+    let config = config.clone();
+    let default_config = default_config.clone();
+
+    thread::spawn(move || answer_query(stream, &config, &default_config, use_cache));
+}
+
+fn answer_query<S: Read + Write>(
+    mut stream: S,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) {
+    let mut line = String::new();
+    if BufReader::new(&mut stream).read_line(&mut line).is_err() {
+        return;
+    }
+
+    let line = line.trim();
+    if line.is_empty() {
+        return;
+    }
+
+    debug!(target: "server", "Received query: {}", line);
+
+    if let Err(err) = run_query(
+        line,
+        OutputFormat::Ndjson,
+        &mut stream,
+        config,
+        default_config,
+        use_cache,
+    ) {
+        let _ = writeln!(stream, "{}", serde_json::json!({ "error": err }));
+    }
+}
+
+/// Runs the query writing its results in the given format, returns the error if the query is invalid
+fn run_query<W: Write>(
+    query_text: &str,
+    output_format: OutputFormat,
+    mut output: W,
+    config: &Config,
+    default_config: &Config,
+    use_cache: bool,
+) -> Result<(), String> {
+    let mut parser = Parser::new();
+    if let Some(ref default_root_options) = config.default_root_options {
+        parser.set_default_root_options(default_root_options)?;
+    }
+    if let Some(ref star_columns) = config.star_columns {
+        parser.set_star_columns(star_columns)?;
+    }
+
+    let mut query = parser.parse(vec![config.expand_constants(query_text)?])?;
+    query.output_format = output_format;
+    query.reorder_conditions();
+
+    if query.explain {
+        return Err(String::from("EXPLAIN is not supported by the server"));
+    }
+
+    // Results only go back to the client, never to files on the server
+    if query.output_sink != OutputSink::default() || query.partition.is_some() {
+        return Err(String::from("Output files and compression are not supported by the server"));
+    }
+
+    // Clients may only look at the files, never change them
+    if query.action.is_some() {
+        return Err(String::from("Actions are not supported by the server"));
+    }
+
+    let plan = query.compile()?;
+
+    let cache_key = match use_cache {
+        true => cache::get_cache_key(&query, config, false),
+        false => None,
+    };
+
+    let cache_ttl = cache::get_cache_ttl(config, default_config);
+    if let Some(results) = cache_key.as_deref().and_then(|key| cache::get_cached_results(key, cache_ttl)) {
+        let _ = output.write_all(&results);
+        return Ok(());
+    }
+
+    let mut searcher = Searcher::new(&query, config, default_config, false);
+    searcher.set_plan(&plan);
+    searcher.redirect_output(Box::new(BufWriter::new(&mut output)));
+    if cache_key.is_some() {
+        searcher.capture_output();
+    }
+
+    if let Err(err) = searcher.list_search_results() {
+        debug!(target: "server", "Could not send results: {}", err);
+        return Ok(());
+    }
+
+    if let Some(cache_key) = cache_key {
+        if searcher.error_count == 0 && searcher.content_skipped_count == 0 {
+            if let Some(results) = searcher.take_captured_output() {
+                cache::store_results(&cache_key, &results);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+    use std::io::Cursor;
+
+    /// In-memory connection with the query to read and a buffer for the response
+    struct Connection {
+        request: Cursor<Vec<u8>>,
+        response: Vec<u8>,
+    }
+
+    impl Read for Connection {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.request.read(buf)
+        }
+    }
+
+    impl Write for Connection {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.response.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn ask(query: &str) -> String {
+        let mut connection = Connection {
+            request: Cursor::new(format!("{}\n", query).into_bytes()),
+            response: vec![],
+        };
+        let config = Config::default();
+        answer_query(&mut connection, &config, &config, false);
+
+        String::from_utf8(connection.response).unwrap()
+    }
+
+    #[test]
+    fn test_answer_query() {
+        let dir = TempDir::new("server");
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "bb").unwrap();
+
+        let results = ask(&format!("name, size from {} order by name", dir.display()));
+        let error = ask("name from where");
+
+        assert_eq!(
+            results,
+            "{\"Name\":\"a.txt\",\"Size\":1}\n{\"Name\":\"b.txt\",\"Size\":2}\n"
+        );
+        assert!(error.starts_with("{\"error\":"));
+    }
+
+    #[test]
+    fn test_actions_rejected() {
+        let error = ask("path from /nonexistent where is_file delete");
+        assert_eq!(error, "{\"error\":\"Actions are not supported by the server\"}\n");
+    }
+}
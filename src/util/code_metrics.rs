@@ -0,0 +1,268 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::util::{get_extension, open_file, DeadlineReader};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct CodeMetrics {
+    pub code_lines: usize,
+    pub comment_lines: usize,
+    pub blank_lines: usize,
+}
+
+/// Comment syntax of a language: line comment prefixes, block comment delimiters,
+/// and the quotes of string literals, comment delimiters within them are skipped
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    quotes: &'static [char],
+}
+
+const C_LIKE: CommentSyntax = CommentSyntax { line: &["//"], block: &[("/*", "*/")], quotes: &['"', '\'', '`'] };
+const CSS: CommentSyntax = CommentSyntax { line: &[], block: &[("/*", "*/")], quotes: &['"', '\''] };
+const HASH: CommentSyntax = CommentSyntax { line: &["#"], block: &[], quotes: &['"', '\''] };
+const PHP: CommentSyntax = CommentSyntax { line: &["//", "#"], block: &[("/*", "*/")], quotes: &['"', '\''] };
+const DASH: CommentSyntax = CommentSyntax { line: &["--"], block: &[], quotes: &['"'] };
+const LUA: CommentSyntax = CommentSyntax { line: &["--"], block: &[("--[[", "]]")], quotes: &['"', '\''] };
+const HASKELL: CommentSyntax = CommentSyntax { line: &["--"], block: &[("{-", "-}")], quotes: &['"'] };
+const SQL: CommentSyntax = CommentSyntax { line: &["--"], block: &[("/*", "*/")], quotes: &['\'', '"'] };
+const SEMICOLON: CommentSyntax = CommentSyntax { line: &[";"], block: &[], quotes: &['"'] };
+const PERCENT: CommentSyntax = CommentSyntax { line: &["%"], block: &[], quotes: &[] };
+const MARKUP: CommentSyntax = CommentSyntax { line: &[], block: &[("<!--", "-->")], quotes: &[] };
+const BASIC: CommentSyntax = CommentSyntax { line: &["'"], block: &[], quotes: &['"'] };
+const PASCAL: CommentSyntax = CommentSyntax { line: &["//"], block: &[("{", "}"), ("(*", "*)")], quotes: &['\''] };
+const JULIA: CommentSyntax = CommentSyntax { line: &["#"], block: &[("#=", "=#")], quotes: &['"'] };
+const NIM: CommentSyntax = CommentSyntax { line: &["#"], block: &[("#[", "]#")], quotes: &['"'] };
+
+fn get_comment_syntax(extension: &str) -> Option<&'static CommentSyntax> {
+    let syntax = match extension {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "cs" | "d" | "java" | "js"
+        | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "go" | "rs" | "swift" | "kt" | "kts" | "scala"
+        | "dart" | "groovy" | "sol" | "vala" | "zig" | "ceylon" | "scss" | "less" | "jsp" => &C_LIKE,
+        "css" => &CSS,
+        "py" | "rb" | "sh" | "bash" | "zsh" | "fish" | "pl" | "pm" | "r" | "tcl" | "yaml" | "yml"
+        | "toml" | "cmake" | "ps1" | "ex" | "exs" | "coffee" | "mk" => &HASH,
+        "php" => &PHP,
+        "ada" | "adb" | "ads" | "elm" => &DASH,
+        "lua" => &LUA,
+        "hs" => &HASKELL,
+        "sql" => &SQL,
+        "clj" | "cljs" | "lisp" | "el" | "scm" | "asm" => &SEMICOLON,
+        "erl" | "hrl" | "tex" => &PERCENT,
+        "html" | "htm" | "xml" | "xhtml" | "svg" => &MARKUP,
+        "vb" | "bas" => &BASIC,
+        "pas" => &PASCAL,
+        "jl" => &JULIA,
+        "nim" => &NIM,
+        _ => return None,
+    };
+
+    Some(syntax)
+}
+
+/// Counts lines of code, comments, and blank lines of the source file, languages are told by the extension.
+/// Lines with both code and a comment count as code. Returns None for unknown languages.
+pub fn get_code_metrics(path: &Path, deadline: Option<Instant>) -> Option<CodeMetrics> {
+    let extension = get_extension(&path.to_string_lossy()).to_ascii_lowercase();
+    let syntax = get_comment_syntax(&extension)?;
+    let file = open_file(path).ok()?;
+
+    count_code_lines(DeadlineReader::new(file, deadline), syntax)
+}
+
+fn count_code_lines(reader: impl Read, syntax: &CommentSyntax) -> Option<CodeMetrics> {
+    let mut metrics = CodeMetrics::default();
+    // The closing delimiter of the block comment the current line starts in
+    let mut block_end: Option<&str> = None;
+
+    for line in BufReader::new(reader).split(b'\n') {
+        let line = line.ok()?;
+        let line = String::from_utf8_lossy(&line);
+        let mut rest = line.trim();
+
+        if rest.is_empty() {
+            metrics.blank_lines += 1;
+            continue;
+        }
+
+        let mut has_code = false;
+        let mut has_comment = false;
+
+        while !rest.is_empty() {
+            if let Some(end) = block_end {
+                has_comment = true;
+                match rest.find(end) {
+                    Some(pos) => {
+                        rest = rest[pos + end.len()..].trim_start();
+                        block_end = None;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+
+            match find_comment_start(rest, syntax) {
+                Some((0, None)) => {
+                    has_comment = true;
+                    break;
+                }
+                Some((0, Some((start, end)))) => {
+                    has_comment = true;
+                    rest = &rest[start.len()..];
+                    block_end = Some(end);
+                }
+                // Anything else is code, comments after it don't change that
+                Some((pos, Some((start, end)))) => {
+                    has_code = true;
+                    rest = &rest[pos + start.len()..];
+                    block_end = Some(end);
+                }
+                _ => {
+                    has_code = true;
+                    break;
+                }
+            }
+        }
+
+        if has_code {
+            metrics.code_lines += 1;
+        } else if has_comment {
+            metrics.comment_lines += 1;
+        }
+    }
+
+    Some(metrics)
+}
+
+/// Counts lines with any of the markers like TODO or FIXME, markers have to be separate words
+pub fn count_marked_lines(reader: impl Read, markers: &[&str]) -> Option<usize> {
+    let mut count = 0;
+
+    for line in BufReader::new(reader).split(b'\n') {
+        let line = line.ok()?;
+        let line = String::from_utf8_lossy(&line);
+
+        if markers.iter().any(|marker| contains_word(&line, marker)) {
+            count += 1;
+        }
+    }
+
+    Some(count)
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    line.match_indices(word).any(|(pos, _)| {
+        let before = line[..pos].chars().next_back();
+        let after = line[pos + word.len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
+
+type BlockDelimiters = (&'static str, &'static str);
+
+/// Finds the earliest comment within the line outside of string literals, along with the delimiters if it's a block comment.
+/// Block comments win over line comments starting at the same position, like `--[[` over `--` in Lua.
+fn find_comment_start(line: &str, syntax: &CommentSyntax) -> Option<(usize, Option<BlockDelimiters>)> {
+    let mut pos = 0;
+
+    while let Some(c) = line[pos..].chars().next() {
+        let rest = &line[pos..];
+
+        if let Some(&delimiters) = syntax.block.iter().find(|(start, _)| rest.starts_with(start)) {
+            return Some((pos, Some(delimiters)));
+        }
+        if syntax.line.iter().any(|prefix| rest.starts_with(prefix)) {
+            return Some((pos, None));
+        }
+
+        pos += match syntax.quotes.contains(&c) {
+            true => get_string_len(rest).unwrap_or(1),
+            false => c.len_utf8(),
+        };
+    }
+
+    None
+}
+
+/// Returns the length of the string literal the text starts with, quotes included.
+/// Quotes not closed within the line don't start a string, like the lifetimes of Rust or the apostrophes in YAML values.
+fn get_string_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    let (_, quote) = chars.next()?;
+
+    while let Some((pos, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return Some(pos + c.len_utf8());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn count(source: &str, extension: &str) -> CodeMetrics {
+        count_code_lines(source.as_bytes(), get_comment_syntax(extension).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_c_like() {
+        let source = "// header\n\n/* block\n   comment */\nint main() { /* inline */\n    return 0; // done\n}\n";
+        assert_eq!(
+            count(source, "c"),
+            CodeMetrics { code_lines: 3, comment_lines: 3, blank_lines: 1 }
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let source = "let g = \"src/*.rs\";\nlet q = '\"'; // quote\nfn f<'a>(s: &'a str) {} /* block\n*/\nlet e = \"\\\"/*\";\n";
+        assert_eq!(
+            count(source, "rs"),
+            CodeMetrics { code_lines: 4, comment_lines: 1, blank_lines: 0 }
+        );
+
+        let source = "url: \"http://example.com/#top\"\ntitle: it's # comment\n";
+        assert_eq!(
+            count(source, "yaml"),
+            CodeMetrics { code_lines: 2, comment_lines: 0, blank_lines: 0 }
+        );
+    }
+
+    #[test]
+    fn test_hash() {
+        let source = "#!/bin/sh\n# comment\n\necho hi # greet\n";
+        assert_eq!(
+            count(source, "sh"),
+            CodeMetrics { code_lines: 1, comment_lines: 2, blank_lines: 1 }
+        );
+    }
+
+    #[test]
+    fn test_lua_block() {
+        let source = "--[[ multi\nline ]] local x = 1\n-- line\nprint(x)\n";
+        assert_eq!(
+            count(source, "lua"),
+            CodeMetrics { code_lines: 2, comment_lines: 2, blank_lines: 0 }
+        );
+    }
+
+    #[test]
+    fn test_count_marked_lines() {
+        let source = "// TODO: split\nfn todo() {}\n// FIXME(user) and TODO\nlet TODOS = 1;\n";
+        assert_eq!(count_marked_lines(source.as_bytes(), &["TODO", "FIXME"]), Some(2));
+        assert_eq!(count_marked_lines(source.as_bytes(), &["HACK"]), Some(0));
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        assert!(get_comment_syntax("txt").is_none());
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::util::open_file;
+
+const SAMPLE_SIZE: u32 = 64;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ImageColors {
+    /// Most common color as `#rrggbb`
+    pub dominant_color: String,
+    /// Average perceived brightness from 0 (black) to 100 (white)
+    pub brightness: u8,
+}
+
+pub fn get_image_colors<T: AsRef<Path>>(path: T) -> Option<ImageColors> {
+    let reader = BufReader::new(open_file(path).ok()?);
+    let image = image::ImageReader::new(reader).with_guessed_format().ok()?.decode().ok()?;
+    let sample = image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgba8();
+
+    // Fully transparent pixels don't contribute to what the image looks like
+    compute_colors(
+        sample
+            .pixels()
+            .filter(|pixel| pixel.0[3] > 0)
+            .map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2]]),
+    )
+}
+
+fn compute_colors(pixels: impl Iterator<Item = [u8; 3]>) -> Option<ImageColors> {
+    // Colors are grouped into buckets with 3 bits per channel,
+    // the dominant color is the average of the most populated bucket
+    let mut buckets: HashMap<(u8, u8, u8), (u64, [u64; 3])> = HashMap::new();
+    let mut luminance_sum = 0.0;
+    let mut count = 0;
+
+    for [r, g, b] in pixels {
+        let bucket = buckets.entry((r >> 5, g >> 5, b >> 5)).or_default();
+        bucket.0 += 1;
+        bucket.1[0] += r as u64;
+        bucket.1[1] += g as u64;
+        bucket.1[2] += b as u64;
+
+        luminance_sum += 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+        count += 1;
+    }
+
+    let (_, (bucket_count, sums)) = buckets
+        .into_iter()
+        .max_by_key(|(key, (bucket_count, _))| (*bucket_count, *key))?;
+
+    let dominant_color = format!(
+        "#{:02x}{:02x}{:02x}",
+        sums[0] / bucket_count,
+        sums[1] / bucket_count,
+        sums[2] / bucket_count
+    );
+    let brightness = (luminance_sum / count as f64 / 255.0 * 100.0).round() as u8;
+
+    Some(ImageColors {
+        dominant_color,
+        brightness,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compute_colors, get_image_colors};
+    use std::error::Error;
+
+    #[test]
+    fn test_compute_colors() {
+        let pixels = vec![[0, 0, 0], [0, 0, 0], [0, 0, 0], [255, 255, 255]];
+        let colors = compute_colors(pixels.into_iter()).unwrap();
+
+        assert_eq!(colors.dominant_color, "#000000");
+        assert_eq!(colors.brightness, 25);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(compute_colors(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn test_image() -> Result<(), Box<dyn Error>> {
+        let path_string =
+            std::env::var("CARGO_MANIFEST_DIR")? + "/resources/test/" + "image/rust-logo-blk.png";
+        let colors = get_image_colors(path_string).unwrap();
+
+        assert_eq!(colors.dominant_color.len(), 7);
+        assert!(colors.brightness <= 100);
+        Ok(())
+    }
+}
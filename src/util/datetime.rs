@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use chrono::{Datelike, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use chrono::{Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike};
 use chrono_english::{parse_date_string, Dialect};
 use regex::Regex;
 
@@ -125,21 +125,24 @@ pub fn parse_datetime(s: &str) -> Result<(NaiveDateTime, NaiveDateTime), String>
     }
 }
 
-pub fn to_local_datetime(dt: &zip::DateTime) -> NaiveDateTime {
-    Local::now()
-        .naive_local()
-        .with_year(dt.year() as i32)
-        .unwrap()
-        .with_month(dt.month() as u32)
-        .unwrap()
-        .with_day(dt.day() as u32)
-        .unwrap()
-        .with_hour(dt.hour() as u32)
-        .unwrap()
-        .with_minute(dt.minute() as u32)
-        .unwrap()
-        .with_second(dt.second() as u32)
-        .unwrap()
+/// Dates like `today` or `2 hours ago` mean different times depending on when they're parsed
+pub fn is_relative_datetime(s: &str) -> bool {
+    !DATE_REGEX.is_match(s)
+}
+
+pub fn to_local_datetime(dt: &zip::DateTime) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(dt.year() as i32, dt.month() as u32, dt.day() as u32)?.and_hms_opt(
+        dt.hour() as u32,
+        dt.minute() as u32,
+        dt.second() as u32,
+    )
+}
+
+pub fn from_unix_timestamp(timestamp: i64) -> Option<NaiveDateTime> {
+    match Local.timestamp_opt(timestamp, 0) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.naive_local()),
+        LocalResult::None => None,
+    }
 }
 
 pub fn format_datetime(dt: &NaiveDateTime) -> String {
@@ -217,4 +220,12 @@ mod tests {
         assert_eq!(result.0, start);
         assert_eq!(result.1, finish);
     }
+
+    #[test]
+    fn test_to_local_datetime() {
+        let dt = zip::DateTime::from_date_and_time(2019, 12, 31, 23, 59, 58).unwrap();
+        let date = NaiveDate::from_ymd_opt(2019, 12, 31).unwrap();
+
+        assert_eq!(to_local_datetime(&dt), date.and_hms_opt(23, 59, 58));
+    }
 }
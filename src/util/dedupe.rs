@@ -0,0 +1,122 @@
+//! Detection of duplicate files and replacing them with links for `replace duplicates with ...`
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sha2::Digest;
+
+use crate::query::LinkKind;
+use crate::util::open_file;
+
+/// Remembers the files seen during the search to find the first one with the same contents
+#[derive(Default)]
+pub struct Deduplicator {
+    /// Files by size, with digests computed only once another file of the same size shows up
+    seen: HashMap<u64, Vec<(PathBuf, Option<String>)>>,
+    pub replaced: u64,
+    pub reclaimed: u64,
+}
+
+impl Deduplicator {
+    pub fn new() -> Deduplicator {
+        Deduplicator::default()
+    }
+
+    /// Returns the first file seen with the same contents, or remembers this one as the first
+    pub fn find_original(&mut self, path: &Path, size: u64) -> Option<PathBuf> {
+        let files = self.seen.entry(size).or_default();
+        if files.is_empty() {
+            files.push((path.to_path_buf(), None));
+            return None;
+        }
+
+        let digest = get_digest(path)?;
+        for (seen_path, seen_digest) in files.iter_mut() {
+            if seen_digest.is_none() {
+                *seen_digest = get_digest(seen_path);
+            }
+
+            if seen_digest.as_ref() == Some(&digest) {
+                return Some(seen_path.clone());
+            }
+        }
+
+        files.push((path.to_path_buf(), Some(digest)));
+        None
+    }
+}
+
+fn get_digest(path: &Path) -> Option<String> {
+    let mut file = open_file(path).ok()?;
+    let mut hasher = sha2::Sha256::new();
+    io::copy(&mut file, &mut hasher).ok()?;
+
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Checks if both paths are already hardlinks to the same file
+#[cfg(unix)]
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn is_same_file(_: &Path, _: &Path) -> bool {
+    false
+}
+
+/// Replaces the duplicate with a link to the original.
+/// The link is created next to the duplicate first, so the duplicate is never lost on failure.
+pub fn replace_with_link(duplicate: &Path, original: &Path, kind: LinkKind) -> io::Result<()> {
+    let file_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+    let tmp_path = duplicate.with_file_name(format!(".{}.fselect-link", file_name));
+
+    match kind {
+        LinkKind::Hard => fs::hard_link(original, &tmp_path)?,
+        LinkKind::Symbolic => {
+            let original = fs::canonicalize(original)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(original, &tmp_path)?;
+            #[cfg(windows)]
+            std::os::windows::fs::symlink_file(original, &tmp_path)?;
+        }
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, duplicate) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_dedupe() {
+        let dir = TempDir::new("dedupe");
+        fs::write(dir.join("a"), "same").unwrap();
+        fs::write(dir.join("b"), "diff").unwrap();
+        fs::write(dir.join("c"), "same").unwrap();
+
+        let mut deduplicator = Deduplicator::new();
+        assert_eq!(deduplicator.find_original(&dir.join("a"), 4), None);
+        assert_eq!(deduplicator.find_original(&dir.join("b"), 4), None);
+        assert_eq!(deduplicator.find_original(&dir.join("c"), 4), Some(dir.join("a")));
+
+        replace_with_link(&dir.join("c"), &dir.join("a"), LinkKind::Hard).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("c")).unwrap(), "same");
+        #[cfg(unix)]
+        assert!(is_same_file(&dir.join("a"), &dir.join("c")));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::util::dimensions::DimensionsExtractor;
+use crate::util::open_file;
 use crate::util::Dimensions;
 use imagesize::ImageError;
 use std::io;
+use std::io::BufReader;
 use std::path::Path;
 
 pub struct ImageDimensionsExtractor;
@@ -21,7 +23,8 @@ impl DimensionsExtractor for ImageDimensionsExtractor {
     }
 
     fn try_read_dimensions(&self, path: &Path) -> io::Result<Option<Dimensions>> {
-        let dimensions = imagesize::size(path).map_err(|err| match err {
+        let reader = BufReader::new(open_file(path)?);
+        let dimensions = imagesize::reader_size(reader).map_err(|err| match err {
             ImageError::NotSupported => {
                 io::Error::new(io::ErrorKind::InvalidInput, ImageError::NotSupported)
             }
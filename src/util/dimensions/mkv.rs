@@ -1,7 +1,7 @@
 use crate::util::dimensions::DimensionsExtractor;
+use crate::util::open_file;
 use crate::util::Dimensions;
 use matroska::MatroskaError;
-use std::fs::File;
 use std::io;
 use std::path::Path;
 
@@ -13,7 +13,7 @@ impl DimensionsExtractor for MkvDimensionsExtractor {
     }
 
     fn try_read_dimensions(&self, path: &Path) -> io::Result<Option<Dimensions>> {
-        let fd = File::open(path)?;
+        let fd = open_file(path)?;
         let matroska = matroska::Matroska::open(fd).map_err(|err| match err {
             MatroskaError::Io(io) => io,
             MatroskaError::UTF8(utf8) => io::Error::new(io::ErrorKind::InvalidData, utf8),
@@ -11,6 +11,8 @@ use mkv::MkvDimensionsExtractor;
 use mp4::Mp4DimensionsExtractor;
 use std::path::Path;
 
+use log::debug;
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Dimensions {
     pub width: usize,
@@ -36,7 +38,13 @@ pub fn get_dimensions<T: AsRef<Path>>(path: T) -> Option<Dimensions> {
     EXTRACTORS
         .iter()
         .find(|extractor| extractor.supports_ext(&extension.to_lowercase()))
-        .and_then(|extractor| extractor.try_read_dimensions(path_ref).unwrap_or_default())
+        .and_then(|extractor| match extractor.try_read_dimensions(path_ref) {
+            Ok(dimensions) => dimensions,
+            Err(err) => {
+                debug!(target: "extractors", "Could not read dimensions of {}: {}", path_ref.display(), err);
+                None
+            }
+        })
 }
 
 #[cfg(test)]
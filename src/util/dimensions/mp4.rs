@@ -1,6 +1,6 @@
 use crate::util::dimensions::DimensionsExtractor;
+use crate::util::open_file;
 use crate::util::Dimensions;
-use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::path::Path;
@@ -13,7 +13,7 @@ impl DimensionsExtractor for Mp4DimensionsExtractor {
     }
 
     fn try_read_dimensions(&self, path: &Path) -> io::Result<Option<Dimensions>> {
-        let mut fd = File::open(path)?;
+        let mut fd = open_file(path)?;
         let mut buf = Vec::new();
         let _ = fd.read_to_end(&mut buf)?;
         let mut c = io::Cursor::new(&buf);
@@ -1,9 +1,9 @@
 use mp3_metadata::MP3Metadata;
-use std::fs::File;
 use std::io;
 use std::path::Path;
 
 use crate::util::duration::DurationExtractor;
+use crate::util::open_file;
 use crate::util::Duration;
 
 use matroska::MatroskaError;
@@ -20,7 +20,7 @@ impl DurationExtractor for MkvDurationExtractor {
         path: &Path,
         _: &Option<MP3Metadata>,
     ) -> io::Result<Option<Duration>> {
-        let fd = File::open(path)?;
+        let fd = open_file(path)?;
         let matroska = matroska::Matroska::open(fd).map_err(|err| match err {
             MatroskaError::Io(io) => io,
             MatroskaError::UTF8(utf8) => io::Error::new(io::ErrorKind::InvalidData, utf8),
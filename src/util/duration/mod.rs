@@ -7,6 +7,7 @@ mod wav;
 
 use std::path::Path;
 
+use log::debug;
 use mp3_metadata::MP3Metadata;
 
 use mkv::MkvDurationExtractor;
@@ -45,9 +46,11 @@ pub fn get_duration<T: AsRef<Path>>(
     EXTRACTORS
         .iter()
         .find(|extractor| extractor.supports_ext(&extension.to_lowercase()))
-        .and_then(|extractor| {
-            extractor
-                .try_read_duration(path_ref, mp3_metadata)
-                .unwrap_or_default()
+        .and_then(|extractor| match extractor.try_read_duration(path_ref, mp3_metadata) {
+            Ok(duration) => duration,
+            Err(err) => {
+                debug!(target: "extractors", "Could not read duration of {}: {}", path_ref.display(), err);
+                None
+            }
         })
 }
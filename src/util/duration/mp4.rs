@@ -1,7 +1,7 @@
 use crate::util::duration::DurationExtractor;
+use crate::util::open_file;
 use crate::util::Duration;
 use mp3_metadata::MP3Metadata;
-use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::path::Path;
@@ -18,7 +18,7 @@ impl DurationExtractor for Mp4DurationExtractor {
         path: &Path,
         _: &Option<MP3Metadata>,
     ) -> io::Result<Option<Duration>> {
-        let mut fd = File::open(path)?;
+        let mut fd = open_file(path)?;
         let mut buf = Vec::new();
         let _ = fd.read_to_end(&mut buf)?;
         let mut c = io::Cursor::new(&buf);
@@ -0,0 +1,122 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::util::{get_extension, open_file};
+
+const HEADER_LEN: usize = 64;
+
+const PGP_ARMOR: &[u8] = b"-----BEGIN PGP MESSAGE-----";
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1\n";
+const AGE_ARMOR: &[u8] = b"-----BEGIN AGE ENCRYPTED FILE-----";
+const LUKS_HEADER: &[u8] = b"LUKS\xba\xbe";
+const ZIP_LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+const CFB_HEADER: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+const CFB_DIR_SEARCH_LEN: u64 = 4096;
+
+/// Detects encrypted files by their magic bytes: PGP messages, age files, LUKS volumes,
+/// zip archives with encrypted entries, and password-protected Office documents.
+/// Only the headers are read, so large files and volume images are cheap to check.
+pub fn is_encrypted(path: &Path) -> Option<bool> {
+    let mut file = open_file(path).ok()?;
+    let extension = get_extension(&path.to_string_lossy()).to_ascii_lowercase();
+
+    check_encrypted(&mut file, &extension)
+}
+
+fn check_encrypted<R: Read + Seek>(reader: &mut R, extension: &str) -> Option<bool> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    reader.by_ref().take(HEADER_LEN as u64).read_to_end(&mut header).ok()?;
+
+    if header.starts_with(PGP_ARMOR)
+        || header.starts_with(AGE_HEADER)
+        || header.starts_with(AGE_ARMOR)
+        || header.starts_with(LUKS_HEADER)
+    {
+        return Some(true);
+    }
+
+    if header.starts_with(ZIP_LOCAL_HEADER) {
+        // Bit 0 of the general purpose flag marks the entry as encrypted
+        return Some(header.len() > 6 && header[6] & 1 == 1);
+    }
+
+    if header.starts_with(CFB_HEADER) {
+        return Some(has_encrypted_package(reader, &header));
+    }
+
+    // Binary OpenPGP messages have no magic, so they're recognized by the extension
+    // and the first packet being an encrypted session key
+    if matches!(extension, "gpg" | "pgp") {
+        return Some(matches!(header.first(), Some(0x84..=0x87 | 0x8c..=0x8f | 0xc1 | 0xc3)));
+    }
+
+    Some(false)
+}
+
+/// Password-protected OOXML documents are stored in a compound file with the `EncryptedPackage` stream
+fn has_encrypted_package<R: Read + Seek>(reader: &mut R, header: &[u8]) -> bool {
+    if header.len() < 52 {
+        return false;
+    }
+
+    let sector_shift = u16::from_le_bytes([header[30], header[31]]) as u32;
+    let first_dir_sector = u32::from_le_bytes([header[48], header[49], header[50], header[51]]) as u64;
+    if !(7..=16).contains(&sector_shift) {
+        return false;
+    }
+
+    let offset = (first_dir_sector + 1) << sector_shift;
+    if reader.seek(SeekFrom::Start(offset)).is_err() {
+        return false;
+    }
+
+    let mut directory = Vec::new();
+    if reader.take(CFB_DIR_SEARCH_LEN).read_to_end(&mut directory).is_err() {
+        return false;
+    }
+
+    let stream_name: Vec<u8> = "EncryptedPackage"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+
+    directory.windows(stream_name.len()).any(|window| window == stream_name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::check_encrypted;
+
+    #[test]
+    fn test_magic_bytes() {
+        let pgp = b"-----BEGIN PGP MESSAGE-----\n\nhQEMA...";
+        assert_eq!(check_encrypted(&mut Cursor::new(pgp), "asc"), Some(true));
+
+        let age = b"age-encryption.org/v1\n-> X25519 abc\n";
+        assert_eq!(check_encrypted(&mut Cursor::new(age), "age"), Some(true));
+
+        let luks = b"LUKS\xba\xbe\x00\x02";
+        assert_eq!(check_encrypted(&mut Cursor::new(luks), "img"), Some(true));
+
+        let text = b"just some text";
+        assert_eq!(check_encrypted(&mut Cursor::new(text), "txt"), Some(false));
+    }
+
+    #[test]
+    fn test_zip() {
+        let encrypted = b"PK\x03\x04\x14\x00\x01\x00\x08\x00";
+        assert_eq!(check_encrypted(&mut Cursor::new(encrypted), "zip"), Some(true));
+
+        let plain = b"PK\x03\x04\x14\x00\x00\x00\x08\x00";
+        assert_eq!(check_encrypted(&mut Cursor::new(plain), "zip"), Some(false));
+    }
+
+    #[test]
+    fn test_binary_pgp() {
+        assert_eq!(check_encrypted(&mut Cursor::new(b"\x85\x01\x0c"), "gpg"), Some(true));
+        assert_eq!(check_encrypted(&mut Cursor::new(b"\x85\x01\x0c"), "bin"), Some(false));
+        assert_eq!(check_encrypted(&mut Cursor::new(b"\x99\x01\x0d"), "gpg"), Some(false));
+    }
+}
@@ -0,0 +1,62 @@
+//! Reading and setting inode flags, as shown by `lsattr` and changed by `chattr`
+
+use std::fs::{File, FileType, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use libc::c_int;
+
+pub const FS_IMMUTABLE_FL: c_int = 0x10;
+pub const FS_APPEND_FL: c_int = 0x20;
+
+/// Opens regular files and directories only, like `lsattr` does,
+/// since opening a device could arm a watchdog or rewind a tape, and the ioctl would go to its driver
+fn open(path: &Path, file_type: FileType) -> io::Result<File> {
+    if !file_type.is_file() && !file_type.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a regular file or directory",
+        ));
+    }
+
+    // Flags of symlinks can't be changed, and the file could be replaced with a pipe after the check
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_NOFOLLOW)
+        .open(path)
+}
+
+pub fn get_file_flags(path: &Path, file_type: FileType) -> io::Result<c_int> {
+    get_flags(&open(path, file_type)?)
+}
+
+fn get_flags(file: &File) -> io::Result<c_int> {
+    let mut flags: c_int = 0;
+
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(flags)
+}
+
+/// Sets and clears the flags, changing the immutable or append-only flags requires CAP_LINUX_IMMUTABLE
+pub fn update_file_flags(path: &Path, file_type: FileType, set: c_int, clear: c_int) -> io::Result<()> {
+    let file = open(path, file_type)?;
+    let flags = get_flags(&file)?;
+
+    let new_flags = (flags | set) & !clear;
+    if new_flags == flags {
+        return Ok(());
+    }
+
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), libc::FS_IOC_SETFLAGS, &new_flags) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Parsing of the `security.ima` extended attribute, where Linux IMA keeps the measurement of the file
+
+const IMA_XATTR_DIGEST: u8 = 0x01;
+const EVM_IMA_XATTR_DIGSIG: u8 = 0x03;
+const IMA_XATTR_DIGEST_NG: u8 = 0x04;
+
+/// Names of the hash algorithms by their IDs in the kernel's `enum hash_algo`
+const HASH_ALGOS: &[&str] = &[
+    "md4", "md5", "sha1", "rmd160", "sha256", "sha384", "sha512", "sha224", "rmd128", "rmd256",
+    "rmd320", "wp256", "wp384", "wp512", "tgr128", "tgr160", "tgr192", "sm3-256",
+    "streebog256", "streebog512",
+];
+
+/// Returns the digest as `ALGO:HEX`, or `ALGO:signed` if the attribute holds a signature instead,
+/// which doesn't include the digest
+pub fn parse_ima_xattr(xattr: &[u8]) -> Option<String> {
+    let (&xattr_type, rest) = xattr.split_first()?;
+
+    match xattr_type {
+        // Legacy format, always SHA-1
+        IMA_XATTR_DIGEST if rest.len() == 20 => Some(format!("sha1:{}", to_hex(rest))),
+        IMA_XATTR_DIGEST_NG => {
+            let (&algo, digest) = rest.split_first()?;
+            if digest.is_empty() {
+                return None;
+            }
+            Some(format!("{}:{}", HASH_ALGOS.get(algo as usize)?, to_hex(digest)))
+        }
+        // Version 2 signature header: version, hash algorithm, key ID, signature size
+        EVM_IMA_XATTR_DIGSIG => {
+            let algo = *rest.get(1)?;
+            Some(format!("{}:signed", HASH_ALGOS.get(algo as usize)?))
+        }
+        _ => None,
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_ima_xattr;
+
+    #[test]
+    fn test_parse_ima_xattr() {
+        let mut sha256 = vec![0x04, 0x04];
+        sha256.extend([0xab; 32]);
+        assert_eq!(parse_ima_xattr(&sha256), Some(format!("sha256:{}", "ab".repeat(32))));
+
+        let mut sha1 = vec![0x01];
+        sha1.extend([0x01; 20]);
+        assert_eq!(parse_ima_xattr(&sha1), Some(format!("sha1:{}", "01".repeat(20))));
+
+        let signature = [0x03, 0x02, 0x04, 0x12, 0x34, 0x56, 0x78, 0x01, 0x00, 0xff];
+        assert_eq!(parse_ima_xattr(&signature), Some(String::from("sha256:signed")));
+
+        assert_eq!(parse_ima_xattr(&[]), None);
+        assert_eq!(parse_ima_xattr(&[0x04, 0x04]), None);
+        assert_eq!(parse_ima_xattr(&[0x02, 0x00]), None);
+    }
+}
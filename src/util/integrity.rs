@@ -0,0 +1,127 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::util::open_file;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_SOI: &[u8] = b"\xff\xd8\xff";
+const JPEG_EOI: &[u8] = b"\xff\xd9";
+const JPEG_TAIL_LEN: u64 = 4096;
+
+/// Checks the structure of JPEG and PNG files without decoding the pixels:
+/// the chunks of a PNG file must lead up to IEND, and a JPEG file must end with the EOI marker.
+/// Returns None for other formats.
+pub fn is_corrupted_image(path: &Path) -> Option<bool> {
+    let mut file = open_file(path).ok()?;
+    check_image(&mut file)
+}
+
+fn check_image<R: Read + Seek>(reader: &mut R) -> Option<bool> {
+    let mut header = [0u8; 8];
+    let len = read_up_to(reader, &mut header)?;
+    let header = &header[..len];
+
+    if header.starts_with(PNG_SIGNATURE) {
+        Some(!has_png_end(reader))
+    } else if header.starts_with(JPEG_SOI) {
+        Some(!has_jpeg_end(reader)?)
+    } else {
+        None
+    }
+}
+
+/// Walks the chunks of the PNG file right after its signature until IEND
+fn has_png_end<R: Read + Seek>(reader: &mut R) -> bool {
+    let mut chunk_header = [0u8; 8];
+
+    loop {
+        if reader.read_exact(&mut chunk_header).is_err() {
+            return false;
+        }
+
+        if &chunk_header[4..] == b"IEND" {
+            return true;
+        }
+
+        let chunk_len = u32::from_be_bytes(chunk_header[..4].try_into().unwrap());
+        let mut crc = [0u8; 4];
+        if reader.seek(SeekFrom::Current(chunk_len as i64)).is_err()
+            || reader.read_exact(&mut crc).is_err()
+        {
+            return false;
+        }
+    }
+}
+
+/// Looks for the EOI marker at the end of the file, allowing the zero padding some writers add
+fn has_jpeg_end<R: Read + Seek>(reader: &mut R) -> Option<bool> {
+    let file_len = reader.seek(SeekFrom::End(0)).ok()?;
+    let tail_start = file_len.saturating_sub(JPEG_TAIL_LEN);
+    reader.seek(SeekFrom::Start(tail_start)).ok()?;
+
+    let mut tail = Vec::with_capacity((file_len - tail_start) as usize);
+    reader.read_to_end(&mut tail).ok()?;
+
+    let end = tail.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+
+    Some(tail[..end].ends_with(JPEG_EOI))
+}
+
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Option<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(_) => return None,
+        }
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::check_image;
+
+    fn png(chunks: &[(&[u8], &[u8])]) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (chunk_type, data) in chunks {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            png.extend_from_slice(chunk_type);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        png
+    }
+
+    #[test]
+    fn test_png() {
+        let complete = png(&[(b"IHDR", &[0; 13]), (b"IDAT", &[1, 2, 3]), (b"IEND", &[])]);
+        assert_eq!(check_image(&mut Cursor::new(&complete)), Some(false));
+
+        let truncated = &complete[..complete.len() - 16];
+        assert_eq!(check_image(&mut Cursor::new(truncated)), Some(true));
+    }
+
+    #[test]
+    fn test_jpeg() {
+        let complete = b"\xff\xd8\xff\xe0\x00\x10JFIF\x00\xff\xda\x01\x02\x03\xff\xd9";
+        assert_eq!(check_image(&mut Cursor::new(&complete[..])), Some(false));
+
+        let padded = [&complete[..], &[0, 0, 0]].concat();
+        assert_eq!(check_image(&mut Cursor::new(&padded)), Some(false));
+
+        let truncated = &complete[..complete.len() - 4];
+        assert_eq!(check_image(&mut Cursor::new(truncated)), Some(true));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        assert_eq!(check_image(&mut Cursor::new(b"GIF89a")), None);
+        assert_eq!(check_image(&mut Cursor::new(b"")), None);
+    }
+}
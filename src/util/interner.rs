@@ -0,0 +1,87 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
+use std::rc::Rc;
+
+/// Row kept until the results are ordered or aggregated, column names and repeated values are shared
+pub type BufferedRow = HashMap<Rc<str>, Rc<str>>;
+
+/// Row kept for ordering, column names with their formatted values in the order of the columns
+pub type OrderedRow = Vec<(Rc<str>, Rc<str>)>;
+
+/// Keeps a single copy of the strings repeated over the buffered rows
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+    /// Bytes taken by the kept strings, each counted once
+    bytes: usize,
+}
+
+impl Interner {
+    pub fn intern(&mut self, value: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(value) {
+            return Rc::clone(interned);
+        }
+
+        let interned: Rc<str> = Rc::from(value);
+        self.bytes += get_string_size(&interned);
+        self.strings.insert(Rc::clone(&interned));
+
+        interned
+    }
+
+    /// Number of distinct strings kept
+    pub fn count(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Estimates the memory taken by the kept strings
+    pub fn get_memory_usage(&self) -> usize {
+        self.bytes + self.strings.capacity() * size_of::<Rc<str>>()
+    }
+
+    /// Estimates the memory taken by the string, which is nothing more if it's kept here
+    pub fn get_size(&self, string: &Rc<str>) -> usize {
+        let is_interned = self
+            .strings
+            .get(string.as_ref())
+            .is_some_and(|interned| Rc::ptr_eq(interned, string));
+
+        match is_interned {
+            true => 0,
+            false => get_string_size(string),
+        }
+    }
+}
+
+/// Contents of the string and its reference counters
+fn get_string_size(string: &Rc<str>) -> usize {
+    string.len() + 2 * size_of::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::default();
+        let first = interner.intern("txt");
+        let second = interner.intern("txt");
+        let other = interner.intern("rs");
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(!Rc::ptr_eq(&first, &other));
+        assert_eq!(interner.count(), 2);
+    }
+
+    #[test]
+    fn test_size() {
+        let mut interner = Interner::default();
+        let interned = interner.intern("txt");
+        let copy: Rc<str> = Rc::from("txt");
+
+        assert_eq!(interner.get_size(&interned), 0);
+        assert_eq!(interner.get_size(&copy), 3 + 2 * size_of::<usize>());
+        assert!(interner.get_memory_usage() >= 3);
+    }
+}
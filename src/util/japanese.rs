@@ -1,3 +1,5 @@
+use wana_kana::ConvertJapanese;
+
 pub fn contains_japanese(s: &str) -> bool {
     s.chars().any(wana_kana::utils::is_char_japanese)
 }
@@ -17,3 +19,29 @@ pub fn contains_kana(s: &str) -> bool {
 pub fn contains_kanji(s: &str) -> bool {
     s.chars().any(wana_kana::utils::is_char_kanji)
 }
+
+/// Converts hiragana and katakana to romaji, leaving everything else as is
+pub fn kana_to_romaji(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut kana = String::new();
+
+    for c in s.chars() {
+        if wana_kana::utils::is_char_kana(c) {
+            kana.push(c);
+            continue;
+        }
+
+        if !kana.is_empty() {
+            result.push_str(&kana.as_str().to_romaji());
+            kana.clear();
+        }
+
+        result.push(c);
+    }
+
+    if !kana.is_empty() {
+        result.push_str(&kana.as_str().to_romaji());
+    }
+
+    result
+}
@@ -0,0 +1,97 @@
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::util::open_file;
+
+/// License tags and texts are expected near the beginning of the file
+const HEADER_LEN: u64 = 8 * 1024;
+
+static SPDX_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"SPDX-License-Identifier:\s*([A-Za-z0-9.+\-]+(?:\s+(?:AND|OR|WITH)\s+[A-Za-z0-9.+\-]+)*)").unwrap()
+});
+
+/// SPDX identifiers along with the phrases, all of which are found in the license text
+const LICENSE_TEXTS: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("LGPL-3.0", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("LGPL-2.1", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"]),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    ("BSD-3-Clause", &["Redistribution and use in source and binary forms", "Neither the name"]),
+    ("BSD-2-Clause", &["Redistribution and use in source and binary forms"]),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    ("ISC", &["Permission to use, copy, modify, and/or distribute this software for any purpose"]),
+    ("Unlicense", &["This is free and unencumbered software released into the public domain"]),
+    ("CC0-1.0", &["CC0 1.0 Universal"]),
+];
+
+/// Detects the license of the file from its SPDX tag, or from the text of LICENSE, COPYING, and README files.
+/// Returns the SPDX identifier of the license.
+pub fn get_license(path: &Path) -> Option<String> {
+    let file = open_file(path).ok()?;
+    let mut header = Vec::new();
+    file.take(HEADER_LEN).read_to_end(&mut header).ok()?;
+    let header = String::from_utf8_lossy(&header);
+
+    let file_name = path.file_name()?.to_string_lossy().to_ascii_uppercase();
+
+    detect_license(&header, is_license_file(&file_name))
+}
+
+fn is_license_file(file_name: &str) -> bool {
+    ["LICENSE", "LICENCE", "COPYING", "README"]
+        .iter()
+        .any(|prefix| file_name.starts_with(prefix))
+}
+
+fn detect_license(header: &str, is_license_file: bool) -> Option<String> {
+    if let Some(caps) = SPDX_REGEX.captures(header) {
+        return Some(caps[1].to_string());
+    }
+
+    if !is_license_file {
+        return None;
+    }
+
+    // Line breaks and indentation of the license texts vary
+    let text = header.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    LICENSE_TEXTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map(|(license, _)| license.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::detect_license;
+
+    #[test]
+    fn test_spdx() {
+        let source = "// SPDX-License-Identifier: MIT OR Apache-2.0\n\nfn main() {}\n";
+        assert_eq!(detect_license(source, false), Some(String::from("MIT OR Apache-2.0")));
+
+        let header = "/* SPDX-License-Identifier: GPL-2.0-only */\n#include <stdio.h>\n";
+        assert_eq!(detect_license(header, false), Some(String::from("GPL-2.0-only")));
+    }
+
+    #[test]
+    fn test_license_texts() {
+        let mit = "MIT License\n\nCopyright (c) 2024 Someone\n\nPermission is hereby granted, free\nof charge, to any person";
+        assert_eq!(detect_license(mit, true), Some(String::from("MIT")));
+        assert_eq!(detect_license(mit, false), None);
+
+        let apache = "                                 Apache License\n                           Version 2.0, January 2004\n";
+        assert_eq!(detect_license(apache, true), Some(String::from("Apache-2.0")));
+
+        let lgpl = "GNU LESSER GENERAL PUBLIC LICENSE\n                       Version 3, 29 June 2007\n";
+        assert_eq!(detect_license(lgpl, true), Some(String::from("LGPL-3.0")));
+
+        assert_eq!(detect_license("# Project\n\nSome description", true), None);
+    }
+}
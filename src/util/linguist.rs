@@ -0,0 +1,355 @@
+//! Detects generated and vendored files the way GitHub Linguist does.
+//! `linguist-generated` and `linguist-vendored` attributes from the .gitattributes files of the repository
+//! take precedence, otherwise the well-known paths, lock files, and markers in the first lines of the file decide.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::util::open_file;
+use crate::util::project::is_git_root;
+
+/// Directories of dependencies, bundled libraries, and build output
+static VENDORED_PATHS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(^|/)(node_modules|bower_components|[Vv]endors?|deps|dist|cache|Godeps/_workspace)/",
+        r"|(^|/)(3rd|[Tt]hird)[-_]?[Pp]arty/",
+        r"|(^|/)[Ee]xtern(als?)?/",
+        r"|(^|/)\.yarn/(releases|plugins|sdks|versions|cache)/",
+        r"|(^|/)jquery([^.]*)\.js$",
+        r"|(^|/)bootstrap([^/.]*)(\..*)?\.(js|css|less|scss|styl)$",
+    ))
+    .unwrap()
+});
+
+/// Lock files, minified and compiled sources, and source maps
+static GENERATED_PATHS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(^|/)(package-lock\.json|npm-shrinkwrap\.json|yarn\.lock|pnpm-lock\.yaml|bun\.lockb",
+        r"|Cargo\.lock|composer\.lock|go\.sum|Gopkg\.lock|glide\.lock|deno\.lock",
+        r"|poetry\.lock|pdm\.lock|Pipfile\.lock|flake\.lock)$",
+        r"|\.min\.(js|css)$|\.(js|css)\.map$",
+        r"|\.pb\.(go|cc|h)$|_pb2(_grpc)?\.py$|\.designer\.cs$",
+    ))
+    .unwrap()
+});
+
+/// Comments left by code generators at the top of the files
+static GENERATED_MARKERS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(do not edit|@generated|<auto-generated|auto-?generated by|automatically generated)")
+        .unwrap()
+});
+
+/// Generated files are recognized by the first lines only
+const HEAD_SIZE: u64 = 1024;
+const HEAD_LINES: usize = 5;
+
+/// Line of .gitattributes setting or unsetting the linguist attributes of the matching files
+struct Rule {
+    regex: Regex,
+    /// Matched against the name of the file instead of its path relative to the .gitattributes file
+    name_only: bool,
+    generated: Option<bool>,
+    vendored: Option<bool>,
+}
+
+/// Rules of a single .gitattributes file
+struct RuleSet {
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+/// Rules applying to the files of a directory, from the root of the repository down to the directory itself
+#[derive(Default)]
+struct DirRules {
+    root: Option<PathBuf>,
+    rule_sets: Vec<Rc<RuleSet>>,
+}
+
+/// Linguist attributes of a file, nothing if they are not set by .gitattributes
+#[derive(Default)]
+struct Attributes {
+    generated: Option<bool>,
+    vendored: Option<bool>,
+}
+
+/// Keeps the rules of the .gitattributes files of the visited directories
+#[derive(Default)]
+pub struct Linguist {
+    dirs: HashMap<PathBuf, Rc<DirRules>>,
+}
+
+impl Linguist {
+    /// Checks if the file is generated by its attributes or by its name,
+    /// nothing if that depends on the first lines of the file
+    pub fn is_generated_by_path(&mut self, path: &Path) -> Option<bool> {
+        let (attributes, relative_path) = self.get_attributes(path);
+
+        match attributes.generated {
+            Some(generated) => Some(generated),
+            None if GENERATED_PATHS.is_match(&relative_path) => Some(true),
+            None => None,
+        }
+    }
+
+    pub fn is_vendored(&mut self, path: &Path) -> bool {
+        let (attributes, relative_path) = self.get_attributes(path);
+
+        attributes
+            .vendored
+            .unwrap_or_else(|| VENDORED_PATHS.is_match(&relative_path))
+    }
+
+    /// Returns the attributes of the file along with its path relative to the root of the repository
+    fn get_attributes(&mut self, path: &Path) -> (Attributes, String) {
+        let mut attributes = Attributes::default();
+
+        let dir_rules = match path.parent() {
+            Some(dir) => self.get_dir_rules(dir),
+            None => Rc::default(),
+        };
+
+        for rule_set in &dir_rules.rule_sets {
+            let Ok(relative_path) = path.strip_prefix(&rule_set.dir) else {
+                continue;
+            };
+            let relative_path = to_slashes(relative_path);
+            let name = relative_path.rsplit('/').next().unwrap_or_default();
+
+            for rule in &rule_set.rules {
+                let subject = if rule.name_only { name } else { relative_path.as_str() };
+                if rule.regex.is_match(subject) {
+                    attributes.generated = rule.generated.or(attributes.generated);
+                    attributes.vendored = rule.vendored.or(attributes.vendored);
+                }
+            }
+        }
+
+        let relative_path = match dir_rules.root {
+            Some(ref root) => path.strip_prefix(root).map_or_else(|_| to_slashes(path), to_slashes),
+            None => to_slashes(path),
+        };
+
+        (attributes, relative_path)
+    }
+
+    fn get_dir_rules(&mut self, dir: &Path) -> Rc<DirRules> {
+        if let Some(dir_rules) = self.dirs.get(dir) {
+            return Rc::clone(dir_rules);
+        }
+
+        let dir_rules = match (is_git_root(dir), dir.parent()) {
+            (true, _) => DirRules {
+                root: Some(dir.to_path_buf()),
+                rule_sets: read_rule_set(dir).into_iter().collect(),
+            },
+            (false, Some(parent)) => {
+                let parent_rules = self.get_dir_rules(parent);
+                match parent_rules.root {
+                    // .gitattributes outside of repositories mean nothing
+                    Some(ref root) => DirRules {
+                        root: Some(root.clone()),
+                        rule_sets: parent_rules
+                            .rule_sets
+                            .iter()
+                            .cloned()
+                            .chain(read_rule_set(dir))
+                            .collect(),
+                    },
+                    None => DirRules::default(),
+                }
+            }
+            (false, None) => DirRules::default(),
+        };
+
+        let dir_rules = Rc::new(dir_rules);
+        self.dirs.insert(dir.to_path_buf(), Rc::clone(&dir_rules));
+
+        dir_rules
+    }
+}
+
+/// Checks the first lines of the file for the comments of code generators
+pub fn has_generated_marker(path: &Path) -> bool {
+    let mut head = vec![];
+    let read = open_file(path).and_then(|file| file.take(HEAD_SIZE).read_to_end(&mut head));
+    if read.is_err() {
+        return false;
+    }
+
+    String::from_utf8_lossy(&head)
+        .lines()
+        .take(HEAD_LINES)
+        .any(|line| GENERATED_MARKERS.is_match(line))
+}
+
+fn read_rule_set(dir: &Path) -> Option<Rc<RuleSet>> {
+    let contents = fs::read_to_string(dir.join(".gitattributes")).ok()?;
+    let rules: Vec<Rule> = contents.lines().filter_map(parse_rule).collect();
+
+    match rules.is_empty() {
+        true => None,
+        false => Some(Rc::new(RuleSet {
+            dir: dir.to_path_buf(),
+            rules,
+        })),
+    }
+}
+
+/// Parses a line like `docs/** linguist-generated` or `vendor/** -linguist-vendored`,
+/// lines without linguist attributes are left out
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut parts = line.split_whitespace();
+    let pattern = parts.next()?;
+
+    let mut generated = None;
+    let mut vendored = None;
+    for attribute in parts {
+        let (name, value) = match attribute.split_once('=') {
+            Some((name, "false")) => (name, false),
+            Some((name, _)) => (name, true),
+            None => match attribute.strip_prefix('-') {
+                Some(name) => (name, false),
+                None => (attribute, true),
+            },
+        };
+
+        match name {
+            "linguist-generated" => generated = Some(value),
+            "linguist-vendored" => vendored = Some(value),
+            _ => {}
+        }
+    }
+
+    // Patterns of directories don't apply to the files inside them
+    if (generated.is_none() && vendored.is_none()) || pattern.ends_with('/') {
+        return None;
+    }
+
+    let name_only = !pattern.contains('/');
+    let regex = convert_glob(pattern.trim_start_matches('/'))?;
+
+    Some(Rule {
+        regex,
+        name_only,
+        generated,
+        vendored,
+    })
+}
+
+/// Converts the .gitattributes pattern to a regex, `*` doesn't cross the directories but `**` does
+fn convert_glob(glob: &str) -> Option<Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                match chars.peek() {
+                    Some('/') => {
+                        chars.next();
+                        pattern.push_str("(.*/)?");
+                    }
+                    _ => pattern.push_str(".*"),
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '[' => {
+                pattern.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    pattern.push('^');
+                }
+            }
+            ']' => pattern.push(']'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+fn to_slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_heuristics() {
+        let dir = TempDir::new("linguist");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join("node_modules/left-pad")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/parser.go"), "// Code generated by goyacc. DO NOT EDIT.\n").unwrap();
+        fs::write(dir.join("src/main.go"), "package main\n").unwrap();
+
+        let mut linguist = Linguist::default();
+        let results = (
+            linguist.is_vendored(&dir.join("node_modules/left-pad/index.js")),
+            linguist.is_vendored(&dir.join("src/main.go")),
+            linguist.is_generated_by_path(&dir.join("Cargo.lock")),
+            linguist.is_generated_by_path(&dir.join("static/app.min.js")),
+            linguist.is_generated_by_path(&dir.join("src/main.go")),
+            has_generated_marker(&dir.join("src/parser.go")),
+            has_generated_marker(&dir.join("src/main.go")),
+        );
+
+        assert_eq!(results, (true, false, Some(true), Some(true), None, true, false));
+    }
+
+    #[test]
+    fn test_gitattributes() {
+        let dir = TempDir::new("gitattributes");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join("api/gen")).unwrap();
+        fs::create_dir_all(dir.join("vendor/own")).unwrap();
+        let attributes = [
+            "# Linguist",
+            "api/gen/** linguist-generated",
+            "*.snap linguist-generated=true",
+            "vendor/own/** -linguist-vendored",
+        ];
+        fs::write(dir.join(".gitattributes"), attributes.join("\n")).unwrap();
+        fs::write(dir.join("api/.gitattributes"), "*.snap linguist-generated=false text\n").unwrap();
+
+        let mut linguist = Linguist::default();
+        let results = (
+            linguist.is_generated_by_path(&dir.join("api/gen/client.ts")),
+            linguist.is_generated_by_path(&dir.join("tests/output.snap")),
+            linguist.is_generated_by_path(&dir.join("api/output.snap")),
+            linguist.is_vendored(&dir.join("vendor/own/lib.c")),
+            linguist.is_vendored(&dir.join("vendor/other/lib.c")),
+        );
+
+        assert_eq!(results, (Some(true), Some(true), Some(false), false, true));
+    }
+
+    #[test]
+    fn test_convert_glob() {
+        let regex = convert_glob("docs/**/*.md").unwrap();
+        assert!(regex.is_match("docs/a.md"));
+        assert!(regex.is_match("docs/a/b/c.md"));
+        assert!(!regex.is_match("src/docs/a.md"));
+
+        let regex = convert_glob("*.[ch]").unwrap();
+        assert!(regex.is_match("main.c"));
+        assert!(!regex.is_match("main.cpp"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Reading of external file lists used as roots, e.g. `from 'files.csv' as csv(path, size, modified)`
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+
+use crate::field::Field;
+use crate::function::{Variant, VariantType};
+use crate::query::{Manifest, ManifestFormat};
+use crate::util::{get_extension, parse_datetime, parse_filesize, str_to_bool};
+
+/// Values of one manifest record by the fields they map onto
+pub type ManifestRow = HashMap<Field, String>;
+
+pub fn read_manifest(path: &Path, manifest: &Manifest) -> Result<Vec<ManifestRow>, String> {
+    let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+
+    match manifest.format {
+        ManifestFormat::Csv => read_csv(&content, &manifest.fields),
+        ManifestFormat::Json => read_json(&content, &manifest.fields),
+    }
+}
+
+/// Columns map onto the given fields by position, a header row repeating them is skipped.
+/// Without the fields the header row names the columns, unknown ones are ignored.
+fn read_csv(content: &str, fields: &[Field]) -> Result<Vec<ManifestRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let mut columns: Vec<Option<Field>> = fields.iter().copied().map(Some).collect();
+    let mut rows = vec![];
+
+    for (pos, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| err.to_string())?;
+
+        if pos == 0 {
+            let header: Vec<Option<Field>> = record
+                .iter()
+                .map(|name| Field::from_str(name.trim()).ok())
+                .collect();
+
+            if columns.is_empty() {
+                columns = header;
+                continue;
+            }
+
+            if header == columns {
+                continue;
+            }
+        }
+
+        let row = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(field, value)| field.map(|field| (field, value.to_string())))
+            .collect();
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Accepts an array of objects or one object per line,
+/// keys map onto fields of the same name and may be restricted to the given fields
+fn read_json(content: &str, fields: &[Field]) -> Result<Vec<ManifestRow>, String> {
+    let values: Vec<serde_json::Value> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(content).map_err(|err| err.to_string())?
+    } else {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.to_string())?
+    };
+
+    let mut rows = vec![];
+
+    for value in values {
+        let serde_json::Value::Object(object) = value else {
+            return Err(String::from("Manifest records must be JSON objects"));
+        };
+
+        let mut row = HashMap::new();
+        for (key, value) in object {
+            let Ok(field) = Field::from_str(&key) else {
+                continue;
+            };
+
+            if !fields.is_empty() && !fields.contains(&field) {
+                continue;
+            }
+
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(s) => s,
+                value => value.to_string(),
+            };
+            row.insert(field, value);
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Typed value of the field for the record.
+/// Name, extension and directory are derived from the path when the manifest lacks them.
+pub fn get_row_value(row: &ManifestRow, field: &Field) -> Variant {
+    let value = match row.get(field) {
+        Some(value) => value.clone(),
+        None => match (field, row.get(&Field::Path)) {
+            (Field::Name, Some(path)) => file_name(path),
+            (Field::Extension, Some(path)) => get_extension(&file_name(path)),
+            (Field::Directory, Some(path)) => Path::new(path)
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            (Field::AbsPath, Some(path)) => path.clone(),
+            _ => return Variant::empty(VariantType::String),
+        },
+    };
+
+    if field.is_numeric_field() {
+        if let Ok(int) = value.parse::<i64>() {
+            return Variant::from_int(int);
+        }
+        if let Ok(float) = value.parse::<f64>() {
+            return Variant::from_float(float);
+        }
+        if let Some(size) = parse_filesize(&value) {
+            return Variant::from_int(size as i64);
+        }
+    } else if field.is_datetime_field() {
+        if let Ok((dt, _)) = parse_datetime(&value) {
+            return Variant::from_datetime(dt);
+        }
+        if let Some(dt) = value.parse::<i64>().ok().and_then(|ts| DateTime::from_timestamp(ts, 0)) {
+            return Variant::from_datetime(dt.with_timezone(&Local).naive_local());
+        }
+    } else if field.is_boolean_field() {
+        if let Some(bool) = str_to_bool(&value) {
+            return Variant::from_bool(bool);
+        }
+    }
+
+    Variant::from_string(&value)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_csv() {
+        let fields = vec![Field::Path, Field::Size];
+        let rows = read_csv("path,size\n/a/b.txt,10\n/a/c.rs,2k\n", &fields).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][&Field::Path], "/a/b.txt");
+
+        let rows = read_csv("/a/b.txt,10\n", &fields).unwrap();
+        assert_eq!(rows.len(), 1);
+
+        let rows = read_csv("size,owner,path\n10,root,/a/b.txt\n", &[]).unwrap();
+        assert_eq!(rows[0][&Field::Size], "10");
+        assert_eq!(rows[0][&Field::Path], "/a/b.txt");
+        assert_eq!(rows[0].len(), 2);
+    }
+
+    #[test]
+    fn test_read_json() {
+        let rows = read_json(r#"[{"path": "/a/b.txt", "size": 10, "bogus": 1}]"#, &[]).unwrap();
+        assert_eq!(rows[0][&Field::Size], "10");
+        assert_eq!(rows[0].len(), 2);
+
+        let ndjson = "{\"path\": \"/a/b.txt\", \"size\": 10}\n\n{\"path\": \"/a/c.rs\"}\n";
+        let rows = read_json(ndjson, &[Field::Path]).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(!rows[0].contains_key(&Field::Size));
+
+        assert!(read_json("[1, 2]", &[]).is_err());
+    }
+
+    #[test]
+    fn test_get_row_value() {
+        let mut row = HashMap::new();
+        row.insert(Field::Path, String::from("/a/b.txt"));
+        row.insert(Field::Size, String::from("2k"));
+        row.insert(Field::Modified, String::from("2024-01-02 03:04:05"));
+        row.insert(Field::IsHidden, String::from("yes"));
+
+        assert_eq!(get_row_value(&row, &Field::Name).to_string(), "b.txt");
+        assert_eq!(get_row_value(&row, &Field::Extension).to_string(), "txt");
+        assert_eq!(get_row_value(&row, &Field::Directory).to_string(), "/a");
+        assert_eq!(get_row_value(&row, &Field::Size).to_int(), 2048);
+        assert!(matches!(
+            get_row_value(&row, &Field::Modified).get_type(),
+            VariantType::DateTime
+        ));
+        assert!(get_row_value(&row, &Field::IsHidden).to_bool());
+        assert_eq!(get_row_value(&row, &Field::Mime).to_string(), "");
+    }
+}
@@ -0,0 +1,150 @@
+//! Classification of files by their detected MIME type, used for the `is_*` fields
+//! when `classify_by = "mime"` is configured
+
+const ARCHIVE_MIMES: &[&str] = &[
+    "application/gzip",
+    "application/vnd.rar",
+    "application/x-7z-compressed",
+    "application/x-bzip",
+    "application/x-bzip2",
+    "application/x-compressed-tar",
+    "application/x-gzip",
+    "application/x-lzip",
+    "application/x-rar",
+    "application/x-rar-compressed",
+    "application/x-tar",
+    "application/x-xz",
+    "application/zip",
+    "application/zstd",
+];
+
+const BOOK_MIMES: &[&str] = &[
+    "application/epub+zip",
+    "application/pdf",
+    "application/vnd.amazon.ebook",
+    "application/vnd.ms-htmlhelp",
+    "application/x-fictionbook+xml",
+    "application/x-mobipocket-ebook",
+    "image/vnd.djvu",
+];
+
+const DOC_MIMES: &[&str] = &[
+    "application/msword",
+    "application/oxps",
+    "application/pdf",
+    "application/rtf",
+    "application/vnd.ms-access",
+    "application/vnd.ms-excel",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.ms-xpsdocument",
+    "text/rtf",
+];
+
+const FONT_MIMES: &[&str] = &[
+    "application/font-sfnt",
+    "application/font-woff",
+    "application/vnd.ms-fontobject",
+    "application/x-font-otf",
+    "application/x-font-ttf",
+];
+
+const SOURCE_MIMES: &[&str] = &[
+    "application/javascript",
+    "application/typescript",
+    "application/x-php",
+    "application/x-ruby",
+    "text/javascript",
+    "text/rust",
+    "text/x-asm",
+    "text/x-c",
+    "text/x-c++hdr",
+    "text/x-c++src",
+    "text/x-chdr",
+    "text/x-csharp",
+    "text/x-csrc",
+    "text/x-go",
+    "text/x-java",
+    "text/x-kotlin",
+    "text/x-lua",
+    "text/x-pascal",
+    "text/x-perl",
+    "text/x-python",
+    "text/x-python3",
+    "text/x-rust",
+    "text/x-scala",
+    "text/x-tcl",
+];
+
+/// MIME types that say nothing about the kind of the file,
+/// such files are classified by their extensions instead
+pub fn is_generic_mime(mime: &str) -> bool {
+    mime.starts_with("inode/")
+        || mime == "application/octet-stream"
+        || mime == "text/plain"
+        || mime == "all/all"
+        || mime == "all/allfiles"
+}
+
+pub fn is_archive_mime(mime: &str) -> bool {
+    ARCHIVE_MIMES.contains(&mime)
+}
+
+pub fn is_audio_mime(mime: &str) -> bool {
+    mime.starts_with("audio/")
+}
+
+pub fn is_book_mime(mime: &str) -> bool {
+    BOOK_MIMES.contains(&mime)
+}
+
+pub fn is_doc_mime(mime: &str) -> bool {
+    DOC_MIMES.contains(&mime)
+        || mime.starts_with("application/vnd.oasis.opendocument.")
+        || mime.starts_with("application/vnd.openxmlformats-officedocument.")
+        || mime.starts_with("application/vnd.ms-excel.")
+        || mime.starts_with("application/vnd.ms-powerpoint.")
+        || mime.starts_with("application/vnd.ms-word.")
+}
+
+pub fn is_font_mime(mime: &str) -> bool {
+    mime.starts_with("font/") || FONT_MIMES.contains(&mime)
+}
+
+pub fn is_image_mime(mime: &str) -> bool {
+    mime.starts_with("image/") && mime != "image/vnd.djvu"
+}
+
+pub fn is_source_mime(mime: &str) -> bool {
+    SOURCE_MIMES.contains(&mime)
+}
+
+pub fn is_video_mime(mime: &str) -> bool {
+    mime.starts_with("video/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mime_classification() {
+        assert!(is_image_mime("image/png"));
+        assert!(is_image_mime("image/svg+xml"));
+        assert!(!is_image_mime("image/vnd.djvu"));
+        assert!(is_book_mime("image/vnd.djvu"));
+
+        assert!(is_video_mime("video/x-matroska"));
+        assert!(is_audio_mime("audio/flac"));
+        assert!(is_archive_mime("application/x-7z-compressed"));
+        assert!(is_font_mime("font/woff2"));
+        assert!(is_source_mime("text/x-rust"));
+
+        assert!(is_doc_mime("application/vnd.openxmlformats-officedocument.wordprocessingml.document"));
+        assert!(is_doc_mime("application/pdf"));
+        assert!(is_book_mime("application/pdf"));
+
+        assert!(is_generic_mime("application/octet-stream"));
+        assert!(is_generic_mime("inode/directory"));
+        assert!(!is_generic_mime("image/png"));
+    }
+}
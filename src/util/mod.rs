@@ -1,12 +1,44 @@
 #[cfg(target_os = "linux")]
 pub(crate) mod capabilities;
+pub mod code_metrics;
+#[cfg(feature = "image-colors")]
+pub mod color;
 mod datetime;
+pub mod dedupe;
 pub mod dimensions;
 pub mod duration;
+pub mod encryption;
+#[cfg(target_os = "linux")]
+pub(crate) mod file_flags;
 mod glob;
+#[cfg(target_os = "linux")]
+pub(crate) mod ima;
+mod interner;
+pub mod integrity;
+pub mod license;
 pub(crate) mod japanese;
+pub mod linguist;
+pub mod manifest;
+pub mod mime;
+#[cfg(target_os = "linux")]
+pub(crate) mod open_files;
 mod top_n;
+pub mod project;
+pub mod structured_data;
+#[cfg(test)]
+pub(crate) mod temp_dir;
+#[cfg(feature = "secrets")]
+pub mod secrets;
+#[cfg(target_os = "linux")]
+pub(crate) mod statx;
+pub mod thumbnail;
+pub mod torrent;
+pub mod trash;
+pub(crate) mod transliteration;
+pub mod vcs;
+pub mod video;
 mod wbuf;
+pub mod xpath;
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -23,7 +55,9 @@ use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::LazyLock;
+use std::time::{Instant, SystemTime};
 use chrono::{Datelike, Local, Timelike};
 use mp3_metadata::MP3Metadata;
 use regex::Regex;
@@ -31,18 +65,25 @@ use sha1::Digest;
 
 pub use self::datetime::format_date;
 pub use self::datetime::format_datetime;
+pub use self::datetime::from_unix_timestamp;
+pub use self::datetime::is_relative_datetime;
 pub use self::datetime::parse_datetime;
 pub use self::datetime::to_local_datetime;
 pub use self::glob::convert_glob_to_pattern;
 pub use self::glob::convert_like_to_pattern;
 pub use self::glob::is_glob;
+pub use self::interner::{BufferedRow, Interner, OrderedRow};
 pub use self::top_n::TopN;
 pub use self::wbuf::WritableBuffer;
 use crate::expr::Expr;
 #[cfg(windows)]
 use crate::mode;
+#[cfg(feature = "image-colors")]
+pub use color::ImageColors;
 pub use dimensions::Dimensions;
 pub use duration::Duration;
+pub use torrent::TorrentInfo;
+pub use video::VideoMetadata;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub struct Criteria<T>
@@ -72,6 +113,11 @@ where
         }
     }
 
+    /// Value of the most significant field
+    pub fn first_value(&self) -> Option<&T> {
+        self.values.first()
+    }
+
     #[inline]
     fn cmp_at(&self, other: &Self, i: usize) -> Ordering
     where
@@ -107,10 +153,10 @@ where
     where
         T: Ord,
     {
-        let a = parse_filesize(&self.values[i].to_string()).unwrap_or(0);
-        let b = parse_filesize(&other.values[i].to_string()).unwrap_or(0);
+        let a = parse_sort_number(&self.values[i].to_string());
+        let b = parse_sort_number(&other.values[i].to_string());
 
-        a.cmp(&b)
+        a.total_cmp(&b)
     }
 
     #[inline]
@@ -171,7 +217,7 @@ pub fn path_error_message(p: &Path, e: io::Error) {
 }
 
 pub fn error_message(source: &str, description: &str) {
-    eprint!("{}: {}", source, description);
+    eprintln!("{}: {}", source, description);
 }
 
 pub fn error_exit(source: &str, description: &str) -> ! {
@@ -186,6 +232,35 @@ pub fn get_extension(s: &str) -> String {
     }
 }
 
+/// Parses a numeric value for sorting, either a plain number (possibly fractional or negative)
+/// or a file size with units
+fn parse_sort_number(s: &str) -> f64 {
+    s.parse::<f64>()
+        .ok()
+        .or_else(|| parse_filesize(s).map(|size| size as f64))
+        .unwrap_or(0.0)
+}
+
+/// Parses a time interval like `10s`, `5min`, `2h`, `1d`, `2w`, or `1y`, plain numbers are seconds
+pub fn parse_interval(s: &str) -> Option<std::time::Duration> {
+    let s = s.trim().to_ascii_lowercase();
+    let units_pos = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (value, units) = s.split_at(units_pos);
+    let value = value.parse::<f64>().ok()?;
+
+    let multiplier = match units.trim() {
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+        "h" | "hour" | "hours" => 3600.0,
+        "d" | "day" | "days" => 86400.0,
+        "w" | "week" | "weeks" => 604800.0,
+        "y" | "year" | "years" => 31536000.0,
+        _ => return None,
+    };
+
+    std::time::Duration::try_from_secs_f64(value * multiplier).ok()
+}
+
 pub fn parse_filesize(s: &str) -> Option<u64> {
     let string = s.to_string().to_ascii_lowercase().replace(" ", "");
     let length = string.len();
@@ -424,6 +499,42 @@ pub fn str_to_bool(val: &str) -> Option<bool> {
     }
 }
 
+/// Escapes the bytes of the path as `%XX` for freedesktop.org `.trashinfo` files, except letters, digits, and `-_.~/`
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn percent_encode(value: &[u8]) -> String {
+    value
+        .iter()
+        .map(|&byte| match byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            true => (byte as char).to_string(),
+            false => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes of URLs and freedesktop.org `.trashinfo` files, invalid escapes are kept as is
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'%' if pos + 2 < bytes.len()
+                && bytes[pos + 1].is_ascii_hexdigit()
+                && bytes[pos + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[pos + 1..pos + 3]).unwrap();
+                result.push(u8::from_str_radix(hex, 16).unwrap());
+                pos += 2;
+            }
+            byte => result.push(byte),
+        }
+        pos += 1;
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
 pub fn capitalize(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -481,11 +592,48 @@ pub fn format_absolute_path(path_buf: &Path) -> String {
     let path = format!("{}", path_buf.to_string_lossy());
 
     #[cfg(windows)]
-    let path = path.replace("\\\\?\\", "");
+    let path = strip_verbatim_prefix(&path);
 
     path
 }
 
+/// Strips the `\\?\` prefix of Windows long paths,
+/// so that `\\?\C:\dir` becomes `C:\dir` and `\\?\UNC\server\share` becomes `\\server\share`
+pub fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{}", rest);
+    }
+
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) => rest.to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Adds the `\\?\` prefix of Windows long paths to absolute paths with a drive letter and to UNC paths.
+/// Relative paths and paths that already have the prefix are returned as is.
+pub fn to_verbatim_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") {
+        return path.to_string();
+    }
+
+    if let Some(rest) = path.strip_prefix(r"\\") {
+        return format!(r"\\?\UNC\{}", rest);
+    }
+
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+
+    if has_drive {
+        return format!(r"\\?\{}", path.replace('/', "\\"));
+    }
+
+    path.to_string()
+}
+
 pub fn get_metadata(entry: &DirEntry, follow_symlinks: bool) -> Option<Metadata> {
     let metadata = match follow_symlinks {
         false => symlink_metadata(entry.path()),
@@ -499,15 +647,37 @@ pub fn get_metadata(entry: &DirEntry, follow_symlinks: bool) -> Option<Metadata>
     None
 }
 
-pub fn get_mp3_metadata(entry: &DirEntry) -> Option<MP3Metadata> {
-    match mp3_metadata::read_from_file(entry.path()) {
-        Ok(mp3_meta) => Some(mp3_meta),
-        _ => None,
+/// Follows the chain of symlinks, relative targets are resolved against the directory of the link.
+/// Returns nothing for broken chains and the ones longer than `max_links`.
+pub fn resolve_symlink(path: &Path, max_links: u32) -> Option<PathBuf> {
+    let mut path = path.to_path_buf();
+    let mut links = 0;
+
+    while symlink_metadata(&path).ok()?.file_type().is_symlink() {
+        if links >= max_links {
+            return None;
+        }
+
+        let target = fs::read_link(&path).ok()?;
+        path = match path.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+        links += 1;
     }
+
+    Some(path)
+}
+
+pub fn get_mp3_metadata(entry: &DirEntry) -> Option<MP3Metadata> {
+    let mut buf = vec![];
+    open_file(entry.path()).ok()?.read_to_end(&mut buf).ok()?;
+
+    mp3_metadata::read_from_slice(&buf).ok()
 }
 
 pub fn get_exif_metadata(entry: &DirEntry) -> Option<HashMap<String, String>> {
-    if let Ok(file) = File::open(entry.path()) {
+    if let Ok(file) = open_file(entry.path()) {
         if let Ok(reader) = exif::Reader::new().read_from_container(&mut BufReader::new(&file)) {
             let mut exif_info = HashMap::new();
 
@@ -592,8 +762,11 @@ fn parse_location_string(s: String, location_ref: String, modifier_value: &str)
     Err(())
 }
 
+/// Listed among the extensions of a file category, makes the files starting with a shebang belong to it
+pub const SHEBANG_MARKER: &str = "#!";
+
 pub fn is_shebang(path: &PathBuf) -> bool {
-    if let Ok(file) = File::open(path) {
+    if let Ok(file) = open_file(path) {
         let mut buf_reader = BufReader::new(file);
         let mut buf = vec![0; 2];
         if buf_reader.read_exact(&mut buf).is_ok() {
@@ -632,82 +805,391 @@ pub fn is_hidden(file_name: &str, metadata: &Option<Metadata>, archive_mode: boo
     }
 }
 
-pub fn get_line_count(entry: &DirEntry) -> Option<usize> {
-    if let Ok(file) = File::open(entry.path()) {
-        let mut reader = BufReader::with_capacity(1024 * 32, file);
-        let mut count = 0;
+/// Device names reserved by Windows regardless of the extension
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Checks if the file name can't be used on Windows: either it's a reserved device name
+/// like `CON` or `nul.txt`, or it ends with a dot or a space
+pub fn has_windows_reserved_name(file_name: &str) -> bool {
+    if file_name == "." || file_name == ".." {
+        return false;
+    }
 
-        loop {
-            let len = {
-                if let Ok(buf) = reader.fill_buf() {
-                    if buf.is_empty() {
-                        break;
-                    }
+    if file_name.ends_with('.') || file_name.ends_with(' ') {
+        return true;
+    }
 
-                    count += bytecount::count(buf, b'\n');
-                    buf.len()
-                } else {
-                    return None;
-                }
-            };
+    let stem = file_name.split('.').next().unwrap_or_default().trim_end();
+
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Checks if the file name contains C0 or C1 control characters such as newlines or escapes
+pub fn has_control_chars(file_name: &str) -> bool {
+    file_name.chars().any(char::is_control)
+}
+
+static NOATIME: AtomicBool = AtomicBool::new(false);
+
+/// Makes files opened with `open_file` keep their access time where permitted
+pub fn set_noatime(noatime: bool) {
+    NOATIME.store(noatime, AtomicOrdering::Relaxed);
+}
+
+/// Opens the file for reading its contents, with O_NOATIME if enabled and permitted.
+/// Anything but regular files is refused before it's opened, since opening a device could have side effects,
+/// and FIFOs or sockets could block the reads.
+pub fn open_file<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref();
+    if !fs::metadata(path)?.is_file() {
+        return Err(not_regular_file());
+    }
+
+    // The file could have been replaced after the check, opening it doesn't wait for a FIFO writer though
+    let file = open_nonblocking(path)?;
+    if !file.metadata()?.is_file() {
+        return Err(not_regular_file());
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        // Regular files are read as usual once they're known to be such
+        let fd = file.as_raw_fd();
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if flags == -1 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(file)
+}
+
+fn not_regular_file() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "not a regular file")
+}
+
+/// Opening a FIFO without a writer would wait for one, unless it's opened with O_NONBLOCK
+fn open_nonblocking(path: &Path) -> io::Result<File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        // O_NOATIME is only allowed for the file owner or with CAP_FOWNER
+        #[cfg(target_os = "linux")]
+        if NOATIME.load(AtomicOrdering::Relaxed) {
+            match fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK | libc::O_NOATIME)
+                .open(path)
+            {
+                Err(e) if e.raw_os_error() == Some(libc::EPERM) => {}
+                result => return result,
+            }
+        }
+
+        fs::OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(path)
+    }
+
+    #[cfg(not(unix))]
+    File::open(path)
+}
+
+/// Files from this size on are read through in bigger chunks
+const LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const READ_BUFFER_SIZE: usize = 32 * 1024;
+const LARGE_READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Returns the size of the buffer to read the whole file with.
+/// On Linux the kernel is also told that large files are read sequentially, so it reads further ahead.
+fn prepare_sequential_read(file: &File) -> usize {
+    let is_large = file.metadata().is_ok_and(|metadata| metadata.len() >= LARGE_FILE_SIZE);
+    if !is_large {
+        return READ_BUFFER_SIZE;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        // It's only a hint, nothing changes if it's ignored
+        unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_SEQUENTIAL) };
+    }
+
+    LARGE_READ_BUFFER_SIZE
+}
 
-            reader.consume(len);
+/// Opens a file for reading its contents.
+/// Gzip and Zstandard compressed files are transparently decompressed.
+pub fn open_content_reader(path: &Path, deadline: Option<Instant>) -> io::Result<Box<dyn Read>> {
+    let file = open_file(path)?;
+    let buffer_size = prepare_sequential_read(&file);
+    let extension = get_extension(&path.to_string_lossy()).to_ascii_lowercase();
+
+    let reader: Box<dyn Read> = match extension.as_str() {
+        "gz" | "gzip" => Box::new(flate2::read::MultiGzDecoder::new(BufReader::with_capacity(
+            buffer_size,
+            file,
+        ))),
+        "zst" | "zstd" => Box::new(zstd::Decoder::with_buffer(BufReader::with_capacity(
+            buffer_size,
+            file,
+        ))?),
+        _ if buffer_size > READ_BUFFER_SIZE => Box::new(BufReader::with_capacity(buffer_size, file)),
+        _ => Box::new(file),
+    };
+
+    Ok(Box::new(DeadlineReader::new(reader, deadline)))
+}
+
+/// Errors that may go away on their own, mostly seen on network filesystems
+pub fn is_transient_error(err: &io::Error) -> bool {
+    if matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ResourceBusy
+    ) {
+        return true;
+    }
+
+    #[cfg(unix)]
+    if let Some(code) = err.raw_os_error() {
+        return matches!(code, libc::EIO | libc::EAGAIN | libc::ESTALE | libc::ETIMEDOUT);
+    }
+
+    // ERROR_UNEXP_NET_ERR, ERROR_NETNAME_DELETED, ERROR_SEM_TIMEOUT
+    #[cfg(windows)]
+    if let Some(code) = err.raw_os_error() {
+        return matches!(code, 59 | 64 | 121);
+    }
+
+    false
+}
+
+/// Runs the operation again after transient errors, waiting twice as long before each next attempt.
+/// Returns the result of the last attempt and the number of retries made.
+pub fn retry_io<T>(
+    retries: u32,
+    delay: std::time::Duration,
+    mut operation: impl FnMut() -> io::Result<T>,
+) -> (io::Result<T>, u32) {
+    let mut attempt = 0;
+
+    loop {
+        match operation() {
+            Err(err) if attempt < retries && is_transient_error(&err) => {
+                std::thread::sleep(delay.saturating_mul(1 << attempt.min(16)));
+                attempt += 1;
+            }
+            result => return (result, attempt),
         }
+    }
+}
+
+/// Reader that fails with `ErrorKind::TimedOut` once the deadline is reached
+pub struct DeadlineReader<R> {
+    inner: R,
+    deadline: Option<Instant>,
+}
 
-        return Some(count);
+impl<R> DeadlineReader<R> {
+    pub fn new(inner: R, deadline: Option<Instant>) -> DeadlineReader<R> {
+        DeadlineReader { inner, deadline }
+    }
+}
+
+impl<R: Read> Read for DeadlineReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "content timeout exceeded"));
+            }
+        }
+
+        self.inner.read(buf)
+    }
+}
+
+pub fn get_line_count(entry: &DirEntry, deadline: Option<Instant>) -> Option<usize> {
+    if let Ok(file) = open_content_reader(&entry.path(), deadline) {
+        return count_lines(file);
     }
 
     None
 }
 
-pub fn get_sha1_file_hash(entry: &DirEntry) -> String {
-    if let Ok(mut file) = File::open(entry.path()) {
-        let mut hasher = sha1::Sha1::new();
-        if io::copy(&mut file, &mut hasher).is_ok() {
-            let hash = hasher.finalize();
-            return format!("{:x}", hash);
-        }
+pub fn count_lines(reader: impl Read) -> Option<usize> {
+    let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, reader);
+    let mut count = 0;
+
+    loop {
+        let len = {
+            if let Ok(buf) = reader.fill_buf() {
+                if buf.is_empty() {
+                    break;
+                }
+
+                count += bytecount::count(buf, b'\n');
+                buf.len()
+            } else {
+                return None;
+            }
+        };
+
+        reader.consume(len);
+    }
+
+    Some(count)
+}
+
+pub fn get_sha1_file_hash(entry: &DirEntry, deadline: Option<Instant>) -> String {
+    if let Ok(file) = open_file(entry.path()) {
+        let buffer_size = prepare_sequential_read(&file);
+        let mut reader = BufReader::with_capacity(buffer_size, DeadlineReader::new(file, deadline));
+        return get_sha1_hash(&mut reader);
     }
 
     String::new()
 }
 
-pub fn get_sha256_file_hash(entry: &DirEntry) -> String {
-    if let Ok(mut file) = File::open(entry.path()) {
-        let mut hasher = sha2::Sha256::new();
-        if io::copy(&mut file, &mut hasher).is_ok() {
-            let hash = hasher.finalize();
-            return format!("{:x}", hash);
-        }
+pub fn get_sha1_hash(reader: &mut dyn Read) -> String {
+    let mut hasher = sha1::Sha1::new();
+    if io::copy(reader, &mut hasher).is_ok() {
+        let hash = hasher.finalize();
+        return format!("{:x}", hash);
     }
 
     String::new()
 }
 
-pub fn get_sha512_file_hash(entry: &DirEntry) -> String {
-    if let Ok(mut file) = File::open(entry.path()) {
-        let mut hasher = sha2::Sha512::new();
-        if io::copy(&mut file, &mut hasher).is_ok() {
-            let hash = hasher.finalize();
-            return format!("{:x}", hash);
-        }
+pub fn get_sha256_file_hash(entry: &DirEntry, deadline: Option<Instant>) -> String {
+    if let Ok(file) = open_file(entry.path()) {
+        let buffer_size = prepare_sequential_read(&file);
+        let mut reader = BufReader::with_capacity(buffer_size, DeadlineReader::new(file, deadline));
+        return get_sha256_hash(&mut reader);
     }
 
     String::new()
 }
 
-pub fn get_sha3_512_file_hash(entry: &DirEntry) -> String {
-    if let Ok(mut file) = File::open(entry.path()) {
-        let mut hasher = sha3::Sha3_512::new();
-        if io::copy(&mut file, &mut hasher).is_ok() {
-            let hash = hasher.finalize();
-            return format!("{:x}", hash);
-        }
+pub fn get_sha256_hash(reader: &mut dyn Read) -> String {
+    let mut hasher = sha2::Sha256::new();
+    if io::copy(reader, &mut hasher).is_ok() {
+        let hash = hasher.finalize();
+        return format!("{:x}", hash);
+    }
+
+    String::new()
+}
+
+pub fn get_sha512_file_hash(entry: &DirEntry, deadline: Option<Instant>) -> String {
+    if let Ok(file) = open_file(entry.path()) {
+        let buffer_size = prepare_sequential_read(&file);
+        let mut reader = BufReader::with_capacity(buffer_size, DeadlineReader::new(file, deadline));
+        return get_sha512_hash(&mut reader);
+    }
+
+    String::new()
+}
+
+pub fn get_sha512_hash(reader: &mut dyn Read) -> String {
+    let mut hasher = sha2::Sha512::new();
+    if io::copy(reader, &mut hasher).is_ok() {
+        let hash = hasher.finalize();
+        return format!("{:x}", hash);
+    }
+
+    String::new()
+}
+
+pub fn get_sha3_512_file_hash(entry: &DirEntry, deadline: Option<Instant>) -> String {
+    if let Ok(file) = open_file(entry.path()) {
+        let buffer_size = prepare_sequential_read(&file);
+        let mut reader = BufReader::with_capacity(buffer_size, DeadlineReader::new(file, deadline));
+        return get_sha3_512_hash(&mut reader);
+    }
+
+    String::new()
+}
+
+pub fn get_sha3_512_hash(reader: &mut dyn Read) -> String {
+    let mut hasher = sha3::Sha3_512::new();
+    if io::copy(reader, &mut hasher).is_ok() {
+        let hash = hasher.finalize();
+        return format!("{:x}", hash);
     }
 
     String::new()
 }
 
+pub type FileHashFn = fn(&DirEntry, Option<Instant>) -> String;
+pub type HashFn = fn(&mut dyn Read) -> String;
+
+/// Returns the file hashing function for the algorithm name along with the length of its hex digest
+pub fn get_file_hash_fn(algorithm: &str) -> Option<(FileHashFn, usize)> {
+    match algorithm.trim().to_ascii_lowercase().as_str() {
+        "sha1" => Some((get_sha1_file_hash, 40)),
+        "sha256" | "sha2_256" => Some((get_sha256_file_hash, 64)),
+        "sha512" | "sha2_512" => Some((get_sha512_file_hash, 128)),
+        "sha3" | "sha3_512" => Some((get_sha3_512_file_hash, 128)),
+        _ => None,
+    }
+}
+
+/// Checks if the expected hash could be a hex digest of the given length,
+/// so that files don't have to be read to compare against a malformed value
+pub fn is_valid_hex_digest(expected: &str, digest_len: usize) -> bool {
+    expected.len() == digest_len && expected.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Reads the archive through, so that its structure and checksums get verified.
+/// Zip archives, as well as .gz and .zst files are supported, None is returned for the rest
+/// and when the file can't be opened or the deadline is reached.
+pub fn is_valid_archive(path: &Path, is_zip: bool, deadline: Option<Instant>) -> Option<bool> {
+    let file = open_file(path).ok()?;
+
+    let result = if is_zip {
+        verify_zip_archive(file, deadline)
+    } else {
+        let reader: Box<dyn Read> = match get_extension(&path.to_string_lossy()).to_ascii_lowercase().as_str() {
+            "gz" | "gzip" => Box::new(flate2::read::MultiGzDecoder::new(BufReader::new(file))),
+            "zst" | "zstd" => Box::new(zstd::Decoder::new(file).ok()?),
+            _ => return None,
+        };
+        io::copy(&mut DeadlineReader::new(reader, deadline), &mut io::sink()).map(|_| ())
+    };
+
+    match result {
+        Ok(_) => Some(true),
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => None,
+        Err(_) => Some(false),
+    }
+}
+
+fn verify_zip_archive(file: File, deadline: Option<Instant>) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for i in 0..archive.len() {
+        let zipped_file = match archive.by_index(i) {
+            Ok(zipped_file) => zipped_file,
+            // Encrypted files and unsupported compression methods can't be checked
+            Err(zip::result::ZipError::UnsupportedArchive(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        io::copy(&mut DeadlineReader::new(zipped_file, deadline), &mut io::sink())?;
+    }
+
+    Ok(())
+}
+
 pub fn is_dir_empty(entry: &DirEntry) -> Option<bool> {
     match fs::read_dir(entry.path()) {
         Ok(dir) => Some(!dir.into_iter().any(|_| true)),
@@ -715,10 +1197,291 @@ pub fn is_dir_empty(entry: &DirEntry) -> Option<bool> {
     }
 }
 
+/// Whether the directory contains nothing but empty directories, symlinks count as contents
+pub fn is_dir_recursively_empty(dir: &Path) -> Option<bool> {
+    for entry in fs::read_dir(dir).ok()? {
+        let entry = entry.ok()?;
+        if !entry.file_type().ok()?.is_dir() || !is_dir_recursively_empty(&entry.path())? {
+            return Some(false);
+        }
+    }
+
+    Some(true)
+}
+
+/// Removes the directory along with the empty directories within it, deepest first.
+/// Never removes files, so the directory is left in place if anything appears in it meanwhile.
+pub fn remove_empty_dirs(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dirs(&entry.path())?;
+        }
+    }
+
+    fs::remove_dir(dir)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirEntryCounts {
+    pub children: usize,
+    pub subdirs: usize,
+    pub files: usize,
+}
+
+/// Counts the immediate children of the directory with a single pass, without descending into subdirectories.
+/// Symlinks and special files are counted as children, but neither as subdirectories nor as files.
+pub fn get_dir_entry_counts<T: AsRef<Path>>(path: T) -> Option<DirEntryCounts> {
+    let mut counts = DirEntryCounts::default();
+
+    for child in fs::read_dir(path).ok()?.flatten() {
+        counts.children += 1;
+
+        match child.file_type() {
+            Ok(file_type) if file_type.is_dir() => counts.subdirs += 1,
+            Ok(file_type) if file_type.is_file() => counts.files += 1,
+            _ => {}
+        }
+    }
+
+    Some(counts)
+}
+
+/// Sums up the sizes of all files within the directory tree. Symlinks are not followed.
+/// Results for every visited directory are put into the cache,
+/// so that searching through the nested directories later doesn't read them again.
+pub fn get_dir_size(path: &Path, cache: &mut HashMap<PathBuf, u64>) -> Option<u64> {
+    if let Some(size) = cache.get(path) {
+        return Some(*size);
+    }
+
+    let mut size = 0;
+
+    for child in fs::read_dir(path).ok()?.flatten() {
+        let Ok(metadata) = child.metadata() else {
+            continue;
+        };
+
+        size += match metadata.is_dir() {
+            true => get_dir_size(&child.path(), cache).unwrap_or(0),
+            false => metadata.len(),
+        };
+    }
+
+    cache.insert(path.to_path_buf(), size);
+
+    Some(size)
+}
+
+/// Modification times of the oldest and the newest entries within a directory tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChildrenModified {
+    pub oldest: SystemTime,
+    pub newest: SystemTime,
+}
+
+impl ChildrenModified {
+    fn merge(self, other: ChildrenModified) -> ChildrenModified {
+        ChildrenModified {
+            oldest: self.oldest.min(other.oldest),
+            newest: self.newest.max(other.newest),
+        }
+    }
+}
+
+/// Walks the directory tree and finds the oldest and the newest modification times of all its entries.
+/// Symlinks are not followed. Results for every visited directory are put into the cache,
+/// so that searching through the nested directories later doesn't read them again.
+pub fn get_children_modified(
+    path: &Path,
+    cache: &mut HashMap<PathBuf, Option<ChildrenModified>>,
+) -> Option<ChildrenModified> {
+    if let Some(children_modified) = cache.get(path) {
+        return *children_modified;
+    }
+
+    let mut result: Option<ChildrenModified> = None;
+
+    for child in fs::read_dir(path).ok()?.flatten() {
+        let Ok(metadata) = child.metadata() else {
+            continue;
+        };
+
+        if let Ok(modified) = metadata.modified() {
+            let child_modified = ChildrenModified {
+                oldest: modified,
+                newest: modified,
+            };
+            result = Some(result.map_or(child_modified, |r| r.merge(child_modified)));
+        }
+
+        if metadata.is_dir() {
+            if let Some(grandchildren_modified) = get_children_modified(&child.path(), cache) {
+                let merged = result.map_or(grandchildren_modified, |r| r.merge(grandchildren_modified));
+                result = Some(merged);
+            }
+        }
+    }
+
+    cache.insert(path.to_path_buf(), result);
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::field::Field;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("10"), Some(std::time::Duration::from_secs(10)));
+        assert_eq!(parse_interval("10s"), Some(std::time::Duration::from_secs(10)));
+        assert_eq!(parse_interval("1.5min"), Some(std::time::Duration::from_secs(90)));
+        assert_eq!(parse_interval("2 hours"), Some(std::time::Duration::from_secs(7200)));
+        assert_eq!(parse_interval("1d"), Some(std::time::Duration::from_secs(86400)));
+        assert_eq!(parse_interval("1y"), Some(std::time::Duration::from_secs(365 * 86400)));
+        assert_eq!(parse_interval("10 parsecs"), None);
+        assert_eq!(parse_interval("s"), None);
+    }
+
+    #[test]
+    fn test_get_dir_entry_counts() {
+        let dir = TempDir::new("dir-counts");
+        fs::create_dir_all(dir.join("sub1")).unwrap();
+        fs::create_dir_all(dir.join("sub2").join("nested")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("sub1").join("b.txt"), "b").unwrap();
+
+        let counts = get_dir_entry_counts(&dir);
+        let file_counts = get_dir_entry_counts(dir.join("a.txt"));
+
+        assert_eq!(
+            counts,
+            Some(DirEntryCounts {
+                children: 3,
+                subdirs: 2,
+                files: 1,
+            })
+        );
+        assert_eq!(file_counts, None);
+    }
+
+    #[test]
+    fn test_get_dir_size() {
+        let dir = TempDir::new("dir-size");
+        let nested = dir.join("sub").join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join("a.txt"), "12345").unwrap();
+        fs::write(nested.join("b.txt"), "123").unwrap();
+
+        let mut cache = HashMap::new();
+        let size = get_dir_size(&dir, &mut cache);
+        let nested_size = cache.get(&nested).copied();
+        let file_size = get_dir_size(&dir.join("a.txt"), &mut cache);
+
+        assert_eq!(size, Some(8));
+        assert_eq!(nested_size, Some(3));
+        assert_eq!(file_size, None);
+    }
+
+    #[test]
+    fn test_get_children_modified() {
+        use std::time::Duration;
+
+        let dir = TempDir::new("children-modified");
+        let nested = dir.join("sub").join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let new = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000);
+        let file = |path: PathBuf, modified: SystemTime| {
+            File::create(path).unwrap().set_modified(modified).unwrap();
+        };
+        file(dir.join("old.txt"), old);
+        file(nested.join("new.txt"), new);
+
+        let mut cache = HashMap::new();
+        let children_modified = get_children_modified(&dir, &mut cache);
+        let nested_modified = cache.get(&nested).copied();
+        let empty_modified = get_children_modified(&dir.join("empty"), &mut cache);
+        let file_modified = get_children_modified(&dir.join("old.txt"), &mut cache);
+
+        let children_modified = children_modified.unwrap();
+        assert_eq!(children_modified.oldest, old);
+        assert!(children_modified.newest >= new);
+        assert_eq!(
+            nested_modified,
+            Some(Some(ChildrenModified {
+                oldest: new,
+                newest: new,
+            }))
+        );
+        assert_eq!(empty_modified, None);
+        assert_eq!(file_modified, None);
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(strip_verbatim_prefix(r"\\?\C:\Users\user"), r"C:\Users\user");
+        assert_eq!(strip_verbatim_prefix(r"\\?\UNC\server\share\dir"), r"\\server\share\dir");
+        assert_eq!(strip_verbatim_prefix(r"\\server\share"), r"\\server\share");
+        assert_eq!(strip_verbatim_prefix("/home/user"), "/home/user");
+    }
+
+    #[test]
+    fn test_to_verbatim_path() {
+        assert_eq!(to_verbatim_path(r"C:\Users\user"), r"\\?\C:\Users\user");
+        assert_eq!(to_verbatim_path("C:/Users/user"), r"\\?\C:\Users\user");
+        assert_eq!(to_verbatim_path(r"\\server\share\dir"), r"\\?\UNC\server\share\dir");
+        assert_eq!(to_verbatim_path(r"\\?\C:\dir"), r"\\?\C:\dir");
+        assert_eq!(to_verbatim_path(r"dir\file"), r"dir\file");
+        assert_eq!(to_verbatim_path("/home/user"), "/home/user");
+    }
+
+    #[test]
+    fn test_has_windows_reserved_name() {
+        assert!(has_windows_reserved_name("CON"));
+        assert!(has_windows_reserved_name("nul.txt"));
+        assert!(has_windows_reserved_name("Com1.tar.gz"));
+        assert!(has_windows_reserved_name("aux .log"));
+        assert!(has_windows_reserved_name("file."));
+        assert!(has_windows_reserved_name("file "));
+
+        assert!(!has_windows_reserved_name("console.txt"));
+        assert!(!has_windows_reserved_name("COM10"));
+        assert!(!has_windows_reserved_name(".nul"));
+        assert!(!has_windows_reserved_name(".."));
+        assert!(!has_windows_reserved_name("file.txt"));
+    }
+
+    #[test]
+    fn test_has_control_chars() {
+        assert!(has_control_chars("new\nline"));
+        assert!(has_control_chars("\u{1b}[31mred"));
+        assert!(has_control_chars("c1\u{85}"));
+
+        assert!(!has_control_chars("plain name.txt"));
+        assert!(!has_control_chars("日本語.txt"));
+    }
+
+    #[test]
+    fn test_hash_equals_precheck() {
+        let empty_sha256 = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+
+        let (_, sha256_len) = get_file_hash_fn("SHA256").unwrap();
+        assert!(is_valid_hex_digest(empty_sha256, sha256_len));
+        assert!(!is_valid_hex_digest(&empty_sha256[1..], sha256_len));
+        assert!(!is_valid_hex_digest(&empty_sha256.replace('E', "g"), sha256_len));
+
+        let (_, sha1_len) = get_file_hash_fn("sha1").unwrap();
+        assert!(!is_valid_hex_digest(empty_sha256, sha1_len));
+
+        assert_eq!(get_file_hash_fn("sha3").map(|(_, len)| len), Some(128));
+        assert!(get_file_hash_fn("md5").is_none());
+    }
 
     fn basic_criteria<T: Ord + Clone + Display>(vals: &[T]) -> Criteria<T> {
         let fields = Rc::new(vec![Expr::field(Field::Size); vals.len()]);
@@ -759,6 +1522,21 @@ mod tests {
         assert_eq!(c1.cmp(&c2), Ordering::Less);
     }
 
+    #[test]
+    fn test_compare_fractional_and_negative() {
+        let c1 = basic_criteria(&["0.0847"]);
+        let c2 = basic_criteria(&["0.233"]);
+        assert_eq!(c1.cmp(&c2), Ordering::Less);
+
+        let c1 = basic_criteria(&["-12.5"]);
+        let c2 = basic_criteria(&["3"]);
+        assert_eq!(c1.cmp(&c2), Ordering::Less);
+
+        let c1 = basic_criteria(&["1.5 MiB"]);
+        let c2 = basic_criteria(&["900 KiB"]);
+        assert_eq!(c1.cmp(&c2), Ordering::Greater);
+    }
+
     #[test]
     fn test_compare_all_fields_reverse() {
         let fields = Rc::new(vec![Expr::field(Field::Size); 3]);
@@ -865,6 +1643,168 @@ mod tests {
         assert_eq!(get_extension("has.extension.foo"), String::from("foo"));
     }
 
+    #[test]
+    fn test_retry_io() {
+        let mut calls = 0;
+        let (result, retries) = retry_io(3, std::time::Duration::ZERO, || {
+            calls += 1;
+            match calls {
+                1 | 2 => Err(io::Error::from(io::ErrorKind::TimedOut)),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(retries, 2);
+
+        let (result, retries) = retry_io(3, std::time::Duration::ZERO, || -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(retries, 0);
+
+        let (result, retries) = retry_io(2, std::time::Duration::ZERO, || -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::Interrupted))
+        });
+        assert!(result.is_err());
+        assert_eq!(retries, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new("symlinks");
+        fs::create_dir_all(dir.join("target")).unwrap();
+        symlink("target", dir.join("first")).unwrap();
+        symlink("first", dir.join("second")).unwrap();
+        symlink("loop", dir.join("loop")).unwrap();
+
+        assert_eq!(resolve_symlink(&dir.join("second"), 40), Some(dir.join("target")));
+        assert_eq!(resolve_symlink(&dir.join("second"), 1), None);
+        assert_eq!(resolve_symlink(&dir.join("target"), 0), Some(dir.join("target")));
+        assert_eq!(resolve_symlink(&dir.join("loop"), 40), None);
+    }
+
+    #[test]
+    fn test_open_content_reader() {
+        use std::io::Write;
+
+        let dir = TempDir::new("open-content-reader");
+
+        let plain = dir.join("plain.log");
+        fs::write(&plain, "foo\nbar\n").unwrap();
+
+        let gzipped = dir.join("rotated.log.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&gzipped).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(b"segfault\nfoo\nbar\n").unwrap();
+        encoder.finish().unwrap();
+
+        let zstd_compressed = dir.join("rotated.log.zst");
+        fs::write(&zstd_compressed, zstd::encode_all(&b"zstd\n"[..], 0).unwrap()).unwrap();
+
+        let mut contents = String::new();
+        open_content_reader(&plain, None).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "foo\nbar\n");
+
+        contents.clear();
+        open_content_reader(&gzipped, None).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "segfault\nfoo\nbar\n");
+
+        contents.clear();
+        open_content_reader(&zstd_compressed, None).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "zstd\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_open_fifo() {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new("open-fifo");
+
+        let fifo = dir.join("script");
+        let _ = fs::remove_file(&fifo);
+        let fifo_path = CString::new(fifo.as_os_str().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo_path.as_ptr(), 0o600) }, 0);
+
+        // Nobody writes to the FIFO, so opening it for reading would wait forever
+        assert!(open_file(&fifo).is_err());
+        assert!(open_content_reader(&fifo, None).is_err());
+        assert!(!is_shebang(&fifo));
+        assert!(open_file(&dir).is_err());
+        assert_eq!(open_file("/dev/null").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        fs::write(dir.join("target"), "#!/bin/sh\n").unwrap();
+        std::os::unix::fs::symlink("target", dir.join("link")).unwrap();
+        assert!(open_file(dir.join("link")).is_ok());
+    }
+
+    #[test]
+    fn test_is_valid_archive() {
+        use std::io::Write;
+
+        let dir = TempDir::new("is-valid-archive");
+
+        let zipped = dir.join("backup.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zipped).unwrap());
+        writer.start_file("notes.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"foo\nbar\n").unwrap();
+        writer.finish().unwrap();
+
+        let truncated = dir.join("truncated.zip");
+        let contents = fs::read(&zipped).unwrap();
+        fs::write(&truncated, &contents[..contents.len() / 2]).unwrap();
+
+        let gzipped = dir.join("backup.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"foo\nbar\n").unwrap();
+        let contents = encoder.finish().unwrap();
+        fs::write(&gzipped, &contents).unwrap();
+
+        let corrupted = dir.join("corrupted.gz");
+        let mut contents = contents.clone();
+        let len = contents.len();
+        contents[len - 5] ^= 0xff;
+        fs::write(&corrupted, &contents).unwrap();
+
+        let plain = dir.join("backup.tar");
+        fs::write(&plain, "foo").unwrap();
+
+        assert_eq!(is_valid_archive(&zipped, true, None), Some(true));
+        assert_eq!(is_valid_archive(&truncated, true, None), Some(false));
+        assert_eq!(is_valid_archive(&gzipped, false, None), Some(true));
+        assert_eq!(is_valid_archive(&corrupted, false, None), Some(false));
+        assert_eq!(is_valid_archive(&plain, false, None), None);
+        assert_eq!(is_valid_archive(&zipped, true, Some(Instant::now())), None);
+    }
+
+    #[test]
+    fn test_deadline_reader() {
+        let mut contents = String::new();
+        let mut reader = DeadlineReader::new(&b"foo"[..], None);
+        assert!(reader.read_to_string(&mut contents).is_ok());
+        assert_eq!(contents, "foo");
+
+        let mut reader = DeadlineReader::new(&b"foo"[..], Some(Instant::now()));
+        let err = reader.read_to_string(&mut contents).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_stream_contents() {
+        assert_eq!(count_lines(&b"foo\nbar\n"[..]), Some(2));
+        assert_eq!(count_lines(&b""[..]), Some(0));
+        assert_eq!(
+            get_sha256_hash(&mut &b"foo"[..]),
+            "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+        );
+    }
+
     #[test]
     fn test_capitalize() {
         assert_eq!(capitalize(""), String::new());
@@ -0,0 +1,102 @@
+//! Detection of files currently held open by any process, from the descriptors listed in `/proc`
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+/// Processes holding files open, by device and inode numbers of the files.
+/// Only the processes visible to the current user are taken into account.
+pub struct OpenFiles {
+    files: HashMap<(u64, u64), Vec<u32>>,
+    #[cfg(feature = "processes")]
+    process_names: HashMap<u32, String>,
+}
+
+impl OpenFiles {
+    pub fn scan() -> OpenFiles {
+        let mut open_files = OpenFiles {
+            files: HashMap::new(),
+            #[cfg(feature = "processes")]
+            process_names: HashMap::new(),
+        };
+
+        let Ok(processes) = fs::read_dir("/proc") else {
+            return open_files;
+        };
+
+        for process in processes.flatten() {
+            let Ok(pid) = process.file_name().to_string_lossy().parse::<u32>() else {
+                continue;
+            };
+
+            // Descriptors of other users' processes are not readable without root privileges
+            let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+                continue;
+            };
+
+            for fd in fds.flatten() {
+                if let Ok(metadata) = fs::metadata(fd.path()) {
+                    let pids = open_files.files.entry((metadata.dev(), metadata.ino())).or_default();
+                    if !pids.contains(&pid) {
+                        pids.push(pid);
+                    }
+                }
+            }
+
+            #[cfg(feature = "processes")]
+            if let Ok(name) = fs::read_to_string(process.path().join("comm")) {
+                open_files.process_names.insert(pid, name.trim_end().to_string());
+            }
+        }
+
+        open_files
+    }
+
+    pub fn is_open(&self, dev: u64, ino: u64) -> bool {
+        self.files.contains_key(&(dev, ino))
+    }
+
+    #[cfg(feature = "processes")]
+    pub fn get_pids(&self, dev: u64, ino: u64) -> Vec<u32> {
+        let mut pids = self.files.get(&(dev, ino)).cloned().unwrap_or_default();
+        pids.sort_unstable();
+        pids
+    }
+
+    /// Names of the processes holding the file open, without repetitions
+    #[cfg(feature = "processes")]
+    pub fn get_process_names(&self, dev: u64, ino: u64) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .get_pids(dev, ino)
+            .iter()
+            .filter_map(|pid| self.process_names.get(pid).cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_open_files() {
+        let dir = TempDir::new("open");
+        let path = dir.join("file");
+        let _file = fs::File::create(&path).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+
+        let open_files = OpenFiles::scan();
+        assert!(open_files.is_open(metadata.dev(), metadata.ino()));
+
+        #[cfg(feature = "processes")]
+        {
+            let pids = open_files.get_pids(metadata.dev(), metadata.ino());
+            assert!(pids.contains(&std::process::id()));
+            assert!(!open_files.get_process_names(metadata.dev(), metadata.ino()).is_empty());
+        }
+    }
+}
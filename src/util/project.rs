@@ -0,0 +1,78 @@
+use std::path::Path;
+
+/// Project types along with the marker files identifying them, in order of precedence
+const PROJECT_MARKERS: [(&str, &[&str]); 4] = [
+    ("cargo", &["Cargo.toml"]),
+    ("npm", &["package.json"]),
+    ("maven", &["pom.xml"]),
+    ("python", &["pyproject.toml", "setup.py", "setup.cfg"]),
+];
+
+/// Checks if the directory is the root of a Git working tree.
+/// `.git` is a file rather than a directory in worktrees and submodules.
+pub fn is_git_root<T: AsRef<Path>>(path: T) -> bool {
+    path.as_ref().join(".git").exists()
+}
+
+/// Checks if the directory is the root of a Mercurial working copy
+pub fn is_hg_root<T: AsRef<Path>>(path: T) -> bool {
+    path.as_ref().join(".hg").is_dir()
+}
+
+/// Checks if the directory is the root of a Subversion working copy, older ones had `.svn` in every directory
+pub fn is_svn_root<T: AsRef<Path>>(path: T) -> bool {
+    path.as_ref().join(".svn").join("wc.db").is_file()
+}
+
+/// Detects the type of the project in the directory by its marker files
+pub fn get_project_type<T: AsRef<Path>>(path: T) -> Option<&'static str> {
+    let path = path.as_ref();
+    if !path.is_dir() {
+        return None;
+    }
+
+    PROJECT_MARKERS
+        .iter()
+        .find(|(_, markers)| markers.iter().any(|marker| path.join(marker).is_file()))
+        .map(|(project_type, _)| *project_type)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_project_detection() {
+        let dir = TempDir::new("project");
+        let python = dir.join("python");
+        let mixed = dir.join("mixed");
+        let plain = dir.join("plain");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(python.join(".hg")).unwrap();
+        fs::create_dir_all(&mixed).unwrap();
+        fs::create_dir_all(&plain).unwrap();
+        fs::write(python.join("setup.py"), "").unwrap();
+        fs::write(mixed.join("package.json"), "{}").unwrap();
+        fs::write(mixed.join("Cargo.toml"), "").unwrap();
+        fs::write(mixed.join(".git"), "gitdir: ../.git/worktrees/mixed").unwrap();
+
+        let results = (
+            is_git_root(&dir),
+            is_git_root(&mixed),
+            is_git_root(&plain),
+            is_hg_root(&python),
+            is_hg_root(&dir),
+            get_project_type(&python),
+            get_project_type(&mixed),
+            get_project_type(&plain),
+            get_project_type(mixed.join("Cargo.toml")),
+        );
+
+        assert_eq!(
+            results,
+            (true, true, false, true, false, Some("python"), Some("cargo"), None, None)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Reading the file attributes std doesn't expose with `statx`, available since Linux 4.11
+
+use std::ffi::CString;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn statx(path: &Path, follow_symlinks: bool, mask: u32) -> io::Result<libc::statx> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+
+    let mut flags = libc::AT_STATX_SYNC_AS_STAT;
+    if !follow_symlinks {
+        flags |= libc::AT_SYMLINK_NOFOLLOW;
+    }
+
+    let mut buf = MaybeUninit::<libc::statx>::zeroed();
+    let result = unsafe { libc::statx(libc::AT_FDCWD, path.as_ptr(), flags, mask, buf.as_mut_ptr()) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(unsafe { buf.assume_init() })
+}
+
+// Not all versions of libc define these
+const STATX_MNT_ID: u32 = 0x1000;
+const STATX_ATTR_VERITY: u64 = 0x100000;
+const STATX_ATTR_DAX: u64 = 0x200000;
+
+/// Attributes of the file std doesn't expose, gathered with a single `statx` call
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StatxInfo {
+    /// Nothing if the kernel or the file system doesn't record it,
+    /// like ext4 formatted with 128-byte inodes, or kernels older than 4.11
+    pub birth_time: Option<SystemTime>,
+    /// Nothing on kernels older than 5.8
+    pub mount_id: Option<u64>,
+    /// The file is accessed directly, bypassing the page cache
+    pub dax: bool,
+    /// The file is protected with fs-verity
+    pub verity: bool,
+}
+
+pub fn get_statx_info(path: &Path, follow_symlinks: bool) -> Option<StatxInfo> {
+    let stx = statx(path, follow_symlinks, libc::STATX_BTIME | STATX_MNT_ID).ok()?;
+
+    let birth_time = match stx.stx_mask & libc::STATX_BTIME {
+        0 => None,
+        _ => to_system_time(stx.stx_btime.tv_sec, stx.stx_btime.tv_nsec),
+    };
+
+    let mount_id = match stx.stx_mask & STATX_MNT_ID {
+        0 => None,
+        _ => Some(stx.stx_mnt_id),
+    };
+
+    Some(StatxInfo {
+        birth_time,
+        mount_id,
+        dax: stx.stx_attributes & STATX_ATTR_DAX != 0,
+        verity: stx.stx_attributes & STATX_ATTR_VERITY != 0,
+    })
+}
+
+fn to_system_time(secs: i64, nanos: u32) -> Option<SystemTime> {
+    let time = match secs >= 0 {
+        true => UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64))?,
+        false => UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))?,
+    };
+
+    time.checked_add(Duration::from_nanos(nanos as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_system_time() {
+        assert_eq!(to_system_time(0, 0), Some(UNIX_EPOCH));
+        assert_eq!(to_system_time(10, 500), Some(UNIX_EPOCH + Duration::new(10, 500)));
+        assert_eq!(
+            to_system_time(-10, 500),
+            Some(UNIX_EPOCH - Duration::from_secs(10) + Duration::from_nanos(500))
+        );
+    }
+
+    #[test]
+    fn test_get_statx_info() {
+        assert!(get_statx_info(Path::new("/nonexistent/file"), true).is_none());
+
+        let info = get_statx_info(&std::env::temp_dir(), true).unwrap();
+        assert!(!info.verity);
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::Read;
+use std::mem::MaybeUninit;
+
+use serde_json::Value;
+use unsafe_libyaml::{
+    yaml_event_delete, yaml_parser_delete, yaml_parser_initialize, yaml_parser_parse,
+    yaml_parser_set_input_string, YAML_STREAM_END_EVENT,
+};
+
+pub fn is_valid_json(reader: impl Read) -> bool {
+    serde_json::from_reader::<_, Value>(reader).is_ok()
+}
+
+/// Every document of a multi-document YAML file has to be valid
+pub fn is_valid_yaml(mut reader: impl Read) -> bool {
+    let mut contents = Vec::new();
+    if reader.read_to_end(&mut contents).is_err() {
+        return false;
+    }
+
+    // The parser only reads the contents, which outlive it, and is deleted before returning
+    unsafe {
+        let mut parser = MaybeUninit::uninit();
+        if yaml_parser_initialize(parser.as_mut_ptr()).fail {
+            return false;
+        }
+        let parser = parser.as_mut_ptr();
+        yaml_parser_set_input_string(parser, contents.as_ptr(), contents.len() as _);
+
+        let mut is_valid = false;
+        let mut event = MaybeUninit::uninit();
+        while !yaml_parser_parse(parser, event.as_mut_ptr()).fail {
+            let is_stream_end = (*event.as_ptr()).type_ == YAML_STREAM_END_EVENT;
+            yaml_event_delete(event.as_mut_ptr());
+
+            if is_stream_end {
+                is_valid = true;
+                break;
+            }
+        }
+
+        yaml_parser_delete(parser);
+
+        is_valid
+    }
+}
+
+pub fn is_valid_toml(mut reader: impl Read) -> bool {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).is_ok() && contents.parse::<toml::Table>().is_ok()
+}
+
+/// Extracts the value of the JSON document at a path like `$.dependencies.serde` or `$.files[0].name`.
+/// Strings are returned as is, other values as JSON.
+pub fn get_json_value(reader: impl Read, path: &str) -> Option<String> {
+    let document: Value = serde_json::from_reader(reader).ok()?;
+
+    let mut value = &document;
+    for segment in parse_json_path(path)? {
+        value = match segment {
+            PathSegment::Key(key) => value.get(key)?,
+            PathSegment::Index(index) => value.get(index)?,
+        };
+    }
+
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Parses the subset of JSONPath with member and index access: `$.a.b`, `$['a']`, `$.a[0]`
+fn parse_json_path(path: &str) -> Option<Vec<PathSegment<'_>>> {
+    let mut rest = path.trim().strip_prefix('$').unwrap_or(path.trim());
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(member) = rest.strip_prefix('.') {
+            let end = member.find(['.', '[']).unwrap_or(member.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(PathSegment::Key(&member[..end]));
+            rest = &member[end..];
+        } else if let Some(subscript) = rest.strip_prefix('[') {
+            let end = subscript.find(']')?;
+            let inner = subscript[..end].trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            match quoted {
+                Some(key) => segments.push(PathSegment::Key(key)),
+                None => segments.push(PathSegment::Index(inner.parse().ok()?)),
+            }
+            rest = &subscript[end + 1..];
+        } else if segments.is_empty() && !path.trim().starts_with('$') {
+            // The leading dollar sign may be omitted: `version` or `files[0].name`
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(PathSegment::Key(&rest[..end]));
+            rest = &rest[end..];
+        } else {
+            return None;
+        }
+    }
+
+    Some(segments)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PACKAGE: &str = r#"{"name": "app", "version": "1.2.0", "files": [{"name": "a.js"}], "private": true}"#;
+
+    #[test]
+    fn test_get_json_value() {
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "$.version"), Some(String::from("1.2.0")));
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "$.files[0].name"), Some(String::from("a.js")));
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "$['name']"), Some(String::from("app")));
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "private"), Some(String::from("true")));
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "$.files[1]"), None);
+        assert_eq!(get_json_value(PACKAGE.as_bytes(), "$.missing"), None);
+        assert_eq!(get_json_value("{broken".as_bytes(), "$.name"), None);
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(
+            parse_json_path("$.a[2]['b.c']"),
+            Some(vec![PathSegment::Key("a"), PathSegment::Index(2), PathSegment::Key("b.c")])
+        );
+        assert_eq!(parse_json_path("$"), Some(vec![]));
+        assert_eq!(parse_json_path("$..a"), None);
+        assert_eq!(parse_json_path("$[x]"), None);
+    }
+
+    #[test]
+    fn test_validity() {
+        assert!(is_valid_json(PACKAGE.as_bytes()));
+        assert!(!is_valid_json(r#"{"name": }"#.as_bytes()));
+
+        assert!(is_valid_yaml("name: app\nlist:\n  - a\n---\nother: 1\n".as_bytes()));
+        assert!(is_valid_yaml("".as_bytes()));
+        assert!(!is_valid_yaml("name: [unclosed\n".as_bytes()));
+        assert!(!is_valid_yaml("name: app\n---\n  - a\n b: c\n".as_bytes()));
+
+        assert!(is_valid_toml("[package]\nname = \"app\"\n".as_bytes()));
+        assert!(!is_valid_toml("[package\nname = ".as_bytes()));
+    }
+}
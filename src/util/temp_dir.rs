@@ -0,0 +1,40 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Directory for the test fixtures, removed with all its contents when dropped,
+/// even if the test panics
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory named after the test and the process
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("fselect-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
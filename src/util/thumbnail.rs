@@ -0,0 +1,103 @@
+use std::fs;
+use std::io;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::util::open_file;
+
+/// Writes the JPEG thumbnail embedded into EXIF metadata to the destination,
+/// failing if it exists unless it's to be overwritten.
+/// Returns `false` if the image has no embedded thumbnail.
+pub fn extract_image_thumbnail(source: &Path, destination: &Path, overwrite: bool) -> io::Result<bool> {
+    let file = open_file(source)?;
+    let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(&file)) {
+        Ok(exif) => exif,
+        Err(_) => return Ok(false),
+    };
+
+    let offset = exif
+        .get_field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)
+        .and_then(|field| field.value.get_uint(0));
+    let length = exif
+        .get_field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)
+        .and_then(|field| field.value.get_uint(0));
+
+    let thumbnail = match (offset, length) {
+        (Some(offset), Some(length)) => {
+            let start = offset as usize;
+            exif.buf().get(start..start.saturating_add(length as usize))
+        }
+        _ => None,
+    };
+
+    match thumbnail {
+        Some(thumbnail) if !thumbnail.is_empty() => {
+            create_parent_dir(destination)?;
+            fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .create_new(!overwrite)
+                .open(destination)?
+                .write_all(thumbnail)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Writes the first keyframe of the video to the destination, requires ffmpeg in PATH.
+/// An existing destination makes ffmpeg fail, unless it's to be overwritten.
+pub fn extract_video_frame(source: &Path, destination: &Path, overwrite: bool) -> io::Result<bool> {
+    create_parent_dir(destination)?;
+
+    let status = Command::new("ffmpeg")
+        .args(["-v", "error", if overwrite { "-y" } else { "-n" }, "-skip_frame", "nokey", "-i"])
+        .arg(source)
+        .args(["-frames:v", "1"])
+        .arg(destination)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(e.kind(), "ffmpeg is required to extract video frames")
+            }
+            _ => e,
+        })?;
+
+    match status.success() {
+        true => Ok(true),
+        false => Err(io::Error::other("ffmpeg could not extract a video frame")),
+    }
+}
+
+fn create_parent_dir(destination: &Path) -> io::Result<()> {
+    match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::extract_image_thumbnail;
+    use crate::util::temp_dir::TempDir;
+    use std::error::Error;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_image_without_thumbnail() -> Result<(), Box<dyn Error>> {
+        let path_string =
+            std::env::var("CARGO_MANIFEST_DIR")? + "/resources/test/" + "image/rust-logo-blk.png";
+        let source = PathBuf::from(path_string);
+        let dir = TempDir::new("no-thumbnail");
+        let destination = dir.join("thumbnail.jpg");
+
+        assert!(!extract_image_thumbnail(&source, &destination, false)?);
+        assert!(!destination.exists());
+        Ok(())
+    }
+}
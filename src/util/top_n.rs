@@ -48,10 +48,18 @@ impl<K: Ord, V> TopN<K, V> {
         None
     }
 
-    // see: https://github.com/rust-lang/rfcs/blob/master/text/1522-conservative-impl-trait.md
-    //    pub fn values(&self) -> impl Iterator<Item=&V> {
-    //        self.echelons.values().flat_map(|v| v)
-    //    }
+    /// The greatest key once the limit is reached, greater keys are not kept anymore
+    pub fn get_bound(&self) -> Option<&K> {
+        match self.limit {
+            Some(limit) if self.count >= limit => self.echelons.keys().next_back(),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &V> {
+        self.echelons.values().flatten()
+    }
+
     pub fn values(&self) -> Vec<V>
     where
         V: Clone,
@@ -135,4 +143,19 @@ mod tests {
         top_n.insert("a", 0);
         assert_eq!(top_n.values(), vec![1, 0, 2, 3]);
     }
+
+    #[test]
+    fn test_bound() {
+        let mut top_n = TopN::new(2);
+        top_n.insert("m", 1);
+        assert_eq!(top_n.get_bound(), None);
+        top_n.insert("x", 2);
+        assert_eq!(top_n.get_bound(), Some(&"x"));
+        top_n.insert("b", 3);
+        assert_eq!(top_n.get_bound(), Some(&"m"));
+
+        let mut top_n = TopN::limitless();
+        top_n.insert("a", 1);
+        assert_eq!(top_n.get_bound(), None);
+    }
 }
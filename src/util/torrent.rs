@@ -0,0 +1,179 @@
+use std::io::Read;
+use std::path::Path;
+
+use log::debug;
+use sha1::Digest;
+
+use crate::util::open_file;
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TorrentInfo {
+    pub info_hash: String,
+    pub piece_count: usize,
+    pub total_size: u64,
+    pub files_count: usize,
+}
+
+enum Value<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(Vec<(&'a [u8], Value<'a>, &'a [u8])>),
+}
+
+impl<'a> Value<'a> {
+    fn get(&self, key: &str) -> Option<&Value<'a>> {
+        self.get_entry(key).map(|(value, _)| value)
+    }
+
+    /// Returns the value of the dictionary key along with its raw bencoded bytes
+    fn get_entry(&self, key: &str) -> Option<(&Value<'a>, &'a [u8])> {
+        match self {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(k, _, _)| *k == key.as_bytes())
+                .map(|(_, value, raw)| (value, *raw)),
+            _ => None,
+        }
+    }
+
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a bencoded value starting at `pos`, returns the value and the position right after it
+fn parse_value(buf: &[u8], pos: usize, depth: usize) -> Option<(Value<'_>, usize)> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    match buf.get(pos)? {
+        b'i' => {
+            let end = pos + 1 + buf[pos + 1..].iter().position(|&b| b == b'e')?;
+            let int = std::str::from_utf8(&buf[pos + 1..end]).ok()?.parse().ok()?;
+            Some((Value::Int(int), end + 1))
+        }
+        b'l' => {
+            let mut items = vec![];
+            let mut pos = pos + 1;
+            while *buf.get(pos)? != b'e' {
+                let (item, next) = parse_value(buf, pos, depth + 1)?;
+                items.push(item);
+                pos = next;
+            }
+            Some((Value::List(items), pos + 1))
+        }
+        b'd' => {
+            let mut entries = vec![];
+            let mut pos = pos + 1;
+            while *buf.get(pos)? != b'e' {
+                let (key, next) = match parse_value(buf, pos, depth + 1)? {
+                    (Value::Bytes(key), next) => (key, next),
+                    _ => return None,
+                };
+                let (value, end) = parse_value(buf, next, depth + 1)?;
+                entries.push((key, value, &buf[next..end]));
+                pos = end;
+            }
+            Some((Value::Dict(entries), pos + 1))
+        }
+        b'0'..=b'9' => {
+            let colon = pos + buf[pos..].iter().position(|&b| b == b':')?;
+            let len: usize = std::str::from_utf8(&buf[pos..colon]).ok()?.parse().ok()?;
+            let start = colon + 1;
+            let end = start.checked_add(len)?;
+            Some((Value::Bytes(buf.get(start..end)?), end))
+        }
+        _ => None,
+    }
+}
+
+fn parse_torrent(buf: &[u8]) -> Option<TorrentInfo> {
+    let (root, _) = parse_value(buf, 0, 0)?;
+    let (info, raw_info) = root.get_entry("info")?;
+
+    let piece_count = match info.get("pieces")? {
+        Value::Bytes(pieces) => pieces.len() / 20,
+        _ => return None,
+    };
+
+    let (total_size, files_count) = match info.get("files") {
+        Some(Value::List(files)) => {
+            let total_size = files
+                .iter()
+                .filter_map(|file| file.get("length").and_then(Value::as_int))
+                .map(|length| length.max(0) as u64)
+                .sum();
+            (total_size, files.len())
+        }
+        _ => (info.get("length")?.as_int()?.max(0) as u64, 1),
+    };
+
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(raw_info);
+    let info_hash = format!("{:x}", hasher.finalize());
+
+    Some(TorrentInfo {
+        info_hash,
+        piece_count,
+        total_size,
+        files_count,
+    })
+}
+
+pub fn get_torrent_info<T: AsRef<Path>>(path: T) -> Option<TorrentInfo> {
+    let path_ref = path.as_ref();
+    let extension = path_ref.extension()?.to_str()?;
+
+    if !extension.eq_ignore_ascii_case("torrent") {
+        return None;
+    }
+
+    let mut buf = vec![];
+    open_file(path_ref).ok()?.read_to_end(&mut buf).ok()?;
+
+    let torrent_info = parse_torrent(&buf);
+    if torrent_info.is_none() {
+        debug!(target: "extractors", "Could not parse torrent file {}", path_ref.display());
+    }
+
+    torrent_info
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_torrent;
+
+    #[test]
+    fn test_single_file() {
+        let torrent = b"d8:announce3:url4:infod6:lengthi12345e4:name5:a.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        let info = parse_torrent(torrent).unwrap();
+
+        assert_eq!(info.piece_count, 1);
+        assert_eq!(info.total_size, 12345);
+        assert_eq!(info.files_count, 1);
+        assert_eq!(info.info_hash, "d25a1fc560acd918d7e22be3fae22cc75c9fd0ce");
+    }
+
+    #[test]
+    fn test_multiple_files() {
+        let torrent = b"d4:infod5:filesld6:lengthi10e4:pathl1:aeed6:lengthi20e4:pathl1:beee4:name3:dir12:piece lengthi16384e6:pieces40:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbee";
+        let info = parse_torrent(torrent).unwrap();
+
+        assert_eq!(info.piece_count, 2);
+        assert_eq!(info.total_size, 30);
+        assert_eq!(info.files_count, 2);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse_torrent(b"d4:info").is_none());
+        assert!(parse_torrent(b"not a torrent").is_none());
+    }
+}
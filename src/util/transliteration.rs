@@ -0,0 +1,62 @@
+use crate::util::japanese::kana_to_romaji;
+
+/// Transliterates the string into ASCII.
+/// Kana becomes romaji, everything else is handled by the general Unicode transliteration.
+pub fn transliterate(s: &str) -> String {
+    deunicode::deunicode(&kana_to_romaji(s))
+}
+
+/// Makes a lowercase ASCII slug out of the string, with words joined by dashes.
+/// A trailing file extension is kept, so that `Café Menu.PDF` becomes `cafe-menu.pdf`.
+pub fn slugify(s: &str) -> String {
+    match s.rfind('.') {
+        Some(idx) if idx > 0 && idx < s.len() - 1 => {
+            let stem = slugify_part(&s[..idx]);
+            let extension = slugify_part(&s[idx + 1..]);
+
+            match (stem.is_empty(), extension.is_empty()) {
+                (false, false) => format!("{}.{}", stem, extension),
+                (true, _) => extension,
+                (_, true) => stem,
+            }
+        }
+        _ => slugify_part(s),
+    }
+}
+
+fn slugify_part(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for c in transliterate(s).chars() {
+        if c.is_ascii_alphanumeric() {
+            result.push(c.to_ascii_lowercase());
+        } else if !result.is_empty() && !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+
+    result.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{slugify, transliterate};
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(transliterate("Crème brûlée"), "Creme brulee");
+        assert_eq!(transliterate("Москва"), "Moskva");
+        assert_eq!(transliterate("ひらがな カタカナ"), "hiragana katakana");
+        assert_eq!(transliterate("plain.txt"), "plain.txt");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Café Menu.PDF"), "cafe-menu.pdf");
+        assert_eq!(slugify("  Hello,   World!  "), "hello-world");
+        assert_eq!(slugify("Привет мир.txt"), "privet-mir.txt");
+        assert_eq!(slugify(".bashrc"), "bashrc");
+        assert_eq!(slugify("archive.tar.gz"), "archive-tar.gz");
+        assert_eq!(slugify("!!!"), "");
+    }
+}
@@ -0,0 +1,481 @@
+//! Locating and reading the trash searched with the `trash://` root,
+//! supports the freedesktop.org Trash, macOS `~/.Trash`, and Windows Recycle Bin
+
+#[cfg(unix)]
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::io::Write;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDateTime};
+#[cfg(not(windows))]
+use directories::BaseDirs;
+
+use crate::util::percent_decode;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::util::percent_encode;
+
+pub const TRASH_ROOT: &str = "trash://";
+
+pub fn is_trash_root(path: &str) -> bool {
+    path.eq_ignore_ascii_case(TRASH_ROOT)
+}
+
+// Only the kinds of the current platform are ever constructed
+#[allow(unused)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrashKind {
+    Freedesktop,
+    MacOs,
+    RecycleBin,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashDir {
+    pub kind: TrashKind,
+    /// Directory holding the trashed items
+    pub files: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashInfo {
+    /// The trashed item the file belongs to, i.e. the topmost entry within the trash
+    pub item: PathBuf,
+    /// Metadata file describing the item, removed along with it
+    pub info_file: Option<PathBuf>,
+    pub original_path: Option<PathBuf>,
+    pub deleted: Option<NaiveDateTime>,
+}
+
+/// Finds the trash directories of the current user
+pub fn get_trash_dirs() -> Vec<TrashDir> {
+    let mut dirs = vec![];
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(base_dirs) = BaseDirs::new() {
+        dirs.push(TrashDir {
+            kind: TrashKind::Freedesktop,
+            files: base_dirs.data_dir().join("Trash").join("files"),
+        });
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(base_dirs) = BaseDirs::new() {
+        dirs.push(TrashDir {
+            kind: TrashKind::MacOs,
+            files: base_dirs.home_dir().join(".Trash"),
+        });
+    }
+
+    #[cfg(windows)]
+    for drive in b'A'..=b'Z' {
+        let recycle_bin = PathBuf::from(format!("{}:\\$Recycle.Bin", drive as char));
+        if let Ok(read_dir) = fs::read_dir(&recycle_bin) {
+            // There's a directory per user SID, only the current user's one is readable
+            for entry in read_dir.flatten() {
+                if fs::read_dir(entry.path()).is_ok() {
+                    dirs.push(TrashDir {
+                        kind: TrashKind::RecycleBin,
+                        files: entry.path(),
+                    });
+                }
+            }
+        }
+    }
+
+    dirs.retain(|dir| dir.files.is_dir());
+    dirs
+}
+
+/// Checks if the file is the Recycle Bin's own metadata rather than a trashed item
+pub fn is_trash_metadata(trash_dirs: &[TrashDir], path: &Path) -> bool {
+    trash_dirs.iter().any(|trash| {
+        trash.kind == TrashKind::RecycleBin
+            && path.parent() == Some(trash.files.as_path())
+            && path.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                name.starts_with("$I") || name.eq_ignore_ascii_case("desktop.ini")
+            })
+    })
+}
+
+/// Reads where the file was deleted from and when, None if the file is not in the trash
+pub fn get_trash_info(trash_dirs: &[TrashDir], path: &Path) -> Option<TrashInfo> {
+    let (trash, relative) = trash_dirs
+        .iter()
+        .find_map(|trash| Some((trash, path.strip_prefix(&trash.files).ok()?)))?;
+
+    let mut components = relative.components();
+    let item_name = components.next()?.as_os_str().to_string_lossy().to_string();
+    let nested: PathBuf = components.collect();
+
+    let mut info = TrashInfo {
+        item: trash.files.join(&item_name),
+        info_file: None,
+        original_path: None,
+        deleted: None,
+    };
+
+    match trash.kind {
+        TrashKind::Freedesktop => {
+            let info_file = trash
+                .files
+                .parent()?
+                .join("info")
+                .join(format!("{}.trashinfo", item_name));
+            if let Ok(content) = fs::read_to_string(&info_file) {
+                (info.original_path, info.deleted) = parse_trashinfo(&content);
+            }
+            info.info_file = Some(info_file);
+        }
+        TrashKind::RecycleBin => {
+            if let Some(suffix) = item_name.strip_prefix("$R") {
+                let info_file = trash.files.join(format!("$I{}", suffix));
+                if let Ok(content) = fs::read(&info_file) {
+                    (info.original_path, info.deleted) = parse_recycle_bin_info(&content);
+                }
+                info.info_file = Some(info_file);
+            }
+        }
+        // Finder keeps the original locations in .DS_Store, which is not supported
+        TrashKind::MacOs => {}
+    }
+
+    if !nested.as_os_str().is_empty() {
+        info.original_path = info.original_path.map(|original| original.join(nested));
+    }
+
+    Some(info)
+}
+
+/// Parses the `.trashinfo` file of the freedesktop.org Trash
+fn parse_trashinfo(content: &str) -> (Option<PathBuf>, Option<NaiveDateTime>) {
+    let mut original_path = None;
+    let mut deleted = None;
+
+    for line in content.lines() {
+        if let Some(path) = line.strip_prefix("Path=") {
+            original_path = Some(PathBuf::from(percent_decode(path.trim())));
+        } else if let Some(date) = line.strip_prefix("DeletionDate=") {
+            deleted = NaiveDateTime::parse_from_str(date.trim(), "%Y-%m-%dT%H:%M:%S").ok();
+        }
+    }
+
+    (original_path, deleted)
+}
+
+/// Parses the `$I` file of the Windows Recycle Bin: version, size, deletion time as FILETIME,
+/// then the original path in UTF-16, either of fixed length (version 1) or prefixed with it (version 2)
+fn parse_recycle_bin_info(content: &[u8]) -> (Option<PathBuf>, Option<NaiveDateTime>) {
+    if content.len() < 24 {
+        return (None, None);
+    }
+
+    let version = u64::from_le_bytes(content[0..8].try_into().unwrap());
+    let filetime = i64::from_le_bytes(content[16..24].try_into().unwrap());
+
+    // FILETIME counts 100 ns intervals since 1601-01-01
+    let deleted = DateTime::from_timestamp(filetime / 10_000_000 - 11_644_473_600, 0)
+        .map(|dt| dt.with_timezone(&Local).naive_local());
+
+    let name = match version {
+        1 => &content[24..],
+        2 if content.len() >= 28 => &content[28..],
+        _ => return (None, deleted),
+    };
+
+    let name: Vec<u16> = name
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect();
+
+    let original_path = match name.is_empty() {
+        true => None,
+        false => Some(PathBuf::from(String::from_utf16_lossy(&name))),
+    };
+
+    (original_path, deleted)
+}
+
+/// Moves the trashed item back to its original location, or into the directory if given
+pub fn restore(info: &TrashInfo, destination: Option<&Path>) -> io::Result<()> {
+    let target = match (destination, &info.original_path) {
+        (Some(dir), original) => {
+            let name = original
+                .as_ref()
+                .and_then(|original| original.file_name())
+                .or(info.item.file_name())
+                .unwrap_or_default();
+            dir.join(name)
+        }
+        (None, Some(original)) => original.clone(),
+        (None, None) => {
+            return Err(io::Error::other("original location is unknown, use RESTORE TO"));
+        }
+    };
+
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::rename(&info.item, &target)?;
+    remove_info_file(info);
+
+    Ok(())
+}
+
+/// Deletes the trashed item permanently
+pub fn purge(info: &TrashInfo) -> io::Result<()> {
+    if fs::symlink_metadata(&info.item)?.is_dir() {
+        fs::remove_dir_all(&info.item)?;
+    } else {
+        fs::remove_file(&info.item)?;
+    }
+    remove_info_file(info);
+
+    Ok(())
+}
+
+/// Moves the file or directory into the trash of the current user, returning where it ended up there.
+/// The trash has to be on the same file system, items are never copied.
+pub fn move_to_trash(path: &Path) -> io::Result<PathBuf> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let base_dirs = BaseDirs::new().ok_or_else(|| io::Error::other("home directory not found"))?;
+        move_to_freedesktop_trash(path, &base_dirs.data_dir().join("Trash"))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let base_dirs = BaseDirs::new().ok_or_else(|| io::Error::other("home directory not found"))?;
+        let trash = base_dirs.home_dir().join(".Trash");
+        let item = get_free_name(&trash, path, |item| fs::symlink_metadata(item).is_err())?;
+        rename_into_trash(path, &item)?;
+        Ok(item)
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "moving to the Recycle Bin is not supported",
+        ))
+    }
+}
+
+/// Writes the `.trashinfo` file before moving the item, the exclusive creation of the file reserves the name
+#[cfg(all(unix, not(target_os = "macos")))]
+fn move_to_freedesktop_trash(path: &Path, trash: &Path) -> io::Result<PathBuf> {
+    let original_path = get_absolute_path(path)?;
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let mut info_file = None;
+    let item = get_free_name(&files, path, |item| {
+        let mut info_name = item.file_name().unwrap_or_default().to_os_string();
+        info_name.push(".trashinfo");
+        let info_path = info.join(info_name);
+
+        match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) if fs::symlink_metadata(item).is_err() => {
+                info_file = Some((file, info_path));
+                true
+            }
+            Ok(_) => {
+                let _ = fs::remove_file(&info_path);
+                false
+            }
+            Err(_) => false,
+        }
+    })?;
+    let Some((mut file, info_path)) = info_file else {
+        return Err(io::Error::other("could not create the trash info file"));
+    };
+
+    let result = write!(
+        file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(original_path.as_os_str().as_bytes()),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )
+    .and_then(|_| rename_into_trash(path, &item));
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(item)
+}
+
+/// Picks the name of the item within the trash, numbering it when the name is already taken,
+/// the check also claims the name when it succeeds
+#[cfg(unix)]
+fn get_free_name(dir: &Path, path: &Path, mut is_free: impl FnMut(&Path) -> bool) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path doesn't name a file or directory")
+    })?;
+
+    for number in 1..=1000 {
+        let mut item_name = OsString::from(name);
+        if number > 1 {
+            item_name.push(format!(".{}", number));
+        }
+
+        let item = dir.join(item_name);
+        if is_free(&item) {
+            return Ok(item);
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many items of the same name in the trash",
+    ))
+}
+
+#[cfg(unix)]
+fn rename_into_trash(path: &Path, item: &Path) -> io::Result<()> {
+    fs::rename(path, item).map_err(|e| match e.kind() {
+        io::ErrorKind::CrossesDevices => {
+            io::Error::new(e.kind(), "the trash is on another file system, not moved")
+        }
+        _ => e,
+    })
+}
+
+/// The original location is absolute, symlinks are kept as they are rather than resolved
+#[cfg(all(unix, not(target_os = "macos")))]
+fn get_absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path doesn't name a file or directory")
+    })?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    Ok(fs::canonicalize(parent)?.join(name))
+}
+
+fn remove_info_file(info: &TrashInfo) {
+    if let Some(ref info_file) = info.info_file {
+        let _ = fs::remove_file(info_file);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+
+    #[test]
+    fn test_parse_trashinfo() {
+        let content = "[Trash Info]\nPath=/home/user/My%20Docs/report.pdf\nDeletionDate=2024-03-01T12:30:00\n";
+        let (original_path, deleted) = parse_trashinfo(content);
+
+        assert_eq!(original_path, Some(PathBuf::from("/home/user/My Docs/report.pdf")));
+        assert_eq!(deleted.unwrap().to_string(), "2024-03-01 12:30:00");
+    }
+
+    #[test]
+    fn test_parse_recycle_bin_info() {
+        let path: Vec<u8> = "C:\\tmp\\a.txt\0"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+
+        let mut content = vec![];
+        content.extend(2u64.to_le_bytes());
+        content.extend(100u64.to_le_bytes());
+        content.extend(133_000_000_000_000_000i64.to_le_bytes());
+        content.extend(13u32.to_le_bytes());
+        content.extend(&path);
+
+        let (original_path, deleted) = parse_recycle_bin_info(&content);
+        assert_eq!(original_path, Some(PathBuf::from("C:\\tmp\\a.txt")));
+        assert!(deleted.is_some());
+
+        assert_eq!(parse_recycle_bin_info(&content[..10]), (None, None));
+    }
+
+    #[test]
+    fn test_trash_info() {
+        let dir = TempDir::new("trash");
+        let files = dir.join("files");
+        fs::create_dir_all(files.join("docs")).unwrap();
+        fs::create_dir_all(dir.join("info")).unwrap();
+        fs::write(files.join("docs").join("a.txt"), "a").unwrap();
+        fs::write(
+            dir.join("info").join("docs.trashinfo"),
+            "[Trash Info]\nPath=/home/user/docs\nDeletionDate=2024-03-01T12:30:00\n",
+        )
+        .unwrap();
+
+        let trash_dirs = vec![TrashDir {
+            kind: TrashKind::Freedesktop,
+            files: files.clone(),
+        }];
+
+        let info = get_trash_info(&trash_dirs, &files.join("docs").join("a.txt")).unwrap();
+        assert_eq!(info.item, files.join("docs"));
+        assert_eq!(info.original_path, Some(PathBuf::from("/home/user/docs/a.txt")));
+        assert!(get_trash_info(&trash_dirs, &dir.join("info")).is_none());
+
+        let info = get_trash_info(&trash_dirs, &files.join("docs")).unwrap();
+        assert_eq!(info.original_path, Some(PathBuf::from("/home/user/docs")));
+
+        let restore_dir = dir.join("restored");
+        restore(&info, Some(&restore_dir)).unwrap();
+        assert!(restore_dir.join("docs").join("a.txt").exists());
+        assert!(!dir.join("info").join("docs.trashinfo").exists());
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_move_to_freedesktop_trash() {
+        let dir = TempDir::new("trash_move");
+        let trash = dir.join("Trash");
+        let trash_dirs = vec![TrashDir {
+            kind: TrashKind::Freedesktop,
+            files: trash.join("files"),
+        }];
+
+        for _ in 0..2 {
+            fs::create_dir_all(dir.join("my docs")).unwrap();
+            fs::write(dir.join("my docs").join("a.txt"), "a").unwrap();
+            move_to_freedesktop_trash(&dir.join("my docs"), &trash).unwrap();
+            assert!(!dir.join("my docs").exists());
+        }
+
+        let original_path = fs::canonicalize(&*dir).unwrap().join("my docs");
+        for item in ["my docs", "my docs.2"] {
+            let info = get_trash_info(&trash_dirs, &trash.join("files").join(item)).unwrap();
+            assert!(info.item.join("a.txt").exists());
+            assert_eq!(info.original_path.as_ref(), Some(&original_path));
+            assert!(info.deleted.is_some());
+        }
+
+        let info = get_trash_info(&trash_dirs, &trash.join("files").join("my docs")).unwrap();
+        restore(&info, None).unwrap();
+        assert!(dir.join("my docs").join("a.txt").exists());
+
+        assert!(move_to_freedesktop_trash(&dir.join("missing"), &trash).is_err());
+        assert!(!trash.join("info").join("missing.trashinfo").exists());
+    }
+}
@@ -0,0 +1,372 @@
+//! Tells whether the files are under version control of Mercurial or Subversion
+//! by reading the metadata of the working copy, without running `hg` or `svn`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::Metadata;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+#[cfg(feature = "sqlite")]
+use rusqlite::{Connection, OpenFlags};
+
+use crate::util::project::{is_hg_root, is_svn_root};
+
+/// Parents of the working copy are written before the entries of the dirstate
+const DIRSTATE_PARENTS_SIZE: usize = 40;
+/// State, mode, size, modification time, and length of the name of the file
+const DIRSTATE_ENTRY_SIZE: usize = 17;
+/// Newer dirstates are kept in another file referenced by the docket starting with this
+const DIRSTATE_V2_MARKER: &[u8] = b"dirstate-v2\n";
+/// Sizes and modification times are written without their sign bit
+const DIRSTATE_RANGE_MASK: u64 = 0x7fffffff;
+
+/// Paths of the nodes present in the working copy, the latest operation on each of them decides
+#[cfg(feature = "sqlite")]
+const SVN_NODES_QUERY: &str = "SELECT local_relpath FROM nodes AS n \
+    WHERE op_depth = (SELECT MAX(op_depth) FROM nodes WHERE wc_id = n.wc_id AND local_relpath = n.local_relpath) \
+    AND presence IN ('normal', 'incomplete')";
+
+/// Entry of the file in the Mercurial dirstate
+struct DirstateEntry {
+    /// `n` for normal, `a` for added, `r` for removed, and `m` for merged
+    state: u8,
+    mode: i32,
+    /// Negative if the file has to be compared by its contents
+    size: i32,
+    /// Negative if it's not recorded
+    mtime: i32,
+}
+
+/// Files known to Mercurial by their paths relative to the root of the repository
+#[derive(Default)]
+struct Dirstate {
+    files: HashMap<String, DirstateEntry>,
+    /// Directories with tracked files inside them, the root is an empty string
+    dirs: HashSet<String>,
+}
+
+/// Keeps the dirstates and the working copy databases of the repositories met so far
+#[derive(Default)]
+pub struct VersionControl {
+    hg_roots: HashMap<PathBuf, Option<PathBuf>>,
+    dirstates: HashMap<PathBuf, Option<Rc<Dirstate>>>,
+    svn_roots: HashMap<PathBuf, Option<PathBuf>>,
+    svn_nodes: HashMap<PathBuf, Option<Rc<HashSet<String>>>>,
+}
+
+impl VersionControl {
+    /// Checks if the file is tracked by Mercurial, directories are tracked if any file inside them is.
+    /// Nothing if the dirstate can't be read, e.g. in the newer dirstate-v2 format.
+    pub fn is_hg_tracked(&mut self, path: &Path, is_dir: bool) -> Option<bool> {
+        let Some((dirstate, relative_path)) = self.get_dirstate(path) else {
+            return Some(false);
+        };
+        let dirstate = dirstate?;
+
+        match is_dir {
+            true => Some(dirstate.dirs.contains(&relative_path)),
+            false => Some(
+                dirstate
+                    .files
+                    .get(&relative_path)
+                    .is_some_and(|entry| entry.state != b'r'),
+            ),
+        }
+    }
+
+    /// Checks if the tracked file has changes not committed yet
+    pub fn is_hg_modified(&mut self, path: &Path, metadata: &Metadata) -> Option<bool> {
+        if metadata.is_dir() {
+            return Some(false);
+        }
+
+        let Some((dirstate, relative_path)) = self.get_dirstate(path) else {
+            return Some(false);
+        };
+        let dirstate = dirstate?;
+
+        Some(
+            dirstate
+                .files
+                .get(&relative_path)
+                .is_some_and(|entry| is_modified(entry, metadata)),
+        )
+    }
+
+    /// Checks if the file or directory is versioned in a Subversion 1.7+ working copy,
+    /// nothing if the working copy database can't be read, or without the `sqlite` feature
+    pub fn is_svn_versioned(&mut self, path: &Path) -> Option<bool> {
+        let root = path
+            .parent()
+            .and_then(|dir| find_root(&mut self.svn_roots, dir, |dir| is_svn_root(dir)));
+        let Some(root) = root else {
+            return Some(false);
+        };
+
+        let relative_path = get_relative_path(&root, path)?;
+        let nodes = self
+            .svn_nodes
+            .entry(root)
+            .or_insert_with_key(|root| read_svn_nodes(root).map(Rc::new))
+            .clone()?;
+
+        Some(nodes.contains(&relative_path))
+    }
+
+    /// Returns the dirstate of the repository along with the path of the file relative to its root,
+    /// nothing if the file is outside of Mercurial repositories
+    fn get_dirstate(&mut self, path: &Path) -> Option<(Option<Rc<Dirstate>>, String)> {
+        let root = find_root(&mut self.hg_roots, path.parent()?, |dir| is_hg_root(dir))?;
+        let relative_path = get_relative_path(&root, path)?;
+        let dirstate = self
+            .dirstates
+            .entry(root)
+            .or_insert_with_key(|root| read_dirstate(root).map(Rc::new))
+            .clone();
+
+        Some((dirstate, relative_path))
+    }
+}
+
+/// Finds the closest of the directory and its parents passing the check, remembering the answer for all of them
+fn find_root(
+    roots: &mut HashMap<PathBuf, Option<PathBuf>>,
+    dir: &Path,
+    is_root: fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    if let Some(root) = roots.get(dir) {
+        return root.clone();
+    }
+
+    let root = match (is_root(dir), dir.parent()) {
+        (true, _) => Some(dir.to_path_buf()),
+        (false, Some(parent)) => find_root(roots, parent, is_root),
+        (false, None) => None,
+    };
+    roots.insert(dir.to_path_buf(), root.clone());
+
+    root
+}
+
+/// Both Mercurial and Subversion keep the paths with forward slashes
+fn get_relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative_path = path.strip_prefix(root).ok()?;
+    Some(relative_path.to_string_lossy().replace('\\', "/"))
+}
+
+fn read_dirstate(root: &Path) -> Option<Dirstate> {
+    match fs::read(root.join(".hg").join("dirstate")) {
+        Ok(data) => parse_dirstate(&data),
+        // Nothing is tracked in a fresh repository yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(Dirstate::default()),
+        Err(_) => None,
+    }
+}
+
+/// Parses the dirstate-v1 format, the newer one is not supported
+fn parse_dirstate(data: &[u8]) -> Option<Dirstate> {
+    if data.starts_with(DIRSTATE_V2_MARKER) {
+        return None;
+    }
+
+    let mut dirstate = Dirstate::default();
+    let mut pos = DIRSTATE_PARENTS_SIZE.min(data.len());
+
+    while pos < data.len() {
+        let header = data.get(pos..pos + DIRSTATE_ENTRY_SIZE)?;
+        let read_i32 = |from: usize| header[from..from + 4].try_into().ok().map(i32::from_be_bytes);
+        let entry = DirstateEntry {
+            state: header[0],
+            mode: read_i32(1)?,
+            size: read_i32(5)?,
+            mtime: read_i32(9)?,
+        };
+        let length = usize::try_from(read_i32(13)?).ok()?;
+        pos += DIRSTATE_ENTRY_SIZE;
+
+        // The source of a copied file follows its name
+        let name = data.get(pos..pos + length)?;
+        let name = name.split(|b| *b == 0).next().unwrap_or_default();
+        let name = String::from_utf8_lossy(name).to_string();
+        pos += length;
+
+        if entry.state != b'r' {
+            dirstate.dirs.insert(String::new());
+            for (slash, _) in name.match_indices('/') {
+                dirstate.dirs.insert(name[..slash].to_string());
+            }
+        }
+
+        dirstate.files.insert(name, entry);
+    }
+
+    Some(dirstate)
+}
+
+/// Compares the file with its entry the way `hg status` does before reading the contents,
+/// so the changes keeping the size and the modification time of the file go unnoticed
+fn is_modified(entry: &DirstateEntry, metadata: &Metadata) -> bool {
+    match entry.state {
+        b'a' | b'm' => true,
+        b'n' => {
+            let size = (metadata.len() & DIRSTATE_RANGE_MASK) as i32;
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| (modified.as_secs() & DIRSTATE_RANGE_MASK) as i32);
+
+            // -2 stands for the files taken from the other parent of the merge
+            entry.size == -2
+                || (entry.size >= 0 && entry.size != size)
+                || (entry.mtime >= 0 && mtime.is_some_and(|mtime| mtime != entry.mtime))
+                || is_exec_changed(entry, metadata)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn is_exec_changed(entry: &DirstateEntry, metadata: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    entry.size >= 0 && (entry.mode as u32 ^ metadata.permissions().mode()) & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_exec_changed(_: &DirstateEntry, _: &Metadata) -> bool {
+    false
+}
+
+#[cfg(feature = "sqlite")]
+fn read_svn_nodes(root: &Path) -> Option<HashSet<String>> {
+    let connection = Connection::open_with_flags(
+        root.join(".svn").join("wc.db"),
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )
+    .ok()?;
+    let mut statement = connection.prepare(SVN_NODES_QUERY).ok()?;
+    let nodes = statement
+        .query_map([], |row| row.get::<_, String>(0))
+        .ok()?
+        .collect::<Result<_, _>>()
+        .ok();
+
+    nodes
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn read_svn_nodes(_: &Path) -> Option<HashSet<String>> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::temp_dir::TempDir;
+
+    fn dirstate_entry(state: u8, size: i32, name: &str) -> Vec<u8> {
+        let mut entry = vec![state];
+        entry.extend(0o100644i32.to_be_bytes());
+        entry.extend(size.to_be_bytes());
+        entry.extend((-1i32).to_be_bytes());
+        entry.extend((name.len() as i32).to_be_bytes());
+        entry.extend(name.as_bytes());
+        entry
+    }
+
+    #[test]
+    fn test_hg() {
+        let dir = TempDir::new("hg");
+        fs::create_dir_all(dir.join(".hg")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        for name in ["src/main.rs", "src/lib.rs", "added.txt", "removed.txt", "copied.rs", "new.txt"] {
+            fs::write(dir.join(name), "hello").unwrap();
+        }
+
+        let mut dirstate = vec![0; DIRSTATE_PARENTS_SIZE];
+        dirstate.extend(dirstate_entry(b'n', 5, "src/main.rs"));
+        dirstate.extend(dirstate_entry(b'n', 3, "src/lib.rs"));
+        dirstate.extend(dirstate_entry(b'a', -1, "added.txt"));
+        dirstate.extend(dirstate_entry(b'r', 0, "removed.txt"));
+        dirstate.extend(dirstate_entry(b'n', -1, "copied.rs\0src/main.rs"));
+        fs::write(dir.join(".hg/dirstate"), dirstate).unwrap();
+
+        let mut version_control = VersionControl::default();
+        let mut check = |name: &str| {
+            let path = dir.join(name);
+            let metadata = fs::metadata(&path).unwrap();
+            (
+                version_control.is_hg_tracked(&path, metadata.is_dir()),
+                version_control.is_hg_modified(&path, &metadata),
+            )
+        };
+
+        let results = [
+            check("src/main.rs"),
+            check("src/lib.rs"),
+            check("added.txt"),
+            check("removed.txt"),
+            check("copied.rs"),
+            check("new.txt"),
+            check("src"),
+            check("docs"),
+        ];
+        let outside = VersionControl::default().is_hg_tracked(&dir, true);
+
+        assert_eq!(
+            results,
+            [
+                (Some(true), Some(false)),
+                (Some(true), Some(true)),
+                (Some(true), Some(true)),
+                (Some(false), Some(false)),
+                (Some(true), Some(false)),
+                (Some(false), Some(false)),
+                (Some(true), Some(false)),
+                (Some(false), Some(false)),
+            ]
+        );
+        assert_eq!(outside, Some(false));
+    }
+
+    #[test]
+    fn test_dirstate_v2() {
+        assert!(parse_dirstate(b"dirstate-v2\n").is_none());
+        assert!(parse_dirstate(b"").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_svn() {
+        let dir = TempDir::new("svn");
+        fs::create_dir_all(dir.join(".svn")).unwrap();
+        fs::create_dir_all(dir.join("trunk")).unwrap();
+
+        let connection = Connection::open(dir.join(".svn/wc.db")).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE nodes (wc_id INTEGER, local_relpath TEXT, op_depth INTEGER, presence TEXT);
+                INSERT INTO nodes VALUES (1, '', 0, 'normal'), (1, 'trunk', 0, 'normal'),
+                    (1, 'trunk/a.txt', 0, 'normal'), (1, 'trunk/b.txt', 0, 'normal'),
+                    (1, 'trunk/b.txt', 1, 'base-deleted'), (1, 'trunk/c.txt', 1, 'normal');",
+            )
+            .unwrap();
+        drop(connection);
+
+        let mut version_control = VersionControl::default();
+        let results: Vec<_> = ["trunk", "trunk/a.txt", "trunk/b.txt", "trunk/c.txt", "trunk/d.txt"]
+            .iter()
+            .map(|name| version_control.is_svn_versioned(&dir.join(name)))
+            .collect();
+
+        assert_eq!(
+            results,
+            vec![Some(true), Some(true), Some(false), Some(true), Some(false)]
+        );
+    }
+}
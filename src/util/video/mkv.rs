@@ -0,0 +1,84 @@
+use std::io;
+use std::path::Path;
+
+use chrono::{Duration, Local, TimeZone, Utc};
+use matroska::{MatroskaError, Tracktype};
+
+use crate::util::open_file;
+use crate::util::video::{non_empty, VideoMetadata, VideoMetadataExtractor};
+
+pub struct MkvMetadataExtractor;
+
+impl VideoMetadataExtractor for MkvMetadataExtractor {
+    fn supports_ext(&self, ext_lowercase: &str) -> bool {
+        "mkv" == ext_lowercase || "webm" == ext_lowercase
+    }
+
+    fn try_read_metadata(&self, path: &Path) -> io::Result<Option<VideoMetadata>> {
+        let fd = open_file(path)?;
+        let matroska = matroska::Matroska::open(fd).map_err(|err| match err {
+            MatroskaError::Io(io) => io,
+            MatroskaError::UTF8(utf8) => io::Error::new(io::ErrorKind::InvalidData, utf8),
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        })?;
+
+        let info = matroska.info;
+
+        let audio_tracks = matroska
+            .tracks
+            .iter()
+            .filter(|track| track.tracktype == Tracktype::Audio)
+            .count();
+
+        // Matroska tracks without an explicit language are English by spec
+        let subtitle_langs: Vec<String> = matroska
+            .tracks
+            .iter()
+            .filter(|track| track.tracktype == Tracktype::Subtitle)
+            .map(|track| {
+                track
+                    .language
+                    .as_ref()
+                    .map(|lang| lang.to_string())
+                    .unwrap_or_else(|| String::from("eng"))
+            })
+            .collect();
+
+        // Matroska dates are nanoseconds since 2001-01-01 00:00:00 UTC
+        let creation_date = info.date_utc.map(|date| {
+            let nanos: i64 = date.into();
+            let utc = Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 0).unwrap()
+                + Duration::nanoseconds(nanos);
+            utc.with_timezone(&Local).naive_local()
+        });
+
+        Ok(Some(VideoMetadata {
+            title: non_empty(info.title),
+            encoder: non_empty(Some(info.writing_app)).or(non_empty(Some(info.muxing_app))),
+            creation_date,
+            audio_tracks,
+            subtitle_tracks: subtitle_langs.len(),
+            subtitle_langs,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MkvMetadataExtractor;
+    use crate::util::video::VideoMetadataExtractor;
+    use std::error::Error;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_success() -> Result<(), Box<dyn Error>> {
+        let path_string =
+            std::env::var("CARGO_MANIFEST_DIR")? + "/resources/test/" + "video/rust-logo-blk.mkv";
+        let path = PathBuf::from(path_string);
+        let metadata = MkvMetadataExtractor.try_read_metadata(&path)?.unwrap();
+        assert!(metadata.encoder.is_some());
+        assert_eq!(metadata.subtitle_tracks, 0);
+        assert!(metadata.subtitle_langs.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use std::io;
+
+mod mkv;
+mod mp4;
+
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use log::debug;
+
+use mkv::MkvMetadataExtractor;
+use mp4::Mp4MetadataExtractor;
+
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct VideoMetadata {
+    pub title: Option<String>,
+    pub encoder: Option<String>,
+    pub creation_date: Option<NaiveDateTime>,
+    pub audio_tracks: usize,
+    pub subtitle_tracks: usize,
+    pub subtitle_langs: Vec<String>,
+}
+
+pub trait VideoMetadataExtractor {
+    fn supports_ext(&self, ext_lowercase: &str) -> bool;
+    fn try_read_metadata(&self, path: &Path) -> io::Result<Option<VideoMetadata>>;
+}
+
+const EXTRACTORS: [&dyn VideoMetadataExtractor; 2] =
+    [&MkvMetadataExtractor, &Mp4MetadataExtractor];
+
+pub fn get_video_metadata<T: AsRef<Path>>(path: T) -> Option<VideoMetadata> {
+    let path_ref = path.as_ref();
+    let extension = path_ref.extension()?.to_str()?;
+
+    EXTRACTORS
+        .iter()
+        .find(|extractor| extractor.supports_ext(&extension.to_lowercase()))
+        .and_then(|extractor| match extractor.try_read_metadata(path_ref) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                debug!(target: "extractors", "Could not read video metadata of {}: {}", path_ref.display(), err);
+                None
+            }
+        })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|s| s.trim_end_matches('\0').trim().to_string())
+        .filter(|s| !s.is_empty())
+}
@@ -0,0 +1,213 @@
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::{Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+
+use crate::util::open_file;
+use crate::util::video::{non_empty, VideoMetadata, VideoMetadataExtractor};
+
+pub struct Mp4MetadataExtractor;
+
+impl VideoMetadataExtractor for Mp4MetadataExtractor {
+    fn supports_ext(&self, ext_lowercase: &str) -> bool {
+        "mp4" == ext_lowercase || "m4v" == ext_lowercase
+    }
+
+    fn try_read_metadata(&self, path: &Path) -> io::Result<Option<VideoMetadata>> {
+        let mut fd = open_file(path)?;
+        let mut buf = Vec::new();
+        let _ = fd.read_to_end(&mut buf)?;
+        let mut c = io::Cursor::new(&buf);
+        let context = mp4parse::read_mp4(&mut c)?;
+
+        let to_string = |value: &Option<mp4parse::TryString>| {
+            non_empty(
+                value
+                    .as_ref()
+                    .map(|s| String::from_utf8_lossy(s).to_string()),
+            )
+        };
+
+        let (title, encoder) = match context.userdata {
+            Some(Ok(mp4parse::UserdataBox {
+                meta: Some(ref metadata),
+            })) => (to_string(&metadata.title), to_string(&metadata.encoder)),
+            _ => (None, None),
+        };
+
+        let moov = find_box(&buf, b"moov");
+
+        let creation_date = moov
+            .and_then(|moov| find_box(moov, b"mvhd"))
+            .and_then(read_mvhd_creation_time)
+            .and_then(to_creation_date);
+
+        let tracks = moov.map(read_tracks).unwrap_or_default();
+
+        let audio_tracks = tracks
+            .iter()
+            .filter(|(handler_type, _)| handler_type == b"soun")
+            .count();
+
+        let subtitle_langs: Vec<String> = tracks
+            .into_iter()
+            .filter(|(handler_type, _)| matches!(handler_type, b"text" | b"sbtl" | b"subt"))
+            .map(|(_, language)| language.unwrap_or_else(|| String::from("und")))
+            .collect();
+
+        Ok(Some(VideoMetadata {
+            title,
+            encoder,
+            creation_date,
+            audio_tracks,
+            subtitle_tracks: subtitle_langs.len(),
+            subtitle_langs,
+        }))
+    }
+}
+
+/// Returns the contents of the first box of the given type at this nesting level
+fn find_box<'a>(buf: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    iter_boxes(buf)
+        .find(|(current_type, _)| current_type == box_type)
+        .map(|(_, contents)| contents)
+}
+
+/// Iterates over the type and contents of boxes at this nesting level
+fn iter_boxes(buf: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut pos = 0;
+
+    std::iter::from_fn(move || {
+        if pos + 8 > buf.len() {
+            return None;
+        }
+
+        let size = u32::from_be_bytes(buf[pos..pos + 4].try_into().ok()?) as usize;
+        let (header_len, size) = match size {
+            0 => (8, buf.len() - pos),
+            1 => {
+                let large_size = buf.get(pos + 8..pos + 16)?;
+                (16, u64::from_be_bytes(large_size.try_into().ok()?) as usize)
+            }
+            _ => (8, size),
+        };
+
+        if size < header_len || size > buf.len() - pos {
+            return None;
+        }
+
+        let item = (&buf[pos + 4..pos + 8], &buf[pos + header_len..pos + size]);
+        pos += size;
+
+        Some(item)
+    })
+}
+
+/// Returns the handler type and the language of every track in the movie
+fn read_tracks(moov: &[u8]) -> Vec<([u8; 4], Option<String>)> {
+    iter_boxes(moov)
+        .filter(|(box_type, _)| box_type == b"trak")
+        .filter_map(|(_, trak)| find_box(trak, b"mdia"))
+        .filter_map(|mdia| {
+            let hdlr = find_box(mdia, b"hdlr")?;
+            let handler_type: [u8; 4] = hdlr.get(8..12)?.try_into().ok()?;
+            let language = find_box(mdia, b"mdhd").and_then(read_mdhd_language);
+            Some((handler_type, language))
+        })
+        .collect()
+}
+
+/// Unpacks the ISO-639-2/T language code stored as three 5-bit characters
+fn read_mdhd_language(mdhd: &[u8]) -> Option<String> {
+    let offset = match mdhd.first()? {
+        0 => 20,
+        1 => 32,
+        _ => return None,
+    };
+
+    let packed = u16::from_be_bytes(mdhd.get(offset..offset + 2)?.try_into().ok()?);
+    let language: String = [(packed >> 10) & 0x1f, (packed >> 5) & 0x1f, packed & 0x1f]
+        .iter()
+        .map(|&c| (c as u8 + 0x60) as char)
+        .collect();
+
+    match language.chars().all(|c| c.is_ascii_lowercase()) {
+        true => Some(language),
+        false => None,
+    }
+}
+
+fn read_mvhd_creation_time(mvhd: &[u8]) -> Option<u64> {
+    match mvhd.first()? {
+        0 => Some(u32::from_be_bytes(mvhd.get(4..8)?.try_into().ok()?) as u64),
+        1 => Some(u64::from_be_bytes(mvhd.get(4..12)?.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// mvhd creation time is seconds since 1904-01-01 00:00:00 UTC, zero means unset.
+/// Version 1 boxes store it in 64 bits, so it could be far past the dates chrono supports
+fn to_creation_date(seconds: u64) -> Option<NaiveDateTime> {
+    if seconds == 0 {
+        return None;
+    }
+
+    let delta = TimeDelta::try_seconds(i64::try_from(seconds).ok()?)?;
+    let utc = Utc.with_ymd_and_hms(1904, 1, 1, 0, 0, 0).single()?.checked_add_signed(delta)?;
+
+    Some(utc.with_timezone(&Local).naive_local())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        find_box, read_mdhd_language, read_mvhd_creation_time, to_creation_date, Mp4MetadataExtractor,
+    };
+    use crate::util::video::VideoMetadataExtractor;
+    use std::error::Error;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_success() -> Result<(), Box<dyn Error>> {
+        let path_string =
+            std::env::var("CARGO_MANIFEST_DIR")? + "/resources/test/" + "video/rust-logo-blk.mp4";
+        let path = PathBuf::from(path_string);
+        let metadata = Mp4MetadataExtractor.try_read_metadata(&path)?.unwrap();
+        assert_eq!(metadata.subtitle_tracks, 0);
+        assert!(metadata.subtitle_langs.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mvhd_creation_time() {
+        let mut buf = vec![0, 0, 0, 28, b'm', b'o', b'o', b'v'];
+        buf.extend_from_slice(&[0, 0, 0, 20, b'm', b'v', b'h', b'd']);
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        buf.extend_from_slice(&3_000_000_000u32.to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+
+        let creation_time = find_box(&buf, b"moov")
+            .and_then(|moov| find_box(moov, b"mvhd"))
+            .and_then(read_mvhd_creation_time);
+
+        assert_eq!(creation_time, Some(3_000_000_000));
+    }
+
+    #[test]
+    fn test_creation_date() {
+        assert!(to_creation_date(3_000_000_000).is_some());
+        assert_eq!(to_creation_date(0), None);
+        assert_eq!(to_creation_date(u64::MAX), None);
+        assert_eq!(to_creation_date(i64::MAX as u64), None);
+    }
+
+    #[test]
+    fn test_mdhd_language() {
+        let mut mdhd = vec![0u8; 24];
+        // "eng" packed as three 5-bit characters offset by 0x60
+        mdhd[20..22].copy_from_slice(&0x15c7u16.to_be_bytes());
+
+        assert_eq!(read_mdhd_language(&mdhd), Some(String::from("eng")));
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::Read;
+
+use roxmltree::{Document, Node};
+
+#[derive(PartialEq, Debug)]
+enum Step<'a> {
+    /// Child elements with the name, or any name for `*`, optionally the n-th of them counting from 1
+    Child(&'a str, Option<usize>),
+    /// Descendant elements with the name, as in `//name`
+    Descendant(&'a str, Option<usize>),
+    Attribute(&'a str),
+    Text,
+}
+
+/// Evaluates a subset of XPath against the XML document and returns the text of the first node found.
+/// Supported are absolute paths of element names like `/project/version`, `//dependency[2]/artifactId`,
+/// `*` for any element, `@name` for attributes, and `text()`.
+/// Namespaces are ignored, so `/project/version` matches in pom.xml with its default namespace.
+pub fn get_xpath_value(mut reader: impl Read, path: &str) -> Option<String> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).ok()?;
+
+    let document = Document::parse(&contents).ok()?;
+    let steps = parse_xpath(path)?;
+
+    evaluate(document.root(), &steps)
+}
+
+fn evaluate(node: Node, steps: &[Step]) -> Option<String> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Some(node.text().unwrap_or_default().trim().to_string());
+    };
+
+    match step {
+        Step::Attribute(name) => {
+            if rest.is_empty() {
+                node.attributes()
+                    .find(|attr| attr.name() == *name)
+                    .map(|attr| attr.value().to_string())
+            } else {
+                None
+            }
+        }
+        Step::Text => {
+            if rest.is_empty() {
+                node.text().map(|text| text.trim().to_string())
+            } else {
+                None
+            }
+        }
+        Step::Child(name, position) => {
+            let children = node.children().filter(|child| matches_name(child, name));
+            evaluate_nodes(children, *position, rest)
+        }
+        Step::Descendant(name, position) => {
+            let descendants = node.descendants().skip(1).filter(|child| matches_name(child, name));
+            evaluate_nodes(descendants, *position, rest)
+        }
+    }
+}
+
+fn evaluate_nodes<'a, 'input: 'a>(
+    mut nodes: impl Iterator<Item = Node<'a, 'input>>,
+    position: Option<usize>,
+    rest: &[Step],
+) -> Option<String> {
+    match position {
+        Some(position) => evaluate(nodes.nth(position.checked_sub(1)?)?, rest),
+        None => nodes.find_map(|node| evaluate(node, rest)),
+    }
+}
+
+fn matches_name(node: &Node, name: &str) -> bool {
+    node.is_element() && (name == "*" || node.tag_name().name() == name)
+}
+
+fn parse_xpath(path: &str) -> Option<Vec<Step<'_>>> {
+    let mut rest = path.trim();
+    let mut steps = vec![];
+
+    if !rest.starts_with('/') {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let descendant = rest.starts_with("//");
+        rest = rest.trim_start_matches('/');
+
+        let end = rest.find('/').unwrap_or(rest.len());
+        let segment = &rest[..end];
+        rest = &rest[end..];
+
+        if segment.is_empty() {
+            return None;
+        }
+
+        let step = if let Some(attribute) = segment.strip_prefix('@') {
+            Step::Attribute(attribute)
+        } else if segment == "text()" {
+            Step::Text
+        } else {
+            let (name, position) = match segment.split_once('[') {
+                Some((name, predicate)) => {
+                    (name, Some(predicate.strip_suffix(']')?.trim().parse().ok()?))
+                }
+                None => (segment, None),
+            };
+
+            match descendant {
+                true => Step::Descendant(name, position),
+                false => Step::Child(name, position),
+            }
+        };
+
+        steps.push(step);
+    }
+
+    Some(steps)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+    <groupId>org.example</groupId>
+    <version>1.4.2</version>
+    <dependencies>
+        <dependency scope="test"><artifactId>junit</artifactId></dependency>
+        <dependency><artifactId>guava</artifactId></dependency>
+    </dependencies>
+</project>
+"#;
+
+    #[test]
+    fn test_get_xpath_value() {
+        let value = |path| get_xpath_value(POM.as_bytes(), path);
+
+        assert_eq!(value("/project/version"), Some(String::from("1.4.2")));
+        assert_eq!(value("/project/version/text()"), Some(String::from("1.4.2")));
+        assert_eq!(value("//artifactId"), Some(String::from("junit")));
+        assert_eq!(value("//dependency[2]/artifactId"), Some(String::from("guava")));
+        assert_eq!(value("/project/*/dependency/@scope"), Some(String::from("test")));
+        assert_eq!(value("/project/name"), None);
+        assert_eq!(value("project/version"), None);
+        assert_eq!(get_xpath_value("<broken>".as_bytes(), "/broken"), None);
+    }
+
+    #[test]
+    fn test_parse_xpath() {
+        assert_eq!(
+            parse_xpath("/a//b[3]/@c"),
+            Some(vec![Step::Child("a", None), Step::Descendant("b", Some(3)), Step::Attribute("c")])
+        );
+        assert_eq!(parse_xpath("/a[x]"), None);
+        assert_eq!(parse_xpath("/a/"), None);
+    }
+}